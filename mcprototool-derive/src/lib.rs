@@ -0,0 +1,386 @@
+//! Derive macros generating `Encode`/`Decode` implementations for `mcprototool` packets.
+//!
+//! Fields are coded in declaration order. A field can be annotated with `#[packet(...)]` to
+//! change how it is written:
+//!
+//! * `varint` / `varlong` - write an `i32` / `i64` as a VarInt / VarLong.
+//! * `prefixed` - a VarInt element count followed by the elements.
+//! * `fixed = N` - exactly `N` elements with no count.
+//! * `optional` - a boolean followed by the value when it is `Some`.
+//! * `rest` - elements with no count, filling the remainder of the packet.
+//! * `skip` - not sent; decoded as `Default::default()`.
+//! * `since = V` / `until = V` - only sent from protocol version `V` onwards / before `V`, where
+//!   `V` names a `ProtocolVersion` variant. Plain `Encode`/`Decode` use the latest layout; the
+//!   bounds apply to `Encode::encode_for`/`Decode::decode_for`.
+//!
+//! The generated `encode_for`/`decode_for` pass the version on to every field, so nested types
+//! whose layout changed between versions are coded for it too.
+//!
+//! Wrapping options apply from the outside in, so `#[packet(optional, fixed = 256)]` on an
+//! `Option<Vec<u8>>` is a boolean followed by 256 raw bytes.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Expr, Fields, Ident, Index, Member, parse_macro_input};
+
+/// Derives `Encode`, `Decode` and `Packet`. The packet ID is given with `#[packet(id = 0x00)]`.
+#[proc_macro_derive(Packet, attributes(packet))]
+pub fn derive_packet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, true, true, true)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Encode` for a struct from its field order.
+#[proc_macro_derive(Encode, attributes(packet))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, true, false, false)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Decode` for a struct from its field order.
+#[proc_macro_derive(Decode, attributes(packet))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, false, true, false)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Layer {
+    Optional,
+    Prefixed,
+    Fixed(Expr),
+    Rest,
+}
+
+enum Leaf {
+    Default,
+    VarInt,
+    VarLong,
+}
+
+struct FieldCodec {
+    member: Member,
+    binding: Ident,
+    layers: Vec<Layer>,
+    leaf: Leaf,
+    skip: bool,
+    since: Option<Ident>,
+    until: Option<Ident>,
+}
+
+fn expand(
+    input: &DeriveInput,
+    encode: bool,
+    decode: bool,
+    packet: bool,
+) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "packet codecs can only be derived for structs; implement Encode/Decode by hand",
+            ));
+        }
+    };
+
+    let fields = parse_fields(&data.fields)?;
+    let protocol = quote!(::mcprototool::protocol);
+    let construct = match &data.fields {
+        Fields::Named(_) => {
+            let members = fields.iter().map(|field| &field.member);
+            let bindings = fields.iter().map(|field| &field.binding);
+            quote!(#name { #(#members: #bindings),* })
+        }
+        Fields::Unnamed(_) => {
+            let bindings = fields.iter().map(|field| &field.binding);
+            quote!(#name(#(#bindings),*))
+        }
+        Fields::Unit => quote!(#name),
+    };
+    let mut output = TokenStream2::new();
+
+    if encode {
+        let steps = encode_steps(&fields, &protocol);
+        output.extend(quote! {
+            impl #impl_generics #protocol::encoding::Encode for #name #ty_generics #where_clause {
+                fn encode(&self, buf: &mut #protocol::encoding::BytesMut) -> #protocol::encoding::EncodeResult<()> {
+                    #protocol::encoding::Encode::encode_for(self, #protocol::version::ProtocolVersion::LATEST, buf)
+                }
+
+                #[allow(unused_variables)]
+                fn encode_for(
+                    &self,
+                    version: #protocol::version::ProtocolVersion,
+                    buf: &mut #protocol::encoding::BytesMut,
+                ) -> #protocol::encoding::EncodeResult<()> {
+                    #steps
+                    Ok(())
+                }
+            }
+        });
+    }
+
+    if decode {
+        let steps = decode_steps(&fields, &protocol);
+        output.extend(quote! {
+            impl #impl_generics #protocol::encoding::Decode for #name #ty_generics #where_clause {
+                fn decode(buf: &mut #protocol::encoding::BytesMut) -> #protocol::encoding::DecodeResult<Self> {
+                    <Self as #protocol::encoding::Decode>::decode_for(#protocol::version::ProtocolVersion::LATEST, buf)
+                }
+
+                #[allow(unused_variables)]
+                fn decode_for(
+                    version: #protocol::version::ProtocolVersion,
+                    buf: &mut #protocol::encoding::BytesMut,
+                ) -> #protocol::encoding::DecodeResult<Self> {
+                    #steps
+                    Ok(#construct)
+                }
+            }
+        });
+    }
+
+    if packet {
+        let id = packet_id(input)?;
+        output.extend(quote! {
+            impl #impl_generics #protocol::encoding::Packet for #name #ty_generics #where_clause {
+                const ID: i32 = #id;
+            }
+        });
+    }
+
+    Ok(output)
+}
+
+/// The condition under which a field is on the wire for `version`, or `None` if it always is.
+fn version_condition(field: &FieldCodec, protocol: &TokenStream2) -> Option<TokenStream2> {
+    let since = field
+        .since
+        .as_ref()
+        .map(|since| quote!(version >= #protocol::version::ProtocolVersion::#since));
+    let until = field
+        .until
+        .as_ref()
+        .map(|until| quote!(version < #protocol::version::ProtocolVersion::#until));
+    match (since, until) {
+        (Some(since), Some(until)) => Some(quote!(#since && #until)),
+        (Some(condition), None) | (None, Some(condition)) => Some(condition),
+        (None, None) => None,
+    }
+}
+
+fn encode_steps(fields: &[FieldCodec], protocol: &TokenStream2) -> TokenStream2 {
+    let steps = fields.iter().filter(|field| !field.skip).map(|field| {
+        let member = &field.member;
+        let step = encode_layers(&quote!(&self.#member), &field.layers, &field.leaf, protocol);
+        match version_condition(field, protocol) {
+            Some(condition) => quote!(if #condition { #step }),
+            None => step,
+        }
+    });
+    quote!(#(#steps)*)
+}
+
+fn decode_steps(fields: &[FieldCodec], protocol: &TokenStream2) -> TokenStream2 {
+    let steps = fields.iter().map(|field| {
+        let binding = &field.binding;
+        let default = quote!(::core::default::Default::default());
+        let value = if field.skip {
+            default
+        } else {
+            let value = decode_layers(&field.layers, &field.leaf, protocol);
+            match version_condition(field, protocol) {
+                Some(condition) => quote!(if #condition { #value } else { #default }),
+                None => value,
+            }
+        };
+        quote!(let #binding = #value;)
+    });
+    quote!(#(#steps)*)
+}
+
+fn packet_id(input: &DeriveInput) -> syn::Result<Expr> {
+    let mut id = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("packet"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse::<Expr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported packet attribute, expected `id = ...`"))
+            }
+        })?;
+    }
+    id.ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "missing `#[packet(id = ...)]` attribute")
+    })
+}
+
+fn parse_fields(fields: &Fields) -> syn::Result<Vec<FieldCodec>> {
+    let mut parsed = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let (member, binding) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), format_ident!("__{}", ident)),
+            None => (
+                Member::Unnamed(Index {
+                    index: index as u32,
+                    span: Span::call_site(),
+                }),
+                format_ident!("__field{}", index),
+            ),
+        };
+        let mut codec = FieldCodec {
+            member,
+            binding,
+            layers: Vec::new(),
+            leaf: Leaf::Default,
+            skip: false,
+            since: None,
+            until: None,
+        };
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("packet"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("varint") {
+                    codec.leaf = Leaf::VarInt;
+                } else if meta.path.is_ident("varlong") {
+                    codec.leaf = Leaf::VarLong;
+                } else if meta.path.is_ident("optional") {
+                    codec.layers.push(Layer::Optional);
+                } else if meta.path.is_ident("prefixed") {
+                    codec.layers.push(Layer::Prefixed);
+                } else if meta.path.is_ident("fixed") {
+                    codec.layers.push(Layer::Fixed(meta.value()?.parse()?));
+                } else if meta.path.is_ident("rest") {
+                    codec.layers.push(Layer::Rest);
+                } else if meta.path.is_ident("skip") {
+                    codec.skip = true;
+                } else if meta.path.is_ident("since") {
+                    codec.since = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("until") {
+                    codec.until = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error(
+                        "unsupported field attribute, expected one of \
+                         `varint`, `varlong`, `optional`, `prefixed`, `fixed = N`, `rest`, `skip`, \
+                         `since = V`, `until = V`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        parsed.push(codec);
+    }
+    Ok(parsed)
+}
+
+fn encode_layers(
+    value: &TokenStream2,
+    layers: &[Layer],
+    leaf: &Leaf,
+    protocol: &TokenStream2,
+) -> TokenStream2 {
+    let Some((layer, inner_layers)) = layers.split_first() else {
+        return match leaf {
+            Leaf::Default => {
+                quote!(#protocol::encoding::Encode::encode_for(#value, version, buf)?;)
+            }
+            Leaf::VarInt => {
+                quote!(#protocol::encoding::write_varint_sync(buf, #protocol::types::VarInt(*#value))?;)
+            }
+            Leaf::VarLong => {
+                quote!(#protocol::encoding::write_varlong_sync(buf, #protocol::types::VarLong(*#value))?;)
+            }
+        };
+    };
+    let inner = encode_layers(&quote!(item), inner_layers, leaf, protocol);
+    match layer {
+        Layer::Optional => quote! {
+            match #value {
+                ::core::option::Option::Some(item) => {
+                    #protocol::encoding::Encode::encode(&true, buf)?;
+                    #inner
+                }
+                ::core::option::Option::None => #protocol::encoding::Encode::encode(&false, buf)?,
+            }
+        },
+        Layer::Prefixed => quote! {
+            #protocol::encoding::write_varint_sync(buf, #protocol::types::VarInt((#value).len() as i32))?;
+            for item in (#value).iter() {
+                #inner
+            }
+        },
+        Layer::Fixed(len) => quote! {
+            if (#value).len() != (#len) as usize {
+                return Err(#protocol::encoding::fixed_length_mismatch((#len) as usize, (#value).len()));
+            }
+            for item in (#value).iter() {
+                #inner
+            }
+        },
+        Layer::Rest => quote! {
+            for item in (#value).iter() {
+                #inner
+            }
+        },
+    }
+}
+
+fn decode_layers(layers: &[Layer], leaf: &Leaf, protocol: &TokenStream2) -> TokenStream2 {
+    let Some((layer, inner_layers)) = layers.split_first() else {
+        return match leaf {
+            Leaf::Default => quote!(#protocol::encoding::Decode::decode_for(version, buf)?),
+            Leaf::VarInt => quote!(#protocol::encoding::read_varint_sync(buf)?.0),
+            Leaf::VarLong => quote!(#protocol::encoding::read_varlong_sync(buf)?.0),
+        };
+    };
+    let inner = decode_layers(inner_layers, leaf, protocol);
+    match layer {
+        Layer::Optional => quote! {
+            if <bool as #protocol::encoding::Decode>::decode(buf)? {
+                ::core::option::Option::Some(#inner)
+            } else {
+                ::core::option::Option::None
+            }
+        },
+        Layer::Prefixed => quote! {{
+            let len = #protocol::encoding::read_length_sync(buf)?;
+            let mut items = ::std::vec::Vec::with_capacity(len.min(buf.len()));
+            for _ in 0..len {
+                items.push(#inner);
+            }
+            items
+        }},
+        Layer::Fixed(len) => quote! {{
+            let len = (#len) as usize;
+            let mut items = ::std::vec::Vec::with_capacity(len.min(buf.len()));
+            for _ in 0..len {
+                items.push(#inner);
+            }
+            items
+        }},
+        Layer::Rest => quote! {{
+            let mut items = ::std::vec::Vec::new();
+            while !buf.is_empty() {
+                items.push(#inner);
+            }
+            items
+        }},
+    }
+}
@@ -0,0 +1,340 @@
+//! Online-mode authentication.
+//!
+//! The server sends its RSA public key in `EncryptionRequest`, the client answers with the
+//! shared secret and verify token encrypted under it, and both sides derive the same server hash.
+//! The client registers that hash with the session service before answering, so the server can
+//! ask the service whether the player really joined with it.
+
+use crate::protocol::login::clientbound::LoginProperty;
+use rand::RngCore;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Key size used by the vanilla server.
+const KEY_BITS: usize = 1024;
+/// Length of the random token the client must echo back encrypted.
+pub const VERIFY_TOKEN_LEN: usize = 4;
+
+const MOJANG_SESSION_URL: &str = "https://sessionserver.mojang.com";
+
+#[derive(Debug)]
+pub enum AuthError {
+    Rsa(rsa::Error),
+    Http(reqwest::Error),
+    /// The client's encrypted verify token did not match the one we sent.
+    VerifyTokenMismatch,
+    /// The session service answered with something other than a profile or "not joined".
+    UnexpectedResponse(String),
+}
+
+impl From<rsa::Error> for AuthError {
+    fn from(e: rsa::Error) -> Self {
+        AuthError::Rsa(e)
+    }
+}
+
+impl From<reqwest::Error> for AuthError {
+    fn from(e: reqwest::Error) -> Self {
+        AuthError::Http(e)
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Rsa(e) => write!(f, "RSA error: {}", e),
+            AuthError::Http(e) => write!(f, "Session service request failed: {}", e),
+            AuthError::VerifyTokenMismatch => write!(f, "Verify token does not match"),
+            AuthError::UnexpectedResponse(status) => {
+                write!(f, "Unexpected session service response: {}", status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AuthError::Rsa(e) => Some(e),
+            AuthError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The server's RSA keypair, generated once at startup and shared by every login.
+pub struct ServerKey {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+}
+
+impl ServerKey {
+    pub fn generate() -> Result<Self, AuthError> {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), KEY_BITS)?;
+        let public_key_der = RsaPublicKey::from(&private_key)
+            .to_public_key_der()
+            .map_err(|e| AuthError::Rsa(rsa::Error::Pkcs8(e.into())))?
+            .into_vec();
+        Ok(ServerKey {
+            private_key,
+            public_key_der,
+        })
+    }
+
+    /// The public key in X.509 SubjectPublicKeyInfo DER form, as sent in `EncryptionRequest`.
+    pub fn public_key_der(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    /// Decrypts a PKCS#1 v1.5 block from `EncryptionResponse`.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, AuthError> {
+        Ok(self.private_key.decrypt(Pkcs1v15Encrypt, data)?)
+    }
+}
+
+/// Generates the token the client must send back to prove it encrypted with our key.
+pub fn generate_verify_token() -> [u8; VERIFY_TOKEN_LEN] {
+    let mut token = [0u8; VERIFY_TOKEN_LEN];
+    rand::thread_rng().fill_bytes(&mut token);
+    token
+}
+
+/// Computes the server hash shared with the session service.
+///
+/// This is the SHA-1 of the server ID, shared secret and public key, printed as a signed
+/// two's-complement number in hex: negative digests get a leading `-` and leading zeros are
+/// dropped.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key_der);
+    minecraft_hex_digest(hasher.finalize().into())
+}
+
+fn minecraft_hex_digest(mut digest: [u8; 20]) -> String {
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        // Two's complement: invert every bit, then add one.
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            let (value, overflow) = (!*byte).overflowing_add(carry as u8);
+            *byte = value;
+            carry = overflow;
+        }
+    }
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let trimmed = hex.trim_start_matches('0');
+    let trimmed = if trimmed.is_empty() { "0" } else { trimmed };
+    if negative {
+        format!("-{}", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// A player profile as returned by the session service.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<LoginProperty>,
+}
+
+pub type SessionFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Option<GameProfile>, AuthError>> + Send + 'a>>;
+
+/// Verifies that a player has joined this server with their account.
+pub trait SessionService: Send + Sync {
+    /// Returns the player's profile if they registered `server_hash` with the service, or `None`
+    /// if they did not.
+    fn has_joined<'a>(
+        &'a self,
+        username: &'a str,
+        server_hash: &'a str,
+        ip: Option<IpAddr>,
+    ) -> SessionFuture<'a>;
+}
+
+/// The session service run by Mojang.
+pub struct MojangSessionService {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl MojangSessionService {
+    pub fn new() -> Self {
+        Self::with_base_url(MOJANG_SESSION_URL)
+    }
+
+    /// Points the service at a compatible server other than Mojang's.
+    pub fn with_base_url(base_url: &str) -> Self {
+        MojangSessionService {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl Default for MojangSessionService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionService for MojangSessionService {
+    fn has_joined<'a>(
+        &'a self,
+        username: &'a str,
+        server_hash: &'a str,
+        ip: Option<IpAddr>,
+    ) -> SessionFuture<'a> {
+        Box::pin(async move {
+            let mut query = vec![
+                ("username", username.to_string()),
+                ("serverId", server_hash.to_string()),
+            ];
+            if let Some(ip) = ip {
+                query.push(("ip", ip.to_string()));
+            }
+            let response = self
+                .client
+                .get(format!("{}/session/minecraft/hasJoined", self.base_url))
+                .query(&query)
+                .send()
+                .await?;
+            match response.status() {
+                reqwest::StatusCode::OK => Ok(Some(response.json().await?)),
+                reqwest::StatusCode::NO_CONTENT => Ok(None),
+                status => Err(AuthError::UnexpectedResponse(status.to_string())),
+            }
+        })
+    }
+}
+
+/// An in-process session service for tests and offline development.
+///
+/// Clients "join" by registering the server hash they computed, just as a real client posts it
+/// to Mojang before answering `EncryptionRequest`.
+#[derive(Default)]
+pub struct MockSessionService {
+    joined: Mutex<HashMap<String, (String, GameProfile)>>,
+    queried_ips: Mutex<Vec<Option<IpAddr>>>,
+}
+
+impl MockSessionService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `profile` joined a server with `server_hash`.
+    pub fn join(&self, profile: GameProfile, server_hash: &str) {
+        self.joined
+            .lock()
+            .unwrap()
+            .insert(profile.name.clone(), (server_hash.to_string(), profile));
+    }
+
+    /// The `ip` passed to each `has_joined` call so far, in order.
+    pub fn queried_ips(&self) -> Vec<Option<IpAddr>> {
+        self.queried_ips.lock().unwrap().clone()
+    }
+}
+
+impl SessionService for MockSessionService {
+    fn has_joined<'a>(
+        &'a self,
+        username: &'a str,
+        server_hash: &'a str,
+        ip: Option<IpAddr>,
+    ) -> SessionFuture<'a> {
+        self.queried_ips.lock().unwrap().push(ip);
+        let profile = match self.joined.lock().unwrap().get(username) {
+            Some((hash, profile)) if hash == server_hash => Some(profile.clone()),
+            _ => None,
+        };
+        Box::pin(async move { Ok(profile) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha1_hex(name: &str) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(name.as_bytes());
+        minecraft_hex_digest(hasher.finalize().into())
+    }
+
+    #[test]
+    fn test_minecraft_hex_digest_known_values() {
+        assert_eq!(
+            sha1_hex("Notch"),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(
+            sha1_hex("jeb_"),
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+        );
+        assert_eq!(sha1_hex("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    #[test]
+    fn test_server_key_round_trip() {
+        let key = ServerKey::generate().unwrap();
+        let public_key = rsa::pkcs8::DecodePublicKey::from_public_key_der(key.public_key_der());
+        let public_key: RsaPublicKey = public_key.unwrap();
+        let secret = [7u8; 16];
+        let encrypted = public_key
+            .encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, &secret)
+            .unwrap();
+        assert_eq!(key.decrypt(&encrypted).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_game_profile_from_session_json() {
+        let profile: GameProfile = serde_json::from_str(
+            r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch",
+                "properties":[{"name":"textures","value":"abc","signature":"sig"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            profile.id,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(profile.properties[0].signature.as_deref(), Some("sig"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_session_checks_hash() {
+        let service = MockSessionService::new();
+        let profile = GameProfile {
+            id: Uuid::new_v4(),
+            name: "Player".to_string(),
+            properties: vec![],
+        };
+        service.join(profile.clone(), "-1234");
+        assert_eq!(
+            service.has_joined("Player", "-1234", None).await.unwrap(),
+            Some(profile)
+        );
+        assert_eq!(
+            service.has_joined("Player", "1234", None).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            service.has_joined("Other", "-1234", None).await.unwrap(),
+            None
+        );
+    }
+}
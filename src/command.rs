@@ -0,0 +1,1080 @@
+//! Server-side commands: the handlers behind Chat Command, and the Commands packet describing
+//! them.
+//!
+//! Commands are registered on a [`CommandDispatcher`] as trees of [`literal`] and [`argument`]
+//! nodes, as in Brigadier. The same registrations are used to parse and run incoming commands,
+//! to answer Command Suggestions Request, and to build the graph each player is sent, without the
+//! nodes that player lacks the permission level for.
+
+use crate::protocol::command::{ArgumentParser, CommandGraph, NodeKind, StringKind, suggestions};
+use crate::protocol::play::clientbound::{CommandSuggestionsResponse, Commands};
+use crate::protocol::play::serverbound::CommandSuggestionsRequest;
+use crate::protocol::text::{NamedColor, TextComponent};
+use crate::protocol::types::{SuggestionMatch, VarInt};
+
+/// Permission level of server operators, enough for every vanilla command.
+pub const OP_PERMISSION_LEVEL: u8 = 4;
+
+const UNKNOWN_COMMAND: &str = "Unknown or incomplete command, see below for error";
+const INCORRECT_ARGUMENT: &str = "Incorrect argument for command";
+const EXPECTED_SEPARATOR: &str = "Expected whitespace to end one argument, but found trailing data";
+/// Characters of input shown before the error position.
+const CONTEXT_LEN: usize = 10;
+const GAME_MODES: [&str; 4] = ["survival", "creative", "adventure", "spectator"];
+
+/// Who is running a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSource {
+    pub name: String,
+    /// From 0, any player, to [`OP_PERMISSION_LEVEL`].
+    pub permission_level: u8,
+}
+
+/// One component of a position argument: `~` makes it relative to the source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub value: f64,
+    pub relative: bool,
+}
+
+impl Coordinate {
+    pub fn resolve(&self, origin: f64) -> f64 {
+        if self.relative {
+            origin + self.value
+        } else {
+            self.value
+        }
+    }
+}
+
+/// A parsed argument. Parsers the server doesn't interpret are kept as the word given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Integer(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Coordinates(Vec<Coordinate>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// The input doesn't parse; `cursor` is the byte offset the error was found at.
+    Syntax { message: String, cursor: usize },
+    /// The command parsed but its handler refused to run.
+    Failed(TextComponent),
+}
+
+impl CommandError {
+    pub fn syntax(message: impl Into<String>, cursor: usize) -> Self {
+        CommandError::Syntax {
+            message: message.into(),
+            cursor,
+        }
+    }
+
+    /// A plain failure message, shown in red like vanilla's.
+    pub fn failed(message: impl Into<String>) -> Self {
+        CommandError::Failed(TextComponent::text(message).color(NamedColor::Red))
+    }
+
+    fn cursor(&self) -> usize {
+        match self {
+            CommandError::Syntax { cursor, .. } => *cursor,
+            CommandError::Failed(_) => 0,
+        }
+    }
+
+    /// The error as shown to the player who sent `input`. Syntax errors are followed by the
+    /// input around the error, marked as vanilla marks it.
+    pub fn to_text(&self, input: &str) -> TextComponent {
+        let (message, cursor) = match self {
+            CommandError::Syntax { message, cursor } => (message, (*cursor).min(input.len())),
+            CommandError::Failed(text) => return text.clone(),
+        };
+        let start = input[..cursor]
+            .char_indices()
+            .rev()
+            .nth(CONTEXT_LEN - 1)
+            .map_or(0, |(index, _)| index);
+        let mut context = TextComponent::text("\n").color(NamedColor::Gray);
+        if start > 0 {
+            context = context.append("...");
+        }
+        context = context.append(&input[start..cursor]);
+        if cursor < input.len() {
+            context = context.append(
+                TextComponent::text(&input[cursor..])
+                    .color(NamedColor::Red)
+                    .underlined(true),
+            );
+        }
+        context = context.append(
+            TextComponent::text("<--[HERE]")
+                .color(NamedColor::Red)
+                .italic(true),
+        );
+        TextComponent::text(message.as_str())
+            .color(NamedColor::Red)
+            .append(context)
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Syntax { message, cursor } => {
+                write!(f, "{} at position {}", message, cursor)
+            }
+            CommandError::Failed(text) => write!(f, "{}", text.to_plain_text()),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// What a handler answers: feedback for the source, if any.
+pub type CommandResult = Result<Option<TextComponent>, CommandError>;
+
+type Handler = Box<dyn Fn(&CommandContext<'_>) -> CommandResult + Send + Sync>;
+type SuggestionProvider = Box<dyn Fn(&CommandSource, &str) -> Vec<String> + Send + Sync>;
+
+/// What a handler is called with.
+pub struct CommandContext<'a> {
+    pub source: &'a CommandSource,
+    /// The whole command, without the leading slash.
+    pub input: &'a str,
+    arguments: Vec<(String, ArgumentValue)>,
+    dispatcher: &'a CommandDispatcher,
+}
+
+impl CommandContext<'_> {
+    pub fn argument(&self, name: &str) -> Option<&ArgumentValue> {
+        self.arguments
+            .iter()
+            .find(|(argument, _)| argument == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.argument(name)? {
+            ArgumentValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        match self.argument(name)? {
+            ArgumentValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn long(&self, name: &str) -> Option<i64> {
+        match self.argument(name)? {
+            ArgumentValue::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, name: &str) -> Option<f32> {
+        match self.argument(name)? {
+            ArgumentValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn double(&self, name: &str) -> Option<f64> {
+        match self.argument(name)? {
+            ArgumentValue::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.argument(name)? {
+            ArgumentValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn coordinates(&self, name: &str) -> Option<&[Coordinate]> {
+        match self.argument(name)? {
+            ArgumentValue::Coordinates(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The dispatcher running the command, for commands that describe the others.
+    pub fn dispatcher(&self) -> &CommandDispatcher {
+        self.dispatcher
+    }
+}
+
+enum BuilderKind {
+    Literal(String),
+    Argument(String, ArgumentParser),
+}
+
+/// A node and its subtree, to be merged into a dispatcher with [`CommandDispatcher::register`].
+pub struct CommandBuilder {
+    kind: BuilderKind,
+    permission_level: u8,
+    handler: Option<Handler>,
+    suggestions: Option<SuggestionProvider>,
+    children: Vec<CommandBuilder>,
+}
+
+/// A node matching `name` exactly.
+pub fn literal(name: &str) -> CommandBuilder {
+    CommandBuilder::new(BuilderKind::Literal(name.to_string()))
+}
+
+/// A node parsing one argument with `parser`, passed to handlers under `name`.
+pub fn argument(name: &str, parser: ArgumentParser) -> CommandBuilder {
+    CommandBuilder::new(BuilderKind::Argument(name.to_string(), parser))
+}
+
+impl CommandBuilder {
+    fn new(kind: BuilderKind) -> Self {
+        CommandBuilder {
+            kind,
+            permission_level: 0,
+            handler: None,
+            suggestions: None,
+            children: vec![],
+        }
+    }
+
+    /// Hides the node, and everything under it, from sources below `level`.
+    pub fn requires(mut self, level: u8) -> Self {
+        self.permission_level = level;
+        self
+    }
+
+    /// Makes the input up to this node a complete command, run by `handler`.
+    pub fn executes(
+        mut self,
+        handler: impl Fn(&CommandContext<'_>) -> CommandResult + Send + Sync + 'static,
+    ) -> Self {
+        self.handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Suggests values for an argument node from the partial input typed so far. The client is
+    /// told to ask the server for them.
+    pub fn suggests(
+        mut self,
+        provider: impl Fn(&CommandSource, &str) -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.suggestions = Some(Box::new(provider));
+        self
+    }
+
+    pub fn then(mut self, child: CommandBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+#[derive(Default)]
+struct NodeData {
+    permission_level: u8,
+    handler: Option<Handler>,
+    suggestions: Option<SuggestionProvider>,
+}
+
+/// Registered commands. Nodes live in a [`CommandGraph`], with the server-only parts of each
+/// node kept alongside at the same index.
+pub struct CommandDispatcher {
+    graph: CommandGraph,
+    nodes: Vec<NodeData>,
+}
+
+impl Default for CommandDispatcher {
+    fn default() -> Self {
+        CommandDispatcher {
+            graph: CommandGraph::new(),
+            nodes: vec![NodeData::default()],
+        }
+    }
+}
+
+impl CommandDispatcher {
+    pub fn new() -> Self {
+        CommandDispatcher::default()
+    }
+
+    /// A dispatcher with the commands every server has: just `/help` for now.
+    pub fn with_builtins() -> Self {
+        let mut dispatcher = CommandDispatcher::new();
+        dispatcher.register(literal("help").executes(|context| {
+            let usage = context.dispatcher().usage(context.source);
+            let lines: Vec<String> = usage.iter().map(|usage| format!("/{}", usage)).collect();
+            Ok(Some(TextComponent::text(lines.join("\n"))))
+        }));
+        dispatcher
+    }
+
+    /// Adds a top-level command. Nodes with the same name as ones already registered are
+    /// merged into them, as in Brigadier; a new handler replaces the old one, and a merged node
+    /// keeps the higher of the two permission levels.
+    pub fn register(&mut self, command: CommandBuilder) -> &mut Self {
+        self.add(CommandGraph::ROOT, command);
+        self
+    }
+
+    fn add(&mut self, parent: usize, builder: CommandBuilder) {
+        let node = match builder.kind {
+            BuilderKind::Literal(name) => self.graph.literal(parent, &name),
+            BuilderKind::Argument(name, parser) => self.graph.argument(parent, &name, parser),
+        };
+        if node == self.nodes.len() {
+            self.nodes.push(NodeData::default());
+        }
+        let level = &mut self.nodes[node].permission_level;
+        *level = (*level).max(builder.permission_level);
+        if let Some(handler) = builder.handler {
+            self.graph.set_executable(node, true);
+            self.nodes[node].handler = Some(handler);
+        }
+        if let Some(provider) = builder.suggestions {
+            self.graph.set_suggestions(node, suggestions::ASK_SERVER);
+            self.nodes[node].suggestions = Some(provider);
+        }
+        for child in builder.children {
+            self.add(node, child);
+        }
+    }
+
+    /// Every registered node, whatever its permission level.
+    pub fn graph(&self) -> &CommandGraph {
+        &self.graph
+    }
+
+    /// The children of `node` that `source` may use.
+    fn children<'a>(
+        &'a self,
+        node: usize,
+        source: &'a CommandSource,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.graph
+            .node(node)
+            .children
+            .iter()
+            .map(|child| *child as usize)
+            .filter(|child| self.nodes[*child].permission_level <= source.permission_level)
+    }
+
+    /// The Commands packet for `source`, leaving out what it may not use.
+    pub fn commands(&self, source: &CommandSource) -> Commands {
+        let mut graph = CommandGraph::new();
+        self.copy_visible(source, CommandGraph::ROOT, &mut graph, CommandGraph::ROOT);
+        Commands::from(graph)
+    }
+
+    fn copy_visible(
+        &self,
+        source: &CommandSource,
+        node: usize,
+        graph: &mut CommandGraph,
+        copy: usize,
+    ) {
+        for child in self.children(node, source) {
+            let original = self.graph.node(child);
+            let child_copy = match &original.kind {
+                NodeKind::Literal { name } => graph.literal(copy, name),
+                NodeKind::Argument {
+                    name,
+                    parser,
+                    suggestions,
+                } => {
+                    let child_copy = graph.argument(copy, name, parser.clone());
+                    if let Some(provider) = suggestions {
+                        graph.set_suggestions(child_copy, provider);
+                    }
+                    child_copy
+                }
+                NodeKind::Root => continue,
+            };
+            graph.set_executable(child_copy, original.executable);
+            self.copy_visible(source, child, graph, child_copy);
+        }
+    }
+
+    /// Parses and runs `input`, a command without its leading slash.
+    pub fn execute(&self, source: &CommandSource, input: &str) -> CommandResult {
+        let mut arguments = vec![];
+        let node = self.parse(source, input, CommandGraph::ROOT, 0, &mut arguments)?;
+        let handler = self.nodes[node]
+            .handler
+            .as_ref()
+            .expect("parse only ends at executable nodes");
+        handler(&CommandContext {
+            source,
+            input,
+            arguments,
+            dispatcher: self,
+        })
+    }
+
+    /// Parses `input` from `cursor` on below `node`, trying literals before arguments and
+    /// backtracking on failure. Returns the executable node the input ends at.
+    fn parse(
+        &self,
+        source: &CommandSource,
+        input: &str,
+        node: usize,
+        cursor: usize,
+        arguments: &mut Vec<(String, ArgumentValue)>,
+    ) -> Result<usize, CommandError> {
+        if cursor == input.len() {
+            return match self.nodes[node].handler {
+                Some(_) if node != CommandGraph::ROOT => Ok(node),
+                _ => Err(CommandError::syntax(UNKNOWN_COMMAND, cursor)),
+            };
+        }
+        let mut children: Vec<usize> = self.children(node, source).collect();
+        children
+            .sort_by_key(|child| !matches!(self.graph.node(*child).kind, NodeKind::Literal { .. }));
+
+        let mut error: Option<CommandError> = None;
+        let mut keep_furthest = |e: CommandError| {
+            if error
+                .as_ref()
+                .is_none_or(|error| e.cursor() > error.cursor())
+            {
+                error = Some(e);
+            }
+        };
+        for child in children {
+            let (value, end) = match parse_node(&self.graph.node(child).kind, input, cursor) {
+                Ok(Some(parsed)) => parsed,
+                Ok(None) => continue,
+                Err(e) => {
+                    keep_furthest(e);
+                    continue;
+                }
+            };
+            if end < input.len() && !input[end..].starts_with(' ') {
+                keep_furthest(CommandError::syntax(EXPECTED_SEPARATOR, end));
+                continue;
+            }
+            let count = arguments.len();
+            if let Some(value) = value {
+                arguments.push((self.graph.node(child).name().to_string(), value));
+            }
+            let next = if end < input.len() { end + 1 } else { end };
+            match self.parse(source, input, child, next, arguments) {
+                Ok(node) => return Ok(node),
+                Err(e) => {
+                    arguments.truncate(count);
+                    keep_furthest(e);
+                }
+            }
+        }
+        Err(error.unwrap_or_else(|| {
+            let message = if node == CommandGraph::ROOT {
+                UNKNOWN_COMMAND
+            } else {
+                INCORRECT_ARGUMENT
+            };
+            CommandError::syntax(message, cursor)
+        }))
+    }
+
+    /// Completions for the last word of `input`, a command without its leading slash: the byte
+    /// offset the word starts at, and what it could be replaced with.
+    pub fn suggest(&self, source: &CommandSource, input: &str) -> (usize, Vec<String>) {
+        let mut node = CommandGraph::ROOT;
+        let mut cursor = 0;
+        'walk: while input[cursor..].contains(' ') {
+            for child in self.children(node, source) {
+                let Ok(Some((_, end))) = parse_node(&self.graph.node(child).kind, input, cursor)
+                else {
+                    continue;
+                };
+                if end == input.len() {
+                    // A greedy argument: the rest of the input is the word.
+                    break 'walk;
+                }
+                if input[end..].starts_with(' ') {
+                    node = child;
+                    cursor = end + 1;
+                    continue 'walk;
+                }
+            }
+            return (cursor, vec![]);
+        }
+
+        let partial = &input[cursor..];
+        let mut matches = vec![];
+        for child in self.children(node, source) {
+            match &self.graph.node(child).kind {
+                NodeKind::Literal { name } => matches.push(name.clone()),
+                NodeKind::Argument { parser, .. } => match &self.nodes[child].suggestions {
+                    Some(provider) => matches.extend(provider(source, partial)),
+                    None => {
+                        matches.extend(parser_suggestions(parser).iter().map(|s| s.to_string()))
+                    }
+                },
+                NodeKind::Root => {}
+            }
+        }
+        let lowercase = partial.to_lowercase();
+        matches.retain(|suggestion| suggestion.to_lowercase().starts_with(&lowercase));
+        matches.sort();
+        matches.dedup();
+        (cursor, matches)
+    }
+
+    /// Answers a Command Suggestions Request, whose text still has its slash. Offsets in the
+    /// response count UTF-16 units, as Java strings do.
+    pub fn suggestions(
+        &self,
+        source: &CommandSource,
+        request: &CommandSuggestionsRequest,
+    ) -> CommandSuggestionsResponse {
+        let text = &request.text;
+        let offset = if text.starts_with('/') { 1 } else { 0 };
+        let (start, matches) = self.suggest(source, &text[offset..]);
+        let start = offset + start;
+        CommandSuggestionsResponse {
+            id: request.transaction_id,
+            start: VarInt(text[..start].encode_utf16().count() as i32),
+            length: VarInt(text[start..].encode_utf16().count() as i32),
+            matches: matches
+                .into_iter()
+                .map(|match_text| SuggestionMatch {
+                    match_text,
+                    tooltip: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Every complete command `source` may run, as `literal <argument>` paths.
+    pub fn usage(&self, source: &CommandSource) -> Vec<String> {
+        let mut usage = vec![];
+        self.collect_usage(source, CommandGraph::ROOT, String::new(), &mut usage);
+        usage
+    }
+
+    fn collect_usage(
+        &self,
+        source: &CommandSource,
+        node: usize,
+        path: String,
+        usage: &mut Vec<String>,
+    ) {
+        for child in self.children(node, source) {
+            let child_path = match &self.graph.node(child).kind {
+                NodeKind::Literal { name } => format!("{}{}", path, name),
+                NodeKind::Argument { name, .. } => format!("{}<{}>", path, name),
+                NodeKind::Root => continue,
+            };
+            if self.nodes[child].handler.is_some() {
+                usage.push(child_path.clone());
+            }
+            self.collect_usage(source, child, child_path + " ", usage);
+        }
+    }
+}
+
+/// The word starting at `cursor`.
+fn word(input: &str, cursor: usize) -> &str {
+    let rest = &input[cursor..];
+    &rest[..rest.find(' ').unwrap_or(rest.len())]
+}
+
+fn is_unquoted(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+/// Matches one node at `cursor`: `None` if a literal doesn't match, otherwise the argument
+/// value, if any, and where the node's input ends.
+fn parse_node(
+    kind: &NodeKind,
+    input: &str,
+    cursor: usize,
+) -> Result<Option<(Option<ArgumentValue>, usize)>, CommandError> {
+    match kind {
+        NodeKind::Literal { name } => {
+            Ok((word(input, cursor) == name).then(|| (None, cursor + name.len())))
+        }
+        NodeKind::Argument { parser, .. } => {
+            let (value, end) = parse_argument(parser, input, cursor)?;
+            Ok(Some((Some(value), end)))
+        }
+        NodeKind::Root => Ok(None),
+    }
+}
+
+macro_rules! parse_number {
+    ($input:expr, $cursor:expr, $ty:ty, $variant:ident, $name:literal, $min:expr, $max:expr) => {{
+        let word = word($input, $cursor);
+        if word.is_empty() {
+            return Err(CommandError::syntax(concat!("Expected ", $name), $cursor));
+        }
+        let value: $ty = word.parse().map_err(|_| {
+            CommandError::syntax(format!(concat!("Invalid ", $name, " '{}'"), word), $cursor)
+        })?;
+        if let Some(min) = $min
+            && value < min
+        {
+            return Err(CommandError::syntax(
+                format!(
+                    concat!(stringify!($variant), " must not be less than {}, found {}"),
+                    min, value
+                ),
+                $cursor,
+            ));
+        }
+        if let Some(max) = $max
+            && value > max
+        {
+            return Err(CommandError::syntax(
+                format!(
+                    concat!(stringify!($variant), " must not be more than {}, found {}"),
+                    max, value
+                ),
+                $cursor,
+            ));
+        }
+        (ArgumentValue::$variant(value), $cursor + word.len())
+    }};
+}
+
+/// Parses one argument at `cursor`, returning it and where it ends.
+fn parse_argument(
+    parser: &ArgumentParser,
+    input: &str,
+    cursor: usize,
+) -> Result<(ArgumentValue, usize), CommandError> {
+    Ok(match parser {
+        ArgumentParser::Bool => match word(input, cursor) {
+            "true" => (ArgumentValue::Bool(true), cursor + 4),
+            "false" => (ArgumentValue::Bool(false), cursor + 5),
+            "" => return Err(CommandError::syntax("Expected boolean", cursor)),
+            other => {
+                return Err(CommandError::syntax(
+                    format!(
+                        "Invalid boolean, expected 'true' or 'false' but found '{}'",
+                        other
+                    ),
+                    cursor,
+                ));
+            }
+        },
+        ArgumentParser::Integer { min, max } => {
+            parse_number!(input, cursor, i32, Integer, "integer", *min, *max)
+        }
+        ArgumentParser::Long { min, max } => {
+            parse_number!(input, cursor, i64, Long, "long", *min, *max)
+        }
+        ArgumentParser::Float { min, max } => {
+            parse_number!(input, cursor, f32, Float, "float", *min, *max)
+        }
+        ArgumentParser::Double { min, max } => {
+            parse_number!(input, cursor, f64, Double, "double", *min, *max)
+        }
+        ArgumentParser::String(StringKind::SingleWord) => {
+            let rest = &input[cursor..];
+            let len = rest.find(|c| !is_unquoted(c)).unwrap_or(rest.len());
+            (ArgumentValue::String(rest[..len].to_string()), cursor + len)
+        }
+        ArgumentParser::String(StringKind::QuotablePhrase) => parse_quotable(input, cursor)?,
+        ArgumentParser::String(StringKind::GreedyPhrase) | ArgumentParser::Message => (
+            ArgumentValue::String(input[cursor..].to_string()),
+            input.len(),
+        ),
+        ArgumentParser::BlockPos => parse_coordinates(input, cursor, 3, true)?,
+        ArgumentParser::ColumnPos => parse_coordinates(input, cursor, 2, true)?,
+        ArgumentParser::Vec3 => parse_coordinates(input, cursor, 3, false)?,
+        ArgumentParser::Vec2 => parse_coordinates(input, cursor, 2, false)?,
+        ArgumentParser::Gamemode => {
+            let word = word(input, cursor);
+            if !GAME_MODES.contains(&word) {
+                return Err(CommandError::syntax(
+                    format!("Unknown game mode: {}", word),
+                    cursor,
+                ));
+            }
+            (ArgumentValue::String(word.to_string()), cursor + word.len())
+        }
+        _ => {
+            let word = word(input, cursor);
+            if word.is_empty() {
+                return Err(CommandError::syntax("Expected argument", cursor));
+            }
+            (ArgumentValue::String(word.to_string()), cursor + word.len())
+        }
+    })
+}
+
+/// A single word, or a string in single or double quotes with `\` escaping the quote and itself.
+fn parse_quotable(input: &str, cursor: usize) -> Result<(ArgumentValue, usize), CommandError> {
+    let rest = &input[cursor..];
+    let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+        let len = rest.find(|c| !is_unquoted(c)).unwrap_or(rest.len());
+        return Ok((ArgumentValue::String(rest[..len].to_string()), cursor + len));
+    };
+    let mut value = String::new();
+    let mut escaped = false;
+    for (index, c) in rest.char_indices().skip(1) {
+        if escaped {
+            if c != quote && c != '\\' {
+                return Err(CommandError::syntax(
+                    format!("Invalid escape sequence '\\{}' in quoted string", c),
+                    cursor + index,
+                ));
+            }
+            value.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Ok((ArgumentValue::String(value), cursor + index + 1));
+        } else {
+            value.push(c);
+        }
+    }
+    Err(CommandError::syntax("Unclosed quoted string", input.len()))
+}
+
+/// `count` space-separated coordinates, each absolute or `~`-relative. Block positions only take
+/// whole absolute values.
+fn parse_coordinates(
+    input: &str,
+    cursor: usize,
+    count: usize,
+    block: bool,
+) -> Result<(ArgumentValue, usize), CommandError> {
+    let mut coordinates = Vec::with_capacity(count);
+    let mut position = cursor;
+    for index in 0..count {
+        if index > 0 {
+            if !input[position..].starts_with(' ') || position + 1 == input.len() {
+                return Err(CommandError::syntax(
+                    format!("Incomplete (expected {} coordinates)", count),
+                    cursor,
+                ));
+            }
+            position += 1;
+        }
+        let word = word(input, position);
+        let (relative, number) = match word.strip_prefix('~') {
+            Some(offset) => (true, offset),
+            None => (false, word),
+        };
+        if number.starts_with('^') || word.starts_with('^') {
+            return Err(CommandError::syntax(
+                "Local coordinates are not supported",
+                position,
+            ));
+        }
+        let value = if relative && number.is_empty() {
+            0.0
+        } else if block && !relative {
+            number
+                .parse::<i32>()
+                .map_err(|_| CommandError::syntax("Expected integer", position))? as f64
+        } else {
+            number
+                .parse::<f64>()
+                .map_err(|_| CommandError::syntax("Expected double", position))?
+        };
+        coordinates.push(Coordinate { value, relative });
+        position += word.len();
+    }
+    Ok((ArgumentValue::Coordinates(coordinates), position))
+}
+
+/// Suggestions parsers imply without a provider of their own.
+fn parser_suggestions(parser: &ArgumentParser) -> &'static [&'static str] {
+    match parser {
+        ArgumentParser::Bool => &["true", "false"],
+        ArgumentParser::Gamemode => &GAME_MODES,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+
+    fn player() -> CommandSource {
+        CommandSource {
+            name: "Steve".to_string(),
+            permission_level: 0,
+        }
+    }
+
+    fn op() -> CommandSource {
+        CommandSource {
+            name: "Alex".to_string(),
+            permission_level: OP_PERMISSION_LEVEL,
+        }
+    }
+
+    fn feedback(result: CommandResult) -> String {
+        result.unwrap().unwrap().to_plain_text()
+    }
+
+    fn dispatcher() -> CommandDispatcher {
+        let mut dispatcher = CommandDispatcher::with_builtins();
+        dispatcher.register(
+            literal("give").then(
+                argument("item", ArgumentParser::ResourceLocation)
+                    .suggests(|_, _| {
+                        vec!["minecraft:stone".to_string(), "minecraft:dirt".to_string()]
+                    })
+                    .executes(|context| {
+                        Ok(Some(TextComponent::text(format!(
+                            "1 {}",
+                            context.string("item").unwrap()
+                        ))))
+                    })
+                    .then(
+                        argument(
+                            "count",
+                            ArgumentParser::Integer {
+                                min: Some(1),
+                                max: Some(64),
+                            },
+                        )
+                        .executes(|context| {
+                            Ok(Some(TextComponent::text(format!(
+                                "{} {}",
+                                context.integer("count").unwrap(),
+                                context.string("item").unwrap()
+                            ))))
+                        }),
+                    ),
+            ),
+        );
+        dispatcher.register(literal("say").then(
+            argument("message", ArgumentParser::Message).executes(|context| {
+                Ok(Some(TextComponent::text(format!(
+                    "[{}] {}",
+                    context.source.name,
+                    context.string("message").unwrap()
+                ))))
+            }),
+        ));
+        dispatcher.register(
+            literal("stop")
+                .requires(OP_PERMISSION_LEVEL)
+                .executes(|_| Err(CommandError::failed("Not stopping in a test"))),
+        );
+        dispatcher
+    }
+
+    #[test]
+    fn test_execute_typed_arguments() {
+        let dispatcher = dispatcher();
+        assert_eq!(
+            feedback(dispatcher.execute(&player(), "give stone")),
+            "1 stone"
+        );
+        assert_eq!(
+            feedback(dispatcher.execute(&player(), "give stone 16")),
+            "16 stone"
+        );
+        assert_eq!(
+            feedback(dispatcher.execute(&player(), "say hello there")),
+            "[Steve] hello there"
+        );
+        assert_eq!(
+            feedback(dispatcher.execute(&player(), "help")),
+            "/help\n/give <item>\n/give <item> <count>\n/say <message>"
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let dispatcher = dispatcher();
+        assert_eq!(
+            dispatcher.execute(&player(), "give stone 65"),
+            Err(CommandError::syntax(
+                "Integer must not be more than 64, found 65",
+                11
+            ))
+        );
+        assert_eq!(
+            dispatcher.execute(&player(), "give stone lots"),
+            Err(CommandError::syntax("Invalid integer 'lots'", 11))
+        );
+        assert_eq!(
+            dispatcher.execute(&player(), "give"),
+            Err(CommandError::syntax(UNKNOWN_COMMAND, 4))
+        );
+        assert_eq!(
+            dispatcher.execute(&player(), "fly"),
+            Err(CommandError::syntax(UNKNOWN_COMMAND, 0))
+        );
+
+        let error = dispatcher.execute(&player(), "give stone 0").unwrap_err();
+        assert_eq!(
+            error.to_text("give stone 0").to_plain_text(),
+            "Integer must not be less than 1, found 0\n...ive stone 0<--[HERE]"
+        );
+        let error = CommandError::syntax(UNKNOWN_COMMAND, 0);
+        assert_eq!(
+            error.to_text("a long unknown command").to_plain_text(),
+            format!("{}\na long unknown command<--[HERE]", UNKNOWN_COMMAND)
+        );
+        let error = CommandError::syntax(INCORRECT_ARGUMENT, 15);
+        assert_eq!(
+            error.to_text("give stone 1 2 3").to_plain_text(),
+            format!("{}\n...stone 1 2 3<--[HERE]", INCORRECT_ARGUMENT)
+        );
+    }
+
+    #[test]
+    fn test_permission_levels() {
+        let dispatcher = dispatcher();
+        assert_eq!(
+            dispatcher.execute(&player(), "stop"),
+            Err(CommandError::syntax(UNKNOWN_COMMAND, 0))
+        );
+        assert_eq!(
+            dispatcher.execute(&op(), "stop"),
+            Err(CommandError::failed("Not stopping in a test"))
+        );
+        assert!(!dispatcher.usage(&player()).contains(&"stop".to_string()));
+        assert!(dispatcher.usage(&op()).contains(&"stop".to_string()));
+        assert_eq!(dispatcher.suggest(&player(), "st"), (0, vec![]));
+        assert_eq!(
+            dispatcher.suggest(&op(), "st"),
+            (0, vec!["stop".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_merged_nodes_keep_the_higher_permission_level() {
+        let mut dispatcher = CommandDispatcher::new();
+        dispatcher.register(literal("team").then(literal("list").executes(|_| Ok(None))));
+        dispatcher.register(
+            literal("team").then(
+                literal("remove")
+                    .requires(OP_PERMISSION_LEVEL)
+                    .executes(|_| Ok(None)),
+            ),
+        );
+        dispatcher.register(
+            literal("team").then(
+                literal("list")
+                    .requires(OP_PERMISSION_LEVEL)
+                    .executes(|_| Ok(None)),
+            ),
+        );
+        dispatcher.register(literal("team").then(literal("remove").executes(|_| Ok(None))));
+        for command in ["team list", "team remove"] {
+            assert_eq!(
+                dispatcher.execute(&player(), command),
+                Err(CommandError::syntax(INCORRECT_ARGUMENT, 5))
+            );
+            assert_eq!(dispatcher.execute(&op(), command), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_commands_follow_permissions() {
+        let dispatcher = dispatcher();
+        let commands = dispatcher.commands(&player());
+        let names: Vec<&str> = commands.nodes.iter().map(|node| node.name()).collect();
+        assert!(names.contains(&"give") && !names.contains(&"stop"));
+        assert_eq!(
+            dispatcher.commands(&op()).nodes.len(),
+            dispatcher.graph().nodes().len()
+        );
+        assert_round_trip(&commands);
+
+        let item = commands
+            .nodes
+            .iter()
+            .find(|node| node.name() == "item")
+            .unwrap();
+        assert!(item.executable);
+        assert!(matches!(
+            &item.kind,
+            NodeKind::Argument { suggestions: Some(provider), .. } if provider == suggestions::ASK_SERVER
+        ));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let dispatcher = dispatcher();
+        assert_eq!(
+            dispatcher.suggest(&player(), "g"),
+            (0, vec!["give".to_string()])
+        );
+        assert_eq!(
+            dispatcher.suggest(&player(), "give minecraft:d"),
+            (5, vec!["minecraft:dirt".to_string()])
+        );
+        assert_eq!(dispatcher.suggest(&player(), "nope "), (0, vec![]));
+
+        let response = dispatcher.suggestions(
+            &player(),
+            &CommandSuggestionsRequest {
+                transaction_id: VarInt(7),
+                text: "/give minecraft:".to_string(),
+            },
+        );
+        assert_eq!(response.id, VarInt(7));
+        assert_eq!(response.start, VarInt(6));
+        assert_eq!(response.length, VarInt(10));
+        assert_eq!(response.matches.len(), 2);
+        assert_eq!(response.matches[0].match_text, "minecraft:dirt");
+    }
+
+    #[test]
+    fn test_argument_parsers() {
+        let parse = |parser: ArgumentParser, input: &str| parse_argument(&parser, input, 0);
+        assert_eq!(
+            parse(ArgumentParser::Bool, "true x"),
+            Ok((ArgumentValue::Bool(true), 4))
+        );
+        assert!(parse(ArgumentParser::Bool, "yes").is_err());
+        assert_eq!(
+            parse(
+                ArgumentParser::String(StringKind::QuotablePhrase),
+                r#""a \"b\"" c"#
+            ),
+            Ok((ArgumentValue::String(r#"a "b""#.to_string()), 9))
+        );
+        assert!(parse(ArgumentParser::String(StringKind::QuotablePhrase), "'open").is_err());
+        assert_eq!(
+            parse(ArgumentParser::BlockPos, "~ 64 ~-2"),
+            Ok((
+                ArgumentValue::Coordinates(vec![
+                    Coordinate {
+                        value: 0.0,
+                        relative: true
+                    },
+                    Coordinate {
+                        value: 64.0,
+                        relative: false
+                    },
+                    Coordinate {
+                        value: -2.0,
+                        relative: true
+                    },
+                ]),
+                8
+            ))
+        );
+        assert!(parse(ArgumentParser::BlockPos, "1.5 2 3").is_err());
+        assert!(parse(ArgumentParser::Vec3, "1 2").is_err());
+        assert_eq!(
+            parse(ArgumentParser::Gamemode, "creative"),
+            Ok((ArgumentValue::String("creative".to_string()), 8))
+        );
+        assert!(parse(ArgumentParser::Gamemode, "peaceful").is_err());
+    }
+}
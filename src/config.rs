@@ -0,0 +1,437 @@
+//! Server settings.
+//!
+//! [`ServerConfig`] is read from a TOML file or a vanilla-style `server.properties`; both use the
+//! vanilla key names (`server-port`, `max-players`, ...). Keys this server doesn't use are
+//! ignored, so an existing `server.properties` can be reused as is. Command-line flags
+//! ([`ConfigOverrides`]) are applied on top of whatever the file says.
+
+use crate::protocol::text::{SECTION_SIGN, TextComponent};
+use clap::Args;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Files tried, in order, when no config file is given.
+pub const DEFAULT_CONFIG_FILES: &[&str] = &["server.toml", "server.properties"];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    /// A `server.properties` value that doesn't parse as its key's type.
+    InvalidValue {
+        key: String,
+        value: String,
+    },
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        ConfigError::Toml(e)
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "Could not read config: {}", e),
+            ConfigError::Toml(e) => write!(f, "Invalid TOML config: {}", e),
+            ConfigError::InvalidValue { key, value } => {
+                write!(f, "Invalid value {:?} for {}", value, key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(e) => Some(e),
+            ConfigError::Toml(e) => Some(e),
+            ConfigError::InvalidValue { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ServerConfig {
+    pub server_ip: String,
+    pub server_port: u16,
+    /// Either `§` formatting codes or MiniMessage-style tags, see [`ServerConfig::motd_component`].
+    pub motd: String,
+    pub max_players: i32,
+    /// Verify players with Mojang's session server.
+    pub online_mode: bool,
+    /// Have the session server check that players reach it from the address they connect from,
+    /// which rejects players behind proxies or NAT hairpins.
+    pub prevent_proxy_connections: bool,
+    /// Packets of at least this many bytes are compressed; negative disables compression.
+    pub network_compression_threshold: i32,
+    /// zlib level for compressed packets, from 0 (store) to 9 (best).
+    pub network_compression_level: u32,
+    pub view_distance: i32,
+    pub simulation_distance: i32,
+    pub enforce_secure_chat: bool,
+    /// 64x64 PNG shown in the server list, if the file exists. Relative to the working directory.
+    pub server_icon: String,
+    /// Players run commands at the operator permission level. In `server.properties`, a
+    /// comma-separated list.
+    pub ops: Vec<String>,
+    /// A data pack directory whose `data` folder adds registry entries to the bundled vanilla
+    /// ones, and tags of those registries. Tags of built-in registries such as `block` and
+    /// `item` are not loaded. Empty for just the bundled entries.
+    pub data_directory: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            server_ip: "127.0.0.1".to_string(),
+            server_port: 25565,
+            motd: "Minimal Rust Server".to_string(),
+            max_players: 20,
+            online_mode: false,
+            prevent_proxy_connections: false,
+            network_compression_threshold: 256,
+            network_compression_level: 6,
+            view_distance: 10,
+            simulation_distance: 10,
+            enforce_secure_chat: false,
+            server_icon: "server-icon.png".to_string(),
+            ops: vec![],
+            data_directory: String::new(),
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+    value.trim().parse().map_err(|_| ConfigError::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    })
+}
+
+impl ServerConfig {
+    /// Loads a config file: TOML if it ends in `.toml`, `server.properties` format otherwise.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            Self::from_toml(&contents)
+        } else {
+            Self::from_properties(&contents)
+        }
+    }
+
+    /// Loads the first of [`DEFAULT_CONFIG_FILES`] in `dir` that exists, or the defaults.
+    pub fn load_default(dir: &Path) -> Result<Self, ConfigError> {
+        for file in DEFAULT_CONFIG_FILES {
+            let path = dir.join(file);
+            if path.exists() {
+                return Self::load(&path);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_properties(contents: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        for (key, value) in parse_properties(contents) {
+            config.set_property(&key, &value)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks values whose type allows more than the key does.
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.network_compression_level > 9 {
+            return Err(ConfigError::InvalidValue {
+                key: "network-compression-level".to_string(),
+                value: self.network_compression_level.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn set_property(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "server-ip" => self.server_ip = value.trim().to_string(),
+            "server-port" => self.server_port = parse_value(key, value)?,
+            "motd" => self.motd = value.to_string(),
+            "max-players" => self.max_players = parse_value(key, value)?,
+            "online-mode" => self.online_mode = parse_value(key, value)?,
+            "prevent-proxy-connections" => {
+                self.prevent_proxy_connections = parse_value(key, value)?
+            }
+            "network-compression-threshold" => {
+                self.network_compression_threshold = parse_value(key, value)?
+            }
+            "network-compression-level" => {
+                self.network_compression_level = parse_value(key, value)?
+            }
+            "view-distance" => self.view_distance = parse_value(key, value)?,
+            "simulation-distance" => self.simulation_distance = parse_value(key, value)?,
+            "enforce-secure-chat" => self.enforce_secure_chat = parse_value(key, value)?,
+            "server-icon" => self.server_icon = value.trim().to_string(),
+            "data-directory" => self.data_directory = value.trim().to_string(),
+            "ops" => {
+                self.ops = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The MOTD as a component. Text with any `§` code is read as legacy formatting, anything
+    /// else as MiniMessage-style tags.
+    pub fn motd_component(&self) -> TextComponent {
+        if self.motd.contains(SECTION_SIGN) {
+            TextComponent::from_legacy(&self.motd)
+        } else {
+            TextComponent::from_minimessage(&self.motd)
+        }
+    }
+
+    /// Whether `name` is listed in `ops`. Names are matched ignoring case, as player names are.
+    pub fn is_op(&self, name: &str) -> bool {
+        self.ops.iter().any(|op| op.eq_ignore_ascii_case(name))
+    }
+
+    /// The address to listen on. An empty `server-ip` means every interface, as in vanilla.
+    pub fn bind_address(&self) -> String {
+        let ip = if self.server_ip.is_empty() {
+            "0.0.0.0"
+        } else {
+            &self.server_ip
+        };
+        format!("{}:{}", ip, self.server_port)
+    }
+}
+
+/// Reads `key=value` lines the way `java.util.Properties` does for the files vanilla writes:
+/// `#`/`!` comments, `=` or `:` separators and backslash escapes, including `\uXXXX`.
+fn parse_properties(contents: &str) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    for line in contents.lines() {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let mut key = String::new();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => key.extend(chars.next()),
+                '=' | ':' => break,
+                _ => key.push(c),
+            }
+        }
+        properties.insert(
+            key.trim_end().to_string(),
+            unescape(chars.as_str().trim_start()),
+        );
+    }
+    properties
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    unescaped.push(c);
+                }
+            }
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// Command-line flags that override the config file.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ConfigOverrides {
+    #[arg(long)]
+    pub server_ip: Option<String>,
+    #[arg(long, short = 'p')]
+    pub server_port: Option<u16>,
+    #[arg(long)]
+    pub motd: Option<String>,
+    #[arg(long)]
+    pub max_players: Option<i32>,
+    #[arg(long)]
+    pub online_mode: Option<bool>,
+    #[arg(long)]
+    pub prevent_proxy_connections: Option<bool>,
+    #[arg(long)]
+    pub network_compression_threshold: Option<i32>,
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=9))]
+    pub network_compression_level: Option<u32>,
+    #[arg(long)]
+    pub view_distance: Option<i32>,
+    #[arg(long)]
+    pub simulation_distance: Option<i32>,
+    #[arg(long)]
+    pub enforce_secure_chat: Option<bool>,
+    #[arg(long)]
+    pub server_icon: Option<String>,
+}
+
+impl ConfigOverrides {
+    pub fn apply(self, config: &mut ServerConfig) {
+        if let Some(server_ip) = self.server_ip {
+            config.server_ip = server_ip;
+        }
+        if let Some(server_port) = self.server_port {
+            config.server_port = server_port;
+        }
+        if let Some(motd) = self.motd {
+            config.motd = motd;
+        }
+        if let Some(max_players) = self.max_players {
+            config.max_players = max_players;
+        }
+        if let Some(online_mode) = self.online_mode {
+            config.online_mode = online_mode;
+        }
+        if let Some(prevent_proxy_connections) = self.prevent_proxy_connections {
+            config.prevent_proxy_connections = prevent_proxy_connections;
+        }
+        if let Some(threshold) = self.network_compression_threshold {
+            config.network_compression_threshold = threshold;
+        }
+        if let Some(level) = self.network_compression_level {
+            config.network_compression_level = level;
+        }
+        if let Some(view_distance) = self.view_distance {
+            config.view_distance = view_distance;
+        }
+        if let Some(simulation_distance) = self.simulation_distance {
+            config.simulation_distance = simulation_distance;
+        }
+        if let Some(enforce_secure_chat) = self.enforce_secure_chat {
+            config.enforce_secure_chat = enforce_secure_chat;
+        }
+        if let Some(server_icon) = self.server_icon {
+            config.server_icon = server_icon;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::text::NamedColor;
+    use clap::Parser;
+
+    #[test]
+    fn test_from_properties() {
+        let config = ServerConfig::from_properties(
+            "#Minecraft server properties\n\
+             server-port=25570\n\
+             motd=\\u00A7aHello\\: world\n\
+             max-players = 50\n\
+             online-mode=true\n\
+             prevent-proxy-connections=true\n\
+             ops=Notch, jeb_\n\
+             level-seed=ignored\n\
+             server-ip=\n",
+        )
+        .unwrap();
+        assert_eq!(config.server_port, 25570);
+        assert_eq!(config.motd, "§aHello: world");
+        assert_eq!(
+            config.motd_component(),
+            TextComponent::text("Hello: world").color(NamedColor::Green)
+        );
+        assert_eq!(config.max_players, 50);
+        assert!(config.online_mode);
+        assert!(config.prevent_proxy_connections);
+        assert_eq!(config.ops, ["Notch", "jeb_"]);
+        assert!(config.is_op("notch") && !config.is_op("Dinnerbone"));
+        assert_eq!(config.bind_address(), "0.0.0.0:25570");
+        assert_eq!(config.view_distance, ServerConfig::default().view_distance);
+    }
+
+    #[test]
+    fn test_from_properties_rejects_bad_values() {
+        assert!(matches!(
+            ServerConfig::from_properties("max-players=lots"),
+            Err(ConfigError::InvalidValue { key, .. }) if key == "max-players"
+        ));
+        assert!(matches!(
+            ServerConfig::from_properties("network-compression-level=10"),
+            Err(ConfigError::InvalidValue { key, .. }) if key == "network-compression-level"
+        ));
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = ServerConfig::from_toml(
+            r#"
+            motd = "From TOML"
+            network-compression-threshold = -1
+            network-compression-level = 9
+            enforce-secure-chat = true
+            ops = ["Notch"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.motd, "From TOML");
+        assert_eq!(config.motd_component(), TextComponent::text("From TOML"));
+        assert_eq!(config.network_compression_threshold, -1);
+        assert_eq!(config.network_compression_level, 9);
+        assert!(config.enforce_secure_chat);
+        assert!(config.is_op("Notch"));
+        assert_eq!(config.server_port, 25565);
+    }
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        overrides: ConfigOverrides,
+    }
+
+    #[test]
+    fn test_overrides_apply_on_top_of_file() {
+        let mut config = ServerConfig::from_properties("server-port=25570\nmotd=File").unwrap();
+        let cli = Cli::parse_from(["server", "--motd", "Flag", "--online-mode", "true"]);
+        cli.overrides.apply(&mut config);
+        assert_eq!(config.motd, "Flag");
+        assert!(config.online_mode);
+        assert_eq!(config.server_port, 25570);
+        assert!(Cli::try_parse_from(["server", "--network-compression-level", "10"]).is_err());
+    }
+}
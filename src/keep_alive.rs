@@ -0,0 +1,180 @@
+//! Keep-alive timing for configuration and play connections.
+//!
+//! The server sends a random ID every [`KEEP_ALIVE_INTERVAL`] and the client echoes it back. A
+//! reply with any other ID, or no reply within [`KEEP_ALIVE_TIMEOUT`], ends the connection. The
+//! time replies take is the player's latency, shown in the tab list.
+
+use std::time::{Duration, Instant};
+
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What to do once [`KeepAlive::deadline`] has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveAction {
+    /// Send a keep-alive with this ID.
+    Send(i64),
+    /// The client hasn't answered in time; disconnect it.
+    TimedOut,
+    /// Nothing is due yet.
+    Wait,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnexpectedKeepAlive {
+    /// The ID waiting for a reply, if any.
+    pub expected: Option<i64>,
+    pub received: i64,
+}
+
+impl std::fmt::Display for UnexpectedKeepAlive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.expected {
+            Some(expected) => write!(
+                f,
+                "Keep-alive ID {} does not match {}",
+                self.received, expected
+            ),
+            None => write!(f, "Unrequested keep-alive ID {}", self.received),
+        }
+    }
+}
+
+impl std::error::Error for UnexpectedKeepAlive {}
+
+/// The keep-alive state of one connection. Only one keep-alive is outstanding at a time.
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    last_sent: Instant,
+    pending: Option<i64>,
+    latency: Option<Duration>,
+}
+
+impl KeepAlive {
+    /// Starts the clock at `now`; the first keep-alive is due an interval later.
+    pub fn new(now: Instant) -> Self {
+        KeepAlive {
+            last_sent: now,
+            pending: None,
+            latency: None,
+        }
+    }
+
+    /// When [`KeepAlive::poll`] next has something to do.
+    pub fn deadline(&self) -> Instant {
+        match self.pending {
+            Some(_) => self.last_sent + KEEP_ALIVE_TIMEOUT,
+            None => self.last_sent + KEEP_ALIVE_INTERVAL,
+        }
+    }
+
+    pub fn poll(&mut self, now: Instant) -> KeepAliveAction {
+        if now < self.deadline() {
+            return KeepAliveAction::Wait;
+        }
+        if self.pending.is_some() {
+            return KeepAliveAction::TimedOut;
+        }
+        let id = rand::random();
+        self.pending = Some(id);
+        self.last_sent = now;
+        KeepAliveAction::Send(id)
+    }
+
+    /// Checks a reply against the outstanding ID and returns the updated latency. Like
+    /// vanilla's, it is a running average weighting the newest reply by a quarter.
+    pub fn receive(&mut self, id: i64, now: Instant) -> Result<Duration, UnexpectedKeepAlive> {
+        if self.pending != Some(id) {
+            return Err(UnexpectedKeepAlive {
+                expected: self.pending,
+                received: id,
+            });
+        }
+        self.pending = None;
+        let elapsed = now.saturating_duration_since(self.last_sent);
+        let latency = match self.latency {
+            Some(latency) => (latency * 3 + elapsed) / 4,
+            None => elapsed,
+        };
+        self.latency = Some(latency);
+        Ok(latency)
+    }
+
+    /// The averaged latency, once the client has answered a keep-alive.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sends_every_interval() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+        assert_eq!(keep_alive.deadline(), start + KEEP_ALIVE_INTERVAL);
+        assert_eq!(
+            keep_alive.poll(start + Duration::from_secs(14)),
+            KeepAliveAction::Wait
+        );
+
+        let sent = start + KEEP_ALIVE_INTERVAL;
+        let KeepAliveAction::Send(id) = keep_alive.poll(sent) else {
+            panic!("expected a keep-alive to be due");
+        };
+        assert_eq!(keep_alive.deadline(), sent + KEEP_ALIVE_TIMEOUT);
+        assert_eq!(
+            keep_alive.receive(id, sent + Duration::from_millis(80)),
+            Ok(Duration::from_millis(80))
+        );
+        assert_eq!(keep_alive.deadline(), sent + KEEP_ALIVE_INTERVAL);
+
+        let KeepAliveAction::Send(id) = keep_alive.poll(sent + KEEP_ALIVE_INTERVAL) else {
+            panic!("expected a keep-alive to be due");
+        };
+        assert_eq!(
+            keep_alive.receive(id, sent + KEEP_ALIVE_INTERVAL + Duration::from_millis(40)),
+            Ok(Duration::from_millis(70))
+        );
+        assert_eq!(keep_alive.latency(), Some(Duration::from_millis(70)));
+    }
+
+    #[test]
+    fn test_times_out_without_reply() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+        let sent = start + KEEP_ALIVE_INTERVAL;
+        assert!(matches!(keep_alive.poll(sent), KeepAliveAction::Send(_)));
+        assert_eq!(
+            keep_alive.poll(sent + KEEP_ALIVE_INTERVAL),
+            KeepAliveAction::Wait
+        );
+        assert_eq!(
+            keep_alive.poll(sent + KEEP_ALIVE_TIMEOUT),
+            KeepAliveAction::TimedOut
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_id() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new(start);
+        assert_eq!(
+            keep_alive.receive(5, start),
+            Err(UnexpectedKeepAlive {
+                expected: None,
+                received: 5
+            })
+        );
+        let KeepAliveAction::Send(id) = keep_alive.poll(start + KEEP_ALIVE_INTERVAL) else {
+            panic!("expected a keep-alive to be due");
+        };
+        let error = keep_alive
+            .receive(id.wrapping_add(1), start + KEEP_ALIVE_INTERVAL)
+            .unwrap_err();
+        assert_eq!(error.expected, Some(id));
+        assert_eq!(keep_alive.latency(), None);
+    }
+}
@@ -0,0 +1,11 @@
+// Lets the derive macros refer to `::mcprototool` from inside this crate too.
+extern crate self as mcprototool;
+
+pub mod auth;
+pub mod command;
+pub mod config;
+pub mod keep_alive;
+pub mod protocol;
+pub mod registry;
+pub mod server;
+pub mod status;
@@ -1,12 +1,29 @@
 use std::error::Error;
+use std::path::PathBuf;
 
-mod protocol;
-mod server;
+use clap::Parser;
+use mcprototool::config::{ConfigOverrides, ServerConfig};
+use mcprototool::server::{self, ServerOptions};
+
+#[derive(Parser)]
+#[command(about = "Minimal Minecraft server")]
+struct Cli {
+    /// TOML or server.properties file; defaults to server.toml or server.properties if present.
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+    #[command(flatten)]
+    overrides: ConfigOverrides,
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let server_address = "127.0.0.1:25565";
+    let cli = Cli::parse();
+    let mut config = match &cli.config {
+        Some(path) => ServerConfig::load(path)?,
+        None => ServerConfig::load_default(&std::env::current_dir()?)?,
+    };
+    cli.overrides.apply(&mut config);
 
-    println!("Starting server on {}...", server_address);
-    server::run_server(server_address).await
+    println!("Starting server on {}...", config.bind_address());
+    server::run_server(ServerOptions::from_config(config)).await
 }
@@ -0,0 +1,221 @@
+//! Sky and block light for a chunk column, as in Chunk Data and Update Light and Update Light.
+//!
+//! Light covers two more sections than the world has blocks: bit 0 of each mask is the section
+//! below the world and the last bit the one above it. A section in a light mask has an array of
+//! levels; one in the matching empty mask is all zeros; one in neither is left as the client has
+//! it.
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_length_sync, write_varint_sync,
+};
+use crate::protocol::types::{BitSet, VarInt};
+use bytes::BytesMut;
+
+/// Bytes in a light array: 4096 four-bit levels.
+pub const LIGHT_ARRAY_LEN: usize = 2048;
+
+/// The light levels of one section, two to a byte with the even index in the low nibble.
+#[derive(Clone, PartialEq, Eq)]
+pub struct LightArray(Box<[u8; LIGHT_ARRAY_LEN]>);
+
+impl LightArray {
+    /// Every block at `level`, in `0..16`.
+    pub fn filled(level: u8) -> Self {
+        let level = level & 15;
+        LightArray(Box::new([level << 4 | level; LIGHT_ARRAY_LEN]))
+    }
+
+    /// The level at section-relative coordinates, each in `0..16`.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        let index = y << 8 | z << 4 | x;
+        self.0[index >> 1] >> ((index & 1) * 4) & 15
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        let index = y << 8 | z << 4 | x;
+        let shift = (index & 1) * 4;
+        let byte = &mut self.0[index >> 1];
+        *byte = *byte & !(15 << shift) | (level & 15) << shift;
+    }
+
+    pub fn as_bytes(&self) -> &[u8; LIGHT_ARRAY_LEN] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for LightArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LightArray({} bytes)", LIGHT_ARRAY_LEN)
+    }
+}
+
+impl Encode for LightArray {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varint_sync(buf, VarInt(LIGHT_ARRAY_LEN as i32))?;
+        buf.extend_from_slice(&self.0[..]);
+        Ok(())
+    }
+}
+
+impl Decode for LightArray {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let len = read_length_sync(buf)?;
+        if len != LIGHT_ARRAY_LEN {
+            return Err(DecodeError::InvalidEnumValue(
+                "light array length",
+                len as i64,
+            ));
+        }
+        if buf.len() < len {
+            return Err(DecodeError::NotEnoughBytes);
+        }
+        let mut levels = Box::new([0; LIGHT_ARRAY_LEN]);
+        levels.copy_from_slice(&buf.split_to(len));
+        Ok(LightArray(levels))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LightData {
+    pub sky_light_mask: BitSet,
+    pub block_light_mask: BitSet,
+    pub empty_sky_light_mask: BitSet,
+    pub empty_block_light_mask: BitSet,
+    /// One per bit of `sky_light_mask`, in bit order.
+    pub sky_light: Vec<LightArray>,
+    /// One per bit of `block_light_mask`, in bit order.
+    pub block_light: Vec<LightArray>,
+}
+
+impl LightData {
+    /// Level 15 sky and block light in every section of a world `section_count` sections tall,
+    /// so it renders lit in any dimension.
+    pub fn full_bright(section_count: usize) -> Self {
+        let sections = section_count + 2;
+        LightData {
+            sky_light_mask: BitSet::filled(sections),
+            block_light_mask: BitSet::filled(sections),
+            empty_sky_light_mask: BitSet::new(),
+            empty_block_light_mask: BitSet::new(),
+            sky_light: vec![LightArray::filled(15); sections],
+            block_light: vec![LightArray::filled(15); sections],
+        }
+    }
+
+    /// No light in any section; only the empty masks are sent.
+    pub fn dark(section_count: usize) -> Self {
+        let sections = section_count + 2;
+        LightData {
+            empty_sky_light_mask: BitSet::filled(sections),
+            empty_block_light_mask: BitSet::filled(sections),
+            ..LightData::default()
+        }
+    }
+
+    /// The sky light of light section `index` (0 is the one below the world), if sent.
+    pub fn sky_light_at(&self, index: usize) -> Option<&LightArray> {
+        Self::array_at(&self.sky_light_mask, &self.sky_light, index)
+    }
+
+    /// The block light of light section `index` (0 is the one below the world), if sent.
+    pub fn block_light_at(&self, index: usize) -> Option<&LightArray> {
+        Self::array_at(&self.block_light_mask, &self.block_light, index)
+    }
+
+    fn array_at<'a>(
+        mask: &BitSet,
+        arrays: &'a [LightArray],
+        index: usize,
+    ) -> Option<&'a LightArray> {
+        if !mask.get(index) {
+            return None;
+        }
+        arrays.get(mask.ones().take_while(|bit| *bit < index).count())
+    }
+}
+
+impl Encode for LightData {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.sky_light_mask.encode(buf)?;
+        self.block_light_mask.encode(buf)?;
+        self.empty_sky_light_mask.encode(buf)?;
+        self.empty_block_light_mask.encode(buf)?;
+        self.sky_light.encode(buf)?;
+        self.block_light.encode(buf)
+    }
+}
+
+impl Decode for LightData {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Ok(LightData {
+            sky_light_mask: BitSet::decode(buf)?,
+            block_light_mask: BitSet::decode(buf)?,
+            empty_sky_light_mask: BitSet::decode(buf)?,
+            empty_block_light_mask: BitSet::decode(buf)?,
+            sky_light: Vec::decode(buf)?,
+            block_light: Vec::decode(buf)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+
+    #[test]
+    fn test_nibble_order() {
+        let mut light = LightArray::filled(0);
+        light.set(0, 0, 0, 3);
+        light.set(1, 0, 0, 12);
+        light.set(0, 1, 0, 15);
+        assert_eq!(light.as_bytes()[0], 0xC3);
+        assert_eq!(light.as_bytes()[128], 0x0F);
+        assert_eq!(light.get(1, 0, 0), 12);
+        assert_eq!(LightArray::filled(15).get(7, 7, 7), 15);
+    }
+
+    #[test]
+    fn test_full_bright() {
+        let light = LightData::full_bright(24);
+        assert_eq!(light.sky_light.len(), 26);
+        assert!(light.sky_light_mask.get(25) && !light.sky_light_mask.get(26));
+        assert_eq!(light.block_light_at(25).map(|a| a.get(0, 0, 0)), Some(15));
+        assert_eq!(light.sky_light_at(26), None);
+
+        let mut buf = light.to_bytes().unwrap();
+        // Each mask is one word: a length of 1, then the 26 low bits set.
+        assert_eq!(&buf[..10], [1, 0, 0, 0, 0, 0x03, 0xFF, 0xFF, 0xFF, 1]);
+        assert_eq!(LightData::decode(&mut buf).unwrap(), light);
+    }
+
+    #[test]
+    fn test_dark_sends_only_empty_masks() {
+        let light = LightData::dark(24);
+        assert!(light.sky_light_mask.is_empty() && light.sky_light.is_empty());
+        assert_eq!(light.empty_sky_light_mask.ones().count(), 26);
+        assert_eq!(light.to_bytes().unwrap().len(), 1 + 1 + 9 + 9 + 1 + 1);
+        assert_round_trip(&light);
+    }
+
+    #[test]
+    fn test_arrays_follow_mask_order() {
+        let mut light = LightData::default();
+        light.sky_light_mask.set(3, true);
+        light.sky_light_mask.set(9, true);
+        light.sky_light = vec![LightArray::filled(1), LightArray::filled(2)];
+        assert_eq!(light.sky_light_at(9).map(|a| a.get(0, 0, 0)), Some(2));
+        assert_eq!(light.sky_light_at(4), None);
+    }
+
+    #[test]
+    fn test_short_light_array_is_rejected() {
+        let mut buf = BytesMut::from(&[0x80, 0x10, 0][..]);
+        assert!(matches!(
+            LightArray::decode(&mut buf),
+            Err(DecodeError::NotEnoughBytes)
+        ));
+        let mut buf = BytesMut::from(&[4, 0, 0, 0, 0][..]);
+        assert!(LightArray::decode(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+//! Chunk column data for Chunk Data and Update Light, and the [`LightData`] it shares with
+//! Update Light.
+//!
+//! A column is its heightmaps, a run of 16-block-tall [`ChunkSection`]s from the bottom of the
+//! world up, and the block entities in it. The number of sections follows from the dimension's
+//! height, which the packet doesn't carry: decoding reads sections until their data runs out.
+//! Before 1.21.5 heightmaps were sent as an NBT compound and each paletted container's data
+//! array was prefixed with its long count.
+
+mod light;
+mod palette;
+
+pub use light::{LIGHT_ARRAY_LEN, LightArray, LightData};
+pub use palette::{Palette, PaletteKind, PalettedContainer};
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_fixed_bytes_sync,
+    read_length_sync, read_varint_sync, write_varint_sync,
+};
+use crate::protocol::types::{Nbt, VarInt};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+
+/// The block state ID of air, which doesn't count towards a section's block count.
+pub const AIR: i32 = 0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSection {
+    /// Non-air blocks in the section. Clients use it to skip empty sections.
+    pub block_count: i16,
+    pub block_states: PalettedContainer,
+    pub biomes: PalettedContainer,
+}
+
+impl ChunkSection {
+    /// A section of air in a single biome.
+    pub fn empty(biome: i32) -> Self {
+        ChunkSection {
+            block_count: 0,
+            block_states: PalettedContainer::single(PaletteKind::BLOCK_STATES, AIR),
+            biomes: PalettedContainer::single(PaletteKind::BIOMES, biome),
+        }
+    }
+
+    /// The block state at section-relative coordinates, each in `0..16`.
+    pub fn block(&self, x: usize, y: usize, z: usize) -> i32 {
+        self.block_states.get(y << 8 | z << 4 | x)
+    }
+
+    /// Sets a block state, keeping `block_count` in step.
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: i32) {
+        let index = y << 8 | z << 4 | x;
+        let previous = self.block_states.get(index);
+        self.block_states.set(index, state);
+        self.block_count += (state != AIR) as i16 - (previous != AIR) as i16;
+    }
+
+    /// The biome at section-relative biome coordinates, each in `0..4`.
+    pub fn biome(&self, x: usize, y: usize, z: usize) -> i32 {
+        self.biomes.get(y << 4 | z << 2 | x)
+    }
+
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: i32) {
+        self.biomes.set(y << 4 | z << 2 | x, biome);
+    }
+}
+
+impl Encode for ChunkSection {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.block_count.encode(buf)?;
+        self.block_states.encode_for(version, buf)?;
+        self.biomes.encode_for(version, buf)
+    }
+}
+
+impl Decode for ChunkSection {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        Ok(ChunkSection {
+            block_count: i16::decode(buf)?,
+            block_states: PalettedContainer::decode_with(version, buf, PaletteKind::BLOCK_STATES)?,
+            biomes: PalettedContainer::decode_with(version, buf, PaletteKind::BIOMES)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapType {
+    WorldSurfaceWg = 0,
+    WorldSurface = 1,
+    OceanFloorWg = 2,
+    OceanFloor = 3,
+    MotionBlocking = 4,
+    MotionBlockingNoLeaves = 5,
+}
+
+impl HeightmapType {
+    const ALL: [HeightmapType; 6] = [
+        HeightmapType::WorldSurfaceWg,
+        HeightmapType::WorldSurface,
+        HeightmapType::OceanFloorWg,
+        HeightmapType::OceanFloor,
+        HeightmapType::MotionBlocking,
+        HeightmapType::MotionBlockingNoLeaves,
+    ];
+
+    /// The key of the heightmap in the NBT compound 1.21.4 sends.
+    pub fn name(self) -> &'static str {
+        match self {
+            HeightmapType::WorldSurfaceWg => "WORLD_SURFACE_WG",
+            HeightmapType::WorldSurface => "WORLD_SURFACE",
+            HeightmapType::OceanFloorWg => "OCEAN_FLOOR_WG",
+            HeightmapType::OceanFloor => "OCEAN_FLOOR",
+            HeightmapType::MotionBlocking => "MOTION_BLOCKING",
+            HeightmapType::MotionBlockingNoLeaves => "MOTION_BLOCKING_NO_LEAVES",
+        }
+    }
+}
+
+impl TryFrom<i32> for HeightmapType {
+    type Error = ();
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => HeightmapType::WorldSurfaceWg,
+            1 => HeightmapType::WorldSurface,
+            2 => HeightmapType::OceanFloorWg,
+            3 => HeightmapType::OceanFloor,
+            4 => HeightmapType::MotionBlocking,
+            5 => HeightmapType::MotionBlockingNoLeaves,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// For each of the 16×16 columns, the height above the world's bottom of the first block over
+/// the highest one matching the heightmap, packed like a paletted container's entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightmap {
+    pub kind: HeightmapType,
+    pub data: Vec<i64>,
+}
+
+impl Heightmap {
+    /// Bits per height for a world `world_height` blocks tall.
+    fn bits(world_height: u32) -> u32 {
+        u32::BITS - world_height.leading_zeros()
+    }
+
+    /// Packs 256 heights, indexed `z * 16 + x`.
+    pub fn from_heights(kind: HeightmapType, heights: &[u16; 256], world_height: u32) -> Self {
+        let bits = Heightmap::bits(world_height);
+        let per_long = 64 / bits as usize;
+        let mut data = vec![0i64; 256usize.div_ceil(per_long)];
+        for (index, height) in heights.iter().enumerate() {
+            let shift = (index % per_long) as u32 * bits;
+            data[index / per_long] |= ((*height as u64) << shift) as i64;
+        }
+        Heightmap { kind, data }
+    }
+
+    /// Unpacks the heights, or `None` if `data` is too short for `world_height`.
+    pub fn heights(&self, world_height: u32) -> Option<[u16; 256]> {
+        let bits = Heightmap::bits(world_height);
+        let per_long = 64 / bits as usize;
+        let mask = (1u64 << bits) - 1;
+        let mut heights = [0; 256];
+        for (index, height) in heights.iter_mut().enumerate() {
+            let long = *self.data.get(index / per_long)? as u64;
+            *height = (long >> ((index % per_long) as u32 * bits) & mask) as u16;
+        }
+        Some(heights)
+    }
+}
+
+impl Encode for Heightmap {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varint_sync(buf, VarInt(self.kind as i32))?;
+        self.data.encode(buf)
+    }
+}
+
+impl Decode for Heightmap {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let kind = read_varint_sync(buf)?.0;
+        Ok(Heightmap {
+            kind: HeightmapType::try_from(kind)
+                .map_err(|_| DecodeError::InvalidEnumValue("HeightmapType", kind as i64))?,
+            data: Vec::decode(buf)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkBlockEntity {
+    /// Chunk-relative, in `0..16`.
+    pub x: u8,
+    /// Chunk-relative, in `0..16`.
+    pub z: u8,
+    /// Absolute.
+    pub y: i16,
+    /// Network ID in the `minecraft:block_entity_type` registry.
+    pub block_entity_type: i32,
+    /// What the client needs to render the block entity, without its position or ID.
+    pub data: Nbt,
+}
+
+impl Encode for ChunkBlockEntity {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        ((self.x & 15) << 4 | (self.z & 15)).encode(buf)?;
+        self.y.encode(buf)?;
+        write_varint_sync(buf, VarInt(self.block_entity_type))?;
+        self.data.encode_for(version, buf)
+    }
+}
+
+impl Decode for ChunkBlockEntity {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let packed_xz = u8::decode(buf)?;
+        Ok(ChunkBlockEntity {
+            x: packed_xz >> 4,
+            z: packed_xz & 15,
+            y: i16::decode(buf)?,
+            block_entity_type: read_varint_sync(buf)?.0,
+            data: Nbt::decode(buf)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChunkData {
+    pub heightmaps: Vec<Heightmap>,
+    /// From the bottom of the world up.
+    pub sections: Vec<ChunkSection>,
+    pub block_entities: Vec<ChunkBlockEntity>,
+}
+
+impl ChunkData {
+    /// A column of `section_count` empty sections in one biome.
+    pub fn empty(section_count: usize, biome: i32) -> Self {
+        ChunkData {
+            heightmaps: vec![],
+            sections: vec![ChunkSection::empty(biome); section_count],
+            block_entities: vec![],
+        }
+    }
+}
+
+/// Writes heightmaps as the compound of long arrays 1.21.4 sends.
+fn encode_heightmaps_v1_21_4(heightmaps: &[Heightmap], buf: &mut BytesMut) -> EncodeResult<()> {
+    let compound = heightmaps
+        .iter()
+        .map(|heightmap| {
+            let data = Nbt::LongArray(heightmap.data.clone());
+            (heightmap.kind.name().to_string(), data)
+        })
+        .collect();
+    Nbt::Compound(compound).encode(buf)
+}
+
+fn decode_heightmaps_v1_21_4(buf: &mut BytesMut) -> DecodeResult<Vec<Heightmap>> {
+    let Nbt::Compound(compound) = Nbt::decode(buf)? else {
+        return Err(DecodeError::InvalidNbt(
+            "heightmaps are not a compound".to_string(),
+        ));
+    };
+    compound
+        .into_iter()
+        .map(|(name, data)| {
+            let kind = HeightmapType::ALL
+                .into_iter()
+                .find(|kind| kind.name() == name)
+                .ok_or_else(|| DecodeError::InvalidNbt(format!("unknown heightmap {}", name)))?;
+            match data {
+                Nbt::LongArray(data) => Ok(Heightmap { kind, data }),
+                _ => Err(DecodeError::InvalidNbt(format!(
+                    "heightmap {} is not a long array",
+                    name
+                ))),
+            }
+        })
+        .collect()
+}
+
+impl Encode for ChunkData {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        if version < ProtocolVersion::V1_21_5 {
+            encode_heightmaps_v1_21_4(&self.heightmaps, buf)?;
+        } else {
+            self.heightmaps.encode(buf)?;
+        }
+        // Sections are sent as one length-prefixed byte array.
+        let mut sections = BytesMut::new();
+        for section in &self.sections {
+            section.encode_for(version, &mut sections)?;
+        }
+        write_varint_sync(buf, VarInt(sections.len() as i32))?;
+        buf.extend_from_slice(&sections);
+        self.block_entities.encode_for(version, buf)
+    }
+}
+
+impl Decode for ChunkData {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let heightmaps = if version < ProtocolVersion::V1_21_5 {
+            decode_heightmaps_v1_21_4(buf)?
+        } else {
+            Vec::decode(buf)?
+        };
+        let len = read_length_sync(buf)?;
+        let mut data = BytesMut::from(&read_fixed_bytes_sync(buf, len)?[..]);
+        let mut sections = vec![];
+        while !data.is_empty() {
+            sections.push(ChunkSection::decode_for(version, &mut data)?);
+        }
+        Ok(ChunkData {
+            heightmaps,
+            sections,
+            block_entities: Vec::decode(buf)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+
+    #[test]
+    fn test_set_block_tracks_count() {
+        let mut section = ChunkSection::empty(1);
+        section.set_block(0, 0, 0, 1);
+        section.set_block(15, 15, 15, 9);
+        section.set_block(15, 15, 15, 10);
+        assert_eq!(section.block_count, 2);
+        section.set_block(0, 0, 0, AIR);
+        assert_eq!(section.block_count, 1);
+        assert_eq!(section.block(15, 15, 15), 10);
+        assert_eq!(section.block_states.get(4095), 10);
+    }
+
+    #[test]
+    fn test_empty_section_layout() {
+        assert_eq!(
+            ChunkSection::empty(3).to_bytes().unwrap().as_ref(),
+            [0, 0, 0, 0, 0, 3]
+        );
+    }
+
+    #[test]
+    fn test_chunk_round_trip() {
+        let mut chunk = ChunkData::empty(24, 0);
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.sections[4].set_block(x, 0, z, 1 + (x + z) as i32 % 3);
+            }
+        }
+        chunk.sections[4].set_biome(1, 2, 3, 7);
+        chunk.heightmaps.push(Heightmap::from_heights(
+            HeightmapType::MotionBlocking,
+            &[65; 256],
+            384,
+        ));
+        chunk.block_entities.push(ChunkBlockEntity {
+            x: 3,
+            z: 12,
+            y: -60,
+            block_entity_type: 7,
+            data: Nbt::from_snbt("{Items: []}").unwrap(),
+        });
+        let mut buf = chunk.to_bytes().unwrap();
+        let decoded = ChunkData::decode(&mut buf).unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(decoded, chunk);
+        assert_eq!(decoded.sections.len(), 24);
+        assert_eq!(decoded.sections[4].block_count, 256);
+        assert_eq!(decoded.sections[4].biome(1, 2, 3), 7);
+        assert_round_trip(&chunk.block_entities[0]);
+    }
+
+    #[test]
+    fn test_v1_21_4_layout() {
+        let version = ProtocolVersion::V1_21_4;
+        let mut chunk = ChunkData::empty(2, 3);
+        chunk.heightmaps.push(Heightmap {
+            kind: HeightmapType::MotionBlocking,
+            data: vec![1, 2],
+        });
+        let mut buf = BytesMut::new();
+        chunk.encode_for(version, &mut buf).unwrap();
+
+        let mut expected = BytesMut::new();
+        Nbt::from_snbt("{MOTION_BLOCKING: [L; 1L, 2L]}")
+            .unwrap()
+            .encode(&mut expected)
+            .unwrap();
+        // Two sections of air in biome 3, each container ending in an empty data array.
+        expected.extend_from_slice(&[16, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0]);
+        assert_eq!(buf, expected);
+
+        let decoded = ChunkData::decode_for(version, &mut buf).unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(decoded, chunk);
+    }
+
+    #[test]
+    fn test_heightmap_packing() {
+        // A 384-block world needs nine bits per height, seven heights to a long.
+        let mut heights = [0u16; 256];
+        heights[0] = 384;
+        heights[7] = 1;
+        let heightmap = Heightmap::from_heights(HeightmapType::WorldSurface, &heights, 384);
+        assert_eq!(heightmap.data.len(), 37);
+        assert_eq!(heightmap.data[0], 384);
+        assert_eq!(heightmap.data[1], 1);
+        assert_eq!(heightmap.heights(384), Some(heights));
+        assert_eq!(heightmap.heights(1 << 20), None);
+    }
+}
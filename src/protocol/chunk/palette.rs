@@ -0,0 +1,419 @@
+//! Paletted containers: the block states or biomes of a chunk section, packed into longs.
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_length_sync, read_varint_sync,
+    write_varint_sync,
+};
+use crate::protocol::types::VarInt;
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+
+/// The size and palette limits of a kind of container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteKind {
+    pub entries: usize,
+    /// Indirect palettes never use fewer bits than this.
+    pub min_bits: u8,
+    /// Above this, entries are global IDs rather than palette indices.
+    pub max_indirect_bits: u8,
+    /// Bits per global ID, `ceil(log2(registry size))` on the receiving end.
+    pub direct_bits: u8,
+}
+
+impl PaletteKind {
+    /// 16×16×16 block states, against the 1.21.5 block state count.
+    pub const BLOCK_STATES: PaletteKind = PaletteKind {
+        entries: 4096,
+        min_bits: 4,
+        max_indirect_bits: 8,
+        direct_bits: 15,
+    };
+    /// 4×4×4 biomes, against the vanilla biome registry. Servers with custom biomes set
+    /// `direct_bits` from their registry's size.
+    pub const BIOMES: PaletteKind = PaletteKind {
+        entries: 64,
+        min_bits: 1,
+        max_indirect_bits: 3,
+        direct_bits: 7,
+    };
+}
+
+/// `ceil(log2(n))`, at least zero.
+fn bits_for(n: usize) -> u8 {
+    (usize::BITS - n.saturating_sub(1).leading_zeros()) as u8
+}
+
+/// Fixed-width entries packed into longs from the low bits up, never spanning two longs.
+#[derive(Debug, Clone, PartialEq)]
+struct BitStorage {
+    bits: u8,
+    data: Vec<i64>,
+}
+
+impl BitStorage {
+    fn longs_for(bits: u8, size: usize) -> usize {
+        if bits == 0 {
+            0
+        } else {
+            size.div_ceil(64 / bits as usize)
+        }
+    }
+
+    fn new(bits: u8, size: usize) -> Self {
+        BitStorage {
+            bits,
+            data: vec![0; BitStorage::longs_for(bits, size)],
+        }
+    }
+
+    fn locate(&self, index: usize) -> (usize, u32) {
+        let per_long = 64 / self.bits as usize;
+        (
+            index / per_long,
+            (index % per_long) as u32 * self.bits as u32,
+        )
+    }
+
+    fn mask(&self) -> u64 {
+        (1u64 << self.bits) - 1
+    }
+
+    fn get(&self, index: usize) -> u32 {
+        let (long, shift) = self.locate(index);
+        ((self.data[long] as u64 >> shift) & self.mask()) as u32
+    }
+
+    fn set(&mut self, index: usize, value: u32) {
+        let (long, shift) = self.locate(index);
+        let mask = self.mask() << shift;
+        let cleared = self.data[long] as u64 & !mask;
+        self.data[long] = (cleared | ((value as u64) << shift & mask)) as i64;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Palette {
+    /// Every entry is this ID; no data follows.
+    Single(i32),
+    /// Entries index into these IDs.
+    Indirect(Vec<i32>),
+    /// Entries are IDs.
+    Direct,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PalettedContainer {
+    kind: PaletteKind,
+    palette: Palette,
+    storage: BitStorage,
+}
+
+impl PalettedContainer {
+    /// A container whose entries are all `value`.
+    pub fn single(kind: PaletteKind, value: i32) -> Self {
+        PalettedContainer {
+            kind,
+            palette: Palette::Single(value),
+            storage: BitStorage::new(0, kind.entries),
+        }
+    }
+
+    /// A container holding `values`, in the smallest encoding that fits them.
+    ///
+    /// # Panics
+    ///
+    /// If `values` doesn't have `kind.entries` entries.
+    pub fn from_values(kind: PaletteKind, values: &[i32]) -> Self {
+        assert_eq!(
+            values.len(),
+            kind.entries,
+            "wrong number of container entries"
+        );
+        let mut palette: Vec<i32> = vec![];
+        for value in values {
+            if !palette.contains(value) {
+                palette.push(*value);
+            }
+        }
+        if let [value] = palette[..] {
+            return PalettedContainer::single(kind, value);
+        }
+        let bits = bits_for(palette.len()).max(kind.min_bits);
+        let mut container = if bits <= kind.max_indirect_bits {
+            PalettedContainer {
+                kind,
+                palette: Palette::Indirect(palette),
+                storage: BitStorage::new(bits, kind.entries),
+            }
+        } else {
+            PalettedContainer {
+                kind,
+                palette: Palette::Direct,
+                storage: BitStorage::new(kind.direct_bits, kind.entries),
+            }
+        };
+        for (index, value) in values.iter().enumerate() {
+            container.set(index, *value);
+        }
+        container
+    }
+
+    pub fn kind(&self) -> PaletteKind {
+        self.kind
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn bits_per_entry(&self) -> u8 {
+        self.storage.bits
+    }
+
+    /// The packed entries, as sent.
+    pub fn data(&self) -> &[i64] {
+        &self.storage.data
+    }
+
+    pub fn get(&self, index: usize) -> i32 {
+        match &self.palette {
+            Palette::Single(value) => *value,
+            Palette::Indirect(palette) => palette[self.storage.get(index) as usize],
+            Palette::Direct => self.storage.get(index) as i32,
+        }
+    }
+
+    /// Sets an entry, growing the palette or switching to direct IDs as needed.
+    pub fn set(&mut self, index: usize, value: i32) {
+        match &mut self.palette {
+            Palette::Single(current) if *current == value => {}
+            Palette::Single(_) => {
+                let mut values = self.values();
+                values[index] = value;
+                *self = PalettedContainer::from_values(self.kind, &values);
+            }
+            Palette::Indirect(palette) => match palette.iter().position(|id| *id == value) {
+                Some(position) => self.storage.set(index, position as u32),
+                None if palette.len() < 1 << self.storage.bits => {
+                    palette.push(value);
+                    let position = palette.len() as u32 - 1;
+                    self.storage.set(index, position);
+                }
+                None => {
+                    let mut values = self.values();
+                    values[index] = value;
+                    *self = PalettedContainer::from_values(self.kind, &values);
+                }
+            },
+            Palette::Direct => self.storage.set(index, value as u32),
+        }
+    }
+
+    /// Sets every entry to `value`.
+    pub fn fill(&mut self, value: i32) {
+        *self = PalettedContainer::single(self.kind, value);
+    }
+
+    pub fn values(&self) -> Vec<i32> {
+        (0..self.kind.entries)
+            .map(|index| self.get(index))
+            .collect()
+    }
+
+    /// Reads a container of `kind` in the layout of `version`.
+    pub fn decode_with(
+        version: ProtocolVersion,
+        buf: &mut BytesMut,
+        kind: PaletteKind,
+    ) -> DecodeResult<Self> {
+        // Before 1.21.5 the data array is prefixed with its long count.
+        let read_data_len = |buf: &mut BytesMut, expected: usize| -> DecodeResult<()> {
+            if version < ProtocolVersion::V1_21_5 {
+                let len = read_length_sync(buf)?;
+                if len != expected {
+                    return Err(DecodeError::InvalidEnumValue(
+                        "data array length",
+                        len as i64,
+                    ));
+                }
+            }
+            Ok(())
+        };
+        let bits = u8::decode(buf)?;
+        if bits == 0 {
+            let value = read_varint_sync(buf)?.0;
+            read_data_len(buf, 0)?;
+            return Ok(PalettedContainer::single(kind, value));
+        }
+        let (palette, bits, kind) = if bits <= kind.max_indirect_bits {
+            let len = read_length_sync(buf)?;
+            let mut palette = Vec::with_capacity(len.min(buf.len()));
+            for _ in 0..len {
+                palette.push(read_varint_sync(buf)?.0);
+            }
+            (Palette::Indirect(palette), bits.max(kind.min_bits), kind)
+        } else if bits <= 32 {
+            let kind = PaletteKind {
+                direct_bits: bits,
+                ..kind
+            };
+            (Palette::Direct, bits, kind)
+        } else {
+            return Err(DecodeError::InvalidEnumValue("bits per entry", bits as i64));
+        };
+        let mut storage = BitStorage::new(bits, kind.entries);
+        read_data_len(buf, storage.data.len())?;
+        for long in storage.data.iter_mut() {
+            *long = i64::decode(buf)?;
+        }
+        if let Palette::Indirect(palette) = &palette
+            && let Some(index) = (0..kind.entries)
+                .map(|index| storage.get(index))
+                .find(|index| *index as usize >= palette.len())
+        {
+            return Err(DecodeError::InvalidEnumValue("palette index", index as i64));
+        }
+        Ok(PalettedContainer {
+            kind,
+            palette,
+            storage,
+        })
+    }
+}
+
+impl Encode for PalettedContainer {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.storage.bits.encode(buf)?;
+        match &self.palette {
+            Palette::Single(value) => write_varint_sync(buf, VarInt(*value))?,
+            Palette::Indirect(palette) => {
+                write_varint_sync(buf, VarInt(palette.len() as i32))?;
+                for id in palette {
+                    write_varint_sync(buf, VarInt(*id))?;
+                }
+            }
+            Palette::Direct => {}
+        }
+        // Since 1.21.5 the long count isn't sent; both sides derive it from the bits per entry.
+        if version < ProtocolVersion::V1_21_5 {
+            write_varint_sync(buf, VarInt(self.storage.data.len() as i32))?;
+        }
+        self.storage
+            .data
+            .iter()
+            .try_for_each(|long| long.encode(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(container: &PalettedContainer) -> PalettedContainer {
+        let version = ProtocolVersion::LATEST;
+        let mut buf = container.to_bytes().unwrap();
+        let decoded = PalettedContainer::decode_with(version, &mut buf, container.kind()).unwrap();
+        assert!(buf.is_empty());
+        decoded
+    }
+
+    #[test]
+    fn test_bits_for() {
+        assert_eq!(
+            [1, 2, 3, 4, 5, 16, 17, 256, 27914].map(bits_for),
+            [0, 1, 2, 2, 3, 4, 5, 8, 15]
+        );
+    }
+
+    #[test]
+    fn test_single_value() {
+        let container = PalettedContainer::single(PaletteKind::BLOCK_STATES, 9);
+        assert_eq!(container.to_bytes().unwrap().as_ref(), [0, 9]);
+        assert_eq!(round_trip(&container), container);
+        assert_eq!(container.get(4095), 9);
+    }
+
+    #[test]
+    fn test_indirect_packing() {
+        let mut values = vec![0; 64];
+        values[0] = 5;
+        values[1] = 7;
+        values[63] = 7;
+        let container = PalettedContainer::from_values(PaletteKind::BIOMES, &values);
+        assert_eq!(container.palette(), &Palette::Indirect(vec![5, 7, 0]));
+        assert_eq!(container.bits_per_entry(), 2);
+        // 32 two-bit entries per long: entry 0 is index 0, entry 1 is index 1, the rest index 2.
+        assert_eq!(container.data().len(), 2);
+        assert_eq!(container.data()[0] as u64 & 0xFF, 0b1010_0100);
+        assert_eq!(round_trip(&container).values(), values);
+    }
+
+    #[test]
+    fn test_entries_never_span_longs() {
+        // Five-bit entries fit twelve to a long, leaving the top four bits unused.
+        let values: Vec<i32> = (0..4096).map(|index| index % 17).collect();
+        let container = PalettedContainer::from_values(PaletteKind::BLOCK_STATES, &values);
+        assert_eq!(container.bits_per_entry(), 5);
+        assert_eq!(container.data().len(), 4096_usize.div_ceil(12));
+        assert!(container.data().iter().all(|long| *long as u64 >> 60 == 0));
+        assert_eq!(round_trip(&container).values(), values);
+    }
+
+    #[test]
+    fn test_set_grows_then_goes_direct() {
+        let mut container = PalettedContainer::single(PaletteKind::BLOCK_STATES, 0);
+        container.set(1, 1);
+        assert_eq!(container.bits_per_entry(), 4);
+        for id in 2..=300 {
+            container.set(id as usize, id);
+        }
+        assert_eq!(container.palette(), &Palette::Direct);
+        assert_eq!(container.bits_per_entry(), 15);
+        assert_eq!(container.get(300), 300);
+        assert_eq!(container.get(301), 0);
+        assert_eq!(round_trip(&container), container);
+
+        container.fill(3);
+        assert_eq!(container.palette(), &Palette::Single(3));
+    }
+
+    #[test]
+    fn test_bad_palette_index_is_rejected() {
+        let mut buf = BytesMut::from(&[1, 1, 4][..]);
+        buf.extend_from_slice(&[0xFF; 16]);
+        let version = ProtocolVersion::LATEST;
+        assert!(PalettedContainer::decode_with(version, &mut buf, PaletteKind::BIOMES).is_err());
+    }
+
+    #[test]
+    fn test_v1_21_4_prefixes_the_data_length() {
+        let version = ProtocolVersion::V1_21_4;
+        let single = PalettedContainer::single(PaletteKind::BIOMES, 9);
+        let mut buf = BytesMut::new();
+        single.encode_for(version, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), [0, 9, 0]);
+        let decoded = PalettedContainer::decode_with(version, &mut buf, PaletteKind::BIOMES);
+        assert_eq!(decoded.unwrap(), single);
+
+        let mut values = vec![0; 64];
+        values[1] = 7;
+        let indirect = PalettedContainer::from_values(PaletteKind::BIOMES, &values);
+        let mut buf = BytesMut::new();
+        indirect.encode_for(version, &mut buf).unwrap();
+        // Two palette entries at one bit each fit in a single long.
+        assert_eq!(buf[..5], [1, 2, 0, 7, 1]);
+        assert_eq!(buf.len(), 5 + 8);
+        let decoded = PalettedContainer::decode_with(version, &mut buf, PaletteKind::BIOMES);
+        assert_eq!(decoded.unwrap().values(), values);
+        assert!(buf.is_empty());
+
+        // A count that doesn't match the bits per entry is rejected.
+        let mut buf = BytesMut::from(&[1, 2, 0, 7, 2][..]);
+        buf.extend_from_slice(&[0; 16]);
+        assert!(PalettedContainer::decode_with(version, &mut buf, PaletteKind::BIOMES).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! `tokio_util` codec for packet frames.
+//!
+//! [`MinecraftCodec`] cuts frames out of the shared read buffer as soon as they are complete,
+//! without copying them, and refuses any length prefix above [`MAX_PACKET_LEN`] before buffering
+//! it. Use it with `Framed`, `FramedRead` or `FramedWrite`; encryption goes underneath, on the
+//! stream itself, and compression is switched on in the codec once `SetCompression` is sent.
+
+use crate::protocol::compression::Compression;
+use crate::protocol::encoding::{
+    DecodeError, DecodeResult, EncodeResult, MAX_PACKET_LEN, packet_len, read_varint_sync,
+    write_varint_sync,
+};
+use crate::protocol::types::VarInt;
+use bytes::{Buf, BytesMut};
+use std::io::{Error as IoError, ErrorKind};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// A length prefix of `MAX_PACKET_LEN` takes three VarInt bytes.
+const MAX_LEN_PREFIX: usize = 3;
+
+/// Frames packets as `(packet ID, data)` pairs, compressed once [`set_compression`] is called.
+///
+/// [`set_compression`]: MinecraftCodec::set_compression
+#[derive(Debug, Clone, Default)]
+pub struct MinecraftCodec {
+    compression: Option<Compression>,
+}
+
+impl MinecraftCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_compression(compression: Compression) -> Self {
+        MinecraftCodec {
+            compression: Some(compression),
+        }
+    }
+
+    pub fn compression(&self) -> Option<&Compression> {
+        self.compression.as_ref()
+    }
+
+    /// Switches framing for every frame after the current one.
+    pub fn set_compression(&mut self, compression: Option<Compression>) {
+        self.compression = compression;
+    }
+}
+
+/// Reads a frame's length prefix without consuming it, returning the prefix size and the frame
+/// length, or `None` if the prefix is still incomplete.
+fn peek_frame_len(src: &[u8]) -> DecodeResult<Option<(usize, usize)>> {
+    let mut value = 0u32;
+    for (i, byte) in src.iter().take(MAX_LEN_PREFIX).enumerate() {
+        value |= u32::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((i + 1, packet_len(VarInt(value as i32))?)));
+        }
+    }
+    if src.len() >= MAX_LEN_PREFIX {
+        return Err(DecodeError::VarIntTooLong);
+    }
+    Ok(None)
+}
+
+impl Decoder for MinecraftCodec {
+    type Item = (VarInt, BytesMut);
+    type Error = DecodeError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> DecodeResult<Option<Self::Item>> {
+        let Some((prefix_len, frame_len)) = peek_frame_len(src)? else {
+            return Ok(None);
+        };
+        if src.len() < prefix_len + frame_len {
+            src.reserve(prefix_len + frame_len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(prefix_len);
+        let mut frame = src.split_to(frame_len);
+        match &self.compression {
+            Some(compression) => compression.decode_frame(frame).map(Some),
+            None => {
+                let packet_id = read_varint_sync(&mut frame)?;
+                Ok(Some((packet_id, frame)))
+            }
+        }
+    }
+}
+
+impl Encoder<(VarInt, BytesMut)> for MinecraftCodec {
+    type Error = IoError;
+
+    fn encode(
+        &mut self,
+        (packet_id, data): (VarInt, BytesMut),
+        dst: &mut BytesMut,
+    ) -> EncodeResult<()> {
+        let contents = match &self.compression {
+            Some(compression) => compression.encode_frame_contents(packet_id, &data)?,
+            None => {
+                let mut contents = BytesMut::with_capacity(data.len() + 5);
+                write_varint_sync(&mut contents, packet_id)?;
+                contents.extend_from_slice(&data);
+                contents
+            }
+        };
+        if contents.len() > MAX_PACKET_LEN {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                DecodeError::PacketTooLarge(contents.len()).to_string(),
+            ));
+        }
+
+        dst.reserve(contents.len() + MAX_LEN_PREFIX);
+        write_varint_sync(dst, VarInt(contents.len() as i32))?;
+        dst.extend_from_slice(&contents);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::write_packet_frame;
+    use crate::protocol::encryption::{EncryptedReader, EncryptedWriter};
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    fn packets() -> Vec<(VarInt, BytesMut)> {
+        vec![
+            (VarInt(0x00), BytesMut::new()),
+            (VarInt(0x27), BytesMut::from(&b"short"[..])),
+            (VarInt(0x7F), BytesMut::from(&[0xAB; 4096][..])),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_decodes_frames_fed_byte_by_byte() {
+        let mut stream = Vec::new();
+        for (id, data) in packets() {
+            write_packet_frame(&mut stream, id, &data).await.unwrap();
+        }
+
+        let mut codec = MinecraftCodec::new();
+        let mut src = BytesMut::new();
+        let mut decoded = vec![];
+        for byte in stream {
+            src.extend_from_slice(&[byte]);
+            if let Some(packet) = codec.decode(&mut src).unwrap() {
+                decoded.push(packet);
+            }
+        }
+        assert_eq!(decoded, packets());
+        assert!(src.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encodes_like_the_frame_helpers() {
+        for (id, data) in packets() {
+            let mut expected = Vec::new();
+            write_packet_frame(&mut expected, id, &data).await.unwrap();
+            let mut dst = BytesMut::new();
+            MinecraftCodec::new()
+                .encode((id, data.clone()), &mut dst)
+                .unwrap();
+            assert_eq!(&dst[..], &expected[..]);
+
+            let compression = Compression::default();
+            let mut dst = BytesMut::new();
+            MinecraftCodec::with_compression(compression)
+                .encode((id, data.clone()), &mut dst)
+                .unwrap();
+            assert_eq!(dst, compression.encode_frame(id, &data).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_rejects_oversized_length_before_buffering() {
+        // Anything above the limit needs a fourth length byte, which is refused on sight.
+        let mut src = BytesMut::new();
+        write_varint_sync(&mut src, VarInt(MAX_PACKET_LEN as i32 + 1)).unwrap();
+        assert!(matches!(
+            MinecraftCodec::new().decode(&mut src),
+            Err(DecodeError::VarIntTooLong)
+        ));
+        assert!(src.capacity() < MAX_PACKET_LEN);
+    }
+
+    #[test]
+    fn test_rejects_overlong_packet_id() {
+        let mut src = BytesMut::from(&[6, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80][..]);
+        assert!(matches!(
+            MinecraftCodec::new().decode(&mut src),
+            Err(DecodeError::VarIntTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_encoder_refuses_oversized_packet() {
+        let data = BytesMut::zeroed(MAX_PACKET_LEN);
+        let mut dst = BytesMut::new();
+        let err = MinecraftCodec::new()
+            .encode((VarInt(0x01), data), &mut dst)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(dst.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_framed_with_compression_and_encryption() {
+        let secret = [0x24u8; 16];
+        let (client, server) = tokio::io::duplex(1024);
+        let compression = Compression {
+            threshold: 64,
+            ..Compression::default()
+        };
+        let mut sink = FramedWrite::new(
+            EncryptedWriter::new(client, &secret).unwrap(),
+            MinecraftCodec::with_compression(compression),
+        );
+        let mut stream = FramedRead::new(
+            EncryptedReader::new(server, &secret).unwrap(),
+            MinecraftCodec::with_compression(compression),
+        );
+
+        let writer = tokio::spawn(async move {
+            for packet in packets() {
+                sink.send(packet).await.unwrap();
+            }
+        });
+        for expected in packets() {
+            assert_eq!(stream.next().await.unwrap().unwrap(), expected);
+        }
+        writer.await.unwrap();
+    }
+}
@@ -0,0 +1,924 @@
+//! Brigadier command graphs, as sent in the Commands packet.
+//!
+//! The client parses and tab-completes commands against this graph. A [`CommandGraph`] keeps its
+//! nodes in one array with the root first, which is already the flattened form the packet
+//! carries: children and redirects are indices into it.
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_varint_sync, unsupported_in,
+    write_varint_sync,
+};
+use crate::protocol::play::clientbound::Commands;
+use crate::protocol::types::{Identifier, VarInt};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+
+const NODE_TYPE_MASK: u8 = 0x03;
+const NODE_ROOT: u8 = 0;
+const NODE_LITERAL: u8 = 1;
+const NODE_ARGUMENT: u8 = 2;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_REDIRECT: u8 = 0x08;
+const FLAG_SUGGESTIONS: u8 = 0x10;
+
+const NUMBER_HAS_MIN: u8 = 0x01;
+const NUMBER_HAS_MAX: u8 = 0x02;
+const ENTITY_SINGLE: u8 = 0x01;
+const ENTITY_PLAYERS_ONLY: u8 = 0x02;
+const SCORE_HOLDER_MULTIPLE: u8 = 0x01;
+
+/// Suggestion providers the client knows, for argument nodes.
+pub mod suggestions {
+    /// The client asks the server with Command Suggestions Request.
+    pub const ASK_SERVER: &str = "minecraft:ask_server";
+    pub const ALL_RECIPES: &str = "minecraft:all_recipes";
+    pub const AVAILABLE_SOUNDS: &str = "minecraft:available_sounds";
+    pub const SUMMONABLE_ENTITIES: &str = "minecraft:summonable_entities";
+}
+
+/// Names of the argument parsers of [`ProtocolVersion::LATEST`], indexed by their ID in the
+/// `minecraft:command_argument_type` registry.
+const PARSERS: &[&str] = &[
+    "brigadier:bool",
+    "brigadier:float",
+    "brigadier:double",
+    "brigadier:integer",
+    "brigadier:long",
+    "brigadier:string",
+    "minecraft:entity",
+    "minecraft:game_profile",
+    "minecraft:block_pos",
+    "minecraft:column_pos",
+    "minecraft:vec3",
+    "minecraft:vec2",
+    "minecraft:block_state",
+    "minecraft:block_predicate",
+    "minecraft:item_stack",
+    "minecraft:item_predicate",
+    "minecraft:color",
+    "minecraft:component",
+    "minecraft:style",
+    "minecraft:message",
+    "minecraft:nbt_compound_tag",
+    "minecraft:nbt_tag",
+    "minecraft:nbt_path",
+    "minecraft:objective",
+    "minecraft:objective_criteria",
+    "minecraft:operation",
+    "minecraft:particle",
+    "minecraft:angle",
+    "minecraft:rotation",
+    "minecraft:scoreboard_slot",
+    "minecraft:score_holder",
+    "minecraft:swizzle",
+    "minecraft:team",
+    "minecraft:item_slot",
+    "minecraft:item_slots",
+    "minecraft:resource_location",
+    "minecraft:function",
+    "minecraft:entity_anchor",
+    "minecraft:int_range",
+    "minecraft:float_range",
+    "minecraft:dimension",
+    "minecraft:gamemode",
+    "minecraft:time",
+    "minecraft:resource_or_tag",
+    "minecraft:resource_or_tag_key",
+    "minecraft:resource",
+    "minecraft:resource_key",
+    "minecraft:resource_selector",
+    "minecraft:template_mirror",
+    "minecraft:template_rotation",
+    "minecraft:heightmap",
+    "minecraft:loot_table",
+    "minecraft:loot_predicate",
+    "minecraft:loot_modifier",
+    "minecraft:uuid",
+];
+
+/// Names of the 1.21.4 argument parsers, which lack `minecraft:resource_selector`.
+const PARSERS_1_21_4: &[&str] = &[
+    "brigadier:bool",
+    "brigadier:float",
+    "brigadier:double",
+    "brigadier:integer",
+    "brigadier:long",
+    "brigadier:string",
+    "minecraft:entity",
+    "minecraft:game_profile",
+    "minecraft:block_pos",
+    "minecraft:column_pos",
+    "minecraft:vec3",
+    "minecraft:vec2",
+    "minecraft:block_state",
+    "minecraft:block_predicate",
+    "minecraft:item_stack",
+    "minecraft:item_predicate",
+    "minecraft:color",
+    "minecraft:component",
+    "minecraft:style",
+    "minecraft:message",
+    "minecraft:nbt_compound_tag",
+    "minecraft:nbt_tag",
+    "minecraft:nbt_path",
+    "minecraft:objective",
+    "minecraft:objective_criteria",
+    "minecraft:operation",
+    "minecraft:particle",
+    "minecraft:angle",
+    "minecraft:rotation",
+    "minecraft:scoreboard_slot",
+    "minecraft:score_holder",
+    "minecraft:swizzle",
+    "minecraft:team",
+    "minecraft:item_slot",
+    "minecraft:item_slots",
+    "minecraft:resource_location",
+    "minecraft:function",
+    "minecraft:entity_anchor",
+    "minecraft:int_range",
+    "minecraft:float_range",
+    "minecraft:dimension",
+    "minecraft:gamemode",
+    "minecraft:time",
+    "minecraft:resource_or_tag",
+    "minecraft:resource_or_tag_key",
+    "minecraft:resource",
+    "minecraft:resource_key",
+    "minecraft:template_mirror",
+    "minecraft:template_rotation",
+    "minecraft:heightmap",
+    "minecraft:loot_table",
+    "minecraft:loot_predicate",
+    "minecraft:loot_modifier",
+    "minecraft:uuid",
+];
+
+fn parsers(version: ProtocolVersion) -> &'static [&'static str] {
+    match version {
+        ProtocolVersion::V1_21_4 => PARSERS_1_21_4,
+        ProtocolVersion::V1_21_5 => PARSERS,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringKind {
+    /// One word of unquoted characters.
+    SingleWord = 0,
+    /// A word, or a quoted string with spaces.
+    QuotablePhrase = 1,
+    /// The rest of the input.
+    GreedyPhrase = 2,
+}
+
+/// How the client parses an argument node, with the parser's properties.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentParser {
+    Bool,
+    Float {
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Long {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    String(StringKind),
+    Entity {
+        single: bool,
+        players_only: bool,
+    },
+    GameProfile,
+    BlockPos,
+    ColumnPos,
+    Vec3,
+    Vec2,
+    BlockState,
+    BlockPredicate,
+    ItemStack,
+    ItemPredicate,
+    Color,
+    Component,
+    Style,
+    Message,
+    NbtCompoundTag,
+    NbtTag,
+    NbtPath,
+    Objective,
+    ObjectiveCriteria,
+    Operation,
+    Particle,
+    Angle,
+    Rotation,
+    ScoreboardSlot,
+    ScoreHolder {
+        allow_multiple: bool,
+    },
+    Swizzle,
+    Team,
+    ItemSlot,
+    ItemSlots,
+    ResourceLocation,
+    Function,
+    EntityAnchor,
+    IntRange,
+    FloatRange,
+    Dimension,
+    Gamemode,
+    /// A duration in ticks, with an optional unit suffix.
+    Time {
+        min: i32,
+    },
+    ResourceOrTag {
+        registry: Identifier,
+    },
+    ResourceOrTagKey {
+        registry: Identifier,
+    },
+    Resource {
+        registry: Identifier,
+    },
+    ResourceKey {
+        registry: Identifier,
+    },
+    ResourceSelector {
+        registry: Identifier,
+    },
+    TemplateMirror,
+    TemplateRotation,
+    Heightmap,
+    LootTable,
+    LootPredicate,
+    LootModifier,
+    Uuid,
+}
+
+impl ArgumentParser {
+    /// An unbounded `brigadier:integer`.
+    pub fn integer() -> Self {
+        ArgumentParser::Integer {
+            min: None,
+            max: None,
+        }
+    }
+
+    /// The parser's registry name, such as `brigadier:integer`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArgumentParser::Bool => "brigadier:bool",
+            ArgumentParser::Float { .. } => "brigadier:float",
+            ArgumentParser::Double { .. } => "brigadier:double",
+            ArgumentParser::Integer { .. } => "brigadier:integer",
+            ArgumentParser::Long { .. } => "brigadier:long",
+            ArgumentParser::String(_) => "brigadier:string",
+            ArgumentParser::Entity { .. } => "minecraft:entity",
+            ArgumentParser::GameProfile => "minecraft:game_profile",
+            ArgumentParser::BlockPos => "minecraft:block_pos",
+            ArgumentParser::ColumnPos => "minecraft:column_pos",
+            ArgumentParser::Vec3 => "minecraft:vec3",
+            ArgumentParser::Vec2 => "minecraft:vec2",
+            ArgumentParser::BlockState => "minecraft:block_state",
+            ArgumentParser::BlockPredicate => "minecraft:block_predicate",
+            ArgumentParser::ItemStack => "minecraft:item_stack",
+            ArgumentParser::ItemPredicate => "minecraft:item_predicate",
+            ArgumentParser::Color => "minecraft:color",
+            ArgumentParser::Component => "minecraft:component",
+            ArgumentParser::Style => "minecraft:style",
+            ArgumentParser::Message => "minecraft:message",
+            ArgumentParser::NbtCompoundTag => "minecraft:nbt_compound_tag",
+            ArgumentParser::NbtTag => "minecraft:nbt_tag",
+            ArgumentParser::NbtPath => "minecraft:nbt_path",
+            ArgumentParser::Objective => "minecraft:objective",
+            ArgumentParser::ObjectiveCriteria => "minecraft:objective_criteria",
+            ArgumentParser::Operation => "minecraft:operation",
+            ArgumentParser::Particle => "minecraft:particle",
+            ArgumentParser::Angle => "minecraft:angle",
+            ArgumentParser::Rotation => "minecraft:rotation",
+            ArgumentParser::ScoreboardSlot => "minecraft:scoreboard_slot",
+            ArgumentParser::ScoreHolder { .. } => "minecraft:score_holder",
+            ArgumentParser::Swizzle => "minecraft:swizzle",
+            ArgumentParser::Team => "minecraft:team",
+            ArgumentParser::ItemSlot => "minecraft:item_slot",
+            ArgumentParser::ItemSlots => "minecraft:item_slots",
+            ArgumentParser::ResourceLocation => "minecraft:resource_location",
+            ArgumentParser::Function => "minecraft:function",
+            ArgumentParser::EntityAnchor => "minecraft:entity_anchor",
+            ArgumentParser::IntRange => "minecraft:int_range",
+            ArgumentParser::FloatRange => "minecraft:float_range",
+            ArgumentParser::Dimension => "minecraft:dimension",
+            ArgumentParser::Gamemode => "minecraft:gamemode",
+            ArgumentParser::Time { .. } => "minecraft:time",
+            ArgumentParser::ResourceOrTag { .. } => "minecraft:resource_or_tag",
+            ArgumentParser::ResourceOrTagKey { .. } => "minecraft:resource_or_tag_key",
+            ArgumentParser::Resource { .. } => "minecraft:resource",
+            ArgumentParser::ResourceKey { .. } => "minecraft:resource_key",
+            ArgumentParser::ResourceSelector { .. } => "minecraft:resource_selector",
+            ArgumentParser::TemplateMirror => "minecraft:template_mirror",
+            ArgumentParser::TemplateRotation => "minecraft:template_rotation",
+            ArgumentParser::Heightmap => "minecraft:heightmap",
+            ArgumentParser::LootTable => "minecraft:loot_table",
+            ArgumentParser::LootPredicate => "minecraft:loot_predicate",
+            ArgumentParser::LootModifier => "minecraft:loot_modifier",
+            ArgumentParser::Uuid => "minecraft:uuid",
+        }
+    }
+
+    /// The parser's ID in `version`, or `None` if `version` doesn't have it.
+    pub fn id(&self, version: ProtocolVersion) -> Option<i32> {
+        let name = self.name();
+        parsers(version)
+            .iter()
+            .position(|known| *known == name)
+            .map(|id| id as i32)
+    }
+
+    fn encode_properties(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        fn bounds<T: Encode>(
+            buf: &mut BytesMut,
+            min: &Option<T>,
+            max: &Option<T>,
+        ) -> EncodeResult<()> {
+            let flags = if min.is_some() { NUMBER_HAS_MIN } else { 0 }
+                | if max.is_some() { NUMBER_HAS_MAX } else { 0 };
+            flags.encode(buf)?;
+            min.iter()
+                .chain(max)
+                .try_for_each(|bound| bound.encode(buf))
+        }
+        match self {
+            ArgumentParser::Float { min, max } => bounds(buf, min, max),
+            ArgumentParser::Double { min, max } => bounds(buf, min, max),
+            ArgumentParser::Integer { min, max } => bounds(buf, min, max),
+            ArgumentParser::Long { min, max } => bounds(buf, min, max),
+            ArgumentParser::String(kind) => write_varint_sync(buf, VarInt(*kind as i32)),
+            ArgumentParser::Entity {
+                single,
+                players_only,
+            } => {
+                let flags = if *single { ENTITY_SINGLE } else { 0 }
+                    | if *players_only {
+                        ENTITY_PLAYERS_ONLY
+                    } else {
+                        0
+                    };
+                flags.encode(buf)
+            }
+            ArgumentParser::ScoreHolder { allow_multiple } => if *allow_multiple {
+                SCORE_HOLDER_MULTIPLE
+            } else {
+                0
+            }
+            .encode(buf),
+            ArgumentParser::Time { min } => min.encode(buf),
+            ArgumentParser::ResourceOrTag { registry }
+            | ArgumentParser::ResourceOrTagKey { registry }
+            | ArgumentParser::Resource { registry }
+            | ArgumentParser::ResourceKey { registry }
+            | ArgumentParser::ResourceSelector { registry } => registry.encode(buf),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Encode for ArgumentParser {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        let id = self
+            .id(version)
+            .ok_or_else(|| unsupported_in(&format!("parser {}", self.name()), version))?;
+        write_varint_sync(buf, VarInt(id))?;
+        self.encode_properties(buf)
+    }
+}
+
+impl Decode for ArgumentParser {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        fn bounds<T: Decode>(buf: &mut BytesMut) -> DecodeResult<(Option<T>, Option<T>)> {
+            let flags = u8::decode(buf)?;
+            let min = if flags & NUMBER_HAS_MIN != 0 {
+                Some(T::decode(buf)?)
+            } else {
+                None
+            };
+            let max = if flags & NUMBER_HAS_MAX != 0 {
+                Some(T::decode(buf)?)
+            } else {
+                None
+            };
+            Ok((min, max))
+        }
+        let id = read_varint_sync(buf)?.0;
+        let name = usize::try_from(id)
+            .ok()
+            .and_then(|id| parsers(version).get(id))
+            .ok_or(DecodeError::InvalidEnumValue("ArgumentParser", id as i64))?;
+        Ok(match *name {
+            "brigadier:bool" => ArgumentParser::Bool,
+            "brigadier:float" => {
+                let (min, max) = bounds(buf)?;
+                ArgumentParser::Float { min, max }
+            }
+            "brigadier:double" => {
+                let (min, max) = bounds(buf)?;
+                ArgumentParser::Double { min, max }
+            }
+            "brigadier:integer" => {
+                let (min, max) = bounds(buf)?;
+                ArgumentParser::Integer { min, max }
+            }
+            "brigadier:long" => {
+                let (min, max) = bounds(buf)?;
+                ArgumentParser::Long { min, max }
+            }
+            "brigadier:string" => ArgumentParser::String(match read_varint_sync(buf)?.0 {
+                0 => StringKind::SingleWord,
+                1 => StringKind::QuotablePhrase,
+                2 => StringKind::GreedyPhrase,
+                other => return Err(DecodeError::InvalidEnumValue("StringKind", other as i64)),
+            }),
+            "minecraft:entity" => {
+                let flags = u8::decode(buf)?;
+                ArgumentParser::Entity {
+                    single: flags & ENTITY_SINGLE != 0,
+                    players_only: flags & ENTITY_PLAYERS_ONLY != 0,
+                }
+            }
+            "minecraft:game_profile" => ArgumentParser::GameProfile,
+            "minecraft:block_pos" => ArgumentParser::BlockPos,
+            "minecraft:column_pos" => ArgumentParser::ColumnPos,
+            "minecraft:vec3" => ArgumentParser::Vec3,
+            "minecraft:vec2" => ArgumentParser::Vec2,
+            "minecraft:block_state" => ArgumentParser::BlockState,
+            "minecraft:block_predicate" => ArgumentParser::BlockPredicate,
+            "minecraft:item_stack" => ArgumentParser::ItemStack,
+            "minecraft:item_predicate" => ArgumentParser::ItemPredicate,
+            "minecraft:color" => ArgumentParser::Color,
+            "minecraft:component" => ArgumentParser::Component,
+            "minecraft:style" => ArgumentParser::Style,
+            "minecraft:message" => ArgumentParser::Message,
+            "minecraft:nbt_compound_tag" => ArgumentParser::NbtCompoundTag,
+            "minecraft:nbt_tag" => ArgumentParser::NbtTag,
+            "minecraft:nbt_path" => ArgumentParser::NbtPath,
+            "minecraft:objective" => ArgumentParser::Objective,
+            "minecraft:objective_criteria" => ArgumentParser::ObjectiveCriteria,
+            "minecraft:operation" => ArgumentParser::Operation,
+            "minecraft:particle" => ArgumentParser::Particle,
+            "minecraft:angle" => ArgumentParser::Angle,
+            "minecraft:rotation" => ArgumentParser::Rotation,
+            "minecraft:scoreboard_slot" => ArgumentParser::ScoreboardSlot,
+            "minecraft:score_holder" => ArgumentParser::ScoreHolder {
+                allow_multiple: u8::decode(buf)? & SCORE_HOLDER_MULTIPLE != 0,
+            },
+            "minecraft:swizzle" => ArgumentParser::Swizzle,
+            "minecraft:team" => ArgumentParser::Team,
+            "minecraft:item_slot" => ArgumentParser::ItemSlot,
+            "minecraft:item_slots" => ArgumentParser::ItemSlots,
+            "minecraft:resource_location" => ArgumentParser::ResourceLocation,
+            "minecraft:function" => ArgumentParser::Function,
+            "minecraft:entity_anchor" => ArgumentParser::EntityAnchor,
+            "minecraft:int_range" => ArgumentParser::IntRange,
+            "minecraft:float_range" => ArgumentParser::FloatRange,
+            "minecraft:dimension" => ArgumentParser::Dimension,
+            "minecraft:gamemode" => ArgumentParser::Gamemode,
+            "minecraft:time" => ArgumentParser::Time {
+                min: i32::decode(buf)?,
+            },
+            "minecraft:resource_or_tag" => ArgumentParser::ResourceOrTag {
+                registry: Identifier::decode(buf)?,
+            },
+            "minecraft:resource_or_tag_key" => ArgumentParser::ResourceOrTagKey {
+                registry: Identifier::decode(buf)?,
+            },
+            "minecraft:resource" => ArgumentParser::Resource {
+                registry: Identifier::decode(buf)?,
+            },
+            "minecraft:resource_key" => ArgumentParser::ResourceKey {
+                registry: Identifier::decode(buf)?,
+            },
+            "minecraft:resource_selector" => ArgumentParser::ResourceSelector {
+                registry: Identifier::decode(buf)?,
+            },
+            "minecraft:template_mirror" => ArgumentParser::TemplateMirror,
+            "minecraft:template_rotation" => ArgumentParser::TemplateRotation,
+            "minecraft:heightmap" => ArgumentParser::Heightmap,
+            "minecraft:loot_table" => ArgumentParser::LootTable,
+            "minecraft:loot_predicate" => ArgumentParser::LootPredicate,
+            "minecraft:loot_modifier" => ArgumentParser::LootModifier,
+            "minecraft:uuid" => ArgumentParser::Uuid,
+            _ => unreachable!("every parser name has a variant"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Root,
+    /// Matches its name exactly.
+    Literal {
+        name: String,
+    },
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+        /// Where the client gets suggestions from, if not the parser; see [`suggestions`].
+        suggestions: Option<Identifier>,
+    },
+}
+
+/// A node of the flattened graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandNode {
+    pub kind: NodeKind,
+    /// The input up to and including this node is a complete command.
+    pub executable: bool,
+    /// Indices of the child nodes.
+    pub children: Vec<i32>,
+    /// Index of the node parsing continues at after this one, as `/execute run` does.
+    pub redirect: Option<i32>,
+}
+
+impl CommandNode {
+    fn new(kind: NodeKind) -> Self {
+        CommandNode {
+            kind,
+            executable: false,
+            children: vec![],
+            redirect: None,
+        }
+    }
+
+    /// The node's name; empty for the root.
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            NodeKind::Root => "",
+            NodeKind::Literal { name } | NodeKind::Argument { name, .. } => name,
+        }
+    }
+}
+
+impl Encode for CommandNode {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        let mut flags = match &self.kind {
+            NodeKind::Root => NODE_ROOT,
+            NodeKind::Literal { .. } => NODE_LITERAL,
+            NodeKind::Argument { suggestions, .. } => {
+                NODE_ARGUMENT
+                    | if suggestions.is_some() {
+                        FLAG_SUGGESTIONS
+                    } else {
+                        0
+                    }
+            }
+        };
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.redirect.is_some() {
+            flags |= FLAG_REDIRECT;
+        }
+        flags.encode(buf)?;
+        write_varint_sync(buf, VarInt(self.children.len() as i32))?;
+        for child in &self.children {
+            write_varint_sync(buf, VarInt(*child))?;
+        }
+        if let Some(redirect) = self.redirect {
+            write_varint_sync(buf, VarInt(redirect))?;
+        }
+        match &self.kind {
+            NodeKind::Root => Ok(()),
+            NodeKind::Literal { name } => name.encode(buf),
+            NodeKind::Argument {
+                name,
+                parser,
+                suggestions,
+            } => {
+                name.encode(buf)?;
+                parser.encode_for(version, buf)?;
+                suggestions.iter().try_for_each(|id| id.encode(buf))
+            }
+        }
+    }
+}
+
+impl Decode for CommandNode {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let flags = u8::decode(buf)?;
+        let children = Vec::<VarInt>::decode(buf)?
+            .into_iter()
+            .map(|child| child.0)
+            .collect();
+        let redirect = if flags & FLAG_REDIRECT != 0 {
+            Some(read_varint_sync(buf)?.0)
+        } else {
+            None
+        };
+        let kind = match flags & NODE_TYPE_MASK {
+            NODE_ROOT => NodeKind::Root,
+            NODE_LITERAL => NodeKind::Literal {
+                name: String::decode(buf)?,
+            },
+            NODE_ARGUMENT => NodeKind::Argument {
+                name: String::decode(buf)?,
+                parser: ArgumentParser::decode_for(version, buf)?,
+                suggestions: if flags & FLAG_SUGGESTIONS != 0 {
+                    Some(Identifier::decode(buf)?)
+                } else {
+                    None
+                },
+            },
+            other => {
+                return Err(DecodeError::InvalidEnumValue(
+                    "command node type",
+                    other as i64,
+                ));
+            }
+        };
+        Ok(CommandNode {
+            kind,
+            executable: flags & FLAG_EXECUTABLE != 0,
+            children,
+            redirect,
+        })
+    }
+}
+
+/// A command graph under construction. Nodes are referred to by index, and the root is
+/// [`CommandGraph::ROOT`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandGraph {
+    nodes: Vec<CommandNode>,
+}
+
+impl Default for CommandGraph {
+    fn default() -> Self {
+        CommandGraph {
+            nodes: vec![CommandNode::new(NodeKind::Root)],
+        }
+    }
+}
+
+impl CommandGraph {
+    pub const ROOT: usize = 0;
+
+    pub fn new() -> Self {
+        CommandGraph::default()
+    }
+
+    pub fn nodes(&self) -> &[CommandNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &CommandNode {
+        &self.nodes[index]
+    }
+
+    /// The child of `parent` named `name`, if any.
+    pub fn child(&self, parent: usize, name: &str) -> Option<usize> {
+        self.nodes[parent]
+            .children
+            .iter()
+            .map(|child| *child as usize)
+            .find(|child| self.nodes[*child].name() == name)
+    }
+
+    fn add_child(&mut self, parent: usize, node: CommandNode) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        self.nodes[parent].children.push(index as i32);
+        index
+    }
+
+    /// The literal child `name` of `parent`, added if missing.
+    pub fn literal(&mut self, parent: usize, name: &str) -> usize {
+        match self.child(parent, name) {
+            Some(child) if matches!(self.nodes[child].kind, NodeKind::Literal { .. }) => child,
+            _ => self.add_child(
+                parent,
+                CommandNode::new(NodeKind::Literal {
+                    name: name.to_string(),
+                }),
+            ),
+        }
+    }
+
+    /// The argument child `name` of `parent`, added with `parser` if missing. An existing
+    /// argument of that name keeps its parser, as in Brigadier.
+    pub fn argument(&mut self, parent: usize, name: &str, parser: ArgumentParser) -> usize {
+        match self.child(parent, name) {
+            Some(child) if matches!(self.nodes[child].kind, NodeKind::Argument { .. }) => child,
+            _ => self.add_child(
+                parent,
+                CommandNode::new(NodeKind::Argument {
+                    name: name.to_string(),
+                    parser,
+                    suggestions: None,
+                }),
+            ),
+        }
+    }
+
+    pub fn set_executable(&mut self, node: usize, executable: bool) -> &mut Self {
+        self.nodes[node].executable = executable;
+        self
+    }
+
+    /// Continues parsing at `target` after `node`.
+    pub fn redirect(&mut self, node: usize, target: usize) -> &mut Self {
+        self.nodes[node].redirect = Some(target as i32);
+        self
+    }
+
+    /// Sets where the client gets suggestions for an argument node. Ignored for other nodes,
+    /// which can't carry one.
+    pub fn set_suggestions(&mut self, node: usize, provider: &str) -> &mut Self {
+        if let NodeKind::Argument { suggestions, .. } = &mut self.nodes[node].kind {
+            *suggestions = Some(provider.to_string());
+        }
+        self
+    }
+}
+
+impl From<CommandGraph> for Commands {
+    fn from(graph: CommandGraph) -> Self {
+        Commands {
+            nodes: graph.nodes,
+            root_index: VarInt(CommandGraph::ROOT as i32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+
+    #[test]
+    fn test_parser_names_match_ids() {
+        assert_eq!(PARSERS.len(), 55);
+        assert_eq!(PARSERS_1_21_4.len(), 54);
+        assert_eq!(ArgumentParser::integer().name(), "brigadier:integer");
+        assert_eq!(ArgumentParser::BlockPos.name(), "minecraft:block_pos");
+        assert_eq!(ArgumentParser::Uuid.name(), "minecraft:uuid");
+        assert_eq!(
+            ArgumentParser::ResourceKey {
+                registry: String::new()
+            }
+            .name(),
+            "minecraft:resource_key"
+        );
+    }
+
+    #[test]
+    fn test_parser_ids_per_version() {
+        let (old, latest) = (ProtocolVersion::V1_21_4, ProtocolVersion::LATEST);
+        let selector = ArgumentParser::ResourceSelector {
+            registry: "minecraft:test_instance".into(),
+        };
+        for (parser, old_id, latest_id) in [
+            (ArgumentParser::Color, Some(16), Some(16)),
+            (ArgumentParser::Message, Some(19), Some(19)),
+            (ArgumentParser::ResourceLocation, Some(35), Some(35)),
+            (ArgumentParser::Gamemode, Some(41), Some(41)),
+            (selector.clone(), None, Some(47)),
+            (ArgumentParser::TemplateMirror, Some(47), Some(48)),
+            (ArgumentParser::Uuid, Some(53), Some(54)),
+        ] {
+            assert_eq!(parser.id(old), old_id, "{}", parser.name());
+            assert_eq!(parser.id(latest), latest_id, "{}", parser.name());
+        }
+
+        let mut graph = CommandGraph::new();
+        let root = CommandGraph::ROOT;
+        graph.argument(root, "id", ArgumentParser::Uuid);
+        let commands = Commands::from(graph.clone());
+        let mut buf = BytesMut::new();
+        commands.encode_for(old, &mut buf).unwrap();
+        assert_eq!(buf[buf.len() - 2..], [53, 0]);
+        let decoded = Commands::decode_for(old, &mut buf).unwrap();
+        assert_eq!(decoded.nodes, commands.nodes);
+
+        graph.argument(root, "test", selector);
+        let commands = Commands::from(graph);
+        assert!(commands.encode_for(old, &mut BytesMut::new()).is_err());
+        assert_round_trip(&commands);
+    }
+
+    #[test]
+    fn test_parser_properties() {
+        let parser = ArgumentParser::Integer {
+            min: Some(0),
+            max: None,
+        };
+        assert_eq!(parser.to_bytes().unwrap().as_ref(), [3, 0x01, 0, 0, 0, 0]);
+        let entity = ArgumentParser::Entity {
+            single: true,
+            players_only: true,
+        };
+        assert_eq!(entity.to_bytes().unwrap().as_ref(), [6, 0x03]);
+        for parser in [
+            parser,
+            entity,
+            ArgumentParser::Double {
+                min: Some(-1.0),
+                max: Some(1.0),
+            },
+            ArgumentParser::String(StringKind::GreedyPhrase),
+            ArgumentParser::ScoreHolder {
+                allow_multiple: true,
+            },
+            ArgumentParser::Time { min: 1 },
+            ArgumentParser::Resource {
+                registry: "minecraft:worldgen/biome".into(),
+            },
+            ArgumentParser::Vec3,
+        ] {
+            assert_round_trip(&parser);
+        }
+    }
+
+    #[test]
+    fn test_graph_flattens_in_creation_order() {
+        let mut graph = CommandGraph::new();
+        let tp = graph.literal(CommandGraph::ROOT, "tp");
+        let target = graph.argument(
+            tp,
+            "target",
+            ArgumentParser::Entity {
+                single: true,
+                players_only: false,
+            },
+        );
+        graph.set_executable(target, true);
+        let alias = graph.literal(CommandGraph::ROOT, "teleport");
+        graph.redirect(alias, tp);
+        assert_eq!(graph.literal(CommandGraph::ROOT, "tp"), tp);
+
+        let commands = Commands::from(graph);
+        assert_eq!(commands.root_index, VarInt(0));
+        assert_eq!(commands.nodes[0].children, [1, 3]);
+        assert_eq!(
+            commands.nodes[1].to_bytes().unwrap().as_ref(),
+            [0x01, 1, 2, 2, b't', b'p']
+        );
+        assert_eq!(
+            commands.nodes[2].to_bytes().unwrap().as_ref(),
+            [0x06, 0, 6, b't', b'a', b'r', b'g', b'e', b't', 6, 0x01]
+        );
+        assert_eq!(&commands.nodes[3].to_bytes().unwrap()[..3], [0x09, 0, 1]);
+        for node in &commands.nodes {
+            assert_round_trip(node);
+        }
+    }
+
+    #[test]
+    fn test_suggestions_only_on_arguments() {
+        let mut graph = CommandGraph::new();
+        let give = graph.literal(CommandGraph::ROOT, "give");
+        let item = graph.argument(give, "item", ArgumentParser::ItemStack);
+        graph
+            .set_suggestions(item, suggestions::ASK_SERVER)
+            .set_suggestions(give, suggestions::ASK_SERVER);
+        assert_eq!(
+            graph.node(give).kind,
+            NodeKind::Literal {
+                name: "give".into()
+            }
+        );
+        let bytes = graph.node(item).to_bytes().unwrap();
+        assert_eq!(bytes[0], 0x12);
+        assert!(bytes.ends_with(b"minecraft:ask_server"));
+        assert_round_trip(graph.node(item));
+    }
+
+    #[test]
+    fn test_invalid_node_type_is_rejected() {
+        let mut buf = BytesMut::from(&[0x03, 0][..]);
+        assert!(matches!(
+            CommandNode::decode(&mut buf),
+            Err(DecodeError::InvalidEnumValue(_, 3))
+        ));
+    }
+}
@@ -0,0 +1,220 @@
+//! Compressed packet framing, enabled by the login `SetCompression` packet.
+//!
+//! Once compression is on, every frame is `length | data length | body`. A data length of zero
+//! means the body (packet ID and data) is sent as is; otherwise it is the size of the body before
+//! zlib compression. Bodies are only compressed once they reach the threshold.
+
+use crate::protocol::encoding::{
+    DecodeError, DecodeResult, EncodeResult, packet_len, read_packet_frame, read_varint,
+    read_varint_sync, write_packet_frame, write_varint_sync,
+};
+use crate::protocol::types::VarInt;
+use bytes::BytesMut;
+use flate2::Compression as Level;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Read, Write};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest uncompressed body a peer may announce, matching the vanilla limit.
+pub const MAX_UNCOMPRESSED_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// Bodies of at least this many bytes are compressed.
+    pub threshold: usize,
+    /// zlib level, from 0 (store) to 9 (best).
+    pub level: u32,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            threshold: 256,
+            level: 6,
+        }
+    }
+}
+
+impl Compression {
+    /// Builds a complete compressed frame for a packet, including the outer length prefix.
+    pub fn encode_frame(&self, packet_id: VarInt, data: &[u8]) -> EncodeResult<BytesMut> {
+        let inner = self.encode_frame_contents(packet_id, data)?;
+        let mut frame = BytesMut::with_capacity(inner.len() + 5);
+        write_varint_sync(&mut frame, VarInt(inner.len() as i32))?;
+        frame.extend_from_slice(&inner);
+        Ok(frame)
+    }
+
+    /// Builds a frame's contents (`data length | body`), without the outer length prefix.
+    pub fn encode_frame_contents(&self, packet_id: VarInt, data: &[u8]) -> EncodeResult<BytesMut> {
+        let mut body = BytesMut::with_capacity(data.len() + 5);
+        write_varint_sync(&mut body, packet_id)?;
+        body.extend_from_slice(data);
+
+        let mut inner = BytesMut::new();
+        if body.len() < self.threshold {
+            write_varint_sync(&mut inner, VarInt(0))?;
+            inner.extend_from_slice(&body);
+        } else {
+            write_varint_sync(&mut inner, VarInt(body.len() as i32))?;
+            let mut encoder = ZlibEncoder::new(Vec::new(), Level::new(self.level));
+            encoder.write_all(&body)?;
+            inner.extend_from_slice(&encoder.finish()?);
+        }
+        Ok(inner)
+    }
+
+    /// Reads the packet ID and data out of a frame's contents (everything after the outer length).
+    pub fn decode_frame(&self, mut frame: BytesMut) -> DecodeResult<(VarInt, BytesMut)> {
+        let data_len = read_varint_sync(&mut frame)?;
+        let mut body = if data_len.0 == 0 {
+            frame
+        } else {
+            let data_len = usize::try_from(data_len.0)
+                .map_err(|_| DecodeError::BadCompression("negative data length"))?;
+            if data_len < self.threshold {
+                return Err(DecodeError::BadCompression(
+                    "compressed body is below the threshold",
+                ));
+            }
+            if data_len > MAX_UNCOMPRESSED_SIZE {
+                return Err(DecodeError::BadCompression(
+                    "uncompressed body is too large",
+                ));
+            }
+            let mut decompressed = Vec::with_capacity(data_len);
+            ZlibDecoder::new(&frame[..])
+                .take(data_len as u64 + 1)
+                .read_to_end(&mut decompressed)?;
+            if decompressed.len() != data_len {
+                return Err(DecodeError::BadCompression(
+                    "body does not match its data length",
+                ));
+            }
+            BytesMut::from(&decompressed[..])
+        };
+        let packet_id = read_varint_sync(&mut body)?;
+        Ok((packet_id, body))
+    }
+}
+
+/// Writes a packet frame, compressed if `compression` is set.
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    compression: Option<&Compression>,
+    packet_id: VarInt,
+    data: &[u8],
+) -> EncodeResult<()> {
+    match compression {
+        Some(compression) => {
+            let frame = compression.encode_frame(packet_id, data)?;
+            writer.write_all(&frame).await
+        }
+        None => write_packet_frame(writer, packet_id, data).await,
+    }
+}
+
+/// Reads a packet frame, decompressing it if `compression` is set.
+pub async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    compression: Option<&Compression>,
+) -> DecodeResult<(VarInt, BytesMut)> {
+    match compression {
+        Some(compression) => {
+            let frame_len = packet_len(read_varint(reader).await?)?;
+            let mut frame = BytesMut::zeroed(frame_len);
+            reader.read_exact(&mut frame).await?;
+            compression.decode_frame(frame)
+        }
+        None => read_packet_frame(reader).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_contents(mut frame: BytesMut) -> BytesMut {
+        let len = read_varint_sync(&mut frame).unwrap();
+        assert_eq!(len.0 as usize, frame.len());
+        frame
+    }
+
+    #[test]
+    fn test_small_packets_are_not_compressed() {
+        let compression = Compression::default();
+        let frame = compression.encode_frame(VarInt(0x01), &[1, 2, 3]).unwrap();
+        assert_eq!(&frame[..], &[0x05, 0x00, 0x01, 1, 2, 3]);
+        let (id, data) = compression.decode_frame(frame_contents(frame)).unwrap();
+        assert_eq!(id, VarInt(0x01));
+        assert_eq!(&data[..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_large_packets_round_trip_compressed() {
+        let compression = Compression {
+            threshold: 64,
+            level: 9,
+        };
+        let data = vec![7u8; 4096];
+        let frame = compression.encode_frame(VarInt(0x27), &data).unwrap();
+        assert!(frame.len() < 200);
+        let mut contents = frame_contents(frame);
+        let (id, decoded) = compression.decode_frame(contents.clone()).unwrap();
+        assert_eq!(id, VarInt(0x27));
+        assert_eq!(&decoded[..], &data[..]);
+        assert_eq!(read_varint_sync(&mut contents).unwrap(), VarInt(4097));
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_data_length() {
+        let compression = Compression {
+            threshold: 16,
+            level: 6,
+        };
+        let frame = compression.encode_frame(VarInt(0x00), &[0u8; 32]).unwrap();
+        let mut contents = frame_contents(frame);
+        read_varint_sync(&mut contents).unwrap();
+        let mut tampered = BytesMut::new();
+        write_varint_sync(&mut tampered, VarInt(40)).unwrap();
+        tampered.extend_from_slice(&contents);
+        assert!(matches!(
+            compression.decode_frame(tampered),
+            Err(DecodeError::BadCompression(_))
+        ));
+
+        let mut below_threshold = BytesMut::new();
+        write_varint_sync(&mut below_threshold, VarInt(4)).unwrap();
+        assert!(matches!(
+            compression.decode_frame(below_threshold),
+            Err(DecodeError::BadCompression(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_frames_round_trip() {
+        let compression = Compression {
+            threshold: 8,
+            level: 1,
+        };
+        let mut stream = Vec::new();
+        write_frame(
+            &mut stream,
+            Some(&compression),
+            VarInt(0x02),
+            b"hello world",
+        )
+        .await
+        .unwrap();
+        write_frame(&mut stream, Some(&compression), VarInt(0x03), b"hi")
+            .await
+            .unwrap();
+        let mut reader = &stream[..];
+        let (id, data) = read_frame(&mut reader, Some(&compression)).await.unwrap();
+        assert_eq!((id, &data[..]), (VarInt(0x02), &b"hello world"[..]));
+        let (id, data) = read_frame(&mut reader, Some(&compression)).await.unwrap();
+        assert_eq!((id, &data[..]), (VarInt(0x03), &b"hi"[..]));
+        assert!(reader.is_empty());
+    }
+}
@@ -1,94 +1,113 @@
+use crate::protocol::encoding::Packet;
 use crate::protocol::types::{
-    CustomReportDetail, Identifier, JsonTextComponent, KnownPack, RegistryEntry, RegistryTagData,
+    CustomReportDetail, Identifier, TextComponent, KnownPack, RegistryEntry, RegistryTagData,
     ServerLink, VarInt,
 };
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x00)]
 pub struct CookieRequestConfiguration {
     pub key: Identifier,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x01)]
 pub struct ClientboundPluginMessageConfiguration {
     pub channel: Identifier,
+    #[packet(rest)]
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x02)]
 pub struct DisconnectConfiguration {
-    pub reason: JsonTextComponent,
+    pub reason: TextComponent,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x03)]
 pub struct FinishConfiguration;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x04)]
 pub struct ClientboundKeepAliveConfiguration {
     pub keep_alive_id: i64,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x05)]
 pub struct PingConfiguration {
     pub id: i32,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x06)]
 pub struct ResetChat;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x07)]
 pub struct RegistryData {
     pub registry_id: Identifier,
     pub entries: Vec<RegistryEntry>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x08)]
 pub struct RemoveResourcePackConfiguration {
     pub uuid: Option<Uuid>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x09)]
 pub struct AddResourcePackConfiguration {
     pub uuid: Uuid,
     pub url: String,
     pub hash: String,
     pub forced: bool,
-    pub prompt_message: Option<JsonTextComponent>,
+    pub prompt_message: Option<TextComponent>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x0A)]
 pub struct StoreCookieConfiguration {
     pub key: Identifier,
     pub payload: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x0B)]
 pub struct TransferConfiguration {
     pub host: String,
     pub port: VarInt,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x0C)]
 pub struct FeatureFlags {
     pub feature_flags: Vec<Identifier>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x0D)]
 pub struct UpdateTagsConfiguration {
     pub tags: Vec<RegistryTagData>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x0E)]
 pub struct ClientboundKnownPacks {
     pub known_packs: Vec<KnownPack>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x0F)]
 pub struct CustomReportDetailsConfiguration {
     pub details: Vec<CustomReportDetail>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x10)]
 pub struct ServerLinksConfiguration {
     pub links: Vec<ServerLink>,
 }
@@ -96,8 +115,9 @@ pub struct ServerLinksConfiguration {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::encoding::assert_round_trip;
     use crate::protocol::types::{
-        BuiltInServerLinkLabel, CustomReportDetail, KnownPack, RegistryEntry, RegistryTagData,
+        BuiltInServerLinkLabel, CustomReportDetail, KnownPack, Nbt, RegistryEntry, RegistryTagData,
         ServerLink, ServerLinkLabel, Tag,
     };
     use uuid::Uuid;
@@ -123,15 +143,15 @@ mod tests {
     #[test]
     fn test_disconnect_config_instantiation() {
         let packet = DisconnectConfiguration {
-            reason: r#"{"text":"Config disconnect"}"#.into(),
+            reason: TextComponent::text("Config disconnect"),
         };
-        assert!(!packet.reason.is_empty());
+        assert_eq!(packet.reason, TextComponent::text("Config disconnect"));
     }
 
     #[test]
     fn test_finish_config_instantiation() {
-        let _packet = FinishConfiguration;
-        assert!(true);
+        let packet = FinishConfiguration;
+        assert_round_trip(&packet);
     }
 
     #[test]
@@ -150,8 +170,8 @@ mod tests {
 
     #[test]
     fn test_reset_chat_instantiation() {
-        let _packet = ResetChat;
-        assert!(true);
+        let packet = ResetChat;
+        assert_round_trip(&packet);
     }
 
     #[test]
@@ -160,7 +180,7 @@ mod tests {
             registry_id: "minecraft:dimension_type".into(),
             entries: vec![RegistryEntry {
                 entry_id: "minecraft:overworld".into(),
-                data: Some(vec![0x0a, 0x00]), /* NBT placeholder */
+                data: Some(Nbt::Compound(Default::default())),
             }],
         };
         assert_eq!(packet.registry_id, "minecraft:dimension_type");
@@ -184,7 +204,7 @@ mod tests {
             url: "http://example.com/pack.zip".into(),
             hash: "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2".into(),
             forced: true,
-            prompt_message: Some(r#"{"text":"Please accept"}"#.into()),
+            prompt_message: Some(TextComponent::text("Please accept")),
         };
         assert_eq!(packet.uuid, uuid);
         assert_eq!(packet.url, "http://example.com/pack.zip");
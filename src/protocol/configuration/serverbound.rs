@@ -1,10 +1,12 @@
+use crate::protocol::encoding::Packet;
 use crate::protocol::types::{
     ChatMode, DisplayedSkinParts, Identifier, KnownPack, MainHand, ParticleStatus,
-    ResourcePackResult, VarInt,
+    ResourcePackResult,
 };
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x00)]
 pub struct ClientInformationConfiguration {
     pub locale: String,
     pub view_distance: i8,
@@ -17,38 +19,46 @@ pub struct ClientInformationConfiguration {
     pub particle_status: ParticleStatus,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x01)]
 pub struct CookieResponseConfiguration {
     pub key: Identifier,
     pub payload: Option<Vec<u8>>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x02)]
 pub struct ServerboundPluginMessageConfiguration {
     pub channel: Identifier,
+    #[packet(rest)]
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x03)]
 pub struct AcknowledgeFinishConfiguration;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x04)]
 pub struct ServerboundKeepAliveConfiguration {
     pub keep_alive_id: i64,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x05)]
 pub struct PongConfiguration {
     pub id: i32,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x06)]
 pub struct ResourcePackResponseConfiguration {
     pub uuid: Uuid,
     pub result: ResourcePackResult,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x07)]
 pub struct ServerboundKnownPacks {
     pub known_packs: Vec<KnownPack>,
 }
@@ -56,6 +66,7 @@ pub struct ServerboundKnownPacks {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::encoding::assert_round_trip;
     use crate::protocol::types::{
         ChatMode, DisplayedSkinParts, KnownPack, MainHand, ParticleStatus, ResourcePackResult,
     };
@@ -113,8 +124,8 @@ mod tests {
 
     #[test]
     fn test_ack_finish_config_instantiation() {
-        let _packet = AcknowledgeFinishConfiguration;
-        assert!(true);
+        let packet = AcknowledgeFinishConfiguration;
+        assert_round_trip(&packet);
     }
 
     #[test]
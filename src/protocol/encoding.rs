@@ -1,11 +1,19 @@
-use crate::protocol::types::VarInt;
-use bytes::{Buf, BufMut, BytesMut};
-use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use crate::protocol::packets::{ConnectionState, PacketDirection};
+use crate::protocol::types::{IdOr, Position, VarInt, VarLong};
+use crate::protocol::version::ProtocolVersion;
+use bytes::{Buf, BufMut};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::Error as IoError;
 use std::string::FromUtf8Error;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use uuid::Uuid;
 
+pub use bytes::BytesMut;
+pub use mcprototool_derive::{Decode, Encode, Packet};
+
 const MAX_VARINT_SIZE: usize = 5;
+const MAX_VARLONG_SIZE: usize = 10;
 const MAX_STRING_LENGTH: usize = 32767;
 
 #[derive(Debug)]
@@ -16,6 +24,15 @@ pub enum DecodeError {
     StringTooLong(usize),
     InvalidUtf8(FromUtf8Error),
     NotEnoughBytes,
+    InvalidEnumValue(&'static str, i64),
+    /// An enum sent by name, as some were before 1.21.5, with a name that isn't known.
+    InvalidEnumName(&'static str, String),
+    UnknownPacketId(ConnectionState, PacketDirection, i32),
+    BadCompression(&'static str),
+    /// A frame announced more than `MAX_PACKET_LEN` bytes.
+    PacketTooLarge(usize),
+    /// Malformed NBT, or NBT that doesn't have the expected shape.
+    InvalidNbt(String),
 }
 
 impl From<IoError> for DecodeError {
@@ -39,6 +56,24 @@ impl std::fmt::Display for DecodeError {
             DecodeError::StringTooLong(len) => write!(f, "String length {} exceeds maximum", len),
             DecodeError::InvalidUtf8(e) => write!(f, "Invalid UTF-8 sequence: {}", e),
             DecodeError::NotEnoughBytes => write!(f, "Not enough bytes in buffer"),
+            DecodeError::InvalidEnumValue(name, value) => {
+                write!(f, "Invalid value {} for {}", value, name)
+            }
+            DecodeError::InvalidEnumName(name, value) => {
+                write!(f, "Invalid name {:?} for {}", value, name)
+            }
+            DecodeError::UnknownPacketId(state, direction, id) => {
+                write!(f, "Unknown {} {} packet ID {:#04x}", direction, state, id)
+            }
+            DecodeError::BadCompression(reason) => write!(f, "Badly compressed packet: {}", reason),
+            DecodeError::PacketTooLarge(len) => {
+                write!(
+                    f,
+                    "Packet of {} bytes exceeds the {} byte limit",
+                    len, MAX_PACKET_LEN
+                )
+            }
+            DecodeError::InvalidNbt(reason) => write!(f, "Invalid NBT: {}", reason),
         }
     }
 }
@@ -106,6 +141,10 @@ pub async fn read_varint<R: AsyncRead + Unpin>(reader: &mut R) -> DecodeResult<V
     let mut result = 0i32;
     let mut shift = 0;
     loop {
+        if num_read >= MAX_VARINT_SIZE {
+            return Err(DecodeError::VarIntTooLong);
+        }
+
         let read = reader.read_u8().await?;
         num_read += 1;
 
@@ -117,10 +156,6 @@ pub async fn read_varint<R: AsyncRead + Unpin>(reader: &mut R) -> DecodeResult<V
         if (read & 0b1000_0000) == 0 {
             break;
         }
-
-        if num_read > MAX_VARINT_SIZE {
-            return Err(DecodeError::VarIntTooLong);
-        }
     }
     Ok(VarInt(result))
 }
@@ -161,6 +196,19 @@ pub async fn read_bytes_prefixed<R: AsyncRead + Unpin>(reader: &mut R) -> Decode
     Ok(buf)
 }
 
+/// Largest frame the protocol allows: the length prefix is at most a 3-byte VarInt.
+pub const MAX_PACKET_LEN: usize = (1 << 21) - 1;
+
+/// Checks a frame's length prefix before anything is allocated for it.
+pub fn packet_len(len: VarInt) -> DecodeResult<usize> {
+    let len = usize::try_from(len.0)
+        .map_err(|_| DecodeError::InvalidEnumValue("packet length", len.0 as i64))?;
+    if len > MAX_PACKET_LEN {
+        return Err(DecodeError::PacketTooLarge(len));
+    }
+    Ok(len)
+}
+
 pub async fn write_packet_frame<W: AsyncWrite + Unpin>(
     writer: &mut W,
     packet_id: VarInt,
@@ -183,13 +231,10 @@ pub async fn write_packet_frame<W: AsyncWrite + Unpin>(
 pub async fn read_packet_frame<R: AsyncRead + Unpin>(
     reader: &mut R,
 ) -> DecodeResult<(VarInt, BytesMut)> {
-    let packet_len_varint = read_varint(reader).await?;
-    let packet_len = packet_len_varint.0 as usize;
+    let packet_len = packet_len(read_varint(reader).await?)?;
 
-    let mut packet_buf = BytesMut::with_capacity(packet_len);
-    let mut temp_vec = vec![0u8; packet_len];
-    reader.read_exact(&mut temp_vec).await?;
-    packet_buf.put_slice(&temp_vec);
+    let mut packet_buf = BytesMut::zeroed(packet_len);
+    reader.read_exact(&mut packet_buf).await?;
 
     let packet_id = read_varint_sync(&mut packet_buf)?;
 
@@ -216,8 +261,10 @@ pub fn read_varint_sync<B: Buf>(buf: &mut B) -> DecodeResult<VarInt> {
     let mut num_read = 0;
     let mut result = 0i32;
     let mut shift = 0;
-    let initial_len = buf.remaining();
     while buf.has_remaining() {
+        if num_read >= MAX_VARINT_SIZE {
+            return Err(DecodeError::VarIntTooLong);
+        }
         let read = buf.get_u8();
         num_read += 1;
         let value = (read & 0b0111_1111) as i32;
@@ -226,9 +273,6 @@ pub fn read_varint_sync<B: Buf>(buf: &mut B) -> DecodeResult<VarInt> {
         if (read & 0b1000_0000) == 0 {
             return Ok(VarInt(result));
         }
-        if num_read > MAX_VARINT_SIZE {
-            return Err(DecodeError::VarIntTooLong);
-        }
     }
 
     if num_read > 0 && shift <= 35 {
@@ -273,3 +317,646 @@ pub fn read_bytes_prefixed_sync(buf: &mut BytesMut) -> Result<Vec<u8>, DecodeErr
     }
     Ok(buf.split_to(len).to_vec())
 }
+
+pub fn write_varlong_sync<B: BufMut>(buf: &mut B, value: VarLong) -> EncodeResult<()> {
+    let mut val = value.0 as u64;
+    loop {
+        let mut temp = (val & 0b0111_1111) as u8;
+        val >>= 7;
+        if val != 0 {
+            temp |= 0b1000_0000;
+        }
+        buf.put_u8(temp);
+        if val == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_varlong_sync<B: Buf>(buf: &mut B) -> DecodeResult<VarLong> {
+    let mut num_read = 0;
+    let mut result = 0i64;
+    let mut shift = 0;
+    while buf.has_remaining() {
+        if num_read >= MAX_VARLONG_SIZE {
+            return Err(DecodeError::VarIntTooLong);
+        }
+        let read = buf.get_u8();
+        num_read += 1;
+        let value = (read & 0b0111_1111) as i64;
+        result |= value << shift;
+        shift += 7;
+        if (read & 0b1000_0000) == 0 {
+            return Ok(VarLong(result));
+        }
+    }
+    Err(DecodeError::NotEnoughBytes)
+}
+
+pub fn write_string_sync<B: BufMut>(buf: &mut B, value: &str) -> EncodeResult<()> {
+    write_varint_sync(buf, VarInt(value.len() as i32))?;
+    buf.put_slice(value.as_bytes());
+    Ok(())
+}
+
+/// Serializes a value into the buffer using the Minecraft wire format.
+pub trait Encode {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()>;
+
+    /// Encodes the value in the layout used by `version`. `encode` is the layout of
+    /// [`ProtocolVersion::LATEST`]; types whose wire form differs between versions, and the types
+    /// holding them, override this.
+    fn encode_for(&self, _version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode(buf)
+    }
+
+    fn to_bytes(&self) -> EncodeResult<BytesMut> {
+        let mut buf = BytesMut::new();
+        self.encode(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Deserializes a value from the front of the buffer, consuming the bytes it reads.
+pub trait Decode: Sized {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self>;
+
+    /// Decodes the value from the layout used by `version`, the counterpart of
+    /// [`Encode::encode_for`].
+    fn decode_for(_version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode(buf)
+    }
+}
+
+/// A packet body together with the ID it is sent under in its connection state and direction.
+///
+/// Usually implemented with `#[derive(Packet)]` and a `#[packet(id = ...)]` attribute. `ID` is the
+/// ID in [`ProtocolVersion::LATEST`]; older versions map it through their own packet tables, and
+/// field-level differences go through [`Encode::encode_for`]/[`Decode::decode_for`].
+pub trait Packet: Encode + Decode {
+    const ID: i32;
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        (**self).encode(buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        (**self).encode_for(version, buf)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        (**self).encode(buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        (**self).encode_for(version, buf)
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Ok(Box::new(T::decode(buf)?))
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        Ok(Box::new(T::decode_for(version, buf)?))
+    }
+}
+
+macro_rules! impl_codec_for_number {
+    ($($ty:ty => $put:ident, $get:ident;)*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+                    buf.$put(*self);
+                    Ok(())
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+                    if buf.remaining() < std::mem::size_of::<$ty>() {
+                        return Err(DecodeError::NotEnoughBytes);
+                    }
+                    Ok(buf.$get())
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_number! {
+    u8 => put_u8, get_u8;
+    i8 => put_i8, get_i8;
+    u16 => put_u16, get_u16;
+    i16 => put_i16, get_i16;
+    i32 => put_i32, get_i32;
+    i64 => put_i64, get_i64;
+    u64 => put_u64, get_u64;
+    f32 => put_f32, get_f32;
+    f64 => put_f64, get_f64;
+}
+
+impl Encode for bool {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        buf.put_u8(if *self { 1 } else { 0 });
+        Ok(())
+    }
+}
+
+impl Decode for bool {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        read_bool_sync(buf)
+    }
+}
+
+impl Encode for VarInt {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varint_sync(buf, *self)
+    }
+}
+
+impl Decode for VarInt {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        read_varint_sync(buf)
+    }
+}
+
+impl Encode for VarLong {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varlong_sync(buf, *self)
+    }
+}
+
+impl Decode for VarLong {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        read_varlong_sync(buf)
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_string_sync(buf, self)
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_string_sync(buf, self)
+    }
+}
+
+impl Decode for String {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        read_string_sync(buf)
+    }
+}
+
+impl Encode for Uuid {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        buf.put_slice(self.as_bytes());
+        Ok(())
+    }
+}
+
+impl Decode for Uuid {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        read_uuid_sync(buf)
+    }
+}
+
+/// Positions are packed into a single `i64`: 26 bits of X, 26 bits of Z, then 12 bits of Y.
+impl Encode for Position {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        let packed = ((self.x as i64 & 0x3FF_FFFF) << 38)
+            | ((self.z as i64 & 0x3FF_FFFF) << 12)
+            | (self.y as i64 & 0xFFF);
+        buf.put_i64(packed);
+        Ok(())
+    }
+}
+
+impl Decode for Position {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let packed = i64::decode(buf)?;
+        Ok(Position {
+            x: (packed >> 38) as i32,
+            y: (packed << 52 >> 52) as i32,
+            z: (packed << 26 >> 38) as i32,
+        })
+    }
+}
+
+/// Optional values are prefixed with a boolean saying whether the value follows.
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            Some(value) => {
+                true.encode(buf)?;
+                value.encode_for(version, buf)
+            }
+            None => false.encode(buf),
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        if bool::decode(buf)? {
+            Ok(Some(T::decode_for(version, buf)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Arrays are prefixed with their element count as a VarInt.
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varint_sync(buf, VarInt(self.len() as i32))?;
+        for item in self {
+            item.encode_for(version, buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.as_slice().encode(buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.as_slice().encode_for(version, buf)
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let len = read_length_sync(buf)?;
+        let mut items = Vec::with_capacity(len.min(buf.remaining()));
+        for _ in 0..len {
+            items.push(T::decode_for(version, buf)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for HashMap<K, V> {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varint_sync(buf, VarInt(self.len() as i32))?;
+        for (key, value) in self {
+            key.encode_for(version, buf)?;
+            value.encode_for(version, buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode> Decode for HashMap<K, V> {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let len = read_length_sync(buf)?;
+        let mut map = HashMap::with_capacity(len.min(buf.remaining()));
+        for _ in 0..len {
+            let key = K::decode_for(version, buf)?;
+            let value = V::decode_for(version, buf)?;
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+macro_rules! impl_codec_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Encode),+> Encode for ($($name,)+) {
+            fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+                self.encode_for(ProtocolVersion::LATEST, buf)
+            }
+
+            #[allow(non_snake_case)]
+            fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+                let ($($name,)+) = self;
+                $($name.encode_for(version, buf)?;)+
+                Ok(())
+            }
+        }
+
+        impl<$($name: Decode),+> Decode for ($($name,)+) {
+            fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+                Self::decode_for(ProtocolVersion::LATEST, buf)
+            }
+
+            fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+                Ok(($($name::decode_for(version, buf)?,)+))
+            }
+        }
+    };
+}
+
+impl_codec_for_tuple!(A, B);
+impl_codec_for_tuple!(A, B, C);
+
+/// `IdOr` values are a VarInt registry ID offset by one, where zero means an inline value follows.
+impl<T: Encode> Encode for IdOr<T> {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            IdOr::Id(id) => write_varint_sync(buf, VarInt(id.0 + 1)),
+            IdOr::Inline(value) => {
+                write_varint_sync(buf, VarInt(0))?;
+                value.encode_for(version, buf)
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for IdOr<T> {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        match read_varint_sync(buf)? {
+            VarInt(0) => Ok(IdOr::Inline(T::decode_for(version, buf)?)),
+            VarInt(id) => Ok(IdOr::Id(VarInt(id - 1))),
+        }
+    }
+}
+
+/// Reads a VarInt array length, rejecting negative values.
+pub fn read_length_sync(buf: &mut BytesMut) -> DecodeResult<usize> {
+    let len = read_varint_sync(buf)?;
+    if len.0 < 0 {
+        return Err(DecodeError::InvalidEnumValue("array length", len.0 as i64));
+    }
+    Ok(len.0 as usize)
+}
+
+/// Reads exactly `len` raw bytes with no length prefix.
+pub fn read_fixed_bytes_sync(buf: &mut BytesMut, len: usize) -> DecodeResult<Vec<u8>> {
+    if buf.remaining() < len {
+        return Err(DecodeError::NotEnoughBytes);
+    }
+    Ok(buf.split_to(len).to_vec())
+}
+
+/// Builds the error returned when a fixed-size array does not hold the expected number of elements.
+pub fn fixed_length_mismatch(expected: usize, actual: usize) -> IoError {
+    IoError::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("expected exactly {} elements, got {}", expected, actual),
+    )
+}
+
+/// Builds the error returned when a field required by the packet layout is `None`.
+pub fn missing_field(packet: &str, field: &str) -> IoError {
+    IoError::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{}.{} is required by the packet layout", packet, field),
+    )
+}
+
+/// Builds the error returned when a value has no representation in the version being encoded for.
+pub fn unsupported_in(what: &str, version: ProtocolVersion) -> IoError {
+    IoError::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{} does not exist in {}", what, version),
+    )
+}
+
+/// Encodes `value`, decodes the result and checks that re-encoding yields identical bytes with
+/// nothing left over in the buffer.
+#[cfg(test)]
+pub(crate) fn assert_round_trip<T: Encode + Decode>(value: &T) {
+    let encoded = value.to_bytes().expect("value should encode");
+    let mut buf = encoded.clone();
+    let decoded = T::decode(&mut buf).expect("encoded bytes should decode");
+    assert!(buf.is_empty(), "{} bytes left after decoding", buf.len());
+    let reencoded = decoded.to_bytes().expect("decoded value should encode");
+    assert_eq!(encoded, reencoded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_known_values() {
+        let cases: [(i32, &[u8]); 6] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (25565, &[0xdd, 0xc7, 0x01]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ];
+        for (value, bytes) in cases {
+            let encoded = VarInt(value).to_bytes().unwrap();
+            assert_eq!(&encoded[..], bytes);
+            assert_eq!(VarInt::decode(&mut encoded.clone()).unwrap(), VarInt(value));
+        }
+    }
+
+    #[test]
+    fn test_varlong_round_trip() {
+        for value in [0, 1, -1, i64::MAX, i64::MIN, 2147483648] {
+            let mut encoded = VarLong(value).to_bytes().unwrap();
+            assert_eq!(VarLong::decode(&mut encoded).unwrap(), VarLong(value));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_overlong_varints() {
+        let overlong = [0x80; 11];
+        assert!(matches!(
+            read_varint_sync(&mut &overlong[..]),
+            Err(DecodeError::VarIntTooLong)
+        ));
+        assert!(matches!(
+            read_varint(&mut &overlong[..]).await,
+            Err(DecodeError::VarIntTooLong)
+        ));
+        assert!(matches!(
+            read_varlong_sync(&mut &overlong[..]),
+            Err(DecodeError::VarIntTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_position_packing() {
+        let position = Position {
+            x: -33554432,
+            y: -2048,
+            z: 33554431,
+        };
+        let mut encoded = position.to_bytes().unwrap();
+        assert_eq!(encoded.len(), 8);
+        assert_eq!(Position::decode(&mut encoded).unwrap(), position);
+
+        let mut known = BytesMut::from(&[0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x10, 0x05][..]);
+        assert_eq!(
+            Position::decode(&mut known).unwrap(),
+            Position { x: 1, y: 5, z: 1 }
+        );
+    }
+
+    #[test]
+    fn test_option_and_vec_prefixes() {
+        let value: Option<Vec<String>> = Some(vec!["a".to_string(), "bc".to_string()]);
+        let encoded = value.to_bytes().unwrap();
+        assert_eq!(&encoded[..], &[0x01, 0x02, 0x01, b'a', 0x02, b'b', b'c']);
+        assert_eq!(
+            Option::<Vec<String>>::decode(&mut encoded.clone()).unwrap(),
+            value
+        );
+        assert_eq!(&None::<u8>.to_bytes().unwrap()[..], &[0x00]);
+    }
+
+    #[test]
+    fn test_id_or_offsets_registry_ids() {
+        let by_id: IdOr<String> = IdOr::Id(VarInt(4));
+        assert_eq!(&by_id.to_bytes().unwrap()[..], &[0x05]);
+        let inline: IdOr<String> = IdOr::Inline("x".to_string());
+        assert_eq!(&inline.to_bytes().unwrap()[..], &[0x00, 0x01, b'x']);
+        assert_round_trip(&by_id);
+        assert_round_trip(&inline);
+    }
+
+    #[test]
+    fn test_decode_reports_short_buffers() {
+        let mut buf = BytesMut::from(&[0x00, 0x01][..]);
+        assert!(matches!(
+            i32::decode(&mut buf),
+            Err(DecodeError::NotEnoughBytes)
+        ));
+        let mut buf = BytesMut::from(&[0x05, b'a'][..]);
+        assert!(matches!(
+            String::decode(&mut buf),
+            Err(DecodeError::NotEnoughBytes)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_packet_frame_enforces_max_length() {
+        let mut frame = BytesMut::new();
+        write_varint_sync(&mut frame, VarInt(MAX_PACKET_LEN as i32 + 1)).unwrap();
+        assert!(matches!(
+            read_packet_frame(&mut &frame[..]).await,
+            Err(DecodeError::PacketTooLarge(len)) if len == MAX_PACKET_LEN + 1
+        ));
+
+        let mut frame = BytesMut::new();
+        write_varint_sync(&mut frame, VarInt(-1)).unwrap();
+        assert!(read_packet_frame(&mut &frame[..]).await.is_err());
+    }
+
+    #[derive(Debug, PartialEq, Packet)]
+    #[packet(id = 0x2A)]
+    struct DerivedPacket {
+        #[packet(varint)]
+        count: i32,
+        #[packet(varlong)]
+        big: i64,
+        #[packet(prefixed, varint)]
+        ids: Vec<i32>,
+        #[packet(fixed = 2)]
+        pair: Vec<u16>,
+        #[packet(optional, fixed = 3)]
+        signature: Option<Vec<u8>>,
+        #[packet(skip)]
+        cached: bool,
+        #[packet(rest)]
+        data: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Packet)]
+    #[packet(id = 0x00)]
+    struct DerivedUnit;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct DerivedTuple(String, #[packet(varint)] i32);
+
+    #[test]
+    fn test_derive_field_attributes() {
+        let packet = DerivedPacket {
+            count: 300,
+            big: 1,
+            ids: vec![1, 128],
+            pair: vec![1, 2],
+            signature: Some(vec![7, 8, 9]),
+            cached: false,
+            data: vec![0xAA, 0xBB],
+        };
+        let encoded = packet.to_bytes().unwrap();
+        assert_eq!(
+            &encoded[..],
+            &[
+                0xAC, 0x02, // count
+                0x01, // big
+                0x02, 0x01, 0x80, 0x01, // ids
+                0x00, 0x01, 0x00, 0x02, // pair
+                0x01, 7, 8, 9, // signature
+                0xAA, 0xBB, // data
+            ]
+        );
+        assert_eq!(DerivedPacket::decode(&mut encoded.clone()).unwrap(), packet);
+        assert_eq!(DerivedPacket::ID, 0x2A);
+    }
+
+    #[test]
+    fn test_derive_unit_and_tuple_structs() {
+        assert!(DerivedUnit.to_bytes().unwrap().is_empty());
+        assert_eq!(
+            DerivedUnit::decode(&mut BytesMut::new()).unwrap(),
+            DerivedUnit
+        );
+        assert_round_trip(&DerivedTuple("a".to_string(), -1));
+        assert_eq!(DerivedTuple("".to_string(), 1).to_bytes().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_derive_rejects_wrong_fixed_length() {
+        let packet = DerivedPacket {
+            count: 0,
+            big: 0,
+            ids: vec![],
+            pair: vec![1],
+            signature: None,
+            cached: true,
+            data: vec![],
+        };
+        assert!(packet.to_bytes().is_err());
+    }
+}
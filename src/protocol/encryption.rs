@@ -0,0 +1,238 @@
+//! AES-128 CFB8 stream encryption, enabled once the client has sent `EncryptionResponse`.
+//!
+//! The shared secret is both the key and the IV, and each direction keeps its own cipher state
+//! for the rest of the connection. Encryption wraps the byte stream below the packet framing,
+//! so it works the same with or without compression.
+
+use aes::Aes128;
+use cfb8::cipher::generic_array::GenericArray;
+use cfb8::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use std::io::{Error as IoError, ErrorKind};
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+type Aes128Cfb8Enc = cfb8::Encryptor<Aes128>;
+type Aes128Cfb8Dec = cfb8::Decryptor<Aes128>;
+
+/// Length of the shared secret, which doubles as the AES key and IV.
+pub const SHARED_SECRET_LEN: usize = 16;
+
+fn invalid_secret() -> IoError {
+    IoError::new(
+        ErrorKind::InvalidInput,
+        format!("shared secret must be {} bytes", SHARED_SECRET_LEN),
+    )
+}
+
+fn encrypt_in_place(cipher: &mut Aes128Cfb8Enc, data: &mut [u8]) {
+    for byte in data {
+        cipher.encrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+    }
+}
+
+fn decrypt_in_place(cipher: &mut Aes128Cfb8Dec, data: &mut [u8]) {
+    for byte in data {
+        cipher.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+    }
+}
+
+/// Decrypts everything read from the wrapped reader.
+pub struct EncryptedReader<R> {
+    inner: R,
+    cipher: Aes128Cfb8Dec,
+}
+
+impl<R> EncryptedReader<R> {
+    pub fn new(inner: R, shared_secret: &[u8]) -> Result<Self, IoError> {
+        Self::with_iv(inner, shared_secret, shared_secret)
+    }
+
+    fn with_iv(inner: R, key: &[u8], iv: &[u8]) -> Result<Self, IoError> {
+        let cipher = Aes128Cfb8Dec::new_from_slices(key, iv).map_err(|_| invalid_secret())?;
+        Ok(EncryptedReader { inner, cipher })
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let start = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        decrypt_in_place(&mut this.cipher, &mut buf.filled_mut()[start..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Encrypts everything written to the wrapped writer.
+///
+/// The cipher state advances as bytes are accepted, so ciphertext the inner writer could not take
+/// yet is held back and written before anything else.
+pub struct EncryptedWriter<W> {
+    inner: W,
+    cipher: Aes128Cfb8Enc,
+    pending: Vec<u8>,
+    written: usize,
+}
+
+impl<W> EncryptedWriter<W> {
+    pub fn new(inner: W, shared_secret: &[u8]) -> Result<Self, IoError> {
+        Self::with_iv(inner, shared_secret, shared_secret)
+    }
+
+    fn with_iv(inner: W, key: &[u8], iv: &[u8]) -> Result<Self, IoError> {
+        let cipher = Aes128Cfb8Enc::new_from_slices(key, iv).map_err(|_| invalid_secret())?;
+        Ok(EncryptedWriter {
+            inner,
+            cipher,
+            pending: Vec::new(),
+            written: 0,
+        })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EncryptedWriter<W> {
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        while self.written < self.pending.len() {
+            let n =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending[self.written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(ErrorKind::WriteZero.into()));
+            }
+            self.written += n;
+        }
+        self.pending.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptedWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        this.pending.extend_from_slice(buf);
+        encrypt_in_place(&mut this.cipher, &mut this.pending);
+        // The bytes are ours now; a pending inner write is retried on the next call or flush.
+        if let Poll::Ready(Err(e)) = this.poll_drain(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::compression::{Compression, read_frame, write_frame};
+    use crate::protocol::types::VarInt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // NIST SP 800-38A, F.3.7 CFB8-AES128.Encrypt.
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    const PLAINTEXT: [u8; 18] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d,
+    ];
+    const CIPHERTEXT: [u8; 18] = [
+        0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a,
+        0x4f, 0x32, 0xb9,
+    ];
+
+    #[tokio::test]
+    async fn test_writer_matches_known_vector() {
+        let mut writer = EncryptedWriter::with_iv(Vec::new(), &KEY, &IV).unwrap();
+        // Split the writes to check the cipher state carries across calls.
+        writer.write_all(&PLAINTEXT[..5]).await.unwrap();
+        writer.write_all(&PLAINTEXT[5..]).await.unwrap();
+        writer.flush().await.unwrap();
+        assert_eq!(writer.into_inner(), CIPHERTEXT);
+    }
+
+    #[tokio::test]
+    async fn test_reader_matches_known_vector() {
+        let mut reader = EncryptedReader::with_iv(&CIPHERTEXT[..], &KEY, &IV).unwrap();
+        let mut first = [0u8; 7];
+        reader.read_exact(&mut first).await.unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(first, PLAINTEXT[..7]);
+        assert_eq!(rest, PLAINTEXT[7..]);
+    }
+
+    #[test]
+    fn test_rejects_wrong_secret_length() {
+        assert!(EncryptedReader::new(&b""[..], &[0u8; 8]).is_err());
+        assert!(EncryptedWriter::new(Vec::<u8>::new(), &[0u8; 32]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_frames_through_encrypted_stream() {
+        let secret = [0x42u8; SHARED_SECRET_LEN];
+        let compression = Compression {
+            threshold: 16,
+            level: 6,
+        };
+        let payload = vec![9u8; 300];
+
+        let mut writer = EncryptedWriter::new(Vec::new(), &secret).unwrap();
+        write_frame(&mut writer, Some(&compression), VarInt(0x10), &payload)
+            .await
+            .unwrap();
+        write_frame(&mut writer, Some(&compression), VarInt(0x11), b"x")
+            .await
+            .unwrap();
+        writer.flush().await.unwrap();
+        let wire = writer.into_inner();
+
+        let mut reader = EncryptedReader::new(&wire[..], &secret).unwrap();
+        let (id, data) = read_frame(&mut reader, Some(&compression)).await.unwrap();
+        assert_eq!(id, VarInt(0x10));
+        assert_eq!(&data[..], &payload[..]);
+        let (id, data) = read_frame(&mut reader, Some(&compression)).await.unwrap();
+        assert_eq!((id, &data[..]), (VarInt(0x11), &b"x"[..]));
+    }
+}
@@ -1,6 +1,8 @@
-use crate::protocol::types::{NextState, VarInt};
+use crate::protocol::encoding::{Decode, Encode, Packet};
+use crate::protocol::types::VarInt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x00)]
 pub struct Handshake {
     pub protocol_version: VarInt,
     pub server_address: String,
@@ -8,7 +10,7 @@ pub struct Handshake {
     pub next_state: VarInt,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Encode, Decode)]
 pub struct LegacyServerListPing {
     pub payload: u8,
 }
@@ -0,0 +1,961 @@
+//! Data components: the typed properties (name, damage, enchantments, contents, ...) an item
+//! stack carries on top of its item's defaults.
+//!
+//! Component type IDs are positions in the `minecraft:data_component_type` registry of
+//! [`ProtocolVersion::LATEST`]; stacks are translated to and from the registry of older versions
+//! as they are encoded and decoded. On the wire a component's data has no length prefix, so only
+//! the types below can be read from a slot; the length-prefixed form of
+//! [`UntrustedSlot`](super::UntrustedSlot) also keeps any other type as raw bytes.
+
+use super::hash::HashValue;
+use super::{ItemStack, MAX_NESTING};
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_fixed_bytes_sync,
+    read_length_sync, read_varint_sync, unsupported_in, write_varint_sync,
+};
+use crate::protocol::types::{Identifier, Nbt, TextComponent, VarInt};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+
+/// Names of the component types of [`ProtocolVersion::LATEST`], indexed by type ID.
+const COMPONENT_TYPES: &[&str] = &[
+    "minecraft:custom_data",
+    "minecraft:max_stack_size",
+    "minecraft:max_damage",
+    "minecraft:damage",
+    "minecraft:unbreakable",
+    "minecraft:custom_name",
+    "minecraft:item_name",
+    "minecraft:item_model",
+    "minecraft:lore",
+    "minecraft:rarity",
+    "minecraft:enchantments",
+    "minecraft:can_place_on",
+    "minecraft:can_break",
+    "minecraft:attribute_modifiers",
+    "minecraft:custom_model_data",
+    "minecraft:tooltip_display",
+    "minecraft:repair_cost",
+    "minecraft:creative_slot_lock",
+    "minecraft:enchantment_glint_override",
+    "minecraft:intangible_projectile",
+    "minecraft:food",
+    "minecraft:consumable",
+    "minecraft:use_remainder",
+    "minecraft:use_cooldown",
+    "minecraft:damage_resistant",
+    "minecraft:tool",
+    "minecraft:weapon",
+    "minecraft:enchantable",
+    "minecraft:equippable",
+    "minecraft:repairable",
+    "minecraft:glider",
+    "minecraft:tooltip_style",
+    "minecraft:death_protection",
+    "minecraft:blocks_attacks",
+    "minecraft:stored_enchantments",
+    "minecraft:dyed_color",
+    "minecraft:map_color",
+    "minecraft:map_id",
+    "minecraft:map_decorations",
+    "minecraft:map_post_processing",
+    "minecraft:charged_projectiles",
+    "minecraft:bundle_contents",
+    "minecraft:potion_contents",
+    "minecraft:potion_duration_scale",
+    "minecraft:suspicious_stew_effects",
+    "minecraft:writable_book_content",
+    "minecraft:written_book_content",
+    "minecraft:trim",
+    "minecraft:debug_stick_state",
+    "minecraft:entity_data",
+    "minecraft:bucket_entity_data",
+    "minecraft:block_entity_data",
+    "minecraft:instrument",
+    "minecraft:provides_trim_material",
+    "minecraft:ominous_bottle_amplifier",
+    "minecraft:jukebox_playable",
+    "minecraft:provides_banner_patterns",
+    "minecraft:recipes",
+    "minecraft:lodestone_tracker",
+    "minecraft:firework_explosion",
+    "minecraft:fireworks",
+    "minecraft:profile",
+    "minecraft:note_block_sound",
+    "minecraft:banner_patterns",
+    "minecraft:base_color",
+    "minecraft:pot_decorations",
+    "minecraft:container",
+    "minecraft:block_state",
+    "minecraft:bees",
+    "minecraft:lock",
+    "minecraft:container_loot",
+    "minecraft:break_sound",
+    "minecraft:villager/variant",
+    "minecraft:wolf/variant",
+    "minecraft:wolf/sound_variant",
+    "minecraft:wolf/collar",
+    "minecraft:fox/variant",
+    "minecraft:salmon/size",
+    "minecraft:parrot/variant",
+    "minecraft:tropical_fish/pattern",
+    "minecraft:tropical_fish/base_color",
+    "minecraft:tropical_fish/pattern_color",
+    "minecraft:mooshroom/variant",
+    "minecraft:rabbit/variant",
+    "minecraft:pig/variant",
+    "minecraft:cow/variant",
+    "minecraft:chicken/variant",
+    "minecraft:frog/variant",
+    "minecraft:horse/variant",
+    "minecraft:painting/variant",
+    "minecraft:llama/variant",
+    "minecraft:axolotl/variant",
+    "minecraft:cat/variant",
+    "minecraft:cat/collar",
+    "minecraft:sheep/color",
+    "minecraft:shulker/color",
+];
+
+/// Names of the 1.21.4 component types. 1.21.5 replaced `hide_tooltip` and
+/// `hide_additional_tooltip` with `tooltip_display`, and added the weapon, attack blocking,
+/// potion duration, trim material, banner pattern and break sound types and the entity variant
+/// types.
+const COMPONENT_TYPES_1_21_4: &[&str] = &[
+    "minecraft:custom_data",
+    "minecraft:max_stack_size",
+    "minecraft:max_damage",
+    "minecraft:damage",
+    "minecraft:unbreakable",
+    "minecraft:custom_name",
+    "minecraft:item_name",
+    "minecraft:item_model",
+    "minecraft:lore",
+    "minecraft:rarity",
+    "minecraft:enchantments",
+    "minecraft:can_place_on",
+    "minecraft:can_break",
+    "minecraft:attribute_modifiers",
+    "minecraft:custom_model_data",
+    "minecraft:hide_additional_tooltip",
+    "minecraft:hide_tooltip",
+    "minecraft:repair_cost",
+    "minecraft:creative_slot_lock",
+    "minecraft:enchantment_glint_override",
+    "minecraft:intangible_projectile",
+    "minecraft:food",
+    "minecraft:consumable",
+    "minecraft:use_remainder",
+    "minecraft:use_cooldown",
+    "minecraft:damage_resistant",
+    "minecraft:tool",
+    "minecraft:enchantable",
+    "minecraft:equippable",
+    "minecraft:repairable",
+    "minecraft:glider",
+    "minecraft:tooltip_style",
+    "minecraft:death_protection",
+    "minecraft:stored_enchantments",
+    "minecraft:dyed_color",
+    "minecraft:map_color",
+    "minecraft:map_id",
+    "minecraft:map_decorations",
+    "minecraft:map_post_processing",
+    "minecraft:charged_projectiles",
+    "minecraft:bundle_contents",
+    "minecraft:potion_contents",
+    "minecraft:suspicious_stew_effects",
+    "minecraft:writable_book_content",
+    "minecraft:written_book_content",
+    "minecraft:trim",
+    "minecraft:debug_stick_state",
+    "minecraft:entity_data",
+    "minecraft:bucket_entity_data",
+    "minecraft:block_entity_data",
+    "minecraft:instrument",
+    "minecraft:ominous_bottle_amplifier",
+    "minecraft:jukebox_playable",
+    "minecraft:recipes",
+    "minecraft:lodestone_tracker",
+    "minecraft:firework_explosion",
+    "minecraft:fireworks",
+    "minecraft:profile",
+    "minecraft:note_block_sound",
+    "minecraft:banner_patterns",
+    "minecraft:base_color",
+    "minecraft:pot_decorations",
+    "minecraft:container",
+    "minecraft:block_state",
+    "minecraft:bees",
+    "minecraft:lock",
+    "minecraft:container_loot",
+];
+
+/// The components whose tooltip lines 1.21.4's `hide_additional_tooltip` hides, which 1.21.5
+/// lists in `tooltip_display` instead.
+const ADDITIONAL_TOOLTIP_COMPONENTS: &[&str] = &[
+    "minecraft:banner_patterns",
+    "minecraft:bees",
+    "minecraft:block_entity_data",
+    "minecraft:block_state",
+    "minecraft:bundle_contents",
+    "minecraft:charged_projectiles",
+    "minecraft:container",
+    "minecraft:container_loot",
+    "minecraft:firework_explosion",
+    "minecraft:fireworks",
+    "minecraft:instrument",
+    "minecraft:map_id",
+    "minecraft:pot_decorations",
+    "minecraft:potion_contents",
+    "minecraft:written_book_content",
+];
+
+/// The components 1.21.4 follows with a `show_in_tooltip` flag, which 1.21.5 moved into
+/// `tooltip_display`. The flag is the last field of each of them.
+const TOOLTIP_FLAG_COMPONENTS: &[&str] = &[
+    "minecraft:unbreakable",
+    "minecraft:enchantments",
+    "minecraft:can_place_on",
+    "minecraft:can_break",
+    "minecraft:attribute_modifiers",
+    "minecraft:stored_enchantments",
+    "minecraft:dyed_color",
+    "minecraft:trim",
+    "minecraft:jukebox_playable",
+];
+
+/// Whether 1.21.4 follows the data of component type `id`, a type ID in
+/// [`ProtocolVersion::LATEST`], with a `show_in_tooltip` flag.
+fn has_tooltip_flag(id: i32) -> bool {
+    component_type_name(ProtocolVersion::LATEST, id)
+        .is_some_and(|name| TOOLTIP_FLAG_COMPONENTS.contains(&name))
+}
+
+fn component_types(version: ProtocolVersion) -> &'static [&'static str] {
+    match version {
+        ProtocolVersion::V1_21_4 => COMPONENT_TYPES_1_21_4,
+        ProtocolVersion::V1_21_5 => COMPONENT_TYPES,
+    }
+}
+
+/// The name of component type `id` in `version`, such as `minecraft:damage`.
+pub fn component_type_name(version: ProtocolVersion, id: i32) -> Option<&'static str> {
+    component_types(version)
+        .get(usize::try_from(id).ok()?)
+        .copied()
+}
+
+/// The type ID of a component type name in `version`, with or without the `minecraft:`
+/// namespace.
+pub fn component_type_id(version: ProtocolVersion, name: &str) -> Option<i32> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    component_types(version)
+        .iter()
+        .position(|known| known["minecraft:".len()..] == *name)
+        .map(|id| id as i32)
+}
+
+/// The type ID in `to` of the type `id` has in `from`.
+fn translate_type_id(id: i32, from: ProtocolVersion, to: ProtocolVersion) -> Option<i32> {
+    if from == to {
+        return Some(id);
+    }
+    component_type_id(to, component_type_name(from, id)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rarity {
+    Common = 0,
+    Uncommon = 1,
+    Rare = 2,
+    Epic = 3,
+}
+
+impl Rarity {
+    fn name(self) -> &'static str {
+        match self {
+            Rarity::Common => "common",
+            Rarity::Uncommon => "uncommon",
+            Rarity::Rare => "rare",
+            Rarity::Epic => "epic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnchantmentLevel {
+    /// Network ID in the `minecraft:enchantment` registry.
+    pub enchantment: i32,
+    pub level: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataComponent {
+    CustomData(Nbt),
+    MaxStackSize(i32),
+    MaxDamage(i32),
+    Damage(i32),
+    Unbreakable,
+    CustomName(TextComponent),
+    ItemName(TextComponent),
+    ItemModel(Identifier),
+    Lore(Vec<TextComponent>),
+    Rarity(Rarity),
+    Enchantments(Vec<EnchantmentLevel>),
+    TooltipDisplay {
+        hide_tooltip: bool,
+        /// Type IDs of the components left out of the tooltip.
+        hidden_components: Vec<i32>,
+    },
+    RepairCost(i32),
+    CreativeSlotLock,
+    EnchantmentGlintOverride(bool),
+    Food {
+        nutrition: i32,
+        saturation: f32,
+        can_always_eat: bool,
+    },
+    Enchantable(i32),
+    Glider,
+    TooltipStyle(Identifier),
+    StoredEnchantments(Vec<EnchantmentLevel>),
+    DyedColor(i32),
+    MapColor(i32),
+    MapId(i32),
+    ChargedProjectiles(Vec<ItemStack>),
+    BundleContents(Vec<ItemStack>),
+    EntityData(Nbt),
+    BlockEntityData(Nbt),
+    OminousBottleAmplifier(i32),
+    Container(Vec<ItemStack>),
+    /// Any other component type, as its undecoded data.
+    Other {
+        id: i32,
+        data: Vec<u8>,
+    },
+}
+
+fn write_varint(buf: &mut BytesMut, value: i32) -> EncodeResult<()> {
+    write_varint_sync(buf, VarInt(value))
+}
+
+fn read_varint(buf: &mut BytesMut) -> DecodeResult<i32> {
+    Ok(read_varint_sync(buf)?.0)
+}
+
+fn unsupported_type(id: i32, version: ProtocolVersion) -> std::io::Error {
+    match component_type_name(ProtocolVersion::LATEST, id) {
+        Some(name) => unsupported_in(&format!("component type {}", name), version),
+        None => unsupported_in(&format!("component type {}", id), version),
+    }
+}
+
+fn write_items(
+    buf: &mut BytesMut,
+    version: ProtocolVersion,
+    items: &[ItemStack],
+) -> EncodeResult<()> {
+    write_varint(buf, items.len() as i32)?;
+    items
+        .iter()
+        .try_for_each(|item| item.encode_for(version, buf))
+}
+
+fn read_items(
+    buf: &mut BytesMut,
+    version: ProtocolVersion,
+    depth: usize,
+) -> DecodeResult<Vec<ItemStack>> {
+    if depth >= MAX_NESTING {
+        return Err(DecodeError::InvalidNbt(format!(
+            "items nested deeper than {} levels",
+            MAX_NESTING
+        )));
+    }
+    let len = read_length_sync(buf)?;
+    let mut items = Vec::with_capacity(len.min(buf.len()));
+    for _ in 0..len {
+        items.push(ItemStack::decode_nested(version, buf, depth + 1)?);
+    }
+    Ok(items)
+}
+
+fn write_enchantments(buf: &mut BytesMut, enchantments: &[EnchantmentLevel]) -> EncodeResult<()> {
+    write_varint(buf, enchantments.len() as i32)?;
+    for enchantment in enchantments {
+        write_varint(buf, enchantment.enchantment)?;
+        write_varint(buf, enchantment.level)?;
+    }
+    Ok(())
+}
+
+fn read_enchantments(buf: &mut BytesMut) -> DecodeResult<Vec<EnchantmentLevel>> {
+    let len = read_length_sync(buf)?;
+    let mut enchantments = Vec::with_capacity(len.min(buf.len()));
+    for _ in 0..len {
+        enchantments.push(EnchantmentLevel {
+            enchantment: read_varint(buf)?,
+            level: read_varint(buf)?,
+        });
+    }
+    Ok(enchantments)
+}
+
+impl DataComponent {
+    /// The component's type ID in [`ProtocolVersion::LATEST`].
+    pub fn id(&self) -> i32 {
+        match self {
+            DataComponent::CustomData(_) => 0,
+            DataComponent::MaxStackSize(_) => 1,
+            DataComponent::MaxDamage(_) => 2,
+            DataComponent::Damage(_) => 3,
+            DataComponent::Unbreakable => 4,
+            DataComponent::CustomName(_) => 5,
+            DataComponent::ItemName(_) => 6,
+            DataComponent::ItemModel(_) => 7,
+            DataComponent::Lore(_) => 8,
+            DataComponent::Rarity(_) => 9,
+            DataComponent::Enchantments(_) => 10,
+            DataComponent::TooltipDisplay { .. } => 15,
+            DataComponent::RepairCost(_) => 16,
+            DataComponent::CreativeSlotLock => 17,
+            DataComponent::EnchantmentGlintOverride(_) => 18,
+            DataComponent::Food { .. } => 20,
+            DataComponent::Enchantable(_) => 27,
+            DataComponent::Glider => 30,
+            DataComponent::TooltipStyle(_) => 31,
+            DataComponent::StoredEnchantments(_) => 34,
+            DataComponent::DyedColor(_) => 35,
+            DataComponent::MapColor(_) => 36,
+            DataComponent::MapId(_) => 37,
+            DataComponent::ChargedProjectiles(_) => 40,
+            DataComponent::BundleContents(_) => 41,
+            DataComponent::EntityData(_) => 49,
+            DataComponent::BlockEntityData(_) => 51,
+            DataComponent::OminousBottleAmplifier(_) => 54,
+            DataComponent::Container(_) => 66,
+            DataComponent::Other { id, .. } => *id,
+        }
+    }
+
+    /// The component's type ID in `version`, or `None` if `version` doesn't have its type.
+    pub fn id_for(&self, version: ProtocolVersion) -> Option<i32> {
+        translate_type_id(self.id(), ProtocolVersion::LATEST, version)
+    }
+
+    /// Writes the component's data for `version`, without its type ID.
+    fn encode_data(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            DataComponent::CustomData(nbt)
+            | DataComponent::EntityData(nbt)
+            | DataComponent::BlockEntityData(nbt) => nbt.encode_for(version, buf),
+            DataComponent::MaxStackSize(value)
+            | DataComponent::MaxDamage(value)
+            | DataComponent::Damage(value)
+            | DataComponent::RepairCost(value)
+            | DataComponent::Enchantable(value)
+            | DataComponent::MapId(value)
+            | DataComponent::OminousBottleAmplifier(value) => write_varint(buf, *value),
+            DataComponent::Unbreakable
+            | DataComponent::CreativeSlotLock
+            | DataComponent::Glider => Ok(()),
+            DataComponent::CustomName(text) | DataComponent::ItemName(text) => {
+                text.encode_for(version, buf)
+            }
+            DataComponent::ItemModel(id) | DataComponent::TooltipStyle(id) => id.encode(buf),
+            DataComponent::Lore(lines) => lines.encode_for(version, buf),
+            DataComponent::Rarity(rarity) => write_varint(buf, *rarity as i32),
+            DataComponent::Enchantments(enchantments)
+            | DataComponent::StoredEnchantments(enchantments) => {
+                write_enchantments(buf, enchantments)
+            }
+            DataComponent::TooltipDisplay {
+                hide_tooltip,
+                hidden_components,
+            } => {
+                hide_tooltip.encode(buf)?;
+                write_varint(buf, hidden_components.len() as i32)?;
+                hidden_components
+                    .iter()
+                    .try_for_each(|id| write_varint(buf, *id))
+            }
+            DataComponent::EnchantmentGlintOverride(value) => value.encode(buf),
+            DataComponent::Food {
+                nutrition,
+                saturation,
+                can_always_eat,
+            } => {
+                write_varint(buf, *nutrition)?;
+                saturation.encode(buf)?;
+                can_always_eat.encode(buf)
+            }
+            DataComponent::DyedColor(rgb) | DataComponent::MapColor(rgb) => rgb.encode(buf),
+            DataComponent::ChargedProjectiles(items)
+            | DataComponent::BundleContents(items)
+            | DataComponent::Container(items) => write_items(buf, version, items),
+            DataComponent::Other { data, .. } => {
+                buf.extend_from_slice(data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reads the data `version` sends for a component of type `id`, a type ID in
+    /// [`ProtocolVersion::LATEST`], or `None` for a type without a variant here.
+    fn decode_data(
+        id: i32,
+        version: ProtocolVersion,
+        buf: &mut BytesMut,
+        depth: usize,
+    ) -> DecodeResult<Option<Self>> {
+        Ok(Some(match id {
+            0 => DataComponent::CustomData(Nbt::decode(buf)?),
+            1 => DataComponent::MaxStackSize(read_varint(buf)?),
+            2 => DataComponent::MaxDamage(read_varint(buf)?),
+            3 => DataComponent::Damage(read_varint(buf)?),
+            4 => DataComponent::Unbreakable,
+            5 => DataComponent::CustomName(TextComponent::decode_for(version, buf)?),
+            6 => DataComponent::ItemName(TextComponent::decode_for(version, buf)?),
+            7 => DataComponent::ItemModel(Identifier::decode(buf)?),
+            8 => DataComponent::Lore(Vec::decode_for(version, buf)?),
+            9 => DataComponent::Rarity(match read_varint(buf)? {
+                0 => Rarity::Common,
+                1 => Rarity::Uncommon,
+                2 => Rarity::Rare,
+                3 => Rarity::Epic,
+                other => return Err(DecodeError::InvalidEnumValue("Rarity", other as i64)),
+            }),
+            10 => DataComponent::Enchantments(read_enchantments(buf)?),
+            15 => DataComponent::TooltipDisplay {
+                hide_tooltip: bool::decode(buf)?,
+                hidden_components: {
+                    let len = read_length_sync(buf)?;
+                    let mut ids = Vec::with_capacity(len.min(buf.len()));
+                    for _ in 0..len {
+                        ids.push(read_varint(buf)?);
+                    }
+                    ids
+                },
+            },
+            16 => DataComponent::RepairCost(read_varint(buf)?),
+            17 => DataComponent::CreativeSlotLock,
+            18 => DataComponent::EnchantmentGlintOverride(bool::decode(buf)?),
+            20 => DataComponent::Food {
+                nutrition: read_varint(buf)?,
+                saturation: f32::decode(buf)?,
+                can_always_eat: bool::decode(buf)?,
+            },
+            27 => DataComponent::Enchantable(read_varint(buf)?),
+            30 => DataComponent::Glider,
+            31 => DataComponent::TooltipStyle(Identifier::decode(buf)?),
+            34 => DataComponent::StoredEnchantments(read_enchantments(buf)?),
+            35 => DataComponent::DyedColor(i32::decode(buf)?),
+            36 => DataComponent::MapColor(i32::decode(buf)?),
+            37 => DataComponent::MapId(read_varint(buf)?),
+            40 => DataComponent::ChargedProjectiles(read_items(buf, version, depth)?),
+            41 => DataComponent::BundleContents(read_items(buf, version, depth)?),
+            49 => DataComponent::EntityData(Nbt::decode(buf)?),
+            51 => DataComponent::BlockEntityData(Nbt::decode(buf)?),
+            54 => DataComponent::OminousBottleAmplifier(read_varint(buf)?),
+            66 => DataComponent::Container(read_items(buf, version, depth)?),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// The value the component's codec produces, for its hash. `None` where that depends on
+    /// registry names this crate doesn't know, such as enchantments and item IDs.
+    pub(super) fn hash_value(&self) -> Option<HashValue> {
+        let unit = || HashValue::Map(vec![]);
+        Some(match self {
+            DataComponent::CustomData(nbt)
+            | DataComponent::EntityData(nbt)
+            | DataComponent::BlockEntityData(nbt) => HashValue::from(nbt),
+            DataComponent::MaxStackSize(value)
+            | DataComponent::MaxDamage(value)
+            | DataComponent::Damage(value)
+            | DataComponent::RepairCost(value)
+            | DataComponent::MapId(value)
+            | DataComponent::OminousBottleAmplifier(value)
+            | DataComponent::DyedColor(value)
+            | DataComponent::MapColor(value) => HashValue::Int(*value),
+            DataComponent::Unbreakable
+            | DataComponent::CreativeSlotLock
+            | DataComponent::Glider => unit(),
+            DataComponent::CustomName(text) | DataComponent::ItemName(text) => {
+                HashValue::from(text)
+            }
+            DataComponent::ItemModel(id) | DataComponent::TooltipStyle(id) => {
+                HashValue::String(id.clone())
+            }
+            DataComponent::Lore(lines) => {
+                HashValue::List(lines.iter().map(HashValue::from).collect())
+            }
+            DataComponent::Rarity(rarity) => HashValue::String(rarity.name().to_string()),
+            DataComponent::TooltipDisplay {
+                hide_tooltip,
+                hidden_components,
+            } => {
+                // Fields at their default value are left out, as by the codec.
+                let mut fields = vec![];
+                if *hide_tooltip {
+                    fields.push(("hide_tooltip", HashValue::Bool(true)));
+                }
+                if !hidden_components.is_empty() {
+                    let names = hidden_components
+                        .iter()
+                        .map(|id| {
+                            let name = component_type_name(ProtocolVersion::LATEST, *id)?;
+                            Some(HashValue::String(name.to_string()))
+                        })
+                        .collect::<Option<_>>()?;
+                    fields.push(("hidden_components", HashValue::List(names)));
+                }
+                HashValue::record(fields)
+            }
+            DataComponent::EnchantmentGlintOverride(value) => HashValue::Bool(*value),
+            DataComponent::Food {
+                nutrition,
+                saturation,
+                can_always_eat,
+            } => {
+                let mut fields = vec![
+                    ("nutrition", HashValue::Int(*nutrition)),
+                    ("saturation", HashValue::Float(*saturation)),
+                ];
+                if *can_always_eat {
+                    fields.push(("can_always_eat", HashValue::Bool(true)));
+                }
+                HashValue::record(fields)
+            }
+            DataComponent::Enchantable(value) => {
+                HashValue::record(vec![("value", HashValue::Int(*value))])
+            }
+            DataComponent::Enchantments(_)
+            | DataComponent::StoredEnchantments(_)
+            | DataComponent::ChargedProjectiles(_)
+            | DataComponent::BundleContents(_)
+            | DataComponent::Container(_)
+            | DataComponent::Other { .. } => return None,
+        })
+    }
+}
+
+/// Writes the component patch of a stack in the layout of `version`, from the number of added
+/// components on. With `length_prefixed`, each component's data is prefixed with its length, as
+/// in [`UntrustedSlot`](super::UntrustedSlot).
+///
+/// For 1.21.4, `tooltip_display` is split back into the `hide_tooltip` and
+/// `hide_additional_tooltip` markers and the `show_in_tooltip` flags of the components it hides.
+/// Removed types that `version` doesn't have are left out, and added ones are an error.
+pub(super) fn encode_patch(
+    components: &[DataComponent],
+    removed_components: &[i32],
+    version: ProtocolVersion,
+    length_prefixed: bool,
+    buf: &mut BytesMut,
+) -> EncodeResult<()> {
+    let split_tooltip = version < ProtocolVersion::V1_21_5;
+    let (hide_tooltip, hidden_components) = components
+        .iter()
+        .find_map(|component| match component {
+            DataComponent::TooltipDisplay {
+                hide_tooltip,
+                hidden_components,
+            } => Some((*hide_tooltip, hidden_components.as_slice())),
+            _ => None,
+        })
+        .unwrap_or((false, &[]));
+
+    let mut added = Vec::with_capacity(components.len() + 2);
+    for component in components {
+        if split_tooltip && matches!(component, DataComponent::TooltipDisplay { .. }) {
+            continue;
+        }
+        let id = component
+            .id_for(version)
+            .ok_or_else(|| unsupported_type(component.id(), version))?;
+        let mut data = BytesMut::new();
+        component.encode_data(version, &mut data)?;
+        if split_tooltip && has_tooltip_flag(component.id()) {
+            (!hidden_components.contains(&component.id())).encode(&mut data)?;
+        }
+        added.push((id, data));
+    }
+    if split_tooltip {
+        let hides_additional = hidden_components.iter().any(|id| {
+            component_type_name(ProtocolVersion::LATEST, *id)
+                .is_some_and(|name| ADDITIONAL_TOOLTIP_COMPONENTS.contains(&name))
+        });
+        for (name, present) in [
+            ("hide_tooltip", hide_tooltip),
+            ("hide_additional_tooltip", hides_additional),
+        ] {
+            if let Some(id) = component_type_id(version, name).filter(|_| present) {
+                added.push((id, BytesMut::new()));
+            }
+        }
+    }
+    let removed: Vec<i32> = removed_components
+        .iter()
+        .filter_map(|id| translate_type_id(*id, ProtocolVersion::LATEST, version))
+        .collect();
+
+    write_varint(buf, added.len() as i32)?;
+    write_varint(buf, removed.len() as i32)?;
+    for (id, data) in added {
+        write_varint(buf, id)?;
+        if length_prefixed {
+            write_varint(buf, data.len() as i32)?;
+        }
+        buf.extend_from_slice(&data);
+    }
+    removed.iter().try_for_each(|id| write_varint(buf, *id))
+}
+
+/// Reads a component patch written by [`encode_patch`] for `version`, as the added components
+/// and removed type IDs of [`ProtocolVersion::LATEST`]. Without `length_prefixed`, a type this
+/// crate can't decode is an error, as the length of its data is unknown; with it, such a type is
+/// kept as [`DataComponent::Other`].
+pub(super) fn decode_patch(
+    version: ProtocolVersion,
+    length_prefixed: bool,
+    buf: &mut BytesMut,
+    depth: usize,
+) -> DecodeResult<(Vec<DataComponent>, Vec<i32>)> {
+    let latest = ProtocolVersion::LATEST;
+    let unknown_type = |id: i32| DecodeError::InvalidEnumValue("data component type", id as i64);
+    let added = read_length_sync(buf)?;
+    let removed = read_length_sync(buf)?;
+    let mut components = Vec::with_capacity(added.min(buf.len()));
+    // The 1.21.4 markers and flags, gathered into a `tooltip_display`.
+    let mut hide_tooltip = false;
+    let mut hidden_components = vec![];
+    for _ in 0..added {
+        let id = read_varint(buf)?;
+        let mut data = match length_prefixed {
+            true => {
+                let len = read_length_sync(buf)?;
+                Some(BytesMut::from(&read_fixed_bytes_sync(buf, len)?[..]))
+            }
+            false => None,
+        };
+        let len = data.as_ref().map_or(0, BytesMut::len);
+        let reader = match &mut data {
+            Some(data) => data,
+            None => &mut *buf,
+        };
+        match component_type_name(version, id) {
+            Some("minecraft:hide_tooltip") => hide_tooltip = true,
+            Some("minecraft:hide_additional_tooltip") => hidden_components.extend(
+                ADDITIONAL_TOOLTIP_COMPONENTS
+                    .iter()
+                    .filter_map(|name| component_type_id(latest, name)),
+            ),
+            _ => {
+                let latest_id = translate_type_id(id, version, latest).ok_or(unknown_type(id))?;
+                let Some(component) =
+                    DataComponent::decode_data(latest_id, version, reader, depth)?
+                else {
+                    let mut data = data.ok_or(unknown_type(id))?.to_vec();
+                    if version < ProtocolVersion::V1_21_5 && has_tooltip_flag(latest_id) {
+                        match data.pop() {
+                            Some(0) => hidden_components.push(latest_id),
+                            Some(_) => {}
+                            None => return Err(DecodeError::NotEnoughBytes),
+                        }
+                    }
+                    components.push(DataComponent::Other {
+                        id: latest_id,
+                        data,
+                    });
+                    continue;
+                };
+                if version < ProtocolVersion::V1_21_5
+                    && has_tooltip_flag(latest_id)
+                    && !bool::decode(reader)?
+                {
+                    hidden_components.push(latest_id);
+                }
+                if data.is_some_and(|data| !data.is_empty()) {
+                    return Err(DecodeError::InvalidEnumValue(
+                        "data component length",
+                        len as i64,
+                    ));
+                }
+                components.push(component);
+            }
+        }
+    }
+    if hide_tooltip || !hidden_components.is_empty() {
+        components.push(DataComponent::TooltipDisplay {
+            hide_tooltip,
+            hidden_components,
+        });
+    }
+
+    let mut removed_components = Vec::with_capacity(removed.min(buf.len()));
+    for _ in 0..removed {
+        let id = read_varint(buf)?;
+        match component_type_name(version, id) {
+            Some("minecraft:hide_tooltip" | "minecraft:hide_additional_tooltip") => {}
+            _ => removed_components
+                .push(translate_type_id(id, version, latest).ok_or(unknown_type(id))?),
+        }
+    }
+    Ok((components, removed_components))
+}
+
+impl Encode for DataComponent {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        let id = self
+            .id_for(version)
+            .ok_or_else(|| unsupported_type(self.id(), version))?;
+        write_varint(buf, id)?;
+        self.encode_data(version, buf)?;
+        // On its own a component has no `tooltip_display` to hide it.
+        if version < ProtocolVersion::V1_21_5 && has_tooltip_flag(self.id()) {
+            true.encode(buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for DataComponent {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    /// Reads a component with its type ID. Fails on a type this crate can't decode, as the
+    /// length of its data is unknown.
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let id = read_varint(buf)?;
+        let component = match translate_type_id(id, version, ProtocolVersion::LATEST) {
+            Some(latest_id) => DataComponent::decode_data(latest_id, version, buf, 0)?,
+            None => None,
+        }
+        .ok_or(DecodeError::InvalidEnumValue(
+            "data component type",
+            id as i64,
+        ))?;
+        if version < ProtocolVersion::V1_21_5 && has_tooltip_flag(component.id()) {
+            bool::decode(buf)?;
+        }
+        Ok(component)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_match_type_names() {
+        let samples = [
+            (DataComponent::Damage(1), "minecraft:damage"),
+            (DataComponent::Unbreakable, "minecraft:unbreakable"),
+            (DataComponent::Lore(vec![]), "minecraft:lore"),
+            (
+                DataComponent::TooltipDisplay {
+                    hide_tooltip: false,
+                    hidden_components: vec![],
+                },
+                "minecraft:tooltip_display",
+            ),
+            (DataComponent::Enchantable(1), "minecraft:enchantable"),
+            (DataComponent::DyedColor(0), "minecraft:dyed_color"),
+            (DataComponent::Container(vec![]), "minecraft:container"),
+        ];
+        let latest = ProtocolVersion::LATEST;
+        for (component, name) in samples {
+            assert_eq!(component_type_name(latest, component.id()), Some(name));
+            assert_eq!(component_type_id(latest, name), Some(component.id()));
+        }
+        assert_eq!(component_type_id(latest, "food"), Some(20));
+        assert_eq!(component_type_id(latest, "villager/variant"), Some(72));
+        assert_eq!(component_type_id(latest, "shulker/color"), Some(95));
+        assert_eq!(component_type_name(latest, 96), None);
+        assert_eq!(component_type_name(latest, -1), None);
+    }
+
+    #[test]
+    fn test_v1_21_4_ids() {
+        let version = ProtocolVersion::V1_21_4;
+        assert_eq!(COMPONENT_TYPES_1_21_4.len(), 67);
+        assert_eq!(DataComponent::Damage(1).id_for(version), Some(3));
+        assert_eq!(DataComponent::RepairCost(1).id_for(version), Some(17));
+        assert_eq!(DataComponent::Enchantable(1).id_for(version), Some(27));
+        assert_eq!(DataComponent::DyedColor(0).id_for(version), Some(34));
+        assert_eq!(DataComponent::Container(vec![]).id_for(version), Some(62));
+        assert_eq!(component_type_id(version, "hide_tooltip"), Some(16));
+        assert_eq!(component_type_id(version, "tooltip_display"), None);
+
+        // minecraft:weapon is new in 1.21.5.
+        let weapon = DataComponent::Other {
+            id: component_type_id(ProtocolVersion::LATEST, "weapon").unwrap(),
+            data: vec![1],
+        };
+        assert_eq!(weapon.id_for(version), None);
+        assert!(weapon.encode_for(version, &mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn test_v1_21_4_components_carry_tooltip_flags() {
+        let version = ProtocolVersion::V1_21_4;
+        let component = DataComponent::DyedColor(0xA06540);
+        let mut buf = BytesMut::new();
+        component.encode_for(version, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), [34, 0, 0xA0, 0x65, 0x40, 1]);
+        assert_eq!(
+            DataComponent::decode_for(version, &mut buf).unwrap(),
+            component
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_components_round_trip() {
+        let components = [
+            DataComponent::CustomData(Nbt::from_snbt("{level: 3, tags: [a, b]}").unwrap()),
+            DataComponent::MaxStackSize(16),
+            DataComponent::Unbreakable,
+            DataComponent::CustomName(TextComponent::text("Sword").bold(true)),
+            DataComponent::Lore(vec!["one".into(), "two".into()]),
+            DataComponent::Rarity(Rarity::Epic),
+            DataComponent::Enchantments(vec![EnchantmentLevel {
+                enchantment: 12,
+                level: 5,
+            }]),
+            DataComponent::TooltipDisplay {
+                hide_tooltip: true,
+                hidden_components: vec![3, 10],
+            },
+            DataComponent::Food {
+                nutrition: 4,
+                saturation: 2.4,
+                can_always_eat: false,
+            },
+            DataComponent::DyedColor(0xA06540),
+            DataComponent::BundleContents(vec![ItemStack::new(1, 3)]),
+        ];
+        for component in components {
+            let mut buf = component.to_bytes().unwrap();
+            assert_eq!(DataComponent::decode(&mut buf).unwrap(), component);
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_unknown_types_fail_to_decode() {
+        // minecraft:tool, whose data this crate doesn't know how to skip.
+        let mut buf = BytesMut::from(&[25, 0, 0, 0, 0][..]);
+        assert!(matches!(
+            DataComponent::decode(&mut buf),
+            Err(DecodeError::InvalidEnumValue(_, 25))
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+//! Component hashes for [`HashedSlot`](super::HashedSlot).
+//!
+//! The client doesn't send component data back in Click Container; it sends a CRC32C of each
+//! component's codec value, serialized by vanilla's `HashOps`: a tag byte per value, numbers
+//! little-endian, strings as UTF-16 code units, and nested values replaced by their own hash.
+
+use crate::protocol::nbt::Tag;
+use crate::protocol::types::TextComponent;
+use serde_json::Value;
+
+const TAG_EMPTY: u8 = 1;
+const TAG_MAP_START: u8 = 2;
+const TAG_MAP_END: u8 = 3;
+const TAG_LIST_START: u8 = 4;
+const TAG_LIST_END: u8 = 5;
+const TAG_BYTE: u8 = 6;
+const TAG_SHORT: u8 = 7;
+const TAG_INT: u8 = 8;
+const TAG_LONG: u8 = 9;
+const TAG_FLOAT: u8 = 10;
+const TAG_DOUBLE: u8 = 11;
+const TAG_STRING: u8 = 12;
+const TAG_BOOLEAN: u8 = 13;
+const TAG_BYTE_ARRAY_START: u8 = 14;
+const TAG_BYTE_ARRAY_END: u8 = 15;
+const TAG_INT_ARRAY_START: u8 = 16;
+const TAG_INT_ARRAY_END: u8 = 17;
+const TAG_LONG_ARRAY_START: u8 = 18;
+const TAG_LONG_ARRAY_END: u8 = 19;
+
+/// CRC32C (Castagnoli) lookup table, reflected polynomial `0x82F63B78`.
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32c(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        CRC32C_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// A value as seen by `HashOps`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum HashValue {
+    Empty,
+    Bool(bool),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    ByteArray(Vec<i8>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+    List(Vec<HashValue>),
+    Map(Vec<(HashValue, HashValue)>),
+}
+
+impl HashValue {
+    /// A map with string keys, as a record codec produces.
+    pub(crate) fn record(fields: Vec<(&str, HashValue)>) -> Self {
+        HashValue::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (HashValue::String(key.to_string()), value))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn hash(&self) -> i32 {
+        let mut bytes = vec![];
+        self.write(&mut bytes);
+        crc32c(&bytes) as i32
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            HashValue::Empty => out.push(TAG_EMPTY),
+            HashValue::Bool(value) => out.extend([TAG_BOOLEAN, *value as u8]),
+            HashValue::Byte(value) => out.extend([TAG_BYTE, *value as u8]),
+            HashValue::Short(value) => {
+                out.push(TAG_SHORT);
+                out.extend(value.to_le_bytes());
+            }
+            HashValue::Int(value) => {
+                out.push(TAG_INT);
+                out.extend(value.to_le_bytes());
+            }
+            HashValue::Long(value) => {
+                out.push(TAG_LONG);
+                out.extend(value.to_le_bytes());
+            }
+            HashValue::Float(value) => {
+                out.push(TAG_FLOAT);
+                out.extend(value.to_le_bytes());
+            }
+            HashValue::Double(value) => {
+                out.push(TAG_DOUBLE);
+                out.extend(value.to_le_bytes());
+            }
+            HashValue::String(value) => {
+                out.push(TAG_STRING);
+                let units: Vec<u16> = value.encode_utf16().collect();
+                out.extend((units.len() as i32).to_le_bytes());
+                for unit in units {
+                    out.extend(unit.to_le_bytes());
+                }
+            }
+            HashValue::ByteArray(values) => {
+                out.push(TAG_BYTE_ARRAY_START);
+                out.extend(values.iter().map(|value| *value as u8));
+                out.push(TAG_BYTE_ARRAY_END);
+            }
+            HashValue::IntArray(values) => {
+                out.push(TAG_INT_ARRAY_START);
+                values
+                    .iter()
+                    .for_each(|value| out.extend(value.to_le_bytes()));
+                out.push(TAG_INT_ARRAY_END);
+            }
+            HashValue::LongArray(values) => {
+                out.push(TAG_LONG_ARRAY_START);
+                values
+                    .iter()
+                    .for_each(|value| out.extend(value.to_le_bytes()));
+                out.push(TAG_LONG_ARRAY_END);
+            }
+            HashValue::List(values) => {
+                out.push(TAG_LIST_START);
+                for value in values {
+                    out.extend(value.hash().to_le_bytes());
+                }
+                out.push(TAG_LIST_END);
+            }
+            HashValue::Map(entries) => {
+                // Entries are ordered by key hash, then value hash, both compared unsigned.
+                let mut hashes: Vec<(u32, u32)> = entries
+                    .iter()
+                    .map(|(key, value)| (key.hash() as u32, value.hash() as u32))
+                    .collect();
+                hashes.sort_unstable();
+                out.push(TAG_MAP_START);
+                for (key, value) in hashes {
+                    out.extend(key.to_le_bytes());
+                    out.extend(value.to_le_bytes());
+                }
+                out.push(TAG_MAP_END);
+            }
+        }
+    }
+}
+
+impl From<&Tag> for HashValue {
+    fn from(tag: &Tag) -> Self {
+        match tag {
+            Tag::End => HashValue::Empty,
+            Tag::Byte(value) => HashValue::Byte(*value),
+            Tag::Short(value) => HashValue::Short(*value),
+            Tag::Int(value) => HashValue::Int(*value),
+            Tag::Long(value) => HashValue::Long(*value),
+            Tag::Float(value) => HashValue::Float(*value),
+            Tag::Double(value) => HashValue::Double(*value),
+            Tag::ByteArray(values) => HashValue::ByteArray(values.clone()),
+            Tag::String(value) => HashValue::String(value.clone()),
+            Tag::List(values) => HashValue::List(values.iter().map(HashValue::from).collect()),
+            Tag::Compound(entries) => HashValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (HashValue::String(key.clone()), HashValue::from(value)))
+                    .collect(),
+            ),
+            Tag::IntArray(values) => HashValue::IntArray(values.clone()),
+            Tag::LongArray(values) => HashValue::LongArray(values.clone()),
+        }
+    }
+}
+
+impl From<&Value> for HashValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Null => HashValue::Empty,
+            Value::Bool(value) => HashValue::Bool(*value),
+            Value::Number(number) => match number.as_i64() {
+                Some(value) => match i32::try_from(value) {
+                    Ok(value) => HashValue::Int(value),
+                    Err(_) => HashValue::Long(value),
+                },
+                None => HashValue::Double(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => HashValue::String(value.clone()),
+            Value::Array(values) => HashValue::List(values.iter().map(HashValue::from).collect()),
+            Value::Object(entries) => HashValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (HashValue::String(key.clone()), HashValue::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<&TextComponent> for HashValue {
+    fn from(text: &TextComponent) -> Self {
+        // A plain string serializes as a bare string, like the text codec's short form.
+        serde_json::to_value(text)
+            .map(|value| HashValue::from(&value))
+            .unwrap_or(HashValue::Empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32c_check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(b""), 0);
+    }
+
+    #[test]
+    fn test_map_hash_ignores_entry_order() {
+        let a = HashValue::record(vec![("a", HashValue::Int(1)), ("b", HashValue::Bool(true))]);
+        let b = HashValue::record(vec![("b", HashValue::Bool(true)), ("a", HashValue::Int(1))]);
+        assert_eq!(a.hash(), b.hash());
+        assert_ne!(a.hash(), HashValue::Map(vec![]).hash());
+    }
+
+    #[test]
+    fn test_scalar_encoding() {
+        let mut bytes = vec![];
+        HashValue::Int(1).write(&mut bytes);
+        assert_eq!(bytes, [TAG_INT, 1, 0, 0, 0]);
+
+        let mut bytes = vec![];
+        HashValue::String("hé".into()).write(&mut bytes);
+        assert_eq!(bytes, [TAG_STRING, 2, 0, 0, 0, b'h', 0, 0xE9, 0]);
+    }
+}
@@ -0,0 +1,408 @@
+//! Item stacks as sent in slots.
+//!
+//! A [`ItemStack`] is an item ID, a count, and a patch against the item's default components:
+//! the [`DataComponent`]s it adds or overrides and the type IDs of those it removes. From 1.21.5
+//! the client echoes slots back in Click Container as a [`HashedSlot`], with each added component
+//! replaced by a hash of its value; [`ItemStack::hashed`] computes that form.
+
+mod component;
+mod hash;
+
+pub use component::{
+    DataComponent, EnchantmentLevel, Rarity, component_type_id, component_type_name,
+};
+
+use crate::protocol::encoding::{
+    Decode, DecodeResult, Encode, EncodeResult, read_varint_sync, write_varint_sync,
+};
+use crate::protocol::types::VarInt;
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+
+/// How deep item stacks may nest inside container components before decoding gives up.
+pub const MAX_NESTING: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemStack {
+    /// Zero or less for an empty slot, in which case nothing else is sent.
+    pub count: i32,
+    /// Network ID in the `minecraft:item` registry.
+    pub item_id: i32,
+    pub components: Vec<DataComponent>,
+    /// Type IDs of default components the stack doesn't have.
+    pub removed_components: Vec<i32>,
+}
+
+impl ItemStack {
+    pub const EMPTY: ItemStack = ItemStack {
+        count: 0,
+        item_id: 0,
+        components: Vec::new(),
+        removed_components: Vec::new(),
+    };
+
+    pub fn new(item_id: i32, count: i32) -> Self {
+        ItemStack {
+            count,
+            item_id,
+            ..ItemStack::EMPTY
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count <= 0
+    }
+
+    /// Adds `component`, replacing one of the same type.
+    pub fn with_component(mut self, component: DataComponent) -> Self {
+        let id = component.id();
+        self.removed_components.retain(|removed| *removed != id);
+        match self.components.iter_mut().find(|c| c.id() == id) {
+            Some(existing) => *existing = component,
+            None => self.components.push(component),
+        }
+        self
+    }
+
+    /// Removes the default component of type `id` from the stack.
+    pub fn without_component(mut self, id: i32) -> Self {
+        self.components.retain(|c| c.id() != id);
+        if !self.removed_components.contains(&id) {
+            self.removed_components.push(id);
+        }
+        self
+    }
+
+    pub fn component(&self, id: i32) -> Option<&DataComponent> {
+        self.components.iter().find(|c| c.id() == id)
+    }
+
+    /// The form the client sends this stack back in. `None` if a component's hash depends on
+    /// registry names this crate doesn't know: enchantments, nested items, or a component kept
+    /// as raw bytes.
+    pub fn hashed(&self) -> Option<HashedSlot> {
+        if self.is_empty() {
+            return Some(None);
+        }
+        let added_components = self
+            .components
+            .iter()
+            .map(|component| {
+                Some(HashedComponent {
+                    id: component.id(),
+                    hash: component.hash_value()?.hash(),
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Some(HashedItemStack {
+            item_id: self.item_id,
+            count: self.count,
+            added_components,
+            removed_components: self.removed_components.clone(),
+        }))
+    }
+
+    fn encode_with(
+        &self,
+        version: ProtocolVersion,
+        length_prefixed: bool,
+        buf: &mut BytesMut,
+    ) -> EncodeResult<()> {
+        if self.is_empty() {
+            return write_varint_sync(buf, VarInt(0));
+        }
+        write_varint_sync(buf, VarInt(self.count))?;
+        write_varint_sync(buf, VarInt(self.item_id))?;
+        component::encode_patch(
+            &self.components,
+            &self.removed_components,
+            version,
+            length_prefixed,
+            buf,
+        )
+    }
+
+    fn decode_with(
+        version: ProtocolVersion,
+        length_prefixed: bool,
+        buf: &mut BytesMut,
+        depth: usize,
+    ) -> DecodeResult<Self> {
+        let count = read_varint_sync(buf)?.0;
+        if count <= 0 {
+            return Ok(ItemStack::EMPTY);
+        }
+        let item_id = read_varint_sync(buf)?.0;
+        let (components, removed_components) =
+            component::decode_patch(version, length_prefixed, buf, depth)?;
+        Ok(ItemStack {
+            count,
+            item_id,
+            components,
+            removed_components,
+        })
+    }
+
+    pub(crate) fn decode_nested(
+        version: ProtocolVersion,
+        buf: &mut BytesMut,
+        depth: usize,
+    ) -> DecodeResult<Self> {
+        ItemStack::decode_with(version, false, buf, depth)
+    }
+}
+
+impl Encode for ItemStack {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_with(version, false, buf)
+    }
+}
+
+impl Decode for ItemStack {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        ItemStack::decode_nested(version, buf, 0)
+    }
+}
+
+/// A slot the client may have edited, as in Set Creative Mode Slot: each component's data is
+/// prefixed with its length, so components of any type can be read, unknown ones as
+/// [`DataComponent::Other`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UntrustedSlot(pub ItemStack);
+
+impl Encode for UntrustedSlot {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.0.encode_with(version, true, buf)
+    }
+}
+
+impl Decode for UntrustedSlot {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        ItemStack::decode_with(version, true, buf, 0).map(UntrustedSlot)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct HashedComponent {
+    #[packet(varint)]
+    pub id: i32,
+    /// CRC32C of the component's value.
+    pub hash: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct HashedItemStack {
+    #[packet(varint)]
+    pub item_id: i32,
+    #[packet(varint)]
+    pub count: i32,
+    pub added_components: Vec<HashedComponent>,
+    #[packet(prefixed, varint)]
+    pub removed_components: Vec<i32>,
+}
+
+/// A slot as the client echoes it back; `None` for an empty slot.
+pub type HashedSlot = Option<HashedItemStack>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+    use crate::protocol::types::{Nbt, TextComponent};
+
+    fn sword() -> ItemStack {
+        ItemStack::new(880, 1)
+            .with_component(DataComponent::Damage(12))
+            .with_component(DataComponent::CustomName(TextComponent::text("Edge")))
+            .without_component(
+                component_type_id(ProtocolVersion::LATEST, "tooltip_display").unwrap(),
+            )
+    }
+
+    #[test]
+    fn test_empty_slot_is_a_zero_count() {
+        assert_eq!(ItemStack::EMPTY.to_bytes().unwrap().as_ref(), [0]);
+        // Anything after a zero count belongs to the next field.
+        let mut buf = BytesMut::from(&[0, 7][..]);
+        assert!(ItemStack::decode(&mut buf).unwrap().is_empty());
+        assert_eq!(buf.as_ref(), [7]);
+    }
+
+    #[test]
+    fn test_item_stack_layout() {
+        let stack = ItemStack::new(1, 64)
+            .with_component(DataComponent::MaxStackSize(99))
+            .without_component(4);
+        assert_eq!(stack.to_bytes().unwrap().as_ref(), [64, 1, 1, 1, 1, 99, 4]);
+        assert_round_trip(&stack);
+        assert_round_trip(&sword());
+    }
+
+    #[test]
+    fn test_with_component_replaces() {
+        let stack = sword().with_component(DataComponent::Damage(3));
+        assert_eq!(stack.components.len(), 2);
+        assert_eq!(stack.component(3), Some(&DataComponent::Damage(3)));
+        let stack = stack.without_component(3);
+        assert_eq!(stack.component(3), None);
+        assert_eq!(stack.removed_components, [15, 3]);
+    }
+
+    #[test]
+    fn test_nested_items_are_limited() {
+        let mut stack = ItemStack::new(1, 1);
+        for _ in 0..MAX_NESTING + 1 {
+            stack = ItemStack::new(1, 1).with_component(DataComponent::BundleContents(vec![stack]));
+        }
+        let mut buf = stack.to_bytes().unwrap();
+        assert!(ItemStack::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_untrusted_slot_keeps_unknown_components() {
+        let stack = ItemStack::new(5, 2)
+            .with_component(DataComponent::Other {
+                id: 25,
+                data: vec![0, 0x3F, 0x80, 0, 0, 1, 0],
+            })
+            .with_component(DataComponent::CustomData(
+                Nbt::from_snbt("{owner: steve}").unwrap(),
+            ));
+        let slot = UntrustedSlot(stack);
+        let mut buf = slot.to_bytes().unwrap();
+        assert_eq!(&buf[..6], [2, 5, 2, 0, 25, 7]);
+        assert_eq!(UntrustedSlot::decode(&mut buf).unwrap(), slot);
+
+        // A known component that doesn't fill its length is rejected.
+        let mut buf = BytesMut::from(&[1, 5, 1, 0, 3, 2, 1, 0][..]);
+        assert!(UntrustedSlot::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_v1_21_4_stack_layout() {
+        let version = ProtocolVersion::V1_21_4;
+        let latest_id = |name| component_type_id(ProtocolVersion::LATEST, name).unwrap();
+        let stack = ItemStack::new(1, 1)
+            .with_component(DataComponent::Unbreakable)
+            .with_component(DataComponent::RepairCost(3))
+            .with_component(DataComponent::TooltipDisplay {
+                hide_tooltip: true,
+                hidden_components: vec![latest_id("unbreakable"), latest_id("bundle_contents")],
+            })
+            .without_component(latest_id("tooltip_style"))
+            .without_component(latest_id("weapon"));
+        let mut buf = BytesMut::new();
+        stack.encode_for(version, &mut buf).unwrap();
+        // Unbreakable with its flag off, repair_cost, the two hide markers, and only the removed
+        // type 1.21.4 has.
+        assert_eq!(buf.as_ref(), [1, 1, 4, 1, 4, 0, 17, 3, 16, 15, 31]);
+
+        let decoded = ItemStack::decode_for(version, &mut buf).unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(decoded.components[..2], stack.components[..2]);
+        let Some(DataComponent::TooltipDisplay {
+            hide_tooltip: true,
+            hidden_components,
+        }) = decoded.component(latest_id("tooltip_display"))
+        else {
+            panic!("expected a tooltip_display: {:?}", decoded.components);
+        };
+        assert!(hidden_components.contains(&latest_id("unbreakable")));
+        assert!(hidden_components.contains(&latest_id("bundle_contents")));
+        assert_eq!(decoded.removed_components, [latest_id("tooltip_style")]);
+
+        let weapon = ItemStack::new(1, 1).with_component(DataComponent::Other {
+            id: latest_id("weapon"),
+            data: vec![1, 0, 0, 0, 0],
+        });
+        assert!(weapon.encode_for(version, &mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn test_v1_21_4_untrusted_slot_translates_ids() {
+        let version = ProtocolVersion::V1_21_4;
+        // minecraft:tool is type 25 in 1.21.5 and 26 in 1.21.4.
+        let slot = UntrustedSlot(ItemStack::new(5, 2).with_component(DataComponent::Other {
+            id: 25,
+            data: vec![0],
+        }));
+        let mut buf = BytesMut::new();
+        slot.encode_for(version, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), [2, 5, 1, 0, 26, 1, 0]);
+        assert_eq!(UntrustedSlot::decode_for(version, &mut buf).unwrap(), slot);
+    }
+
+    #[test]
+    fn test_v1_21_4_untrusted_slot_translates_tooltip_flags() {
+        let version = ProtocolVersion::V1_21_4;
+        let trim = component_type_id(ProtocolVersion::LATEST, "trim").unwrap();
+        let old_trim = component_type_id(version, "trim").unwrap() as u8;
+        let shown = UntrustedSlot(ItemStack::new(5, 1).with_component(DataComponent::Other {
+            id: trim,
+            data: vec![1, 2],
+        }));
+        let mut buf = BytesMut::new();
+        shown.encode_for(version, &mut buf).unwrap();
+        // The trim's material and pattern, then its show_in_tooltip flag.
+        assert_eq!(buf.as_ref(), [1, 5, 1, 0, old_trim, 3, 1, 2, 1]);
+        assert_eq!(UntrustedSlot::decode_for(version, &mut buf).unwrap(), shown);
+
+        let hidden = UntrustedSlot(shown.0.with_component(DataComponent::TooltipDisplay {
+            hide_tooltip: false,
+            hidden_components: vec![trim],
+        }));
+        let mut buf = BytesMut::new();
+        hidden.encode_for(version, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), [1, 5, 1, 0, old_trim, 3, 1, 2, 0]);
+        assert_eq!(UntrustedSlot::decode_for(version, &mut buf).unwrap(), hidden);
+    }
+
+    #[test]
+    fn test_untrusted_slot_keeps_entity_variants() {
+        let wolf_variant = component_type_id(ProtocolVersion::LATEST, "wolf/variant").unwrap();
+        let mut buf = BytesMut::from(&[1, 9, 1, 0, wolf_variant as u8, 1, 2][..]);
+        let slot = UntrustedSlot::decode(&mut buf).unwrap();
+        assert_eq!(
+            slot.0.components,
+            [DataComponent::Other {
+                id: wolf_variant,
+                data: vec![2],
+            }]
+        );
+        assert!(slot.encode_for(ProtocolVersion::V1_21_4, &mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn test_hashed_slot() {
+        assert_eq!(ItemStack::EMPTY.hashed(), Some(None));
+
+        let hashed = sword().hashed().unwrap().unwrap();
+        assert_eq!((hashed.item_id, hashed.count), (880, 1));
+        assert_eq!(hashed.removed_components, [15]);
+        assert_eq!(hashed.added_components[0].id, 3);
+        assert_eq!(
+            hashed.added_components[0].hash,
+            hash::crc32c(&[8, 12, 0, 0, 0]) as i32
+        );
+        assert_round_trip(&Some(hashed));
+
+        let enchanted = sword().with_component(DataComponent::Enchantments(vec![]));
+        assert_eq!(enchanted.hashed(), None);
+    }
+}
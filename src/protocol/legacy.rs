@@ -0,0 +1,199 @@
+//! Server list pings from clients older than 1.7.
+//!
+//! These clients open with a `0xFE` byte instead of a VarInt length, and expect a single kick
+//! packet (`0xFF`, then a UTF-16BE string prefixed with its length in code units) holding the
+//! status fields. Beta 1.8 to 1.3 send only `0xFE`; 1.4 and 1.5 add the payload byte `0x01`;
+//! 1.6 follows it with an `MC|PingHost` plugin message naming the address it connected to.
+
+use crate::protocol::encoding::DecodeResult;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// First byte of a legacy ping. A modern handshake would need a length prefix of at least 254 to
+/// start with it, which no real client sends.
+pub const LEGACY_PING_ID: u8 = 0xFE;
+/// Packet ID of the kick packet carrying the response.
+pub const LEGACY_KICK_ID: u8 = 0xFF;
+/// Packet ID of the plugin message a 1.6 client sends after the ping.
+const LEGACY_PLUGIN_MESSAGE_ID: u8 = 0xFA;
+/// How long to wait for the bytes that tell the ping formats apart. Beta clients send nothing
+/// after `0xFE`, so the only way to recognise them is that nothing arrives.
+const FORMAT_WAIT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3.
+    Beta,
+    /// 1.4 and 1.5.
+    V1_4,
+    /// 1.6, which also says which address and port it pinged.
+    V1_6 {
+        protocol_version: u8,
+        hostname: String,
+        port: i32,
+    },
+}
+
+/// The status fields a legacy client can show.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyStatus {
+    pub protocol: i32,
+    pub version_name: String,
+    pub motd: String,
+    pub online_players: i32,
+    pub max_players: i32,
+}
+
+async fn read_legacy_string<R: AsyncRead + Unpin>(reader: &mut R) -> DecodeResult<String> {
+    let len = reader.read_u16().await?;
+    let mut units = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        units.push(reader.read_u16().await?);
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+async fn read_within<R: AsyncRead + Unpin>(reader: &mut R) -> DecodeResult<Option<u8>> {
+    match tokio::time::timeout(FORMAT_WAIT, reader.read_u8()).await {
+        Ok(Ok(byte)) => Ok(Some(byte)),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Reads the rest of a legacy ping, after its [`LEGACY_PING_ID`] byte.
+pub async fn read_legacy_ping<R: AsyncRead + Unpin>(reader: &mut R) -> DecodeResult<LegacyPing> {
+    if read_within(reader).await? != Some(0x01) {
+        return Ok(LegacyPing::Beta);
+    }
+    if read_within(reader).await? != Some(LEGACY_PLUGIN_MESSAGE_ID) {
+        return Ok(LegacyPing::V1_4);
+    }
+
+    // MC|PingHost: channel name, then a length-prefixed body.
+    let _channel = read_legacy_string(reader).await?;
+    let _body_len = reader.read_u16().await?;
+    let protocol_version = reader.read_u8().await?;
+    let hostname = read_legacy_string(reader).await?;
+    let port = reader.read_i32().await?;
+    Ok(LegacyPing::V1_6 {
+        protocol_version,
+        hostname,
+        port,
+    })
+}
+
+/// Removes `§` formatting codes, which the beta response can't carry: it uses `§` as its
+/// field separator.
+fn strip_formatting(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Builds the kick packet answering `ping`.
+pub fn legacy_status_response(ping: &LegacyPing, status: &LegacyStatus) -> Vec<u8> {
+    let text = match ping {
+        LegacyPing::Beta => format!(
+            "{}§{}§{}",
+            strip_formatting(&status.motd),
+            status.online_players,
+            status.max_players
+        ),
+        LegacyPing::V1_4 | LegacyPing::V1_6 { .. } => format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            status.protocol,
+            status.version_name,
+            status.motd,
+            status.online_players,
+            status.max_players
+        ),
+    };
+
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let mut response = Vec::with_capacity(3 + units.len() * 2);
+    response.push(LEGACY_KICK_ID);
+    response.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        response.extend_from_slice(&unit.to_be_bytes());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> LegacyStatus {
+        LegacyStatus {
+            protocol: 770,
+            version_name: "1.21.5".to_string(),
+            motd: "§aHello".to_string(),
+            online_players: 3,
+            max_players: 20,
+        }
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    fn legacy_string(text: &str) -> Vec<u8> {
+        let mut bytes = (text.encode_utf16().count() as u16).to_be_bytes().to_vec();
+        bytes.extend(utf16be(text));
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_reads_each_ping_format() {
+        assert_eq!(
+            read_legacy_ping(&mut &b""[..]).await.unwrap(),
+            LegacyPing::Beta
+        );
+        assert_eq!(
+            read_legacy_ping(&mut &[0x01][..]).await.unwrap(),
+            LegacyPing::V1_4
+        );
+
+        let mut body = vec![73];
+        body.extend(legacy_string("localhost"));
+        body.extend(25565i32.to_be_bytes());
+        let mut ping = vec![0x01, LEGACY_PLUGIN_MESSAGE_ID];
+        ping.extend(legacy_string("MC|PingHost"));
+        ping.extend((body.len() as u16).to_be_bytes());
+        ping.extend(body);
+        assert_eq!(
+            read_legacy_ping(&mut &ping[..]).await.unwrap(),
+            LegacyPing::V1_6 {
+                protocol_version: 73,
+                hostname: "localhost".to_string(),
+                port: 25565,
+            }
+        );
+    }
+
+    #[test]
+    fn test_beta_response_strips_formatting() {
+        let response = legacy_status_response(&LegacyPing::Beta, &status());
+        assert_eq!(response[0], LEGACY_KICK_ID);
+        assert_eq!(&response[1..], &legacy_string("Hello§3§20")[..]);
+    }
+
+    #[test]
+    fn test_modern_legacy_response() {
+        let response = legacy_status_response(&LegacyPing::V1_4, &status());
+        assert_eq!(response[0], LEGACY_KICK_ID);
+        assert_eq!(
+            &response[1..],
+            &legacy_string(&["§1", "770", "1.21.5", "§aHello", "3", "20"].join("\0"))[..]
+        );
+    }
+}
@@ -1,12 +1,16 @@
-use crate::protocol::types::{Identifier, JsonTextComponent, Property, VarInt};
+use crate::protocol::encoding::{Decode, Encode, Packet};
+use crate::protocol::types::{Identifier, JsonTextComponent, VarInt};
+use serde::Deserialize;
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x00)]
 pub struct DisconnectLogin {
     pub reason: JsonTextComponent,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x01)]
 pub struct EncryptionRequest {
     pub server_id: String,
     pub public_key: Vec<u8>,
@@ -14,34 +18,41 @@ pub struct EncryptionRequest {
     pub should_authenticate: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x02)]
 pub struct LoginSuccess {
     pub uuid: Uuid,
     pub username: String,
     pub properties: Vec<LoginProperty>,
+    /// Not part of the current login layout, so it is never written.
+    #[packet(skip)]
     pub strict_error_handling: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Deserialize)]
 pub struct LoginProperty {
     pub name: String,
     pub value: String,
     pub signature: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x03)]
 pub struct SetCompression {
     pub threshold: VarInt,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x04)]
 pub struct LoginPluginRequest {
     pub message_id: VarInt,
     pub channel: String,
+    #[packet(rest)]
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x05)]
 pub struct CookieRequestLogin {
     pub key: Identifier,
 }
@@ -49,7 +60,6 @@ pub struct CookieRequestLogin {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::types::Property;
     use uuid::Uuid;
 
     #[test]
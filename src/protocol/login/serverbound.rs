@@ -1,28 +1,36 @@
+use crate::protocol::encoding::Packet;
 use crate::protocol::types::{Identifier, VarInt};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x00)]
 pub struct LoginStart {
     pub name: String,
     pub player_uuid: Uuid,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x01)]
 pub struct EncryptionResponse {
     pub shared_secret: Vec<u8>,
     pub verify_token: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x02)]
 pub struct LoginPluginResponse {
     pub message_id: VarInt,
+    /// The response data has no length prefix: it fills the rest of the packet after the success flag.
+    #[packet(optional, rest)]
     pub data: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Packet)]
+#[packet(id = 0x03)]
 pub struct LoginAcknowledged;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x04)]
 pub struct CookieResponseLogin {
     pub key: Identifier,
     pub payload: Option<Vec<u8>>,
@@ -31,6 +39,7 @@ pub struct CookieResponseLogin {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::encoding::assert_round_trip;
     use uuid::Uuid;
 
     #[test]
@@ -73,8 +82,8 @@ mod tests {
 
     #[test]
     fn test_login_acknowledged_instantiation() {
-        let _packet = LoginAcknowledged;
-        assert!(true);
+        let packet = LoginAcknowledged;
+        assert_round_trip(&packet);
     }
 
     #[test]
@@ -0,0 +1,844 @@
+//! Entity metadata, as sent in Set Entity Metadata.
+//!
+//! Metadata is a set of indexed entries, each tagged with the serializer type of its value.
+//! What an index means depends on the entity class; the base `Entity` indices every entity shares
+//! are in [`index`], and [`EntityMetadata`] has typed accessors for the most used ones.
+//! Serializer type IDs differ between versions: 1.21.5 dropped the NBT type and added the cow,
+//! wolf sound, pig and chicken variants.
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_varint_sync, unsupported_in,
+    write_varint_sync,
+};
+use crate::protocol::particle::Particle;
+use crate::protocol::types::{
+    IdOr, Identifier, Nbt, Position, Slot, TextComponent, VarInt, VarLong,
+};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Ends the entry list. Never a valid index.
+pub const TERMINATOR: u8 = 0xFF;
+
+/// Names of the `EntityDataSerializers` of [`ProtocolVersion::LATEST`], indexed by type ID.
+const SERIALIZERS: &[&str] = &[
+    "byte",
+    "int",
+    "long",
+    "float",
+    "string",
+    "component",
+    "optional_component",
+    "item_stack",
+    "boolean",
+    "rotations",
+    "block_pos",
+    "optional_block_pos",
+    "direction",
+    "optional_uuid",
+    "block_state",
+    "optional_block_state",
+    "particle",
+    "particles",
+    "villager_data",
+    "optional_unsigned_int",
+    "pose",
+    "cat_variant",
+    "cow_variant",
+    "wolf_variant",
+    "wolf_sound_variant",
+    "frog_variant",
+    "pig_variant",
+    "chicken_variant",
+    "optional_global_pos",
+    "painting_variant",
+    "sniffer_state",
+    "armadillo_state",
+    "vector3",
+    "quaternion",
+];
+
+/// Names of the 1.21.4 serializers.
+const SERIALIZERS_1_21_4: &[&str] = &[
+    "byte",
+    "int",
+    "long",
+    "float",
+    "string",
+    "component",
+    "optional_component",
+    "item_stack",
+    "boolean",
+    "rotations",
+    "block_pos",
+    "optional_block_pos",
+    "direction",
+    "optional_uuid",
+    "block_state",
+    "optional_block_state",
+    "compound_tag",
+    "particle",
+    "particles",
+    "villager_data",
+    "optional_unsigned_int",
+    "pose",
+    "cat_variant",
+    "wolf_variant",
+    "frog_variant",
+    "optional_global_pos",
+    "painting_variant",
+    "sniffer_state",
+    "armadillo_state",
+    "vector3",
+    "quaternion",
+];
+
+fn serializers(version: ProtocolVersion) -> &'static [&'static str] {
+    match version {
+        ProtocolVersion::V1_21_4 => SERIALIZERS_1_21_4,
+        ProtocolVersion::V1_21_5 => SERIALIZERS,
+    }
+}
+
+/// Indices of the base `Entity` entries.
+pub mod index {
+    /// [`EntityFlags`](super::EntityFlags), a byte.
+    pub const FLAGS: u8 = 0;
+    pub const AIR_SUPPLY: u8 = 1;
+    pub const CUSTOM_NAME: u8 = 2;
+    pub const CUSTOM_NAME_VISIBLE: u8 = 3;
+    pub const SILENT: u8 = 4;
+    pub const NO_GRAVITY: u8 = 5;
+    pub const POSE: u8 = 6;
+    pub const TICKS_FROZEN: u8 = 7;
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct EntityFlags: u8 {
+        const ON_FIRE = 0x01;
+        const SNEAKING = 0x02;
+        const SPRINTING = 0x08;
+        const SWIMMING = 0x10;
+        const INVISIBLE = 0x20;
+        const GLOWING = 0x40;
+        const FALL_FLYING = 0x80;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Down = 0,
+    Up = 1,
+    North = 2,
+    South = 3,
+    West = 4,
+    East = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pose {
+    #[default]
+    Standing = 0,
+    FallFlying = 1,
+    Sleeping = 2,
+    Swimming = 3,
+    SpinAttack = 4,
+    Crouching = 5,
+    LongJumping = 6,
+    Dying = 7,
+    Croaking = 8,
+    UsingTongue = 9,
+    Sitting = 10,
+    Roaring = 11,
+    Sniffing = 12,
+    Emerging = 13,
+    Digging = 14,
+    Sliding = 15,
+    Shooting = 16,
+    Inhaling = 17,
+}
+
+impl TryFrom<i32> for Direction {
+    type Error = ();
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Direction::Down,
+            1 => Direction::Up,
+            2 => Direction::North,
+            3 => Direction::South,
+            4 => Direction::West,
+            5 => Direction::East,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl TryFrom<i32> for Pose {
+    type Error = ();
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Pose::Standing,
+            1 => Pose::FallFlying,
+            2 => Pose::Sleeping,
+            3 => Pose::Swimming,
+            4 => Pose::SpinAttack,
+            5 => Pose::Crouching,
+            6 => Pose::LongJumping,
+            7 => Pose::Dying,
+            8 => Pose::Croaking,
+            9 => Pose::UsingTongue,
+            10 => Pose::Sitting,
+            11 => Pose::Roaring,
+            12 => Pose::Sniffing,
+            13 => Pose::Emerging,
+            14 => Pose::Digging,
+            15 => Pose::Sliding,
+            16 => Pose::Shooting,
+            17 => Pose::Inhaling,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// An inline painting variant, sent when it isn't one of the registry's.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct PaintingVariant {
+    #[packet(varint)]
+    pub width: i32,
+    #[packet(varint)]
+    pub height: i32,
+    pub asset_id: Identifier,
+    pub title: Option<TextComponent>,
+    pub author: Option<TextComponent>,
+}
+
+/// A metadata value, tagged on the wire with the ID of its `EntityDataSerializers` type in the
+/// version being encoded for. The values of the `*Variant` and state entries are network IDs in
+/// the matching registries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(i32),
+    VarLong(i64),
+    Float(f32),
+    String(String),
+    Text(TextComponent),
+    OptionalText(Option<TextComponent>),
+    Slot(Slot),
+    Boolean(bool),
+    Rotations {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    Position(Position),
+    OptionalPosition(Option<Position>),
+    Direction(Direction),
+    OptionalLivingEntity(Option<Uuid>),
+    BlockState(i32),
+    /// Sent as zero for `None`, as zero is air.
+    OptionalBlockState(Option<i32>),
+    /// Only in 1.21.4, where players carry their shoulder parrots as NBT.
+    Nbt(Nbt),
+    Particle(Particle),
+    Particles(Vec<Particle>),
+    VillagerData {
+        villager_type: i32,
+        profession: i32,
+        level: i32,
+    },
+    /// Sent as zero for `None` and the value plus one otherwise.
+    OptionalVarInt(Option<i32>),
+    Pose(Pose),
+    CatVariant(i32),
+    CowVariant(i32),
+    WolfVariant(i32),
+    WolfSoundVariant(i32),
+    FrogVariant(i32),
+    PigVariant(i32),
+    ChickenVariant(i32),
+    OptionalGlobalPosition(Option<(Identifier, Position)>),
+    PaintingVariant(IdOr<PaintingVariant>),
+    SnifferState(i32),
+    ArmadilloState(i32),
+    Vector3 {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    Quaternion {
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    },
+}
+
+impl MetadataValue {
+    /// The name of the value's serializer type, such as `optional_block_pos`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MetadataValue::Byte(_) => "byte",
+            MetadataValue::VarInt(_) => "int",
+            MetadataValue::VarLong(_) => "long",
+            MetadataValue::Float(_) => "float",
+            MetadataValue::String(_) => "string",
+            MetadataValue::Text(_) => "component",
+            MetadataValue::OptionalText(_) => "optional_component",
+            MetadataValue::Slot(_) => "item_stack",
+            MetadataValue::Boolean(_) => "boolean",
+            MetadataValue::Rotations { .. } => "rotations",
+            MetadataValue::Position(_) => "block_pos",
+            MetadataValue::OptionalPosition(_) => "optional_block_pos",
+            MetadataValue::Direction(_) => "direction",
+            MetadataValue::OptionalLivingEntity(_) => "optional_uuid",
+            MetadataValue::BlockState(_) => "block_state",
+            MetadataValue::OptionalBlockState(_) => "optional_block_state",
+            MetadataValue::Nbt(_) => "compound_tag",
+            MetadataValue::Particle(_) => "particle",
+            MetadataValue::Particles(_) => "particles",
+            MetadataValue::VillagerData { .. } => "villager_data",
+            MetadataValue::OptionalVarInt(_) => "optional_unsigned_int",
+            MetadataValue::Pose(_) => "pose",
+            MetadataValue::CatVariant(_) => "cat_variant",
+            MetadataValue::CowVariant(_) => "cow_variant",
+            MetadataValue::WolfVariant(_) => "wolf_variant",
+            MetadataValue::WolfSoundVariant(_) => "wolf_sound_variant",
+            MetadataValue::FrogVariant(_) => "frog_variant",
+            MetadataValue::PigVariant(_) => "pig_variant",
+            MetadataValue::ChickenVariant(_) => "chicken_variant",
+            MetadataValue::OptionalGlobalPosition(_) => "optional_global_pos",
+            MetadataValue::PaintingVariant(_) => "painting_variant",
+            MetadataValue::SnifferState(_) => "sniffer_state",
+            MetadataValue::ArmadilloState(_) => "armadillo_state",
+            MetadataValue::Vector3 { .. } => "vector3",
+            MetadataValue::Quaternion { .. } => "quaternion",
+        }
+    }
+
+    /// The value's serializer type ID in `version`, or `None` if `version` doesn't have its type.
+    pub fn type_id(&self, version: ProtocolVersion) -> Option<i32> {
+        let name = self.type_name();
+        serializers(version)
+            .iter()
+            .position(|known| *known == name)
+            .map(|id| id as i32)
+    }
+}
+
+impl Encode for MetadataValue {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        let type_id = self.type_id(version).ok_or_else(|| {
+            unsupported_in(&format!("metadata type {}", self.type_name()), version)
+        })?;
+        write_varint_sync(buf, VarInt(type_id))?;
+        match self {
+            MetadataValue::Byte(value) => value.encode(buf),
+            MetadataValue::VarInt(value)
+            | MetadataValue::BlockState(value)
+            | MetadataValue::CatVariant(value)
+            | MetadataValue::CowVariant(value)
+            | MetadataValue::WolfVariant(value)
+            | MetadataValue::WolfSoundVariant(value)
+            | MetadataValue::FrogVariant(value)
+            | MetadataValue::PigVariant(value)
+            | MetadataValue::ChickenVariant(value)
+            | MetadataValue::SnifferState(value)
+            | MetadataValue::ArmadilloState(value) => write_varint_sync(buf, VarInt(*value)),
+            MetadataValue::VarLong(value) => VarLong(*value).encode(buf),
+            MetadataValue::Float(value) => value.encode(buf),
+            MetadataValue::String(value) => value.encode(buf),
+            MetadataValue::Text(text) => text.encode_for(version, buf),
+            MetadataValue::OptionalText(text) => text.encode_for(version, buf),
+            MetadataValue::Slot(slot) => slot.encode_for(version, buf),
+            MetadataValue::Boolean(value) => value.encode(buf),
+            MetadataValue::Rotations { x, y, z } | MetadataValue::Vector3 { x, y, z } => {
+                (*x, *y, *z).encode(buf)
+            }
+            MetadataValue::Position(position) => position.encode(buf),
+            MetadataValue::OptionalPosition(position) => position.encode(buf),
+            MetadataValue::Direction(direction) => {
+                write_varint_sync(buf, VarInt(*direction as i32))
+            }
+            MetadataValue::OptionalLivingEntity(uuid) => uuid.encode(buf),
+            MetadataValue::OptionalBlockState(state) => {
+                write_varint_sync(buf, VarInt(state.unwrap_or(0)))
+            }
+            MetadataValue::Nbt(nbt) => nbt.encode_for(version, buf),
+            MetadataValue::Particle(particle) => particle.encode_for(version, buf),
+            MetadataValue::Particles(particles) => particles.encode_for(version, buf),
+            MetadataValue::VillagerData {
+                villager_type,
+                profession,
+                level,
+            } => {
+                write_varint_sync(buf, VarInt(*villager_type))?;
+                write_varint_sync(buf, VarInt(*profession))?;
+                write_varint_sync(buf, VarInt(*level))
+            }
+            MetadataValue::OptionalVarInt(value) => {
+                write_varint_sync(buf, VarInt(value.map_or(0, |value| value + 1)))
+            }
+            MetadataValue::Pose(pose) => write_varint_sync(buf, VarInt(*pose as i32)),
+            MetadataValue::OptionalGlobalPosition(position) => position.encode(buf),
+            MetadataValue::PaintingVariant(variant) => variant.encode_for(version, buf),
+            MetadataValue::Quaternion { x, y, z, w } => {
+                (*x, *y, *z).encode(buf)?;
+                w.encode(buf)
+            }
+        }
+    }
+}
+
+impl Decode for MetadataValue {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let varint = |buf: &mut BytesMut| -> DecodeResult<i32> { Ok(read_varint_sync(buf)?.0) };
+        let type_id = varint(buf)?;
+        let name = usize::try_from(type_id)
+            .ok()
+            .and_then(|id| serializers(version).get(id))
+            .ok_or(DecodeError::InvalidEnumValue(
+                "metadata value type",
+                type_id as i64,
+            ))?;
+        Ok(match *name {
+            "byte" => MetadataValue::Byte(i8::decode(buf)?),
+            "int" => MetadataValue::VarInt(varint(buf)?),
+            "long" => MetadataValue::VarLong(VarLong::decode(buf)?.0),
+            "float" => MetadataValue::Float(f32::decode(buf)?),
+            "string" => MetadataValue::String(String::decode(buf)?),
+            "component" => MetadataValue::Text(TextComponent::decode_for(version, buf)?),
+            "optional_component" => MetadataValue::OptionalText(Option::decode_for(version, buf)?),
+            "item_stack" => MetadataValue::Slot(Slot::decode_for(version, buf)?),
+            "boolean" => MetadataValue::Boolean(bool::decode(buf)?),
+            "rotations" => {
+                let (x, y, z) = <(f32, f32, f32)>::decode(buf)?;
+                MetadataValue::Rotations { x, y, z }
+            }
+            "block_pos" => MetadataValue::Position(Position::decode(buf)?),
+            "optional_block_pos" => MetadataValue::OptionalPosition(Option::decode(buf)?),
+            "direction" => {
+                let value = varint(buf)?;
+                MetadataValue::Direction(
+                    Direction::try_from(value)
+                        .map_err(|_| DecodeError::InvalidEnumValue("Direction", value as i64))?,
+                )
+            }
+            "optional_uuid" => MetadataValue::OptionalLivingEntity(Option::decode(buf)?),
+            "block_state" => MetadataValue::BlockState(varint(buf)?),
+            "optional_block_state" => {
+                MetadataValue::OptionalBlockState(Some(varint(buf)?).filter(|state| *state != 0))
+            }
+            "compound_tag" => MetadataValue::Nbt(Nbt::decode(buf)?),
+            "particle" => MetadataValue::Particle(Particle::decode_for(version, buf)?),
+            "particles" => MetadataValue::Particles(Vec::decode_for(version, buf)?),
+            "villager_data" => MetadataValue::VillagerData {
+                villager_type: varint(buf)?,
+                profession: varint(buf)?,
+                level: varint(buf)?,
+            },
+            "optional_unsigned_int" => MetadataValue::OptionalVarInt(match varint(buf)? {
+                0 => None,
+                value => Some(value - 1),
+            }),
+            "pose" => {
+                let value = varint(buf)?;
+                MetadataValue::Pose(
+                    Pose::try_from(value)
+                        .map_err(|_| DecodeError::InvalidEnumValue("Pose", value as i64))?,
+                )
+            }
+            "cat_variant" => MetadataValue::CatVariant(varint(buf)?),
+            "cow_variant" => MetadataValue::CowVariant(varint(buf)?),
+            "wolf_variant" => MetadataValue::WolfVariant(varint(buf)?),
+            "wolf_sound_variant" => MetadataValue::WolfSoundVariant(varint(buf)?),
+            "frog_variant" => MetadataValue::FrogVariant(varint(buf)?),
+            "pig_variant" => MetadataValue::PigVariant(varint(buf)?),
+            "chicken_variant" => MetadataValue::ChickenVariant(varint(buf)?),
+            "optional_global_pos" => MetadataValue::OptionalGlobalPosition(Option::decode(buf)?),
+            "painting_variant" => MetadataValue::PaintingVariant(IdOr::decode_for(version, buf)?),
+            "sniffer_state" => MetadataValue::SnifferState(varint(buf)?),
+            "armadillo_state" => MetadataValue::ArmadilloState(varint(buf)?),
+            "vector3" => {
+                let (x, y, z) = <(f32, f32, f32)>::decode(buf)?;
+                MetadataValue::Vector3 { x, y, z }
+            }
+            "quaternion" => {
+                let (x, y, z) = <(f32, f32, f32)>::decode(buf)?;
+                let w = f32::decode(buf)?;
+                MetadataValue::Quaternion { x, y, z, w }
+            }
+            _ => unreachable!("every serializer name has a variant"),
+        })
+    }
+}
+
+/// Entries keyed by index; they are written in index order, then the terminator.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityMetadata {
+    entries: BTreeMap<u8, MetadataValue>,
+}
+
+impl EntityMetadata {
+    pub fn new() -> Self {
+        EntityMetadata::default()
+    }
+
+    /// Sets the entry at `index`, replacing any previous value.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is [`TERMINATOR`].
+    pub fn set(&mut self, index: u8, value: MetadataValue) -> &mut Self {
+        assert_ne!(index, TERMINATOR, "0xFF terminates entity metadata");
+        self.entries.insert(index, value);
+        self
+    }
+
+    pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+        self.entries.get(&index)
+    }
+
+    pub fn remove(&mut self, index: u8) -> Option<MetadataValue> {
+        self.entries.remove(&index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &MetadataValue)> {
+        self.entries.iter().map(|(index, value)| (*index, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The base entity flags, empty if unset.
+    pub fn flags(&self) -> EntityFlags {
+        match self.get(index::FLAGS) {
+            Some(MetadataValue::Byte(bits)) => EntityFlags::from_bits_retain(*bits as u8),
+            _ => EntityFlags::empty(),
+        }
+    }
+
+    pub fn set_flags(&mut self, flags: EntityFlags) -> &mut Self {
+        self.set(index::FLAGS, MetadataValue::Byte(flags.bits() as i8))
+    }
+
+    fn set_flag(&mut self, flag: EntityFlags, value: bool) -> &mut Self {
+        let mut flags = self.flags();
+        flags.set(flag, value);
+        self.set_flags(flags)
+    }
+
+    pub fn is_on_fire(&self) -> bool {
+        self.flags().contains(EntityFlags::ON_FIRE)
+    }
+
+    pub fn set_on_fire(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::ON_FIRE, value)
+    }
+
+    pub fn is_sneaking(&self) -> bool {
+        self.flags().contains(EntityFlags::SNEAKING)
+    }
+
+    pub fn set_sneaking(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::SNEAKING, value)
+    }
+
+    pub fn is_sprinting(&self) -> bool {
+        self.flags().contains(EntityFlags::SPRINTING)
+    }
+
+    pub fn set_sprinting(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::SPRINTING, value)
+    }
+
+    pub fn is_swimming(&self) -> bool {
+        self.flags().contains(EntityFlags::SWIMMING)
+    }
+
+    pub fn set_swimming(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::SWIMMING, value)
+    }
+
+    pub fn is_invisible(&self) -> bool {
+        self.flags().contains(EntityFlags::INVISIBLE)
+    }
+
+    pub fn set_invisible(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::INVISIBLE, value)
+    }
+
+    pub fn is_glowing(&self) -> bool {
+        self.flags().contains(EntityFlags::GLOWING)
+    }
+
+    pub fn set_glowing(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::GLOWING, value)
+    }
+
+    pub fn is_fall_flying(&self) -> bool {
+        self.flags().contains(EntityFlags::FALL_FLYING)
+    }
+
+    pub fn set_fall_flying(&mut self, value: bool) -> &mut Self {
+        self.set_flag(EntityFlags::FALL_FLYING, value)
+    }
+
+    pub fn pose(&self) -> Pose {
+        match self.get(index::POSE) {
+            Some(MetadataValue::Pose(pose)) => *pose,
+            _ => Pose::Standing,
+        }
+    }
+
+    pub fn set_pose(&mut self, pose: Pose) -> &mut Self {
+        self.set(index::POSE, MetadataValue::Pose(pose))
+    }
+
+    pub fn set_custom_name(&mut self, name: Option<TextComponent>) -> &mut Self {
+        self.set(index::CUSTOM_NAME, MetadataValue::OptionalText(name))
+    }
+}
+
+impl Encode for EntityMetadata {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        for (index, value) in &self.entries {
+            index.encode(buf)?;
+            value.encode_for(version, buf)?;
+        }
+        TERMINATOR.encode(buf)
+    }
+}
+
+impl Decode for EntityMetadata {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let mut metadata = EntityMetadata::new();
+        loop {
+            let index = u8::decode(buf)?;
+            if index == TERMINATOR {
+                return Ok(metadata);
+            }
+            metadata
+                .entries
+                .insert(index, MetadataValue::decode_for(version, buf)?);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+    use crate::protocol::particle::{self, ParticleOptions};
+
+    #[test]
+    fn test_empty_metadata_is_the_terminator() {
+        assert_eq!(EntityMetadata::new().to_bytes().unwrap().as_ref(), [0xFF]);
+        let mut buf = BytesMut::from(&[0xFF, 1][..]);
+        assert!(EntityMetadata::decode(&mut buf).unwrap().is_empty());
+        assert_eq!(buf.as_ref(), [1]);
+    }
+
+    #[test]
+    fn test_entry_layout() {
+        let mut metadata = EntityMetadata::new();
+        metadata
+            .set(index::AIR_SUPPLY, MetadataValue::VarInt(300))
+            .set_sneaking(true);
+        assert_eq!(
+            metadata.to_bytes().unwrap().as_ref(),
+            [0, 0, 0x02, 1, 1, 0xAC, 0x02, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_flag_accessors() {
+        let mut metadata = EntityMetadata::new();
+        assert!(!metadata.is_on_fire());
+        metadata
+            .set_on_fire(true)
+            .set_invisible(true)
+            .set_sprinting(true);
+        assert!(metadata.is_on_fire() && metadata.is_invisible() && metadata.is_sprinting());
+        assert!(!metadata.is_sneaking());
+        metadata.set_on_fire(false);
+        assert_eq!(
+            metadata.flags(),
+            EntityFlags::INVISIBLE | EntityFlags::SPRINTING
+        );
+        metadata.set_fall_flying(true);
+        assert_eq!(
+            metadata.get(index::FLAGS),
+            Some(&MetadataValue::Byte(-0x58))
+        );
+        assert!(metadata.is_fall_flying());
+    }
+
+    #[test]
+    fn test_all_value_types_round_trip() {
+        let values = vec![
+            MetadataValue::Byte(-1),
+            MetadataValue::VarInt(7),
+            MetadataValue::VarLong(1 << 40),
+            MetadataValue::Float(0.5),
+            MetadataValue::String("hi".into()),
+            MetadataValue::Text(TextComponent::text("Name")),
+            MetadataValue::OptionalText(None),
+            MetadataValue::Slot(Slot::new(1, 3)),
+            MetadataValue::Boolean(true),
+            MetadataValue::Rotations {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            MetadataValue::Position(Position { x: 1, y: 2, z: 3 }),
+            MetadataValue::OptionalPosition(Some(Position { x: -1, y: 0, z: 5 })),
+            MetadataValue::Direction(Direction::East),
+            MetadataValue::OptionalLivingEntity(Some(Uuid::from_u128(42))),
+            MetadataValue::BlockState(9),
+            MetadataValue::OptionalBlockState(None),
+            MetadataValue::OptionalBlockState(Some(1)),
+            MetadataValue::Particle(Particle {
+                id: particle::DUST,
+                options: ParticleOptions::Dust {
+                    color: 0x123456,
+                    scale: 2.0,
+                },
+            }),
+            MetadataValue::Particles(vec![Particle::simple(0), Particle::simple(4)]),
+            MetadataValue::VillagerData {
+                villager_type: 2,
+                profession: 5,
+                level: 1,
+            },
+            MetadataValue::OptionalVarInt(Some(0)),
+            MetadataValue::OptionalVarInt(None),
+            MetadataValue::Pose(Pose::Sleeping),
+            MetadataValue::CatVariant(1),
+            MetadataValue::CowVariant(2),
+            MetadataValue::WolfVariant(3),
+            MetadataValue::WolfSoundVariant(4),
+            MetadataValue::FrogVariant(5),
+            MetadataValue::PigVariant(6),
+            MetadataValue::ChickenVariant(7),
+            MetadataValue::OptionalGlobalPosition(Some((
+                "minecraft:overworld".into(),
+                Position { x: 0, y: 64, z: 0 },
+            ))),
+            MetadataValue::PaintingVariant(IdOr::Inline(PaintingVariant {
+                width: 2,
+                height: 1,
+                asset_id: "minecraft:kebab".into(),
+                title: Some("Kebab".into()),
+                author: None,
+            })),
+            MetadataValue::SnifferState(1),
+            MetadataValue::ArmadilloState(2),
+            MetadataValue::Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            MetadataValue::Quaternion {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+        ];
+        assert_eq!(values.len(), 36);
+        let mut metadata = EntityMetadata::new();
+        for (index, value) in values.into_iter().enumerate() {
+            metadata.set(index as u8, value);
+        }
+        assert_round_trip(&metadata);
+    }
+
+    #[test]
+    fn test_optional_varint_is_offset() {
+        let mut buf = MetadataValue::OptionalVarInt(Some(0)).to_bytes().unwrap();
+        assert_eq!(buf.as_ref(), [19, 1]);
+        assert_eq!(
+            MetadataValue::decode(&mut buf).unwrap(),
+            MetadataValue::OptionalVarInt(Some(0))
+        );
+    }
+
+    #[test]
+    fn test_type_ids_per_version() {
+        let (old, latest) = (ProtocolVersion::V1_21_4, ProtocolVersion::LATEST);
+        let particle = MetadataValue::Particle(Particle::simple(0));
+        assert_eq!(particle.type_id(old), Some(17));
+        assert_eq!(particle.type_id(latest), Some(16));
+        assert_eq!(MetadataValue::Pose(Pose::Standing).type_id(old), Some(21));
+        assert_eq!(MetadataValue::CatVariant(0).type_id(old), Some(22));
+        assert_eq!(MetadataValue::FrogVariant(0).type_id(old), Some(24));
+        assert_eq!(MetadataValue::FrogVariant(0).type_id(latest), Some(25));
+        let quaternion = MetadataValue::Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+        assert_eq!(quaternion.type_id(old), Some(30));
+        assert_eq!(quaternion.type_id(latest), Some(33));
+        assert_eq!(MetadataValue::CowVariant(0).type_id(old), None);
+        assert_eq!(MetadataValue::Nbt(Nbt::default()).type_id(latest), None);
+    }
+
+    #[test]
+    fn test_v1_21_4_layout() {
+        let version = ProtocolVersion::V1_21_4;
+        let mut metadata = EntityMetadata::new();
+        metadata
+            .set(17, MetadataValue::FrogVariant(2))
+            .set(19, MetadataValue::Nbt(Nbt::from_snbt("{}").unwrap()));
+        let mut buf = BytesMut::new();
+        metadata.encode_for(version, &mut buf).unwrap();
+        assert_eq!(buf.as_ref(), [17, 24, 2, 19, 16, 10, 0, 0xFF]);
+        assert_eq!(
+            EntityMetadata::decode_for(version, &mut buf).unwrap(),
+            metadata
+        );
+        assert!(buf.is_empty());
+
+        // Values 1.21.4 has no serializer for can't be sent to it, nor NBT to 1.21.5.
+        metadata.set(20, MetadataValue::PigVariant(1));
+        assert!(metadata.encode_for(version, &mut BytesMut::new()).is_err());
+        metadata.remove(20);
+        assert!(metadata.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_unknown_value_type_is_rejected() {
+        let mut buf = BytesMut::from(&[0, 99, 0xFF][..]);
+        assert!(matches!(
+            EntityMetadata::decode(&mut buf),
+            Err(DecodeError::InvalidEnumValue(_, 99))
+        ));
+    }
+}
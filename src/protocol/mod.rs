@@ -4,4 +4,17 @@ pub mod status;
 pub mod login;
 pub mod configuration;
 pub mod play;
-pub mod encoding;
\ No newline at end of file
+pub mod encoding;
+pub mod compression;
+pub mod encryption;
+pub mod packets;
+pub mod version;
+pub mod codec;
+pub mod legacy;
+pub mod text;
+pub mod nbt;
+pub mod item;
+pub mod particle;
+pub mod metadata;
+pub mod chunk;
+pub mod command;
\ No newline at end of file
@@ -0,0 +1,424 @@
+//! Deserializing Rust values from tags.
+//!
+//! The inverse of the mapping in `ser`, and lenient where the types allow: any integer tag reads
+//! as any integer type it fits, a byte reads as a `bool`, and arrays read as sequences.
+
+use super::{
+    BYTE_ARRAY_TOKEN, Compound, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN, NbtError, NbtResult, TAG_TOKEN,
+    Tag,
+};
+use serde::de::value::{MapDeserializer, SeqDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::{Deserialize, Deserializer, forward_to_deserialize_any};
+use std::fmt;
+
+pub fn from_tag<T: DeserializeOwned>(tag: Tag) -> NbtResult<T> {
+    T::deserialize(tag)
+}
+
+fn visit_list<'de, V, I>(visitor: V, items: I) -> NbtResult<V::Value>
+where
+    V: Visitor<'de>,
+    I: Iterator<Item = Tag>,
+{
+    let mut seq = SeqDeserializer::new(items);
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_compound<'de, V: Visitor<'de>>(visitor: V, entries: Compound) -> NbtResult<V::Value> {
+    let mut map = MapDeserializer::new(entries.into_iter());
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+impl<'de> IntoDeserializer<'de, NbtError> for Tag {
+    type Deserializer = Tag;
+
+    fn into_deserializer(self) -> Tag {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Tag {
+    type Error = NbtError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self {
+            Tag::End => visitor.visit_unit(),
+            Tag::Byte(value) => visitor.visit_i8(value),
+            Tag::Short(value) => visitor.visit_i16(value),
+            Tag::Int(value) => visitor.visit_i32(value),
+            Tag::Long(value) => visitor.visit_i64(value),
+            Tag::Float(value) => visitor.visit_f32(value),
+            Tag::Double(value) => visitor.visit_f64(value),
+            Tag::ByteArray(values) => visit_list(visitor, values.into_iter().map(Tag::Byte)),
+            Tag::String(value) => visitor.visit_string(value),
+            Tag::List(items) => visit_list(visitor, items.into_iter()),
+            Tag::Compound(entries) => visit_compound(visitor, entries),
+            Tag::IntArray(values) => visit_list(visitor, values.into_iter().map(Tag::Int)),
+            Tag::LongArray(values) => visit_list(visitor, values.into_iter().map(Tag::Long)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self {
+            Tag::Byte(value) => visitor.visit_bool(value != 0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> NbtResult<V::Value> {
+        match self {
+            Tag::End => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        if name != TAG_TOKEN {
+            return visitor.visit_newtype_struct(self);
+        }
+        // A `Tag` is being read, which needs to know an array from a list: see `TagVisitor`.
+        let (token, items): (_, Vec<Tag>) = match self {
+            Tag::ByteArray(values) => (
+                BYTE_ARRAY_TOKEN,
+                values.into_iter().map(Tag::Byte).collect(),
+            ),
+            Tag::IntArray(values) => (INT_ARRAY_TOKEN, values.into_iter().map(Tag::Int).collect()),
+            Tag::LongArray(values) => (
+                LONG_ARRAY_TOKEN,
+                values.into_iter().map(Tag::Long).collect(),
+            ),
+            other => return other.deserialize_any(visitor),
+        };
+        let mut map = MapDeserializer::new(std::iter::once((token, Tag::List(items))));
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        match self {
+            Tag::String(variant) => {
+                visitor.visit_enum(StringDeserializer::<NbtError>::new(variant))
+            }
+            Tag::Compound(entries) if entries.len() == 1 => {
+                let (variant, value) = entries.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            other => Err(de::Error::invalid_type(
+                de::Unexpected::Other(&format!("{:?}", other)),
+                &"a string or a compound with one entry",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Tag,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = NbtError;
+    type Variant = Tag;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> NbtResult<(V::Value, Tag)> {
+        let variant = seed.deserialize(StringDeserializer::<NbtError>::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Tag {
+    type Error = NbtError;
+
+    fn unit_variant(self) -> NbtResult<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> NbtResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> NbtResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> NbtResult<V::Value> {
+        self.deserialize_map(visitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(TAG_TOKEN, TagVisitor)
+    }
+}
+
+/// Builds a tag from any self-describing format. [`from_tag`] hands it arrays as a map from the
+/// array type's token to the elements, so they don't come back as lists.
+struct TagVisitor;
+
+impl<'de> Visitor<'de> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an NBT value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Tag, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i8<E: de::Error>(self, value: i8) -> Result<Tag, E> {
+        Ok(Tag::Byte(value))
+    }
+
+    fn visit_i16<E: de::Error>(self, value: i16) -> Result<Tag, E> {
+        Ok(Tag::Short(value))
+    }
+
+    fn visit_i32<E: de::Error>(self, value: i32) -> Result<Tag, E> {
+        Ok(Tag::Int(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Tag, E> {
+        Ok(Tag::Long(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Tag, E> {
+        i64::try_from(value)
+            .map(Tag::Long)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_f32<E: de::Error>(self, value: f32) -> Result<Tag, E> {
+        Ok(Tag::Float(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Tag, E> {
+        Ok(Tag::Double(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Tag, E> {
+        Ok(value.into())
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Tag, E> {
+        Ok(value.into())
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Tag, E> {
+        Ok(Tag::ByteArray(
+            value.iter().map(|byte| *byte as i8).collect(),
+        ))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Tag, E> {
+        Ok(Tag::End)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Tag, E> {
+        Ok(Tag::End)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Tag, D::Error> {
+        Tag::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Tag, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Tag, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Tag::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Tag, A::Error> {
+        let mut entries = Compound::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                BYTE_ARRAY_TOKEN if entries.is_empty() => {
+                    return Ok(Tag::ByteArray(map.next_value()?));
+                }
+                INT_ARRAY_TOKEN if entries.is_empty() => {
+                    return Ok(Tag::IntArray(map.next_value()?));
+                }
+                LONG_ARRAY_TOKEN if entries.is_empty() => {
+                    return Ok(Tag::LongArray(map.next_value()?));
+                }
+                _ => {
+                    let value = map.next_value()?;
+                    entries.insert(key, value);
+                }
+            }
+        }
+        Ok(Tag::Compound(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::nbt::{self, to_tag};
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Line(i32, i32),
+        Rect { width: u16, height: u16 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entity {
+        id: String,
+        health: f32,
+        on_ground: bool,
+        level: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        custom_name: Option<String>,
+        #[serde(with = "nbt::int_array")]
+        uuid: Vec<i32>,
+        #[serde(with = "nbt::long_array")]
+        states: Vec<i64>,
+        motion: Vec<f64>,
+        shapes: Vec<Shape>,
+        counts: BTreeMap<String, i64>,
+        extra: Tag,
+    }
+
+    fn entity() -> Entity {
+        Entity {
+            id: "minecraft:pig".to_string(),
+            health: 10.0,
+            on_ground: true,
+            level: 200,
+            custom_name: None,
+            uuid: vec![1, 2, 3, 4],
+            states: vec![-1],
+            motion: vec![0.0, -0.08, 0.0],
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle(1.5),
+                Shape::Line(1, 2),
+                Shape::Rect {
+                    width: 3,
+                    height: 4,
+                },
+            ],
+            counts: BTreeMap::from([("jumps".to_string(), 3)]),
+            extra: Tag::Compound(Compound::from([
+                ("bytes".to_string(), Tag::ByteArray(vec![1, 2])),
+                ("flag".to_string(), Tag::Byte(1)),
+            ])),
+        }
+    }
+
+    #[test]
+    fn test_struct_maps_to_tag_types() {
+        let tag = to_tag(&entity()).unwrap();
+        assert_eq!(tag.get("id"), Some(&Tag::from("minecraft:pig")));
+        assert_eq!(tag.get("health"), Some(&Tag::Float(10.0)));
+        assert_eq!(tag.get("on_ground"), Some(&Tag::Byte(1)));
+        assert_eq!(tag.get("level"), Some(&Tag::Short(200)));
+        assert_eq!(tag.get("custom_name"), None);
+        assert_eq!(tag.get("uuid"), Some(&Tag::IntArray(vec![1, 2, 3, 4])));
+        assert_eq!(tag.get("states"), Some(&Tag::LongArray(vec![-1])));
+        assert!(matches!(tag.get("motion"), Some(Tag::List(items)) if items.len() == 3));
+        assert_eq!(
+            tag.get("shapes"),
+            Some(&Tag::List(vec![
+                "Point".into(),
+                Tag::Compound(Compound::from([("Circle".to_string(), Tag::Double(1.5))])),
+                Tag::Compound(Compound::from([(
+                    "Line".to_string(),
+                    Tag::List(vec![Tag::Int(1), Tag::Int(2)])
+                )])),
+                Tag::Compound(Compound::from([(
+                    "Rect".to_string(),
+                    Tag::Compound(Compound::from([
+                        ("height".to_string(), Tag::Int(4)),
+                        ("width".to_string(), Tag::Int(3)),
+                    ]))
+                )])),
+            ]))
+        );
+        assert_eq!(tag.get("extra"), Some(&entity().extra));
+    }
+
+    #[test]
+    fn test_struct_round_trip() {
+        let entity = entity();
+        let tag = to_tag(&entity).unwrap();
+        assert_eq!(from_tag::<Entity>(tag.clone()).unwrap(), entity);
+        // Through the binary form too, which reorders nothing that matters.
+        let mut buf = Vec::new();
+        nbt::write_network(&mut buf, &tag).unwrap();
+        let read = nbt::read_network(&mut &buf[..]).unwrap();
+        assert_eq!(from_tag::<Entity>(read).unwrap(), entity);
+    }
+
+    #[test]
+    fn test_lenient_reads() {
+        assert_eq!(from_tag::<i64>(Tag::Byte(-3)).unwrap(), -3);
+        assert_eq!(from_tag::<u8>(Tag::Int(255)).unwrap(), 255);
+        assert!(from_tag::<u8>(Tag::Int(256)).is_err());
+        assert!(from_tag::<bool>(Tag::Byte(2)).unwrap());
+        assert_eq!(from_tag::<f64>(Tag::Float(0.5)).unwrap(), 0.5);
+        assert_eq!(
+            from_tag::<Vec<i32>>(Tag::IntArray(vec![1, 2])).unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(from_tag::<Option<i32>>(Tag::End).unwrap(), None);
+        assert!(from_tag::<String>(Tag::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_tag_through_json() {
+        let tag = entity().extra;
+        let json = serde_json::to_string(&tag).unwrap();
+        assert_eq!(json, r#"{"bytes":[1,2],"flag":1}"#);
+        // JSON has no integer widths or arrays, so only the shape survives.
+        assert_eq!(
+            serde_json::from_str::<Tag>(&json).unwrap(),
+            Tag::Compound(Compound::from([
+                (
+                    "bytes".to_string(),
+                    Tag::List(vec![Tag::Long(1), Tag::Long(2)])
+                ),
+                ("flag".to_string(), Tag::Long(1)),
+            ]))
+        );
+    }
+}
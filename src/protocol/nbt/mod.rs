@@ -0,0 +1,846 @@
+//! Named Binary Tag, the format of world files and of the item, block entity and registry data
+//! carried in packets.
+//!
+//! A [`Tag`] is read and written either in the network form, where the root tag has no name
+//! (since 1.20.2), or in the file form, where it does and the whole file may be gzip or zlib
+//! compressed. [`to_tag`] and [`from_tag`] map Rust types to and from tags with serde.
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, unsupported_in,
+};
+use crate::protocol::version::ProtocolVersion;
+use bytes::{Buf, BufMut, BytesMut};
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use serde::{Deserialize, Deserializer, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind, Read, Write};
+
+mod de;
+mod ser;
+mod snbt;
+
+pub use de::from_tag;
+pub use ser::to_tag;
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// Nesting of lists and compounds allowed when reading, as in vanilla.
+pub const MAX_DEPTH: usize = 512;
+
+/// Newtype struct names that mark a sequence as one of the array types, see [`byte_array`].
+const BYTE_ARRAY_TOKEN: &str = "$nbt::ByteArray";
+const INT_ARRAY_TOKEN: &str = "$nbt::IntArray";
+const LONG_ARRAY_TOKEN: &str = "$nbt::LongArray";
+/// Newtype struct name with which [`Tag`]'s `Deserialize` asks [`from_tag`] to keep array types.
+const TAG_TOKEN: &str = "$nbt::Tag";
+
+pub type Compound = BTreeMap<String, Tag>;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Tag {
+    /// Closes a compound. As a root tag, an absent value, such as an empty optional NBT field.
+    #[default]
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// Elements of different types are written wrapped in compounds, as vanilla has since 1.21.5.
+    /// Older versions can't read such lists, so encoding one for them is an error.
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// The type ID written before the tag.
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::End => TAG_END,
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(_) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    /// The entry `key` of a compound.
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        self.as_compound()?.get(key)
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value of any integer tag.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(value) => Some(value.into()),
+            Tag::Short(value) => Some(value.into()),
+            Tag::Int(value) => Some(value.into()),
+            Tag::Long(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value of any numeric tag.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Tag::Float(value) => Some(value.into()),
+            Tag::Double(value) => Some(value),
+            _ => self.as_i64().map(|value| value as f64),
+        }
+    }
+}
+
+impl From<bool> for Tag {
+    fn from(value: bool) -> Self {
+        Tag::Byte(value.into())
+    }
+}
+
+impl From<i8> for Tag {
+    fn from(value: i8) -> Self {
+        Tag::Byte(value)
+    }
+}
+
+impl From<i16> for Tag {
+    fn from(value: i16) -> Self {
+        Tag::Short(value)
+    }
+}
+
+impl From<i32> for Tag {
+    fn from(value: i32) -> Self {
+        Tag::Int(value)
+    }
+}
+
+impl From<i64> for Tag {
+    fn from(value: i64) -> Self {
+        Tag::Long(value)
+    }
+}
+
+impl From<f32> for Tag {
+    fn from(value: f32) -> Self {
+        Tag::Float(value)
+    }
+}
+
+impl From<f64> for Tag {
+    fn from(value: f64) -> Self {
+        Tag::Double(value)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Tag::String(value.to_string())
+    }
+}
+
+impl From<String> for Tag {
+    fn from(value: String) -> Self {
+        Tag::String(value)
+    }
+}
+
+impl From<Vec<Tag>> for Tag {
+    fn from(items: Vec<Tag>) -> Self {
+        Tag::List(items)
+    }
+}
+
+impl From<Compound> for Tag {
+    fn from(entries: Compound) -> Self {
+        Tag::Compound(entries)
+    }
+}
+
+#[derive(Debug)]
+pub enum NbtError {
+    Io(IoError),
+    /// The input ended inside a tag.
+    UnexpectedEof,
+    InvalidTagType(u8),
+    NegativeLength(i32),
+    /// Lists and compounds nested deeper than the reader allows.
+    TooDeep(usize),
+    /// A string that isn't valid modified UTF-8.
+    InvalidString,
+    /// A string over the 65535 bytes its length prefix can hold.
+    StringTooLong(usize),
+    /// SNBT that doesn't parse, with the byte offset where parsing stopped.
+    InvalidSnbt {
+        offset: usize,
+        reason: String,
+    },
+    /// A value that can't be written as NBT, or a tag that doesn't fit the type it is read as.
+    Message(String),
+}
+
+pub type NbtResult<T> = Result<T, NbtError>;
+
+impl From<IoError> for NbtError {
+    fn from(e: IoError) -> Self {
+        NbtError::Io(e)
+    }
+}
+
+impl fmt::Display for NbtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NbtError::Io(e) => write!(f, "IO error: {}", e),
+            NbtError::UnexpectedEof => write!(f, "NBT ended unexpectedly"),
+            NbtError::InvalidTagType(id) => write!(f, "Unknown tag type {}", id),
+            NbtError::NegativeLength(len) => write!(f, "Negative length {}", len),
+            NbtError::TooDeep(max_depth) => {
+                write!(f, "Tags nested deeper than {} levels", max_depth)
+            }
+            NbtError::InvalidString => write!(f, "Malformed modified UTF-8 string"),
+            NbtError::StringTooLong(len) => write!(f, "String of {} bytes is too long", len),
+            NbtError::InvalidSnbt { offset, reason } => {
+                write!(f, "Invalid SNBT at offset {}: {}", offset, reason)
+            }
+            NbtError::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for NbtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NbtError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl serde::ser::Error for NbtError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        NbtError::Message(message.to_string())
+    }
+}
+
+impl serde::de::Error for NbtError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        NbtError::Message(message.to_string())
+    }
+}
+
+impl From<NbtError> for IoError {
+    fn from(e: NbtError) -> Self {
+        match e {
+            NbtError::Io(e) => e,
+            e => IoError::new(ErrorKind::InvalidInput, e.to_string()),
+        }
+    }
+}
+
+impl From<NbtError> for DecodeError {
+    fn from(e: NbtError) -> Self {
+        match e {
+            NbtError::Io(e) => DecodeError::Io(e),
+            NbtError::UnexpectedEof => DecodeError::NotEnoughBytes,
+            e => DecodeError::InvalidNbt(e.to_string()),
+        }
+    }
+}
+
+/// Reads a network tag, whose root has no name.
+pub fn read_network<B: Buf>(buf: &mut B) -> NbtResult<Tag> {
+    read_network_with_limit(buf, MAX_DEPTH)
+}
+
+/// Reads a network tag, rejecting lists and compounds nested more than `max_depth` deep.
+pub fn read_network_with_limit<B: Buf>(buf: &mut B, max_depth: usize) -> NbtResult<Tag> {
+    need(buf, 1)?;
+    let id = buf.get_u8();
+    read_payload(buf, id, 0, max_depth)
+}
+
+pub fn write_network<B: BufMut>(buf: &mut B, tag: &Tag) -> NbtResult<()> {
+    buf.put_u8(tag.id());
+    write_payload(buf, tag)
+}
+
+/// Reads a tag with a named root, as stored in files.
+pub fn read_named<B: Buf>(buf: &mut B) -> NbtResult<(String, Tag)> {
+    need(buf, 1)?;
+    let id = buf.get_u8();
+    if id == TAG_END {
+        return Ok((String::new(), Tag::End));
+    }
+    let name = read_string(buf)?;
+    Ok((name, read_payload(buf, id, 0, MAX_DEPTH)?))
+}
+
+pub fn write_named<B: BufMut>(buf: &mut B, name: &str, tag: &Tag) -> NbtResult<()> {
+    buf.put_u8(tag.id());
+    if *tag != Tag::End {
+        write_string(buf, name)?;
+    }
+    write_payload(buf, tag)
+}
+
+/// How an NBT file is compressed. Vanilla gzips `level.dat` and player data, and zlib
+/// compresses the chunks in region files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCompression {
+    None,
+    Gzip,
+    Zlib,
+}
+
+impl FileCompression {
+    /// Guesses from the first bytes of a file. A raw file starts with a tag type, which is never
+    /// the first byte of a gzip or zlib header.
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0x1F, 0x8B, ..] => FileCompression::Gzip,
+            [first @ 0x78, second, ..] if u16::from_be_bytes([*first, *second]) % 31 == 0 => {
+                FileCompression::Zlib
+            }
+            _ => FileCompression::None,
+        }
+    }
+}
+
+/// Reads an NBT file, decompressing it first if it is gzip or zlib compressed.
+pub fn read_file(bytes: &[u8]) -> NbtResult<(String, Tag)> {
+    let mut decompressed = Vec::new();
+    let mut raw = match FileCompression::detect(bytes) {
+        FileCompression::None => bytes,
+        FileCompression::Gzip => {
+            GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+            &decompressed[..]
+        }
+        FileCompression::Zlib => {
+            ZlibDecoder::new(bytes).read_to_end(&mut decompressed)?;
+            &decompressed[..]
+        }
+    };
+    read_named(&mut raw)
+}
+
+pub fn write_file(name: &str, tag: &Tag, compression: FileCompression) -> NbtResult<Vec<u8>> {
+    let mut raw = Vec::new();
+    write_named(&mut raw, name, tag)?;
+    Ok(match compression {
+        FileCompression::None => raw,
+        FileCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&raw)?;
+            encoder.finish()?
+        }
+        FileCompression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&raw)?;
+            encoder.finish()?
+        }
+    })
+}
+
+/// Written in the network form, with a nameless root.
+impl Encode for Tag {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        Ok(write_network(buf, self)?)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        if version < ProtocolVersion::V1_21_5 && has_wrapped_list(self) {
+            return Err(unsupported_in("NBT list of mixed types", version));
+        }
+        self.encode(buf)
+    }
+}
+
+/// Whether a list in `tag` is written with its elements wrapped in compounds.
+fn has_wrapped_list(tag: &Tag) -> bool {
+    match tag {
+        Tag::List(items) => {
+            let mixed = items.windows(2).any(|pair| pair[0].id() != pair[1].id());
+            let wrapper_like = items
+                .iter()
+                .any(|item| matches!(item, Tag::Compound(entries) if is_wrapper(entries)));
+            mixed || wrapper_like || items.iter().any(has_wrapped_list)
+        }
+        Tag::Compound(entries) => entries.values().any(has_wrapped_list),
+        _ => false,
+    }
+}
+
+impl Decode for Tag {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Ok(read_network(buf)?)
+    }
+}
+
+fn need<B: Buf>(buf: &B, len: usize) -> NbtResult<()> {
+    if buf.remaining() < len {
+        return Err(NbtError::UnexpectedEof);
+    }
+    Ok(())
+}
+
+fn read_length<B: Buf>(buf: &mut B) -> NbtResult<usize> {
+    need(buf, 4)?;
+    let len = buf.get_i32();
+    usize::try_from(len).map_err(|_| NbtError::NegativeLength(len))
+}
+
+/// Reads `len` elements of `size` bytes, checking they are there before allocating.
+fn read_array<B: Buf, T>(
+    buf: &mut B,
+    size: usize,
+    read: impl Fn(&mut B) -> T,
+) -> NbtResult<Vec<T>> {
+    let len = read_length(buf)?;
+    need(buf, len.saturating_mul(size))?;
+    Ok((0..len).map(|_| read(buf)).collect())
+}
+
+/// A compound holding a list element under the empty name, which lets a list mix types.
+fn is_wrapper(entries: &Compound) -> bool {
+    entries.len() == 1 && entries.contains_key("")
+}
+
+fn read_payload<B: Buf>(buf: &mut B, id: u8, depth: usize, max_depth: usize) -> NbtResult<Tag> {
+    if matches!(id, TAG_LIST | TAG_COMPOUND) && depth >= max_depth {
+        return Err(NbtError::TooDeep(max_depth));
+    }
+    Ok(match id {
+        TAG_END => Tag::End,
+        TAG_BYTE => {
+            need(buf, 1)?;
+            Tag::Byte(buf.get_i8())
+        }
+        TAG_SHORT => {
+            need(buf, 2)?;
+            Tag::Short(buf.get_i16())
+        }
+        TAG_INT => {
+            need(buf, 4)?;
+            Tag::Int(buf.get_i32())
+        }
+        TAG_LONG => {
+            need(buf, 8)?;
+            Tag::Long(buf.get_i64())
+        }
+        TAG_FLOAT => {
+            need(buf, 4)?;
+            Tag::Float(buf.get_f32())
+        }
+        TAG_DOUBLE => {
+            need(buf, 8)?;
+            Tag::Double(buf.get_f64())
+        }
+        TAG_BYTE_ARRAY => Tag::ByteArray(read_array(buf, 1, |buf| buf.get_i8())?),
+        TAG_STRING => Tag::String(read_string(buf)?),
+        TAG_LIST => {
+            need(buf, 1)?;
+            let element_id = buf.get_u8();
+            let len = read_length(buf)?;
+            if element_id == TAG_END && len > 0 {
+                return Err(NbtError::Message("list of end tags".to_string()));
+            }
+            let mut items = Vec::with_capacity(len.min(buf.remaining()));
+            for _ in 0..len {
+                let item = match read_payload(buf, element_id, depth + 1, max_depth)? {
+                    Tag::Compound(mut entries) if is_wrapper(&entries) => {
+                        entries.remove("").unwrap()
+                    }
+                    item => item,
+                };
+                items.push(item);
+            }
+            Tag::List(items)
+        }
+        TAG_COMPOUND => {
+            let mut entries = Compound::new();
+            loop {
+                need(buf, 1)?;
+                let entry_id = buf.get_u8();
+                if entry_id == TAG_END {
+                    break;
+                }
+                let name = read_string(buf)?;
+                entries.insert(name, read_payload(buf, entry_id, depth + 1, max_depth)?);
+            }
+            Tag::Compound(entries)
+        }
+        TAG_INT_ARRAY => Tag::IntArray(read_array(buf, 4, |buf| buf.get_i32())?),
+        TAG_LONG_ARRAY => Tag::LongArray(read_array(buf, 8, |buf| buf.get_i64())?),
+        _ => return Err(NbtError::InvalidTagType(id)),
+    })
+}
+
+fn write_length<B: BufMut>(buf: &mut B, len: usize) -> NbtResult<()> {
+    let len = i32::try_from(len)
+        .map_err(|_| NbtError::Message(format!("{} elements don't fit in an NBT array", len)))?;
+    buf.put_i32(len);
+    Ok(())
+}
+
+fn write_payload<B: BufMut>(buf: &mut B, tag: &Tag) -> NbtResult<()> {
+    match tag {
+        Tag::End => {}
+        Tag::Byte(value) => buf.put_i8(*value),
+        Tag::Short(value) => buf.put_i16(*value),
+        Tag::Int(value) => buf.put_i32(*value),
+        Tag::Long(value) => buf.put_i64(*value),
+        Tag::Float(value) => buf.put_f32(*value),
+        Tag::Double(value) => buf.put_f64(*value),
+        Tag::ByteArray(values) => {
+            write_length(buf, values.len())?;
+            values.iter().for_each(|value| buf.put_i8(*value));
+        }
+        Tag::String(value) => write_string(buf, value)?,
+        Tag::List(items) => {
+            if items.contains(&Tag::End) {
+                return Err(NbtError::Message("lists can't hold end tags".to_string()));
+            }
+            let element_id = match items.first() {
+                Some(first) if items.iter().all(|item| item.id() == first.id()) => first.id(),
+                Some(_) => TAG_COMPOUND,
+                None => TAG_END,
+            };
+            buf.put_u8(element_id);
+            write_length(buf, items.len())?;
+            for item in items {
+                match item {
+                    // A compound that looks like a wrapper is wrapped too, so that reading it
+                    // back doesn't unwrap it.
+                    Tag::Compound(entries) if !is_wrapper(entries) => write_payload(buf, item)?,
+                    _ if element_id == TAG_COMPOUND => {
+                        buf.put_u8(item.id());
+                        write_string(buf, "")?;
+                        write_payload(buf, item)?;
+                        buf.put_u8(TAG_END);
+                    }
+                    _ => write_payload(buf, item)?,
+                }
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, entry) in entries {
+                if *entry == Tag::End {
+                    return Err(NbtError::Message(format!(
+                        "end tag as compound entry {}",
+                        name
+                    )));
+                }
+                buf.put_u8(entry.id());
+                write_string(buf, name)?;
+                write_payload(buf, entry)?;
+            }
+            buf.put_u8(TAG_END);
+        }
+        Tag::IntArray(values) => {
+            write_length(buf, values.len())?;
+            values.iter().for_each(|value| buf.put_i32(*value));
+        }
+        Tag::LongArray(values) => {
+            write_length(buf, values.len())?;
+            values.iter().for_each(|value| buf.put_i64(*value));
+        }
+    }
+    Ok(())
+}
+
+/// NBT strings are Java's modified UTF-8: `\0` takes two bytes and characters outside the BMP
+/// are written as surrogate pairs.
+fn write_string<B: BufMut>(buf: &mut B, value: &str) -> NbtResult<()> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    let len = u16::try_from(bytes.len()).map_err(|_| NbtError::StringTooLong(bytes.len()))?;
+    buf.put_u16(len);
+    buf.put_slice(&bytes);
+    Ok(())
+}
+
+fn read_string<B: Buf>(buf: &mut B) -> NbtResult<String> {
+    need(buf, 2)?;
+    let len = buf.get_u16() as usize;
+    need(buf, len)?;
+    let mut bytes = vec![0; len];
+    buf.copy_to_slice(&mut bytes);
+    let mut units = Vec::with_capacity(len);
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i] as u16;
+        let continuation = |offset: usize| match bytes.get(i + offset) {
+            Some(byte) if byte & 0xC0 == 0x80 => Ok(*byte as u16 & 0x3F),
+            _ => Err(NbtError::InvalidString),
+        };
+        let (unit, width) = match byte {
+            0x00..=0x7F => (byte, 1),
+            0xC0..=0xDF => ((byte & 0x1F) << 6 | continuation(1)?, 2),
+            0xE0..=0xEF => (
+                (byte & 0x0F) << 12 | continuation(1)? << 6 | continuation(2)?,
+                3,
+            ),
+            _ => return Err(NbtError::InvalidString),
+        };
+        units.push(unit);
+        i += width;
+    }
+    String::from_utf16(&units).map_err(|_| NbtError::InvalidString)
+}
+
+/// Serializes a `Vec<i8>` field as a byte array rather than a list of bytes, with
+/// `#[serde(with = "nbt::byte_array")]`. Other formats see a plain sequence.
+pub mod byte_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[i8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(BYTE_ARRAY_TOKEN, values)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<i8>, D::Error> {
+        Vec::deserialize(deserializer)
+    }
+}
+
+/// Serializes a `Vec<i32>` field as an int array, see [`byte_array`].
+pub mod int_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[i32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(INT_ARRAY_TOKEN, values)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<i32>, D::Error> {
+        Vec::deserialize(deserializer)
+    }
+}
+
+/// Serializes a `Vec<i64>` field as a long array, see [`byte_array`].
+pub mod long_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[i64], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(LONG_ARRAY_TOKEN, values)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<i64>, D::Error> {
+        Vec::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Tag {
+        let mut nested = Compound::new();
+        nested.insert("name".to_string(), "Bananrama".into());
+        nested.insert("empty".to_string(), Tag::List(vec![]));
+        let mut root = Compound::new();
+        root.insert("byte".to_string(), Tag::Byte(-1));
+        root.insert("short".to_string(), Tag::Short(32767));
+        root.insert("int".to_string(), Tag::Int(-7));
+        root.insert("long".to_string(), Tag::Long(i64::MAX));
+        root.insert("float".to_string(), Tag::Float(0.5));
+        root.insert("double".to_string(), Tag::Double(-1.25));
+        root.insert("bytes".to_string(), Tag::ByteArray(vec![1, -2, 3]));
+        root.insert("string".to_string(), "a\0é😀".into());
+        root.insert(
+            "list".to_string(),
+            Tag::List(vec![Tag::Int(1), Tag::Int(2)]),
+        );
+        root.insert("nested".to_string(), Tag::Compound(nested));
+        root.insert("ints".to_string(), Tag::IntArray(vec![i32::MIN, 0]));
+        root.insert("longs".to_string(), Tag::LongArray(vec![1, -1]));
+        Tag::Compound(root)
+    }
+
+    #[test]
+    fn test_network_round_trip() {
+        let tag = sample();
+        let mut buf = BytesMut::new();
+        tag.encode(&mut buf).unwrap();
+        assert_eq!(buf[0], TAG_COMPOUND);
+        assert_eq!(Tag::decode(&mut buf).unwrap(), tag);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_network_and_named_roots() {
+        let mut network = Vec::new();
+        write_network(&mut network, &Tag::Short(3)).unwrap();
+        assert_eq!(network, [TAG_SHORT, 0, 3]);
+
+        let mut named = Vec::new();
+        write_named(&mut named, "hi", &Tag::Short(3)).unwrap();
+        assert_eq!(named, [TAG_SHORT, 0, 2, b'h', b'i', 0, 3]);
+        assert_eq!(
+            read_named(&mut &named[..]).unwrap(),
+            ("hi".to_string(), Tag::Short(3))
+        );
+
+        // An empty optional NBT field is a lone end tag.
+        assert_eq!(read_network(&mut &[TAG_END][..]).unwrap(), Tag::End);
+    }
+
+    #[test]
+    fn test_files_round_trip_with_each_compression() {
+        let tag = sample();
+        for compression in [
+            FileCompression::None,
+            FileCompression::Gzip,
+            FileCompression::Zlib,
+        ] {
+            let bytes = write_file("Data", &tag, compression).unwrap();
+            assert_eq!(FileCompression::detect(&bytes), compression);
+            assert_eq!(
+                read_file(&bytes).unwrap(),
+                ("Data".to_string(), tag.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_mixed_lists_are_wrapped() {
+        let mut wrapper_like = Compound::new();
+        wrapper_like.insert(String::new(), Tag::Int(1));
+        let list = Tag::List(vec![
+            Tag::Int(1),
+            "two".into(),
+            Tag::Compound(Compound::new()),
+            Tag::Compound(wrapper_like),
+        ]);
+        let mut buf = Vec::new();
+        write_network(&mut buf, &list).unwrap();
+        assert_eq!(&buf[..6], &[TAG_LIST, TAG_COMPOUND, 0, 0, 0, 4]);
+        assert_eq!(&buf[6..14], &[TAG_INT, 0, 0, 0, 0, 0, 1, TAG_END]);
+        assert_eq!(read_network(&mut &buf[..]).unwrap(), list);
+    }
+
+    #[test]
+    fn test_mixed_lists_need_1_21_5() {
+        let mut compound = Compound::new();
+        compound.insert(
+            "mixed".to_string(),
+            Tag::List(vec![Tag::Int(1), "two".into()]),
+        );
+        let tag = Tag::Compound(compound);
+        let mut buf = BytesMut::new();
+        assert!(tag.encode_for(ProtocolVersion::V1_21_4, &mut buf).is_err());
+        tag.encode_for(ProtocolVersion::V1_21_5, &mut buf).unwrap();
+        assert_eq!(Tag::decode(&mut buf).unwrap(), tag);
+
+        let uniform = Tag::List(vec![Tag::Int(1), Tag::Int(2)]);
+        let mut buf = BytesMut::new();
+        uniform
+            .encode_for(ProtocolVersion::V1_21_4, &mut buf)
+            .unwrap();
+        assert_eq!(buf, uniform.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let mut tag = Tag::List(vec![]);
+        for _ in 0..10 {
+            tag = Tag::List(vec![tag]);
+        }
+        let mut buf = Vec::new();
+        write_network(&mut buf, &tag).unwrap();
+        assert_eq!(read_network_with_limit(&mut &buf[..], 11).unwrap(), tag);
+        assert!(matches!(
+            read_network_with_limit(&mut &buf[..], 10),
+            Err(NbtError::TooDeep(10))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        let read = |bytes: &[u8]| read_network(&mut &bytes[..]).unwrap_err();
+        assert!(matches!(read(&[TAG_INT, 0, 0]), NbtError::UnexpectedEof));
+        assert!(matches!(read(&[13]), NbtError::InvalidTagType(13)));
+        assert!(matches!(
+            read(&[TAG_INT_ARRAY, 0xFF, 0xFF, 0xFF, 0xFF]),
+            NbtError::NegativeLength(-1)
+        ));
+        // Claims a billion longs: fails on length rather than allocating.
+        assert!(matches!(
+            read(&[TAG_LONG_ARRAY, 0x40, 0, 0, 0, 1]),
+            NbtError::UnexpectedEof
+        ));
+        assert!(matches!(
+            read(&[TAG_STRING, 0, 1, 0x80]),
+            NbtError::InvalidString
+        ));
+        assert!(matches!(
+            Tag::decode(&mut BytesMut::from(&[TAG_INT, 0][..])),
+            Err(DecodeError::NotEnoughBytes)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unwritable_tags() {
+        let mut compound = Compound::new();
+        compound.insert("x".to_string(), Tag::End);
+        for tag in [
+            Tag::Compound(compound),
+            Tag::List(vec![Tag::End]),
+            "x".repeat(65536).into(),
+        ] {
+            assert!(write_network(&mut Vec::new(), &tag).is_err());
+        }
+    }
+}
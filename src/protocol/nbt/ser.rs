@@ -0,0 +1,402 @@
+//! Serializing Rust values into tags.
+//!
+//! Integers keep their width, widening unsigned ones so they keep their value (`u8` becomes a
+//! short), and `bool` becomes a byte. Structs and maps become compounds, sequences become lists
+//! unless marked with [`byte_array`](super::byte_array) and friends. Enum variants are written as
+//! in serde_json: a string for a unit variant, otherwise a compound with the variant name as its
+//! only key. `None` and `()` become [`Tag::End`], which compounds leave out.
+
+use super::{
+    BYTE_ARRAY_TOKEN, Compound, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN, NbtError, NbtResult, Tag,
+};
+use serde::ser::{self, Serialize};
+
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> NbtResult<Tag> {
+    value.serialize(TagSerializer)
+}
+
+impl Serialize for Tag {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Tag::End => serializer.serialize_unit(),
+            Tag::Byte(value) => serializer.serialize_i8(*value),
+            Tag::Short(value) => serializer.serialize_i16(*value),
+            Tag::Int(value) => serializer.serialize_i32(*value),
+            Tag::Long(value) => serializer.serialize_i64(*value),
+            Tag::Float(value) => serializer.serialize_f32(*value),
+            Tag::Double(value) => serializer.serialize_f64(*value),
+            Tag::ByteArray(values) => serializer.serialize_newtype_struct(BYTE_ARRAY_TOKEN, values),
+            Tag::String(value) => serializer.serialize_str(value),
+            Tag::List(items) => items.serialize(serializer),
+            Tag::Compound(entries) => entries.serialize(serializer),
+            Tag::IntArray(values) => serializer.serialize_newtype_struct(INT_ARRAY_TOKEN, values),
+            Tag::LongArray(values) => serializer.serialize_newtype_struct(LONG_ARRAY_TOKEN, values),
+        }
+    }
+}
+
+fn message(message: impl Into<String>) -> NbtError {
+    NbtError::Message(message.into())
+}
+
+/// The elements of a sequence serialized for one of the array types.
+fn array_elements<T: TryFrom<i64>>(tag: Tag) -> NbtResult<Vec<T>> {
+    let items = match tag {
+        Tag::List(items) => items,
+        Tag::ByteArray(values) => values.into_iter().map(Tag::Byte).collect(),
+        Tag::IntArray(values) => values.into_iter().map(Tag::Int).collect(),
+        Tag::LongArray(values) => values.into_iter().map(Tag::Long).collect(),
+        other => return Err(message(format!("expected an array, not {:?}", other))),
+    };
+    items
+        .iter()
+        .map(|item| {
+            item.as_i64()
+                .and_then(|value| T::try_from(value).ok())
+                .ok_or_else(|| message(format!("{:?} doesn't fit the array", item)))
+        })
+        .collect()
+}
+
+/// A unit variant's name, or a compound holding the variant's value under its name.
+fn variant(name: Option<&'static str>, tag: Tag) -> Tag {
+    match name {
+        Some(name) => Tag::Compound(Compound::from([(name.to_string(), tag)])),
+        None => tag,
+    }
+}
+
+struct TagSerializer;
+
+impl ser::Serializer for TagSerializer {
+    type Ok = Tag;
+    type Error = NbtError;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = SerializeCompound;
+
+    fn serialize_bool(self, value: bool) -> NbtResult<Tag> {
+        Ok(value.into())
+    }
+
+    fn serialize_i8(self, value: i8) -> NbtResult<Tag> {
+        Ok(Tag::Byte(value))
+    }
+
+    fn serialize_i16(self, value: i16) -> NbtResult<Tag> {
+        Ok(Tag::Short(value))
+    }
+
+    fn serialize_i32(self, value: i32) -> NbtResult<Tag> {
+        Ok(Tag::Int(value))
+    }
+
+    fn serialize_i64(self, value: i64) -> NbtResult<Tag> {
+        Ok(Tag::Long(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> NbtResult<Tag> {
+        Ok(Tag::Short(value.into()))
+    }
+
+    fn serialize_u16(self, value: u16) -> NbtResult<Tag> {
+        Ok(Tag::Int(value.into()))
+    }
+
+    fn serialize_u32(self, value: u32) -> NbtResult<Tag> {
+        Ok(Tag::Long(value.into()))
+    }
+
+    fn serialize_u64(self, value: u64) -> NbtResult<Tag> {
+        i64::try_from(value)
+            .map(Tag::Long)
+            .map_err(|_| message(format!("{} doesn't fit in a long", value)))
+    }
+
+    fn serialize_f32(self, value: f32) -> NbtResult<Tag> {
+        Ok(Tag::Float(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> NbtResult<Tag> {
+        Ok(Tag::Double(value))
+    }
+
+    fn serialize_char(self, value: char) -> NbtResult<Tag> {
+        Ok(Tag::String(value.to_string()))
+    }
+
+    fn serialize_str(self, value: &str) -> NbtResult<Tag> {
+        Ok(value.into())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> NbtResult<Tag> {
+        Ok(Tag::ByteArray(
+            value.iter().map(|byte| *byte as i8).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> NbtResult<Tag> {
+        Ok(Tag::End)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> NbtResult<Tag> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> NbtResult<Tag> {
+        Ok(Tag::End)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> NbtResult<Tag> {
+        Ok(Tag::End)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> NbtResult<Tag> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> NbtResult<Tag> {
+        let tag = value.serialize(self)?;
+        Ok(match name {
+            BYTE_ARRAY_TOKEN => Tag::ByteArray(array_elements(tag)?),
+            INT_ARRAY_TOKEN => Tag::IntArray(array_elements(tag)?),
+            LONG_ARRAY_TOKEN => Tag::LongArray(array_elements(tag)?),
+            _ => tag,
+        })
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant_name: &'static str,
+        value: &T,
+    ) -> NbtResult<Tag> {
+        Ok(variant(Some(variant_name), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> NbtResult<SerializeList> {
+        Ok(SerializeList {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> NbtResult<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> NbtResult<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> NbtResult<SerializeList> {
+        Ok(SerializeList {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> NbtResult<SerializeCompound> {
+        Ok(SerializeCompound {
+            variant: None,
+            entries: Compound::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> NbtResult<SerializeCompound> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> NbtResult<SerializeCompound> {
+        Ok(SerializeCompound {
+            variant: Some(variant),
+            entries: Compound::new(),
+            key: None,
+        })
+    }
+}
+
+struct SerializeList {
+    variant: Option<&'static str>,
+    items: Vec<Tag>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        match to_tag(value)? {
+            Tag::End => Err(message("lists can't hold None or ()")),
+            tag => {
+                self.items.push(tag);
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self) -> Tag {
+        variant(self.variant, Tag::List(self.items))
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
+
+struct SerializeCompound {
+    variant: Option<&'static str>,
+    entries: Compound,
+    /// The key of a map entry whose value comes next.
+    key: Option<String>,
+}
+
+impl SerializeCompound {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> NbtResult<()> {
+        let tag = to_tag(value)?;
+        if tag != Tag::End {
+            self.entries.insert(key, tag);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Tag {
+        variant(self.variant, Tag::Compound(self.entries))
+    }
+}
+
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    /// Keys may be strings, or anything that serializes as one or as an integer.
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> NbtResult<()> {
+        let key = match to_tag(key)? {
+            Tag::String(key) => key,
+            tag => match tag.as_i64() {
+                Some(key) => key.to_string(),
+                None => return Err(message(format!("{:?} can't be a compound key", tag))),
+            },
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> NbtResult<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| message("map value without a key"))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> NbtResult<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeCompound {
+    type Ok = Tag;
+    type Error = NbtError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> NbtResult<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> NbtResult<Tag> {
+        Ok(self.finish())
+    }
+}
@@ -0,0 +1,503 @@
+//! SNBT, the text form of NBT used in commands: `{id:"minecraft:stone",count:1b,tags:[I;1,2]}`.
+//!
+//! Numbers carry their type as a suffix (`1b`, `1s`, `1L`, `1.5f`, `1.5d`; plain `1` is an int and
+//! `1.5` a double), `true` and `false` are bytes, and typed arrays are written `[B;...]`,
+//! `[I;...]` and `[L;...]`. Strings and keys may be left unquoted if they only use
+//! `0-9A-Za-z_-.+` and don't read as a number. Lists may mix types and end with a trailing comma.
+
+use super::{Compound, MAX_DEPTH, NbtError, NbtResult, Tag};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+const INDENT: &str = "    ";
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-.+".contains(c)
+}
+
+impl Tag {
+    /// Parses SNBT. Anything but whitespace after the value is an error.
+    pub fn from_snbt(snbt: &str) -> NbtResult<Tag> {
+        let mut parser = Parser {
+            input: snbt,
+            position: 0,
+            depth: 0,
+        };
+        let tag = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < snbt.len() {
+            return Err(parser.error("trailing characters after the value"));
+        }
+        Ok(tag)
+    }
+
+    /// The compact SNBT form, as in `{a:1b,b:[I;1,2]}`. Also what `Display` writes.
+    pub fn to_snbt(&self) -> String {
+        self.to_string()
+    }
+
+    /// SNBT with compounds and lists of compounds or lists spread over indented lines, for logs
+    /// and test fixtures. `{:#}` writes the same.
+    pub fn to_snbt_pretty(&self) -> String {
+        format!("{:#}", self)
+    }
+
+    fn write_snbt(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        match self {
+            Tag::End => Ok(()),
+            Tag::Byte(value) => write!(f, "{}b", value),
+            Tag::Short(value) => write!(f, "{}s", value),
+            Tag::Int(value) => write!(f, "{}", value),
+            Tag::Long(value) => write!(f, "{}L", value),
+            Tag::Float(value) => write!(f, "{:?}f", value),
+            Tag::Double(value) => write!(f, "{:?}d", value),
+            Tag::ByteArray(values) => write_array(f, 'B', values.iter().map(|v| format!("{}B", v))),
+            Tag::String(value) => write_quoted(f, value),
+            Tag::List(items) => {
+                let multiline = f.alternate()
+                    && items
+                        .iter()
+                        .any(|item| matches!(item, Tag::Compound(_) | Tag::List(_)));
+                let entries = items.iter().map(|item| (None, item));
+                write_entries(f, ('[', ']'), entries, multiline, indent)
+            }
+            Tag::Compound(entries) => {
+                let multiline = f.alternate() && !entries.is_empty();
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| (Some(key.as_str()), value));
+                write_entries(f, ('{', '}'), entries, multiline, indent)
+            }
+            Tag::IntArray(values) => write_array(f, 'I', values.iter().map(|v| v.to_string())),
+            Tag::LongArray(values) => write_array(f, 'L', values.iter().map(|v| format!("{}L", v))),
+        }
+    }
+}
+
+/// Compact by default; `{:#}` pretty-prints.
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_snbt(f, 0)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = NbtError;
+
+    fn from_str(snbt: &str) -> NbtResult<Tag> {
+        Tag::from_snbt(snbt)
+    }
+}
+
+fn write_array(
+    f: &mut fmt::Formatter<'_>,
+    prefix: char,
+    values: impl Iterator<Item = String>,
+) -> fmt::Result {
+    write!(f, "[{};", prefix)?;
+    let separator = if f.alternate() { ", " } else { "," };
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        f.write_str(&value)?;
+    }
+    f.write_char(']')
+}
+
+/// Writes a list's items or a compound's entries between `open` and `close`, one per line if
+/// `multiline`.
+fn write_entries<'a>(
+    f: &mut fmt::Formatter<'_>,
+    (open, close): (char, char),
+    entries: impl Iterator<Item = (Option<&'a str>, &'a Tag)>,
+    multiline: bool,
+    indent: usize,
+) -> fmt::Result {
+    f.write_char(open)?;
+    let mut empty = true;
+    for (i, (key, value)) in entries.enumerate() {
+        empty = false;
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        if multiline {
+            f.write_char('\n')?;
+            f.write_str(&INDENT.repeat(indent + 1))?;
+        } else if i > 0 && f.alternate() {
+            f.write_char(' ')?;
+        }
+        if let Some(key) = key {
+            if !key.is_empty() && key.chars().all(is_unquoted_char) {
+                f.write_str(key)?;
+            } else {
+                write_quoted(f, key)?;
+            }
+            f.write_str(if f.alternate() { ": " } else { ":" })?;
+        }
+        value.write_snbt(f, indent + 1)?;
+    }
+    if multiline && !empty {
+        f.write_char('\n')?;
+        f.write_str(&INDENT.repeat(indent))?;
+    }
+    f.write_char(close)
+}
+
+/// Quotes with `"`, or with `'` if that saves escaping, as vanilla does.
+fn write_quoted(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    let quote = if value.contains('"') && !value.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+    f.write_char(quote)?;
+    for c in value.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c == quote => write!(f, "\\{}", c)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char(quote)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// Lists and compounds currently open.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: impl Into<String>) -> NbtError {
+        NbtError::InvalidSnbt {
+            offset: self.position,
+            reason: reason.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> NbtResult<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error(format!("expected '{}'", expected))),
+        }
+    }
+
+    fn value(&mut self) -> NbtResult<Tag> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Parser::compound),
+            Some('[') => self.nested(Parser::list),
+            Some('"' | '\'') => Ok(Tag::String(self.quoted()?)),
+            Some(_) => {
+                let word = self.unquoted()?;
+                Ok(scalar(word).unwrap_or_else(|| word.into()))
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> NbtResult<Tag>) -> NbtResult<Tag> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(format!("nested deeper than {} levels", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let tag = parse(self);
+        self.depth -= 1;
+        tag
+    }
+
+    /// Reads comma-separated elements up to `close`, allowing a trailing comma.
+    fn elements(
+        &mut self,
+        close: char,
+        mut element: impl FnMut(&mut Self) -> NbtResult<()>,
+    ) -> NbtResult<()> {
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(close) {
+                self.position += 1;
+                return Ok(());
+            }
+            element(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(c) if c == close => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error(format!("expected ',' or '{}'", close))),
+            }
+        }
+    }
+
+    fn compound(&mut self) -> NbtResult<Tag> {
+        self.expect('{')?;
+        let mut entries = Compound::new();
+        self.elements('}', |parser| {
+            parser.skip_whitespace();
+            let key = match parser.peek() {
+                Some('"' | '\'') => parser.quoted()?,
+                _ => parser.unquoted()?.to_string(),
+            };
+            parser.expect(':')?;
+            let value = parser.value()?;
+            entries.insert(key, value);
+            Ok(())
+        })?;
+        Ok(Tag::Compound(entries))
+    }
+
+    fn list(&mut self) -> NbtResult<Tag> {
+        self.expect('[')?;
+        let rest = &self.input[self.position..];
+        let array_type = match rest.as_bytes() {
+            [kind @ (b'B' | b'I' | b'L'), b';', ..] => Some(*kind),
+            _ => None,
+        };
+        let mut items = Vec::new();
+        if array_type.is_some() {
+            self.position += 2;
+        }
+        self.elements(']', |parser| {
+            items.push(parser.value()?);
+            Ok(())
+        })?;
+        let Some(array_type) = array_type else {
+            return Ok(Tag::List(items));
+        };
+        Ok(match array_type {
+            b'B' => Tag::ByteArray(self.array_elements(items)?),
+            b'I' => Tag::IntArray(self.array_elements(items)?),
+            _ => Tag::LongArray(self.array_elements(items)?),
+        })
+    }
+
+    fn array_elements<T: TryFrom<i64>>(&self, items: Vec<Tag>) -> NbtResult<Vec<T>> {
+        items
+            .iter()
+            .map(|item| {
+                item.as_i64()
+                    .and_then(|value| T::try_from(value).ok())
+                    .ok_or_else(|| self.error(format!("{} doesn't fit the array", item)))
+            })
+            .collect()
+    }
+
+    fn unquoted(&mut self) -> NbtResult<&'a str> {
+        let start = self.position;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.position += 1;
+        }
+        if self.position == start {
+            return Err(self.error("expected a value"));
+        }
+        Ok(&self.input[start..self.position])
+    }
+
+    fn quoted(&mut self) -> NbtResult<String> {
+        let quote = self.next().unwrap();
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c @ ('\\' | '"' | '\'')) => c,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    value.push(escaped);
+                }
+                Some(c) if c == quote => return Ok(value),
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+}
+
+/// A number or boolean written without quotes, or `None` if it is a plain string.
+fn scalar(word: &str) -> Option<Tag> {
+    match word {
+        "true" => return Some(Tag::Byte(1)),
+        "false" => return Some(Tag::Byte(0)),
+        _ => {}
+    }
+    let is_integer = |digits: &str| {
+        let digits = digits.strip_prefix(['-', '+']).unwrap_or(digits);
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    };
+    // Rust also parses `inf` and `NaN`, which SNBT leaves as strings.
+    let is_decimal = |digits: &str| {
+        digits.bytes().any(|b| b.is_ascii_digit())
+            && digits
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+    };
+    let (digits, suffix) = word.split_at(word.len() - 1);
+    match suffix {
+        "b" | "B" if is_integer(digits) => digits.parse().ok().map(Tag::Byte),
+        "s" | "S" if is_integer(digits) => digits.parse().ok().map(Tag::Short),
+        "l" | "L" if is_integer(digits) => digits.parse().ok().map(Tag::Long),
+        "f" | "F" if is_decimal(digits) => digits.parse().ok().map(Tag::Float),
+        "d" | "D" if is_decimal(digits) => digits.parse().ok().map(Tag::Double),
+        _ if is_integer(word) => word.parse().ok().map(Tag::Int),
+        _ if is_decimal(word) && word.contains('.') => word.parse().ok().map(Tag::Double),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_every_tag_type() {
+        let tag = Tag::from_snbt(
+            r#"{
+                byte: 1b, short: -2s, int: 3, long: 4L, float: 0.5f, double: 1.5,
+                bytes: [B; 1b, -2B], ints: [I;1,2,], longs: [L;], bool: true,
+                string: "say \"hi\"", single: 'it\'s', plain: minecraft.stone,
+                list: [1, "two", {}], "quoted key": {nested: [[]]},
+            }"#,
+        )
+        .unwrap();
+        let expected = Compound::from([
+            ("byte".to_string(), Tag::Byte(1)),
+            ("short".to_string(), Tag::Short(-2)),
+            ("int".to_string(), Tag::Int(3)),
+            ("long".to_string(), Tag::Long(4)),
+            ("float".to_string(), Tag::Float(0.5)),
+            ("double".to_string(), Tag::Double(1.5)),
+            ("bytes".to_string(), Tag::ByteArray(vec![1, -2])),
+            ("ints".to_string(), Tag::IntArray(vec![1, 2])),
+            ("longs".to_string(), Tag::LongArray(vec![])),
+            ("bool".to_string(), Tag::Byte(1)),
+            ("string".to_string(), "say \"hi\"".into()),
+            ("single".to_string(), "it's".into()),
+            ("plain".to_string(), "minecraft.stone".into()),
+            (
+                "list".to_string(),
+                Tag::List(vec![
+                    Tag::Int(1),
+                    "two".into(),
+                    Tag::Compound(Compound::new()),
+                ]),
+            ),
+            (
+                "quoted key".to_string(),
+                Tag::Compound(Compound::from([(
+                    "nested".to_string(),
+                    Tag::List(vec![Tag::List(vec![])]),
+                )])),
+            ),
+        ]);
+        assert_eq!(tag, Tag::Compound(expected));
+    }
+
+    #[test]
+    fn test_numbers_and_strings() {
+        for (snbt, tag) in [
+            ("1", Tag::Int(1)),
+            ("+1", Tag::Int(1)),
+            ("1.", Tag::Double(1.0)),
+            (".5", Tag::Double(0.5)),
+            ("1e3d", Tag::Double(1000.0)),
+            ("1.5e-3F", Tag::Float(0.0015)),
+            ("2147483648", "2147483648".into()),
+            ("128b", "128b".into()),
+            ("1e3", "1e3".into()),
+            ("infd", "infd".into()),
+            ("b", "b".into()),
+        ] {
+            assert_eq!(Tag::from_snbt(snbt).unwrap(), tag, "{}", snbt);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_snbt() {
+        for (snbt, offset) in [
+            ("{a:1", 4),
+            ("{a 1}", 3),
+            ("[1 2]", 3),
+            ("\"open", 5),
+            ("[B;1,2,300]", 11),
+            ("{} x", 3),
+            ("{a:minecraft:stone}", 12),
+            ("", 0),
+        ] {
+            match Tag::from_snbt(snbt) {
+                Err(NbtError::InvalidSnbt { offset: at, .. }) => assert_eq!(at, offset, "{}", snbt),
+                other => panic!("{}: {:?}", snbt, other),
+            }
+        }
+        let deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        assert!(Tag::from_snbt(&deep).is_err());
+    }
+
+    #[test]
+    fn test_compact_output() {
+        let tag = Tag::from_snbt(
+            r#"{id: "minecraft:stone", Count: 1b, "a b": [I; 1, 2], q: 'say "hi"', l: [1L, 2.5f]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tag.to_snbt(),
+            r#"{Count:1b,"a b":[I;1,2],id:"minecraft:stone",l:[1L,2.5f],q:'say "hi"'}"#
+        );
+        assert_eq!(Tag::from_snbt(&tag.to_snbt()).unwrap(), tag);
+    }
+
+    #[test]
+    fn test_pretty_output() {
+        let tag: Tag = r#"{name: "x", pos: [1, 2], items: [{id: 1b}, {}], empty: {}}"#
+            .parse()
+            .unwrap();
+        let pretty = tag.to_snbt_pretty();
+        assert_eq!(
+            pretty,
+            "{\n    empty: {},\n    items: [\n        {\n            id: 1b\n        },\n        {}\n    ],\n    name: \"x\",\n    pos: [1, 2]\n}"
+        );
+        assert_eq!(Tag::from_snbt(&pretty).unwrap(), tag);
+    }
+
+    #[test]
+    fn test_round_trips_strings_and_floats() {
+        for tag in [
+            Tag::from("line\nbreak\t\\ 'both' \"quotes\""),
+            Tag::from(""),
+            Tag::Float(1e-7),
+            Tag::Double(-123456.789),
+            Tag::Long(i64::MIN),
+        ] {
+            assert_eq!(Tag::from_snbt(&tag.to_snbt()).unwrap(), tag, "{}", tag);
+        }
+    }
+}
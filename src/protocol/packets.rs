@@ -0,0 +1,722 @@
+//! Packet ID registry.
+//!
+//! Every (connection state, direction) pair has an enum listing the packets it can carry, and
+//! each enum can decode a frame body from its packet ID. [`decode_packet`] picks the right enum
+//! for a given state and direction, so a frame from `read_packet_frame` can be dispatched
+//! without comparing IDs by hand.
+
+use crate::protocol::configuration::{
+    clientbound as configuration_clientbound, serverbound as configuration_serverbound,
+};
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, Packet, unsupported_in,
+};
+use crate::protocol::handshaking::serverbound as handshaking_serverbound;
+use crate::protocol::login::{clientbound as login_clientbound, serverbound as login_serverbound};
+use crate::protocol::play::{clientbound as play_clientbound, serverbound as play_serverbound};
+use crate::protocol::status::{
+    clientbound as status_clientbound, serverbound as status_serverbound,
+};
+use crate::protocol::types::VarInt;
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    Handshaking,
+    Status,
+    Login,
+    Configuration,
+    Play,
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConnectionState::Handshaking => "handshaking",
+            ConnectionState::Status => "status",
+            ConnectionState::Login => "login",
+            ConnectionState::Configuration => "configuration",
+            ConnectionState::Play => "play",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketDirection {
+    Clientbound,
+    Serverbound,
+}
+
+impl fmt::Display for PacketDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PacketDirection::Clientbound => "clientbound",
+            PacketDirection::Serverbound => "serverbound",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Implemented by every packet enum, so code that drives a connection can send and receive the
+/// packets of whichever state it is in.
+pub trait StatePacket: Sized {
+    const STATE: ConnectionState;
+    const DIRECTION: PacketDirection;
+
+    fn decode_for(version: ProtocolVersion, id: VarInt, buf: &mut BytesMut) -> DecodeResult<Self>;
+    fn encode_for(&self, version: ProtocolVersion) -> EncodeResult<(VarInt, BytesMut)>;
+    fn name(&self) -> &'static str;
+}
+
+/// Declares the packet enum for one state and direction. Each variant wraps the packet struct
+/// of the same name, and the struct's `Packet::ID` is the ID the variant is decoded from.
+macro_rules! packet_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident($state:ident, $direction:ident) in $module:ident {
+            $($packet:ident),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($packet($module::$packet),)*
+        }
+
+        impl $name {
+            pub const STATE: ConnectionState = ConnectionState::$state;
+            pub const DIRECTION: PacketDirection = PacketDirection::$direction;
+            /// Every packet in this state and direction as `(id, name)`, in ID order.
+            pub const PACKETS: &'static [(i32, &'static str)] = &[
+                $((<$module::$packet as Packet>::ID, stringify!($packet)),)*
+            ];
+
+            /// Decodes a packet body, choosing the packet type from its ID.
+            pub fn decode(id: VarInt, buf: &mut BytesMut) -> DecodeResult<Self> {
+                match id.0 {
+                    $(<$module::$packet as Packet>::ID => {
+                        Ok($name::$packet(<$module::$packet as Decode>::decode(buf)?))
+                    })*
+                    id => Err(DecodeError::UnknownPacketId(Self::STATE, Self::DIRECTION, id)),
+                }
+            }
+
+            /// Decodes a packet body sent by a peer speaking `version`.
+            pub fn decode_for(
+                version: ProtocolVersion,
+                id: VarInt,
+                buf: &mut BytesMut,
+            ) -> DecodeResult<Self> {
+                let unknown = DecodeError::UnknownPacketId(Self::STATE, Self::DIRECTION, id.0);
+                match version.latest_id(Self::STATE, Self::DIRECTION, id.0).ok_or(unknown)? {
+                    $(<$module::$packet as Packet>::ID => {
+                        Ok($name::$packet(<$module::$packet as Decode>::decode_for(version, buf)?))
+                    })*
+                    id => Err(DecodeError::UnknownPacketId(Self::STATE, Self::DIRECTION, id)),
+                }
+            }
+
+            /// Encodes the packet for a peer speaking `version`, returning the ID to frame it
+            /// with and the packet body.
+            pub fn encode_for(&self, version: ProtocolVersion) -> EncodeResult<(VarInt, BytesMut)> {
+                let id = version
+                    .packet_id(Self::STATE, Self::DIRECTION, self.id().0)
+                    .ok_or_else(|| unsupported_in(self.name(), version))?;
+                let mut buf = BytesMut::new();
+                match self {
+                    $($name::$packet(packet) => packet.encode_for(version, &mut buf)?,)*
+                }
+                Ok((VarInt(id), buf))
+            }
+
+            pub fn id(&self) -> VarInt {
+                match self {
+                    $($name::$packet(_) => VarInt(<$module::$packet as Packet>::ID),)*
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($name::$packet(_) => stringify!($packet),)*
+                }
+            }
+        }
+
+        impl StatePacket for $name {
+            const STATE: ConnectionState = ConnectionState::$state;
+            const DIRECTION: PacketDirection = PacketDirection::$direction;
+
+            fn decode_for(
+                version: ProtocolVersion,
+                id: VarInt,
+                buf: &mut BytesMut,
+            ) -> DecodeResult<Self> {
+                $name::decode_for(version, id, buf)
+            }
+
+            fn encode_for(&self, version: ProtocolVersion) -> EncodeResult<(VarInt, BytesMut)> {
+                $name::encode_for(self, version)
+            }
+
+            fn name(&self) -> &'static str {
+                $name::name(self)
+            }
+        }
+
+        /// Encodes the packet body only; the ID is written by the frame.
+        impl Encode for $name {
+            fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+                match self {
+                    $($name::$packet(packet) => packet.encode(buf),)*
+                }
+            }
+        }
+
+        $(
+            impl From<$module::$packet> for $name {
+                fn from(packet: $module::$packet) -> Self {
+                    $name::$packet(packet)
+                }
+            }
+        )*
+    };
+}
+
+packet_enum! {
+    /// Packets sent by the client before a state has been chosen.
+    ServerboundHandshakingPacket(Handshaking, Serverbound) in handshaking_serverbound {
+        Handshake,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the server during the status state.
+    ClientboundStatusPacket(Status, Clientbound) in status_clientbound {
+        StatusResponse,
+        PongResponse,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the client during the status state.
+    ServerboundStatusPacket(Status, Serverbound) in status_serverbound {
+        StatusRequest,
+        PingRequest,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the server during the login state.
+    ClientboundLoginPacket(Login, Clientbound) in login_clientbound {
+        DisconnectLogin,
+        EncryptionRequest,
+        LoginSuccess,
+        SetCompression,
+        LoginPluginRequest,
+        CookieRequestLogin,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the client during the login state.
+    ServerboundLoginPacket(Login, Serverbound) in login_serverbound {
+        LoginStart,
+        EncryptionResponse,
+        LoginPluginResponse,
+        LoginAcknowledged,
+        CookieResponseLogin,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the server during the configuration state.
+    ClientboundConfigurationPacket(Configuration, Clientbound) in configuration_clientbound {
+        CookieRequestConfiguration,
+        ClientboundPluginMessageConfiguration,
+        DisconnectConfiguration,
+        FinishConfiguration,
+        ClientboundKeepAliveConfiguration,
+        PingConfiguration,
+        ResetChat,
+        RegistryData,
+        RemoveResourcePackConfiguration,
+        AddResourcePackConfiguration,
+        StoreCookieConfiguration,
+        TransferConfiguration,
+        FeatureFlags,
+        UpdateTagsConfiguration,
+        ClientboundKnownPacks,
+        CustomReportDetailsConfiguration,
+        ServerLinksConfiguration,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the client during the configuration state.
+    ServerboundConfigurationPacket(Configuration, Serverbound) in configuration_serverbound {
+        ClientInformationConfiguration,
+        CookieResponseConfiguration,
+        ServerboundPluginMessageConfiguration,
+        AcknowledgeFinishConfiguration,
+        ServerboundKeepAliveConfiguration,
+        PongConfiguration,
+        ResourcePackResponseConfiguration,
+        ServerboundKnownPacks,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the server during the play state.
+    ClientboundPlayPacket(Play, Clientbound) in play_clientbound {
+        BundleDelimiter,
+        SpawnEntity,
+        EntityAnimationPacket,
+        AwardStatistics,
+        AcknowledgeBlockChange,
+        SetBlockDestroyStage,
+        BlockEntityData,
+        BlockAction,
+        BlockUpdate,
+        BossBar,
+        ChangeDifficulty,
+        ChunkBatchFinished,
+        ChunkBatchStart,
+        ChunkBiomes,
+        ClearTitles,
+        CommandSuggestionsResponse,
+        Commands,
+        CloseContainer,
+        SetContainerContent,
+        SetContainerProperty,
+        SetContainerSlot,
+        CookieRequestPlay,
+        SetCooldown,
+        ChatSuggestions,
+        ClientboundPluginMessagePlay,
+        DamageEvent,
+        DebugSample,
+        DeleteMessage,
+        DisconnectPlay,
+        DisguisedChatMessage,
+        EntityEvent,
+        TeleportEntityPlay,
+        Explosion,
+        UnloadChunk,
+        GameEvent,
+        OpenHorseScreen,
+        HurtAnimation,
+        InitializeWorldBorder,
+        ClientboundKeepAlivePlay,
+        ChunkDataAndUpdateLight,
+        WorldEvent,
+        Particle,
+        UpdateLight,
+        LoginPlay,
+        MapData,
+        MerchantOffers,
+        UpdateEntityPosition,
+        UpdateEntityPositionAndRotation,
+        MoveMinecartAlongTrack,
+        UpdateEntityRotation,
+        MoveVehicle,
+        OpenBook,
+        OpenScreen,
+        OpenSignEditor,
+        PingPlay,
+        PingResponsePlay,
+        PlaceGhostRecipe,
+        PlayerAbilities,
+        PlayerChatMessage,
+        EndCombat,
+        EnterCombat,
+        CombatDeath,
+        PlayerInfoRemove,
+        PlayerInfoUpdate,
+        LookAt,
+        SynchronizePlayerPosition,
+        PlayerRotation,
+        RecipeBookAdd,
+        RecipeBookRemove,
+        RecipeBookSettings,
+        RemoveEntities,
+        RemoveEntityEffect,
+        ResetScore,
+        RemoveResourcePackPlay,
+        AddResourcePackPlay,
+        Respawn,
+        SetHeadRotation,
+        UpdateSectionBlocks,
+        SelectAdvancementsTab,
+        ServerData,
+        SetActionBarText,
+        SetBorderCenter,
+        SetBorderLerpSize,
+        SetBorderSize,
+        SetBorderWarningDelay,
+        SetBorderWarningDistance,
+        SetCamera,
+        SetCenterChunk,
+        SetRenderDistance,
+        SetCursorItem,
+        SetDefaultSpawnPosition,
+        DisplayObjective,
+        SetEntityMetadata,
+        LinkEntities,
+        SetEntityVelocity,
+        SetEquipment,
+        SetExperience,
+        SetHealth,
+        SetHeldItem,
+        UpdateObjectives,
+        SetPassengers,
+        SetPlayerInventorySlot,
+        UpdateTeams,
+        UpdateScore,
+        SetSimulationDistance,
+        SetSubtitleText,
+        UpdateTime,
+        SetTitleText,
+        SetTitleAnimationTimes,
+        EntitySoundEffect,
+        SoundEffect,
+        StartConfiguration,
+        StopSound,
+        StoreCookiePlay,
+        SystemChatMessage,
+        SetTabListHeaderAndFooter,
+        TagQueryResponse,
+        PickupItem,
+        SynchronizeVehiclePosition,
+        TestInstanceBlockStatus,
+        SetTickingState,
+        StepTick,
+        TransferPlay,
+        UpdateAdvancements,
+        UpdateAttributes,
+        EntityEffect,
+        UpdateRecipes,
+        UpdateTagsPlay,
+        ProjectilePower,
+        CustomReportDetailsPlay,
+        ServerLinksPlay,
+    }
+}
+
+packet_enum! {
+    /// Packets sent by the client during the play state.
+    ServerboundPlayPacket(Play, Serverbound) in play_serverbound {
+        ConfirmTeleportation,
+        QueryBlockEntityTag,
+        BundleItemSelected,
+        ChangeDifficulty,
+        AcknowledgeMessage,
+        ChatCommand,
+        SignedChatCommand,
+        ChatMessage,
+        PlayerSession,
+        ChunkBatchReceived,
+        ClientStatus,
+        ClientTickEnd,
+        ClientInformation,
+        CommandSuggestionsRequest,
+        AcknowledgeConfiguration,
+        ClickContainerButton,
+        ClickContainer,
+        CloseContainer,
+        ChangeContainerSlotState,
+        CookieResponse,
+        ServerboundPluginMessage,
+        DebugSampleSubscription,
+        EditBook,
+        QueryEntityTag,
+        Interact,
+        JigsawGenerate,
+        ServerboundKeepAlive,
+        LockDifficulty,
+        SetPlayerPosition,
+        SetPlayerPositionAndRotation,
+        SetPlayerRotation,
+        SetPlayerMovementFlags,
+        MoveVehicle,
+        PaddleBoat,
+        PickItemFromBlock,
+        PickItemFromEntity,
+        PingRequest,
+        PlaceRecipe,
+        ServerboundPlayerAbilities,
+        PlayerAction,
+        PlayerCommand,
+        PlayerInput,
+        PlayerLoaded,
+        Pong,
+        ChangeRecipeBookSettings,
+        SetSeenRecipe,
+        RenameItem,
+        ServerboundResourcePackResponse,
+        SeenAdvancements,
+        SelectTrade,
+        SetBeaconEffect,
+        SetHeldItem,
+        ProgramCommandBlock,
+        ProgramCommandBlockMinecart,
+        SetCreativeModeSlot,
+        ProgramJigsawBlock,
+        ProgramStructureBlock,
+        SetTestBlock,
+        UpdateSign,
+        SwingArm,
+        TeleportToEntity,
+        TestInstanceBlockAction,
+        UseItemOn,
+        UseItem,
+    }
+}
+
+/// A decoded packet from any state and direction.
+pub enum AnyPacket {
+    ServerboundHandshaking(ServerboundHandshakingPacket),
+    ClientboundStatus(ClientboundStatusPacket),
+    ServerboundStatus(ServerboundStatusPacket),
+    ClientboundLogin(ClientboundLoginPacket),
+    ServerboundLogin(ServerboundLoginPacket),
+    ClientboundConfiguration(ClientboundConfigurationPacket),
+    ServerboundConfiguration(ServerboundConfigurationPacket),
+    /// Boxed: text-heavy play packets such as `UpdateTeams` dwarf every other variant.
+    ClientboundPlay(Box<ClientboundPlayPacket>),
+    ServerboundPlay(ServerboundPlayPacket),
+}
+
+impl AnyPacket {
+    pub fn state(&self) -> ConnectionState {
+        match self {
+            AnyPacket::ServerboundHandshaking(_) => ServerboundHandshakingPacket::STATE,
+            AnyPacket::ClientboundStatus(_) => ClientboundStatusPacket::STATE,
+            AnyPacket::ServerboundStatus(_) => ServerboundStatusPacket::STATE,
+            AnyPacket::ClientboundLogin(_) => ClientboundLoginPacket::STATE,
+            AnyPacket::ServerboundLogin(_) => ServerboundLoginPacket::STATE,
+            AnyPacket::ClientboundConfiguration(_) => ClientboundConfigurationPacket::STATE,
+            AnyPacket::ServerboundConfiguration(_) => ServerboundConfigurationPacket::STATE,
+            AnyPacket::ClientboundPlay(_) => ClientboundPlayPacket::STATE,
+            AnyPacket::ServerboundPlay(_) => ServerboundPlayPacket::STATE,
+        }
+    }
+
+    pub fn direction(&self) -> PacketDirection {
+        match self {
+            AnyPacket::ServerboundHandshaking(_) => ServerboundHandshakingPacket::DIRECTION,
+            AnyPacket::ClientboundStatus(_) => ClientboundStatusPacket::DIRECTION,
+            AnyPacket::ServerboundStatus(_) => ServerboundStatusPacket::DIRECTION,
+            AnyPacket::ClientboundLogin(_) => ClientboundLoginPacket::DIRECTION,
+            AnyPacket::ServerboundLogin(_) => ServerboundLoginPacket::DIRECTION,
+            AnyPacket::ClientboundConfiguration(_) => ClientboundConfigurationPacket::DIRECTION,
+            AnyPacket::ServerboundConfiguration(_) => ServerboundConfigurationPacket::DIRECTION,
+            AnyPacket::ClientboundPlay(_) => ClientboundPlayPacket::DIRECTION,
+            AnyPacket::ServerboundPlay(_) => ServerboundPlayPacket::DIRECTION,
+        }
+    }
+
+    pub fn id(&self) -> VarInt {
+        match self {
+            AnyPacket::ServerboundHandshaking(packet) => packet.id(),
+            AnyPacket::ClientboundStatus(packet) => packet.id(),
+            AnyPacket::ServerboundStatus(packet) => packet.id(),
+            AnyPacket::ClientboundLogin(packet) => packet.id(),
+            AnyPacket::ServerboundLogin(packet) => packet.id(),
+            AnyPacket::ClientboundConfiguration(packet) => packet.id(),
+            AnyPacket::ServerboundConfiguration(packet) => packet.id(),
+            AnyPacket::ClientboundPlay(packet) => packet.id(),
+            AnyPacket::ServerboundPlay(packet) => packet.id(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnyPacket::ServerboundHandshaking(packet) => packet.name(),
+            AnyPacket::ClientboundStatus(packet) => packet.name(),
+            AnyPacket::ServerboundStatus(packet) => packet.name(),
+            AnyPacket::ClientboundLogin(packet) => packet.name(),
+            AnyPacket::ServerboundLogin(packet) => packet.name(),
+            AnyPacket::ClientboundConfiguration(packet) => packet.name(),
+            AnyPacket::ServerboundConfiguration(packet) => packet.name(),
+            AnyPacket::ClientboundPlay(packet) => packet.name(),
+            AnyPacket::ServerboundPlay(packet) => packet.name(),
+        }
+    }
+}
+
+/// The packets of the latest protocol version for `state` and `direction` as `(id, name)`.
+pub fn packet_table(
+    state: ConnectionState,
+    direction: PacketDirection,
+) -> &'static [(i32, &'static str)] {
+    use ConnectionState::*;
+    use PacketDirection::*;
+
+    match (state, direction) {
+        (Handshaking, Serverbound) => ServerboundHandshakingPacket::PACKETS,
+        (Status, Clientbound) => ClientboundStatusPacket::PACKETS,
+        (Status, Serverbound) => ServerboundStatusPacket::PACKETS,
+        (Login, Clientbound) => ClientboundLoginPacket::PACKETS,
+        (Login, Serverbound) => ServerboundLoginPacket::PACKETS,
+        (Configuration, Clientbound) => ClientboundConfigurationPacket::PACKETS,
+        (Configuration, Serverbound) => ServerboundConfigurationPacket::PACKETS,
+        (Play, Clientbound) => ClientboundPlayPacket::PACKETS,
+        (Play, Serverbound) => ServerboundPlayPacket::PACKETS,
+        (Handshaking, Clientbound) => &[],
+    }
+}
+
+/// Decodes a frame body sent in `state` and travelling in `direction` by a peer speaking
+/// `version`.
+pub fn decode_packet(
+    version: ProtocolVersion,
+    state: ConnectionState,
+    direction: PacketDirection,
+    id: VarInt,
+    buf: &mut BytesMut,
+) -> DecodeResult<AnyPacket> {
+    use ConnectionState::*;
+    use PacketDirection::*;
+
+    Ok(match (state, direction) {
+        (Handshaking, Serverbound) => AnyPacket::ServerboundHandshaking(
+            ServerboundHandshakingPacket::decode_for(version, id, buf)?,
+        ),
+        (Status, Clientbound) => {
+            AnyPacket::ClientboundStatus(ClientboundStatusPacket::decode_for(version, id, buf)?)
+        }
+        (Status, Serverbound) => {
+            AnyPacket::ServerboundStatus(ServerboundStatusPacket::decode_for(version, id, buf)?)
+        }
+        (Login, Clientbound) => {
+            AnyPacket::ClientboundLogin(ClientboundLoginPacket::decode_for(version, id, buf)?)
+        }
+        (Login, Serverbound) => {
+            AnyPacket::ServerboundLogin(ServerboundLoginPacket::decode_for(version, id, buf)?)
+        }
+        (Configuration, Clientbound) => AnyPacket::ClientboundConfiguration(
+            ClientboundConfigurationPacket::decode_for(version, id, buf)?,
+        ),
+        (Configuration, Serverbound) => AnyPacket::ServerboundConfiguration(
+            ServerboundConfigurationPacket::decode_for(version, id, buf)?,
+        ),
+        (Play, Clientbound) => AnyPacket::ClientboundPlay(Box::new(
+            ClientboundPlayPacket::decode_for(version, id, buf)?,
+        )),
+        (Play, Serverbound) => {
+            AnyPacket::ServerboundPlay(ServerboundPlayPacket::decode_for(version, id, buf)?)
+        }
+        (Handshaking, Clientbound) => {
+            return Err(DecodeError::UnknownPacketId(state, direction, id.0));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::status::serverbound::PingRequest;
+    use crate::protocol::text::TextComponent;
+
+    fn assert_contiguous(packets: &[(i32, &str)]) {
+        for (index, (id, name)) in packets.iter().enumerate() {
+            assert_eq!(*id, index as i32, "{} is out of order", name);
+        }
+    }
+
+    #[test]
+    fn test_packet_tables_are_contiguous() {
+        assert_contiguous(ServerboundHandshakingPacket::PACKETS);
+        assert_contiguous(ClientboundStatusPacket::PACKETS);
+        assert_contiguous(ServerboundStatusPacket::PACKETS);
+        assert_contiguous(ClientboundLoginPacket::PACKETS);
+        assert_contiguous(ServerboundLoginPacket::PACKETS);
+        assert_contiguous(ClientboundConfigurationPacket::PACKETS);
+        assert_contiguous(ServerboundConfigurationPacket::PACKETS);
+        assert_contiguous(ClientboundPlayPacket::PACKETS);
+        assert_contiguous(ServerboundPlayPacket::PACKETS);
+        assert_eq!(ClientboundPlayPacket::PACKETS.len(), 0x83);
+        assert_eq!(ServerboundPlayPacket::PACKETS.len(), 0x40);
+    }
+
+    #[test]
+    fn test_decode_dispatches_on_id() {
+        let packet = ServerboundStatusPacket::from(PingRequest { payload: 42 });
+        let mut buf = packet.to_bytes().unwrap();
+        match ServerboundStatusPacket::decode(packet.id(), &mut buf).unwrap() {
+            ServerboundStatusPacket::PingRequest(ping) => assert_eq!(ping.payload, 42),
+            other => panic!("decoded {}", other.name()),
+        }
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_packet_by_state_and_direction() {
+        let mut buf = BytesMut::new();
+        let packet = decode_packet(
+            ProtocolVersion::LATEST,
+            ConnectionState::Configuration,
+            PacketDirection::Clientbound,
+            VarInt(0x03),
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(packet.name(), "FinishConfiguration");
+        assert_eq!(packet.state(), ConnectionState::Configuration);
+        assert_eq!(packet.direction(), PacketDirection::Clientbound);
+        assert_eq!(packet.id(), VarInt(0x03));
+    }
+
+    #[test]
+    fn test_encode_for_uses_version_ids() {
+        let packet = ClientboundPlayPacket::from(play_clientbound::SetTickingState {
+            tick_rate: 20.0,
+            is_frozen: false,
+        });
+        let (latest_id, _) = packet.encode_for(ProtocolVersion::LATEST).unwrap();
+        let (old_id, mut body) = packet.encode_for(ProtocolVersion::V1_21_4).unwrap();
+        assert_eq!(latest_id, packet.id());
+        assert_eq!(old_id.0, latest_id.0 - 1);
+
+        match ClientboundPlayPacket::decode_for(ProtocolVersion::V1_21_4, old_id, &mut body) {
+            Ok(ClientboundPlayPacket::SetTickingState(decoded)) => {
+                assert_eq!(decoded.tick_rate, 20.0)
+            }
+            _ => panic!("expected the 1.21.4 ID to select SetTickingState"),
+        }
+
+        let missing = ClientboundPlayPacket::from(play_clientbound::TestInstanceBlockStatus {
+            status: TextComponent::text(""),
+            size: None,
+        });
+        assert!(missing.encode_for(ProtocolVersion::V1_21_4).is_err());
+    }
+
+    #[test]
+    fn test_unknown_id_is_an_error() {
+        let err = ServerboundLoginPacket::decode(VarInt(0x7F), &mut BytesMut::new())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            DecodeError::UnknownPacketId(
+                ConnectionState::Login,
+                PacketDirection::Serverbound,
+                0x7F
+            )
+        ));
+        assert_eq!(err.to_string(), "Unknown serverbound login packet ID 0x7f");
+        assert!(
+            decode_packet(
+                ProtocolVersion::LATEST,
+                ConnectionState::Handshaking,
+                PacketDirection::Clientbound,
+                VarInt(0),
+                &mut BytesMut::new(),
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Particles with their options, as carried by entity metadata.
+//!
+//! A particle is its ID in the `minecraft:particle_type` registry of
+//! [`ProtocolVersion::LATEST`](crate::protocol::version::ProtocolVersion::LATEST), followed by
+//! options whose layout depends on the type. Most types have no options.
+
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_varint_sync, write_varint_sync,
+};
+use crate::protocol::types::{Position, Slot, VarInt};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+
+pub const BLOCK: i32 = 1;
+pub const BLOCK_MARKER: i32 = 2;
+pub const DUST: i32 = 13;
+pub const DUST_COLOR_TRANSITION: i32 = 14;
+pub const ENTITY_EFFECT: i32 = 20;
+pub const FALLING_DUST: i32 = 28;
+pub const TINTED_LEAVES: i32 = 35;
+pub const SCULK_CHARGE: i32 = 37;
+pub const ITEM: i32 = 46;
+pub const VIBRATION: i32 = 47;
+pub const TRAIL: i32 = 48;
+pub const SHRIEK: i32 = 102;
+pub const DUST_PILLAR: i32 = 108;
+pub const BLOCK_CRUMBLE: i32 = 112;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VibrationSource {
+    Block(Position),
+    Entity { entity_id: i32, eye_height: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParticleOptions {
+    None,
+    /// A block state ID, for `block`, `block_marker`, `falling_dust`, `dust_pillar` and
+    /// `block_crumble`.
+    BlockState(i32),
+    Dust {
+        /// RGB.
+        color: i32,
+        scale: f32,
+    },
+    DustColorTransition {
+        from_color: i32,
+        to_color: i32,
+        scale: f32,
+    },
+    /// An ARGB color, for `entity_effect` and `tinted_leaves`.
+    Color(i32),
+    SculkCharge {
+        roll: f32,
+    },
+    Item(Slot),
+    Vibration {
+        source: VibrationSource,
+        ticks: i32,
+    },
+    Trail {
+        target: (f64, f64, f64),
+        color: i32,
+        duration: i32,
+    },
+    Shriek {
+        delay: i32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub id: i32,
+    pub options: ParticleOptions,
+}
+
+impl Particle {
+    /// A particle type without options.
+    pub fn simple(id: i32) -> Self {
+        Particle {
+            id,
+            options: ParticleOptions::None,
+        }
+    }
+}
+
+impl Encode for Particle {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        write_varint_sync(buf, VarInt(self.id))?;
+        match &self.options {
+            ParticleOptions::None => Ok(()),
+            ParticleOptions::BlockState(state) => write_varint_sync(buf, VarInt(*state)),
+            ParticleOptions::Dust { color, scale } => {
+                color.encode(buf)?;
+                scale.encode(buf)
+            }
+            ParticleOptions::DustColorTransition {
+                from_color,
+                to_color,
+                scale,
+            } => {
+                from_color.encode(buf)?;
+                to_color.encode(buf)?;
+                scale.encode(buf)
+            }
+            ParticleOptions::Color(color) => color.encode(buf),
+            ParticleOptions::SculkCharge { roll } => roll.encode(buf),
+            ParticleOptions::Item(item) => item.encode_for(version, buf),
+            ParticleOptions::Vibration { source, ticks } => {
+                match source {
+                    VibrationSource::Block(position) => {
+                        write_varint_sync(buf, VarInt(0))?;
+                        position.encode(buf)?;
+                    }
+                    VibrationSource::Entity {
+                        entity_id,
+                        eye_height,
+                    } => {
+                        write_varint_sync(buf, VarInt(1))?;
+                        write_varint_sync(buf, VarInt(*entity_id))?;
+                        eye_height.encode(buf)?;
+                    }
+                }
+                write_varint_sync(buf, VarInt(*ticks))
+            }
+            ParticleOptions::Trail {
+                target,
+                color,
+                duration,
+            } => {
+                target.encode(buf)?;
+                color.encode(buf)?;
+                write_varint_sync(buf, VarInt(*duration))
+            }
+            ParticleOptions::Shriek { delay } => write_varint_sync(buf, VarInt(*delay)),
+        }
+    }
+}
+
+impl Decode for Particle {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let id = read_varint_sync(buf)?.0;
+        let options = match id {
+            BLOCK | BLOCK_MARKER | FALLING_DUST | DUST_PILLAR | BLOCK_CRUMBLE => {
+                ParticleOptions::BlockState(read_varint_sync(buf)?.0)
+            }
+            DUST => ParticleOptions::Dust {
+                color: i32::decode(buf)?,
+                scale: f32::decode(buf)?,
+            },
+            DUST_COLOR_TRANSITION => ParticleOptions::DustColorTransition {
+                from_color: i32::decode(buf)?,
+                to_color: i32::decode(buf)?,
+                scale: f32::decode(buf)?,
+            },
+            ENTITY_EFFECT | TINTED_LEAVES => ParticleOptions::Color(i32::decode(buf)?),
+            SCULK_CHARGE => ParticleOptions::SculkCharge {
+                roll: f32::decode(buf)?,
+            },
+            ITEM => ParticleOptions::Item(Slot::decode_for(version, buf)?),
+            VIBRATION => {
+                let source = match read_varint_sync(buf)?.0 {
+                    0 => VibrationSource::Block(Position::decode(buf)?),
+                    1 => VibrationSource::Entity {
+                        entity_id: read_varint_sync(buf)?.0,
+                        eye_height: f32::decode(buf)?,
+                    },
+                    other => {
+                        return Err(DecodeError::InvalidEnumValue(
+                            "VibrationSource",
+                            other as i64,
+                        ));
+                    }
+                };
+                ParticleOptions::Vibration {
+                    source,
+                    ticks: read_varint_sync(buf)?.0,
+                }
+            }
+            TRAIL => ParticleOptions::Trail {
+                target: <(f64, f64, f64)>::decode(buf)?,
+                color: i32::decode(buf)?,
+                duration: read_varint_sync(buf)?.0,
+            },
+            SHRIEK => ParticleOptions::Shriek {
+                delay: read_varint_sync(buf)?.0,
+            },
+            _ => ParticleOptions::None,
+        };
+        Ok(Particle { id, options })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::assert_round_trip;
+
+    #[test]
+    fn test_particles_round_trip() {
+        let particles = [
+            Particle::simple(0),
+            Particle {
+                id: BLOCK,
+                options: ParticleOptions::BlockState(1),
+            },
+            Particle {
+                id: DUST,
+                options: ParticleOptions::Dust {
+                    color: 0xFF0000,
+                    scale: 1.5,
+                },
+            },
+            Particle {
+                id: ITEM,
+                options: ParticleOptions::Item(Slot::new(1, 1)),
+            },
+            Particle {
+                id: VIBRATION,
+                options: ParticleOptions::Vibration {
+                    source: VibrationSource::Entity {
+                        entity_id: 7,
+                        eye_height: 1.62,
+                    },
+                    ticks: 20,
+                },
+            },
+            Particle {
+                id: TRAIL,
+                options: ParticleOptions::Trail {
+                    target: (1.0, 2.0, 3.0),
+                    color: 0x00FF00,
+                    duration: 40,
+                },
+            },
+        ];
+        for particle in &particles {
+            assert_round_trip(particle);
+        }
+    }
+
+    #[test]
+    fn test_options_follow_the_id() {
+        let particle = Particle {
+            id: SHRIEK,
+            options: ParticleOptions::Shriek { delay: 5 },
+        };
+        assert_eq!(particle.to_bytes().unwrap().as_ref(), [102, 5]);
+    }
+}
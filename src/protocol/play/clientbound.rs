@@ -1,21 +1,28 @@
+use bytes::{BufMut, BytesMut};
 use uuid::Uuid;
 
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, Packet, missing_field,
+    read_fixed_bytes_sync,
+};
 use crate::protocol::types::{
-    Advancement, AdvancementFrameType, AdvancementProgress, Angle, AttributeProperty,
-    BossBarAction, BossBarColor, BossBarDivision, BossBarFlags, ChatSuggestionAction, ChatTypeData,
-    ChunkData, ChunkSectionBlockUpdate, CollisionRule, CustomReportDetail, Difficulty,
-    EntityAnimation, EntityEffectFlags, EntityMetadata, EquipmentEntry, FilterType, FriendlyFlags,
-    GameEventType, GameMode, Hand, IdOr, IdSet, Identifier, JsonTextComponent, LightData,
-    LookAtAnchor, MapColorPatch, MapIcon, MerchantTrade, NameTagVisibility, Nbt, NumberFormat,
-    ObjectiveMode, ObjectiveType, PlayerInfoEntry, Position, PreviousGameMode, RecipeBookEntry,
-    RecipeDisplay, RespawnDataKeptFlags, ServerLink, Slot, SlotDisplay, SoundCategory, SoundEvent,
-    Statistic, StopSoundFlags, SuggestionMatch, TeamColor, TeamMethod, TeleportFlags, VarInt,
-    VarLong, VillagerLevel,
+    Advancement, AdvancementProgress, Angle, AttributeProperty, BossBarAction, ChatTypeData,
+    ChunkData, ChunkSectionBlockUpdate, CollisionRule, CommandNode, CustomReportDetail,
+    EntityMetadata, EquipmentEntry, FilterType, IdOr, Identifier, TextComponent, LightData,
+    MapColorPatch, MapIcon, MerchantTrade, NameTagVisibility, Nbt, NumberFormat, ObjectiveMode,
+    PlayerInfoActionData, PlayerInfoEntry,
+    Position, RecipeBookEntry, RecipeDisplay, RegistryTagData, ServerLink, Slot, SoundEvent, Statistic,
+    StopSoundFlags, SuggestionMatch, TeamMethod, VarInt, VarLong,
 };
+use crate::protocol::version::ProtocolVersion;
 use std::collections::HashMap;
 
+#[derive(Packet)]
+#[packet(id = 0x00)]
 pub struct BundleDelimiter;
 
+#[derive(Packet)]
+#[packet(id = 0x01)]
 pub struct SpawnEntity {
     pub entity_id: VarInt,
     pub entity_uuid: Uuid,
@@ -32,31 +39,43 @@ pub struct SpawnEntity {
     pub velocity_z: i16,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x02)]
 pub struct EntityAnimationPacket {
     pub entity_id: VarInt,
     pub animation: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x03)]
 pub struct AwardStatistics {
     pub statistics: Vec<Statistic>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x04)]
 pub struct AcknowledgeBlockChange {
     pub sequence_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x05)]
 pub struct SetBlockDestroyStage {
     pub entity_id: VarInt,
     pub location: Position,
     pub destroy_stage: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x06)]
 pub struct BlockEntityData {
     pub location: Position,
     pub block_entity_type: VarInt,
     pub nbt_data: Nbt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x07)]
 pub struct BlockAction {
     pub location: Position,
     pub action_id: u8,
@@ -64,6 +83,8 @@ pub struct BlockAction {
     pub block_type: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x08)]
 pub struct BlockUpdate {
     pub location: Position,
     pub block_id: VarInt,
@@ -72,7 +93,7 @@ pub struct BlockUpdate {
 pub enum BossBar {
     Add {
         uuid: Uuid,
-        title: JsonTextComponent,
+        title: TextComponent,
         health: f32,
         color: VarInt,
         division: VarInt,
@@ -87,7 +108,7 @@ pub enum BossBar {
     },
     UpdateTitle {
         uuid: Uuid,
-        title: JsonTextComponent,
+        title: TextComponent,
     },
     UpdateStyle {
         uuid: Uuid,
@@ -100,25 +121,42 @@ pub enum BossBar {
     },
 }
 
+impl Packet for BossBar {
+    const ID: i32 = 0x09;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x0A)]
 pub struct ChangeDifficulty {
     pub difficulty: u8,
     pub difficulty_locked: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0B)]
 pub struct ChunkBatchFinished {
     pub batch_size: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0C)]
 pub struct ChunkBatchStart;
 
+#[derive(Packet)]
+#[packet(id = 0x0D)]
 pub struct ChunkBiomes {
+    #[packet(rest)]
     pub chunk_biome_data: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0E)]
 pub struct ClearTitles {
     pub reset: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0F)]
 pub struct CommandSuggestionsResponse {
     pub id: VarInt,
     pub start: VarInt,
@@ -126,15 +164,21 @@ pub struct CommandSuggestionsResponse {
     pub matches: Vec<SuggestionMatch>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x10)]
 pub struct Commands {
-    pub nodes: Vec<u8>,
+    pub nodes: Vec<CommandNode>,
     pub root_index: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x11)]
 pub struct CloseContainer {
     pub window_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x12)]
 pub struct SetContainerContent {
     pub window_id: VarInt,
     pub state_id: VarInt,
@@ -142,12 +186,16 @@ pub struct SetContainerContent {
     pub carried_item: Slot,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x13)]
 pub struct SetContainerProperty {
     pub window_id: VarInt,
     pub property: i16,
     pub value: i16,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x14)]
 pub struct SetContainerSlot {
     pub window_id: i8,
     pub state_id: VarInt,
@@ -155,25 +203,36 @@ pub struct SetContainerSlot {
     pub slot_data: Slot,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x15)]
 pub struct CookieRequestPlay {
     pub key: Identifier,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x16)]
 pub struct SetCooldown {
     pub item_id: VarInt,
     pub cooldown_ticks: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x17)]
 pub struct ChatSuggestions {
     pub action: VarInt,
     pub entries: Vec<String>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x18)]
 pub struct ClientboundPluginMessagePlay {
     pub channel: Identifier,
+    #[packet(rest)]
     pub data: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x19)]
 pub struct DamageEvent {
     pub entity_id: VarInt,
     pub source_type_id: VarInt,
@@ -182,6 +241,8 @@ pub struct DamageEvent {
     pub source_position: Option<(f64, f64, f64)>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1A)]
 pub struct DebugSample {
     pub sample: Vec<i64>,
     pub sample_type: VarInt,
@@ -192,22 +253,34 @@ pub struct DeleteMessage {
     pub signature: Option<Vec<u8>>,
 }
 
+impl Packet for DeleteMessage {
+    const ID: i32 = 0x1B;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x1C)]
 pub struct DisconnectPlay {
-    pub reason: JsonTextComponent,
+    pub reason: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1D)]
 pub struct DisguisedChatMessage {
-    pub message: JsonTextComponent,
+    pub message: TextComponent,
     pub chat_type: IdOr<ChatTypeData>,
-    pub sender_name: JsonTextComponent,
-    pub target_name: Option<JsonTextComponent>,
+    pub sender_name: TextComponent,
+    pub target_name: Option<TextComponent>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1E)]
 pub struct EntityEvent {
     pub entity_id: i32,
     pub entity_status: i8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1F)]
 pub struct TeleportEntityPlay {
     pub entity_id: VarInt,
     pub x: f64,
@@ -221,6 +294,8 @@ pub struct TeleportEntityPlay {
     pub on_ground: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x20)]
 pub struct Explosion {
     pub x: f64,
     pub y: f64,
@@ -231,27 +306,37 @@ pub struct Explosion {
     pub explosion_sound: IdOr<SoundEvent>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x21)]
 pub struct UnloadChunk {
     pub chunk_x: i32,
     pub chunk_z: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x22)]
 pub struct GameEvent {
     pub event: u8,
     pub value: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x23)]
 pub struct OpenHorseScreen {
     pub window_id: VarInt,
     pub inventory_columns_count: VarInt,
     pub entity_id: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x24)]
 pub struct HurtAnimation {
     pub entity_id: VarInt,
     pub yaw: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x25)]
 pub struct InitializeWorldBorder {
     pub x: f64,
     pub z: f64,
@@ -263,10 +348,14 @@ pub struct InitializeWorldBorder {
     pub warning_time: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x26)]
 pub struct ClientboundKeepAlivePlay {
     pub keep_alive_id: i64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x27)]
 pub struct ChunkDataAndUpdateLight {
     pub chunk_x: i32,
     pub chunk_z: i32,
@@ -274,6 +363,8 @@ pub struct ChunkDataAndUpdateLight {
     pub light_data: LightData,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x28)]
 pub struct WorldEvent {
     pub event: i32,
     pub location: Position,
@@ -281,6 +372,8 @@ pub struct WorldEvent {
     pub disable_relative_volume: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x29)]
 pub struct Particle {
     pub long_distance: bool,
     pub always_visible: bool,
@@ -293,15 +386,20 @@ pub struct Particle {
     pub max_speed: f32,
     pub particle_count: i32,
     pub particle_id: VarInt,
+    #[packet(rest)]
     pub data: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2A)]
 pub struct UpdateLight {
     pub chunk_x: VarInt,
     pub chunk_z: VarInt,
     pub light_data: LightData,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2B)]
 pub struct LoginPlay {
     pub entity_id: i32,
     pub is_hardcore: bool,
@@ -333,6 +431,12 @@ pub struct MapData {
     pub color_patch: Option<MapColorPatch>,
 }
 
+impl Packet for MapData {
+    const ID: i32 = 0x2C;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x2D)]
 pub struct MerchantOffers {
     pub window_id: VarInt,
     pub trades: Vec<MerchantTrade>,
@@ -342,6 +446,8 @@ pub struct MerchantOffers {
     pub can_restock: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2E)]
 pub struct UpdateEntityPosition {
     pub entity_id: VarInt,
     pub delta_x: i16,
@@ -350,6 +456,8 @@ pub struct UpdateEntityPosition {
     pub on_ground: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2F)]
 pub struct UpdateEntityPositionAndRotation {
     pub entity_id: VarInt,
     pub delta_x: i16,
@@ -360,12 +468,16 @@ pub struct UpdateEntityPositionAndRotation {
     pub on_ground: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x30)]
 pub struct MoveMinecartAlongTrack {
     pub entity_id: VarInt,
     pub steps: Vec<u8>,
     pub weight: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x31)]
 pub struct UpdateEntityRotation {
     pub entity_id: VarInt,
     pub yaw: Angle,
@@ -373,6 +485,8 @@ pub struct UpdateEntityRotation {
     pub on_ground: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x32)]
 pub struct MoveVehicle {
     pub x: f64,
     pub y: f64,
@@ -381,34 +495,48 @@ pub struct MoveVehicle {
     pub pitch: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x33)]
 pub struct OpenBook {
     pub hand: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x34)]
 pub struct OpenScreen {
     pub window_id: VarInt,
     pub window_type: VarInt,
-    pub window_title: JsonTextComponent,
+    pub window_title: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x35)]
 pub struct OpenSignEditor {
     pub location: Position,
     pub is_front_text: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x36)]
 pub struct PingPlay {
     pub id: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x37)]
 pub struct PingResponsePlay {
     pub payload: i64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x38)]
 pub struct PlaceGhostRecipe {
     pub window_id: VarInt,
     pub recipe_display: RecipeDisplay,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x39)]
 pub struct PlayerAbilities {
     pub flags: u8,
     pub flying_speed: f32,
@@ -424,25 +552,119 @@ pub struct PlayerChatMessage {
     pub timestamp: i64,
     pub salt: i64,
     pub previous_messages: Vec<u8>,
-    pub unsigned_content: Option<JsonTextComponent>,
+    pub unsigned_content: Option<TextComponent>,
     pub filter_type: VarInt,
     pub filter_type_bits: Option<Vec<u8>>,
     pub chat_type: IdOr<ChatTypeData>,
-    pub sender_name: JsonTextComponent,
-    pub target_name: Option<JsonTextComponent>,
-}
-
+    pub sender_name: TextComponent,
+    pub target_name: Option<TextComponent>,
+}
+
+impl Packet for PlayerChatMessage {
+    const ID: i32 = 0x3A;
+}
+
+/// `global_index` was added in 1.21.5; older clients do not receive it.
+impl Encode for PlayerChatMessage {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        if version >= ProtocolVersion::V1_21_5 {
+            self.global_index.encode(buf)?;
+        }
+        self.sender.encode(buf)?;
+        self.index.encode(buf)?;
+        self.message_signature.is_some().encode(buf)?;
+        if let Some(signature) = &self.message_signature {
+            buf.put_slice(signature);
+        }
+        self.message.encode(buf)?;
+        self.timestamp.encode(buf)?;
+        self.salt.encode(buf)?;
+        self.previous_messages.encode(buf)?;
+        self.unsigned_content.encode_for(version, buf)?;
+        self.filter_type.encode(buf)?;
+        if self.filter_type.0 == FilterType::PartiallyFiltered as i32 {
+            self.filter_type_bits
+                .as_ref()
+                .ok_or_else(|| missing_field("PlayerChatMessage", "filter_type_bits"))?
+                .encode(buf)?;
+        }
+        self.chat_type.encode_for(version, buf)?;
+        self.sender_name.encode_for(version, buf)?;
+        self.target_name.encode_for(version, buf)
+    }
+}
+
+impl Decode for PlayerChatMessage {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let global_index = if version >= ProtocolVersion::V1_21_5 {
+            VarInt::decode(buf)?
+        } else {
+            VarInt(0)
+        };
+        let sender = Uuid::decode(buf)?;
+        let index = VarInt::decode(buf)?;
+        let message_signature = if bool::decode(buf)? {
+            Some(read_fixed_bytes_sync(buf, MESSAGE_SIGNATURE_LEN)?)
+        } else {
+            None
+        };
+        let message = String::decode(buf)?;
+        let timestamp = i64::decode(buf)?;
+        let salt = i64::decode(buf)?;
+        let previous_messages = Vec::decode(buf)?;
+        let unsigned_content = Option::decode_for(version, buf)?;
+        let filter_type = VarInt::decode(buf)?;
+        let filter_type_bits = if filter_type.0 == FilterType::PartiallyFiltered as i32 {
+            Some(Vec::decode(buf)?)
+        } else {
+            None
+        };
+        Ok(PlayerChatMessage {
+            global_index,
+            sender,
+            index,
+            message_signature,
+            message,
+            timestamp,
+            salt,
+            previous_messages,
+            unsigned_content,
+            filter_type,
+            filter_type_bits,
+            chat_type: IdOr::decode_for(version, buf)?,
+            sender_name: TextComponent::decode_for(version, buf)?,
+            target_name: Option::decode_for(version, buf)?,
+        })
+    }
+}
+
+#[derive(Packet)]
+#[packet(id = 0x3B)]
 pub struct EndCombat {
     pub duration: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3C)]
 pub struct EnterCombat;
 
+#[derive(Packet)]
+#[packet(id = 0x3D)]
 pub struct CombatDeath {
     pub player_id: VarInt,
-    pub message: JsonTextComponent,
+    pub message: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3E)]
 pub struct PlayerInfoRemove {
     pub uuids: Vec<Uuid>,
 }
@@ -452,6 +674,12 @@ pub struct PlayerInfoUpdate {
     pub players: Vec<PlayerInfoEntry>,
 }
 
+impl Packet for PlayerInfoUpdate {
+    const ID: i32 = 0x3F;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x40)]
 pub struct LookAt {
     pub feet_or_eyes: VarInt,
     pub target_x: f64,
@@ -460,6 +688,8 @@ pub struct LookAt {
     pub entity_target: Option<(VarInt, VarInt)>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x41)]
 pub struct SynchronizePlayerPosition {
     pub teleport_id: VarInt,
     pub x: f64,
@@ -470,23 +700,32 @@ pub struct SynchronizePlayerPosition {
     pub velocity_z: f64,
     pub yaw: f32,
     pub pitch: f32,
-    pub flags: u8,
+    /// `TeleportFlags` bits, sent as an Int since 1.21.2.
+    pub flags: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x42)]
 pub struct PlayerRotation {
     pub yaw: f32,
     pub pitch: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x43)]
 pub struct RecipeBookAdd {
     pub recipes: Vec<RecipeBookEntry>,
     pub replace: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x44)]
 pub struct RecipeBookRemove {
     pub recipes: Vec<VarInt>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x45)]
 pub struct RecipeBookSettings {
     pub crafting_open: bool,
     pub crafting_filter: bool,
@@ -498,32 +737,44 @@ pub struct RecipeBookSettings {
     pub smoker_filter: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x46)]
 pub struct RemoveEntities {
     pub entity_ids: Vec<VarInt>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x47)]
 pub struct RemoveEntityEffect {
     pub entity_id: VarInt,
     pub effect_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x48)]
 pub struct ResetScore {
     pub entity_name: String,
     pub objective_name: Option<String>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x49)]
 pub struct RemoveResourcePackPlay {
     pub uuid: Option<Uuid>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x4A)]
 pub struct AddResourcePackPlay {
     pub uuid: Uuid,
     pub url: String,
     pub hash: String,
     pub forced: bool,
-    pub prompt_message: Option<JsonTextComponent>,
+    pub prompt_message: Option<TextComponent>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x4B)]
 pub struct Respawn {
     pub dimension_type: VarInt,
     pub dimension_name: Identifier,
@@ -538,89 +789,127 @@ pub struct Respawn {
     pub data_kept: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x4C)]
 pub struct SetHeadRotation {
     pub entity_id: VarInt,
     pub head_yaw: Angle,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x4D)]
 pub struct UpdateSectionBlocks {
     pub chunk_section_position: i64,
     pub blocks: Vec<ChunkSectionBlockUpdate>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x4E)]
 pub struct SelectAdvancementsTab {
     pub identifier: Option<Identifier>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x4F)]
 pub struct ServerData {
-    pub motd: JsonTextComponent,
+    pub motd: TextComponent,
     pub icon: Option<Vec<u8>>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x50)]
 pub struct SetActionBarText {
-    pub action_bar_text: JsonTextComponent,
+    pub action_bar_text: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x51)]
 pub struct SetBorderCenter {
     pub x: f64,
     pub z: f64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x52)]
 pub struct SetBorderLerpSize {
     pub old_diameter: f64,
     pub new_diameter: f64,
     pub speed: VarLong,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x53)]
 pub struct SetBorderSize {
     pub diameter: f64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x54)]
 pub struct SetBorderWarningDelay {
     pub warning_time: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x55)]
 pub struct SetBorderWarningDistance {
     pub warning_blocks: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x56)]
 pub struct SetCamera {
     pub camera_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x57)]
 pub struct SetCenterChunk {
     pub chunk_x: VarInt,
     pub chunk_z: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x58)]
 pub struct SetRenderDistance {
     pub view_distance: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x59)]
 pub struct SetCursorItem {
     pub carried_item: Slot,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x5A)]
 pub struct SetDefaultSpawnPosition {
     pub location: Position,
     pub angle: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x5B)]
 pub struct DisplayObjective {
     pub position: VarInt,
     pub score_name: String,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x5C)]
 pub struct SetEntityMetadata {
     pub entity_id: VarInt,
     pub metadata: EntityMetadata,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x5D)]
 pub struct LinkEntities {
     pub attached_entity_id: i32,
     pub holding_entity_id: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x5E)]
 pub struct SetEntityVelocity {
     pub entity_id: VarInt,
     pub velocity_x: i16,
@@ -633,18 +922,28 @@ pub struct SetEquipment {
     pub equipment: Vec<EquipmentEntry>,
 }
 
+impl Packet for SetEquipment {
+    const ID: i32 = 0x5F;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x60)]
 pub struct SetExperience {
     pub experience_bar: f32,
     pub level: VarInt,
     pub total_experience: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x61)]
 pub struct SetHealth {
     pub health: f32,
     pub food: VarInt,
     pub food_saturation: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x62)]
 pub struct SetHeldItem {
     pub slot: VarInt,
 }
@@ -652,16 +951,24 @@ pub struct SetHeldItem {
 pub struct UpdateObjectives {
     pub objective_name: String,
     pub mode: u8,
-    pub objective_value: Option<JsonTextComponent>,
+    pub objective_value: Option<TextComponent>,
     pub objective_type: Option<VarInt>,
     pub number_format: Option<NumberFormat>,
 }
 
+impl Packet for UpdateObjectives {
+    const ID: i32 = 0x63;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x64)]
 pub struct SetPassengers {
     pub entity_id: VarInt,
     pub passengers: Vec<VarInt>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x65)]
 pub struct SetPlayerInventorySlot {
     pub slot: VarInt,
     pub slot_data: Slot,
@@ -670,13 +977,13 @@ pub struct SetPlayerInventorySlot {
 pub enum UpdateTeams {
     Create {
         team_name: String,
-        display_name: JsonTextComponent,
+        display_name: TextComponent,
         friendly_flags: u8,
-        name_tag_visibility: String,
-        collision_rule: String,
+        name_tag_visibility: NameTagVisibility,
+        collision_rule: CollisionRule,
         team_color: VarInt,
-        team_prefix: JsonTextComponent,
-        team_suffix: JsonTextComponent,
+        team_prefix: TextComponent,
+        team_suffix: TextComponent,
         entities: Vec<String>,
     },
     Remove {
@@ -684,13 +991,13 @@ pub enum UpdateTeams {
     },
     UpdateInfo {
         team_name: String,
-        display_name: JsonTextComponent,
+        display_name: TextComponent,
         friendly_flags: u8,
-        name_tag_visibility: String,
-        collision_rule: String,
+        name_tag_visibility: NameTagVisibility,
+        collision_rule: CollisionRule,
         team_color: VarInt,
-        team_prefix: JsonTextComponent,
-        team_suffix: JsonTextComponent,
+        team_prefix: TextComponent,
+        team_suffix: TextComponent,
     },
     AddEntities {
         team_name: String,
@@ -702,38 +1009,56 @@ pub enum UpdateTeams {
     },
 }
 
+impl Packet for UpdateTeams {
+    const ID: i32 = 0x66;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x67)]
 pub struct UpdateScore {
     pub entity_name: String,
     pub objective_name: String,
     pub value: VarInt,
-    pub display_name: Option<JsonTextComponent>,
+    pub display_name: Option<TextComponent>,
     pub number_format: Option<NumberFormat>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x68)]
 pub struct SetSimulationDistance {
     pub simulation_distance: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x69)]
 pub struct SetSubtitleText {
-    pub subtitle_text: JsonTextComponent,
+    pub subtitle_text: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x6A)]
 pub struct UpdateTime {
     pub world_age: i64,
     pub time_of_day: i64,
     pub time_of_day_increasing: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x6B)]
 pub struct SetTitleText {
-    pub title_text: JsonTextComponent,
+    pub title_text: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x6C)]
 pub struct SetTitleAnimationTimes {
     pub fade_in: i32,
     pub stay: i32,
     pub fade_out: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x6D)]
 pub struct EntitySoundEffect {
     pub sound_event: IdOr<SoundEvent>,
     pub sound_category: VarInt,
@@ -743,6 +1068,8 @@ pub struct EntitySoundEffect {
     pub seed: i64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x6E)]
 pub struct SoundEffect {
     pub sound_event: IdOr<SoundEvent>,
     pub sound_category: VarInt,
@@ -754,6 +1081,8 @@ pub struct SoundEffect {
     pub seed: i64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x6F)]
 pub struct StartConfiguration;
 
 pub struct StopSound {
@@ -762,32 +1091,48 @@ pub struct StopSound {
     pub sound: Option<Identifier>,
 }
 
+impl Packet for StopSound {
+    const ID: i32 = 0x70;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x71)]
 pub struct StoreCookiePlay {
     pub key: Identifier,
     pub payload: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x72)]
 pub struct SystemChatMessage {
-    pub content: JsonTextComponent,
+    pub content: TextComponent,
     pub overlay: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x73)]
 pub struct SetTabListHeaderAndFooter {
-    pub header: JsonTextComponent,
-    pub footer: JsonTextComponent,
+    pub header: TextComponent,
+    pub footer: TextComponent,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x74)]
 pub struct TagQueryResponse {
     pub transaction_id: VarInt,
     pub nbt: Nbt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x75)]
 pub struct PickupItem {
     pub collected_entity_id: VarInt,
     pub collector_entity_id: VarInt,
     pub pickup_item_count: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x76)]
 pub struct SynchronizeVehiclePosition {
     pub entity_id: VarInt,
     pub x: f64,
@@ -802,25 +1147,35 @@ pub struct SynchronizeVehiclePosition {
     pub on_ground: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x77)]
 pub struct TestInstanceBlockStatus {
-    pub status: JsonTextComponent,
+    pub status: TextComponent,
     pub size: Option<(f64, f64, f64)>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x78)]
 pub struct SetTickingState {
     pub tick_rate: f32,
     pub is_frozen: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x79)]
 pub struct StepTick {
     pub tick_steps: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x7A)]
 pub struct TransferPlay {
     pub host: String,
     pub port: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x7B)]
 pub struct UpdateAdvancements {
     pub reset_clear: bool,
     pub advancement_mapping: HashMap<Identifier, Advancement>,
@@ -829,11 +1184,15 @@ pub struct UpdateAdvancements {
     pub show_advancements: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x7C)]
 pub struct UpdateAttributes {
     pub entity_id: VarInt,
     pub properties: Vec<AttributeProperty>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x7D)]
 pub struct EntityEffect {
     pub entity_id: VarInt,
     pub effect_id: VarInt,
@@ -842,84 +1201,643 @@ pub struct EntityEffect {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x7E)]
 pub struct UpdateRecipes {
     pub property_sets: Vec<u8>,
     pub stonecutter_recipes: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x7F)]
 pub struct UpdateTagsPlay {
-    pub tags: HashMap<Identifier, Vec<u8>>,
+    pub tags: Vec<RegistryTagData>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x80)]
 pub struct ProjectilePower {
     pub entity_id: VarInt,
     pub power: f64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x81)]
 pub struct CustomReportDetailsPlay {
     pub details: Vec<CustomReportDetail>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x82)]
 pub struct ServerLinksPlay {
     pub links: Vec<ServerLink>,
 }
 
+/// Message signatures are always 256 bytes, so they are written without a length prefix.
+const MESSAGE_SIGNATURE_LEN: usize = 256;
+
+impl Encode for BossBar {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            BossBar::Add {
+                uuid,
+                title,
+                health,
+                color,
+                division,
+                flags,
+            } => {
+                uuid.encode_for(version, buf)?;
+                VarInt(BossBarAction::Add as i32).encode_for(version, buf)?;
+                title.encode_for(version, buf)?;
+                health.encode_for(version, buf)?;
+                color.encode_for(version, buf)?;
+                division.encode_for(version, buf)?;
+                flags.encode_for(version, buf)
+            }
+            BossBar::Remove { uuid } => {
+                uuid.encode_for(version, buf)?;
+                VarInt(BossBarAction::Remove as i32).encode_for(version, buf)
+            }
+            BossBar::UpdateHealth { uuid, health } => {
+                uuid.encode_for(version, buf)?;
+                VarInt(BossBarAction::UpdateHealth as i32).encode_for(version, buf)?;
+                health.encode_for(version, buf)
+            }
+            BossBar::UpdateTitle { uuid, title } => {
+                uuid.encode_for(version, buf)?;
+                VarInt(BossBarAction::UpdateTitle as i32).encode_for(version, buf)?;
+                title.encode_for(version, buf)
+            }
+            BossBar::UpdateStyle {
+                uuid,
+                color,
+                division,
+            } => {
+                uuid.encode_for(version, buf)?;
+                VarInt(BossBarAction::UpdateStyle as i32).encode_for(version, buf)?;
+                color.encode_for(version, buf)?;
+                division.encode_for(version, buf)
+            }
+            BossBar::UpdateFlags { uuid, flags } => {
+                uuid.encode_for(version, buf)?;
+                VarInt(BossBarAction::UpdateFlags as i32).encode_for(version, buf)?;
+                flags.encode_for(version, buf)
+            }
+        }
+    }
+}
+
+impl Decode for BossBar {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let uuid = Uuid::decode_for(version, buf)?;
+        match VarInt::decode_for(version, buf)?.0 {
+            0 => Ok(BossBar::Add {
+                uuid,
+                title: TextComponent::decode_for(version, buf)?,
+                health: f32::decode_for(version, buf)?,
+                color: VarInt::decode_for(version, buf)?,
+                division: VarInt::decode_for(version, buf)?,
+                flags: u8::decode_for(version, buf)?,
+            }),
+            1 => Ok(BossBar::Remove { uuid }),
+            2 => Ok(BossBar::UpdateHealth {
+                uuid,
+                health: f32::decode_for(version, buf)?,
+            }),
+            3 => Ok(BossBar::UpdateTitle {
+                uuid,
+                title: TextComponent::decode_for(version, buf)?,
+            }),
+            4 => Ok(BossBar::UpdateStyle {
+                uuid,
+                color: VarInt::decode_for(version, buf)?,
+                division: VarInt::decode_for(version, buf)?,
+            }),
+            5 => Ok(BossBar::UpdateFlags {
+                uuid,
+                flags: u8::decode_for(version, buf)?,
+            }),
+            other => Err(DecodeError::InvalidEnumValue("BossBarAction", other as i64)),
+        }
+    }
+}
+
+/// The signature is only present when the message is referenced by signature (ID zero).
+impl Encode for DeleteMessage {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.message_id.encode(buf)?;
+        if let Some(signature) = &self.signature {
+            buf.put_slice(signature);
+        }
+        Ok(())
+    }
+}
+
+impl Decode for DeleteMessage {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let message_id = VarInt::decode(buf)?;
+        let signature = if message_id.0 == 0 {
+            Some(read_fixed_bytes_sync(buf, MESSAGE_SIGNATURE_LEN)?)
+        } else {
+            None
+        };
+        Ok(DeleteMessage {
+            message_id,
+            signature,
+        })
+    }
+}
+
+/// The color patch is introduced by its column count; a count of zero means no patch follows.
+impl Encode for MapData {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.map_id.encode_for(version, buf)?;
+        self.scale.encode_for(version, buf)?;
+        self.locked.encode_for(version, buf)?;
+        self.icons.encode_for(version, buf)?;
+        match &self.color_patch {
+            Some(patch) => {
+                patch.columns.encode_for(version, buf)?;
+                patch.rows.encode_for(version, buf)?;
+                patch.x.encode_for(version, buf)?;
+                patch.z.encode_for(version, buf)?;
+                patch.data.encode_for(version, buf)
+            }
+            None => 0u8.encode_for(version, buf),
+        }
+    }
+}
+
+impl Decode for MapData {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let map_id = VarInt::decode_for(version, buf)?;
+        let scale = i8::decode_for(version, buf)?;
+        let locked = bool::decode_for(version, buf)?;
+        let icons = Option::decode_for(version, buf)?;
+        let columns = u8::decode_for(version, buf)?;
+        let color_patch = if columns > 0 {
+            Some(MapColorPatch {
+                columns,
+                rows: u8::decode_for(version, buf)?,
+                x: u8::decode_for(version, buf)?,
+                z: u8::decode_for(version, buf)?,
+                data: Vec::decode_for(version, buf)?,
+            })
+        } else {
+            None
+        };
+        Ok(MapData {
+            map_id,
+            scale,
+            locked,
+            icons,
+            color_patch,
+        })
+    }
+}
+
+fn player_info_action_bit(action: &PlayerInfoActionData) -> u8 {
+    match action {
+        PlayerInfoActionData::AddPlayer { .. } => 0x01,
+        PlayerInfoActionData::InitializeChat { .. } => 0x02,
+        PlayerInfoActionData::UpdateGameMode { .. } => 0x04,
+        PlayerInfoActionData::UpdateListed { .. } => 0x08,
+        PlayerInfoActionData::UpdateLatency { .. } => 0x10,
+        PlayerInfoActionData::UpdateDisplayName { .. } => 0x20,
+        PlayerInfoActionData::UpdateListPriority { .. } => 0x40,
+        PlayerInfoActionData::UpdateHat { .. } => 0x80,
+    }
+}
+
+fn encode_player_info_action(
+    action: &PlayerInfoActionData,
+    version: ProtocolVersion,
+    buf: &mut BytesMut,
+) -> EncodeResult<()> {
+    match action {
+        PlayerInfoActionData::AddPlayer { name, properties } => {
+            name.encode(buf)?;
+            properties.encode(buf)
+        }
+        PlayerInfoActionData::InitializeChat { chat_session } => chat_session.encode(buf),
+        PlayerInfoActionData::UpdateGameMode { game_mode } => game_mode.encode(buf),
+        PlayerInfoActionData::UpdateListed { listed } => listed.encode(buf),
+        PlayerInfoActionData::UpdateLatency { ping } => ping.encode(buf),
+        PlayerInfoActionData::UpdateDisplayName { display_name } => {
+            display_name.encode_for(version, buf)
+        }
+        PlayerInfoActionData::UpdateListPriority { priority } => priority.encode(buf),
+        PlayerInfoActionData::UpdateHat { visible } => visible.encode(buf),
+    }
+}
+
+fn decode_player_info_action(
+    bit: u8,
+    version: ProtocolVersion,
+    buf: &mut BytesMut,
+) -> DecodeResult<PlayerInfoActionData> {
+    Ok(match bit {
+        0x01 => PlayerInfoActionData::AddPlayer {
+            name: String::decode(buf)?,
+            properties: Vec::decode(buf)?,
+        },
+        0x02 => PlayerInfoActionData::InitializeChat {
+            chat_session: Option::decode(buf)?,
+        },
+        0x04 => PlayerInfoActionData::UpdateGameMode {
+            game_mode: VarInt::decode(buf)?,
+        },
+        0x08 => PlayerInfoActionData::UpdateListed {
+            listed: bool::decode(buf)?,
+        },
+        0x10 => PlayerInfoActionData::UpdateLatency {
+            ping: VarInt::decode(buf)?,
+        },
+        0x20 => PlayerInfoActionData::UpdateDisplayName {
+            display_name: Option::decode_for(version, buf)?,
+        },
+        0x40 => PlayerInfoActionData::UpdateListPriority {
+            priority: VarInt::decode(buf)?,
+        },
+        _ => PlayerInfoActionData::UpdateHat {
+            visible: bool::decode(buf)?,
+        },
+    })
+}
+
+/// Each player entry carries one action payload per bit set in `actions`, in ascending bit order.
+impl Encode for PlayerInfoUpdate {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.actions.encode_for(version, buf)?;
+        VarInt(self.players.len() as i32).encode_for(version, buf)?;
+        for player in &self.players {
+            player.uuid.encode_for(version, buf)?;
+            for bit in (0..8).map(|shift| 1u8 << shift) {
+                if self.actions & bit == 0 {
+                    continue;
+                }
+                let action = player
+                    .actions
+                    .iter()
+                    .find(|action| player_info_action_bit(action) == bit)
+                    .ok_or_else(|| missing_field("PlayerInfoEntry", "actions"))?;
+                encode_player_info_action(action, version, buf)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Decode for PlayerInfoUpdate {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let actions = u8::decode_for(version, buf)?;
+        let count = VarInt::decode_for(version, buf)?.0.max(0) as usize;
+        let mut players = Vec::with_capacity(count.min(buf.len()));
+        for _ in 0..count {
+            let uuid = Uuid::decode_for(version, buf)?;
+            let mut entry_actions = Vec::new();
+            for bit in (0..8).map(|shift| 1u8 << shift) {
+                if actions & bit != 0 {
+                    entry_actions.push(decode_player_info_action(bit, version, buf)?);
+                }
+            }
+            players.push(PlayerInfoEntry {
+                uuid,
+                actions: entry_actions,
+            });
+        }
+        Ok(PlayerInfoUpdate { actions, players })
+    }
+}
+
+/// The objective value, type and number format are only sent when creating or updating.
+impl Encode for UpdateObjectives {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.objective_name.encode_for(version, buf)?;
+        self.mode.encode_for(version, buf)?;
+        if self.mode == ObjectiveMode::Create as u8 || self.mode == ObjectiveMode::Update as u8 {
+            self.objective_value
+                .as_ref()
+                .ok_or_else(|| missing_field("UpdateObjectives", "objective_value"))?
+                .encode_for(version, buf)?;
+            self.objective_type
+                .ok_or_else(|| missing_field("UpdateObjectives", "objective_type"))?
+                .encode_for(version, buf)?;
+            self.number_format.encode_for(version, buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for UpdateObjectives {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let objective_name = String::decode_for(version, buf)?;
+        let mode = u8::decode_for(version, buf)?;
+        if mode == ObjectiveMode::Create as u8 || mode == ObjectiveMode::Update as u8 {
+            Ok(UpdateObjectives {
+                objective_name,
+                mode,
+                objective_value: Some(TextComponent::decode_for(version, buf)?),
+                objective_type: Some(VarInt::decode_for(version, buf)?),
+                number_format: Option::decode_for(version, buf)?,
+            })
+        } else {
+            Ok(UpdateObjectives {
+                objective_name,
+                mode,
+                objective_value: None,
+                objective_type: None,
+                number_format: None,
+            })
+        }
+    }
+}
+
+impl Encode for UpdateTeams {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            UpdateTeams::Create {
+                team_name,
+                display_name,
+                friendly_flags,
+                name_tag_visibility,
+                collision_rule,
+                team_color,
+                team_prefix,
+                team_suffix,
+                entities,
+            } => {
+                team_name.encode_for(version, buf)?;
+                (TeamMethod::Create as u8).encode_for(version, buf)?;
+                display_name.encode_for(version, buf)?;
+                friendly_flags.encode_for(version, buf)?;
+                name_tag_visibility.encode_for(version, buf)?;
+                collision_rule.encode_for(version, buf)?;
+                team_color.encode_for(version, buf)?;
+                team_prefix.encode_for(version, buf)?;
+                team_suffix.encode_for(version, buf)?;
+                entities.encode_for(version, buf)
+            }
+            UpdateTeams::Remove { team_name } => {
+                team_name.encode_for(version, buf)?;
+                (TeamMethod::Remove as u8).encode_for(version, buf)
+            }
+            UpdateTeams::UpdateInfo {
+                team_name,
+                display_name,
+                friendly_flags,
+                name_tag_visibility,
+                collision_rule,
+                team_color,
+                team_prefix,
+                team_suffix,
+            } => {
+                team_name.encode_for(version, buf)?;
+                (TeamMethod::UpdateInfo as u8).encode_for(version, buf)?;
+                display_name.encode_for(version, buf)?;
+                friendly_flags.encode_for(version, buf)?;
+                name_tag_visibility.encode_for(version, buf)?;
+                collision_rule.encode_for(version, buf)?;
+                team_color.encode_for(version, buf)?;
+                team_prefix.encode_for(version, buf)?;
+                team_suffix.encode_for(version, buf)
+            }
+            UpdateTeams::AddEntities {
+                team_name,
+                entities,
+            } => {
+                team_name.encode_for(version, buf)?;
+                (TeamMethod::AddEntities as u8).encode_for(version, buf)?;
+                entities.encode_for(version, buf)
+            }
+            UpdateTeams::RemoveEntities {
+                team_name,
+                entities,
+            } => {
+                team_name.encode_for(version, buf)?;
+                (TeamMethod::RemoveEntities as u8).encode_for(version, buf)?;
+                entities.encode_for(version, buf)
+            }
+        }
+    }
+}
+
+impl Decode for UpdateTeams {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let team_name = String::decode_for(version, buf)?;
+        match u8::decode_for(version, buf)? {
+            0 => Ok(UpdateTeams::Create {
+                team_name,
+                display_name: TextComponent::decode_for(version, buf)?,
+                friendly_flags: u8::decode_for(version, buf)?,
+                name_tag_visibility: NameTagVisibility::decode_for(version, buf)?,
+                collision_rule: CollisionRule::decode_for(version, buf)?,
+                team_color: VarInt::decode_for(version, buf)?,
+                team_prefix: TextComponent::decode_for(version, buf)?,
+                team_suffix: TextComponent::decode_for(version, buf)?,
+                entities: Vec::decode_for(version, buf)?,
+            }),
+            1 => Ok(UpdateTeams::Remove { team_name }),
+            2 => Ok(UpdateTeams::UpdateInfo {
+                team_name,
+                display_name: TextComponent::decode_for(version, buf)?,
+                friendly_flags: u8::decode_for(version, buf)?,
+                name_tag_visibility: NameTagVisibility::decode_for(version, buf)?,
+                collision_rule: CollisionRule::decode_for(version, buf)?,
+                team_color: VarInt::decode_for(version, buf)?,
+                team_prefix: TextComponent::decode_for(version, buf)?,
+                team_suffix: TextComponent::decode_for(version, buf)?,
+            }),
+            3 => Ok(UpdateTeams::AddEntities {
+                team_name,
+                entities: Vec::decode_for(version, buf)?,
+            }),
+            4 => Ok(UpdateTeams::RemoveEntities {
+                team_name,
+                entities: Vec::decode_for(version, buf)?,
+            }),
+            other => Err(DecodeError::InvalidEnumValue("TeamMethod", other as i64)),
+        }
+    }
+}
+
+/// The source and sound are only present when their bit is set in `flags`.
+impl Encode for StopSound {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        let mut flags = StopSoundFlags::empty();
+        flags.set(StopSoundFlags::HAS_SOURCE, self.source.is_some());
+        flags.set(StopSoundFlags::HAS_SOUND, self.sound.is_some());
+        flags.bits().encode(buf)?;
+        if let Some(source) = &self.source {
+            source.encode(buf)?;
+        }
+        if let Some(sound) = &self.sound {
+            sound.encode(buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for StopSound {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let flags = u8::decode(buf)?;
+        let source = if flags & StopSoundFlags::HAS_SOURCE.bits() != 0 {
+            Some(VarInt::decode(buf)?)
+        } else {
+            None
+        };
+        let sound = if flags & StopSoundFlags::HAS_SOUND.bits() != 0 {
+            Some(Identifier::decode(buf)?)
+        } else {
+            None
+        };
+        Ok(StopSound {
+            flags,
+            source,
+            sound,
+        })
+    }
+}
+
+/// Equipment entries have no count prefix; the top bit of each slot byte marks that another follows.
+impl Encode for SetEquipment {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.entity_id.encode_for(version, buf)?;
+        for (index, entry) in self.equipment.iter().enumerate() {
+            let has_more = index + 1 < self.equipment.len();
+            let slot = if has_more {
+                entry.slot | 0x80
+            } else {
+                entry.slot & 0x7F
+            };
+            slot.encode_for(version, buf)?;
+            entry.item.encode_for(version, buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for SetEquipment {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let entity_id = VarInt::decode_for(version, buf)?;
+        let mut equipment = Vec::new();
+        loop {
+            let slot = u8::decode_for(version, buf)?;
+            equipment.push(EquipmentEntry {
+                slot: slot & 0x7F,
+                item: Slot::decode_for(version, buf)?,
+            });
+            if slot & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(SetEquipment {
+            entity_id,
+            equipment,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::protocol::command::CommandGraph;
+    use crate::protocol::encoding::assert_round_trip;
     use crate::protocol::types::{
-        BuiltInServerLinkLabel, EquipmentSlot, MapIconType, PlayerAbilityFlags,
-        PlayerInfoActionData, PlayerInfoProperty, RecipeBookFlags, ServerLinkLabel,
-        StatisticCategory, TradeItem,
+        BossBarColor, BossBarDivision, BossBarFlags, BuiltInServerLinkLabel, ChatSuggestionAction,
+        DataComponent, Difficulty, EntityAnimation, EntityEffectFlags, EquipmentSlot, FriendlyFlags,
+        GameEventType, GameMode, Hand, LookAtAnchor, MapIconType, ObjectiveType,
+        PlayerAbilityFlags, PlayerInfoActionData, PreviousGameMode, RecipeBookFlags,
+        RespawnDataKeptFlags, ServerLinkLabel, SoundCategory, StatisticCategory, TeamColor,
+        TradeItem, VillagerLevel,
     };
 
     use super::*;
     use std::collections::HashMap;
 
-    fn dummy_text() -> JsonTextComponent {
-        r#"{"text":""}"#.into()
+    fn dummy_text() -> TextComponent {
+        TextComponent::text("")
     }
     fn dummy_id() -> Identifier {
         "minecraft:stone".into()
     }
     fn dummy_slot() -> Slot {
-        vec![]
+        Slot::new(1, 1).with_component(DataComponent::Damage(2))
     }
     fn dummy_nbt() -> Nbt {
-        vec![]
+        Nbt::Compound(Default::default())
     }
     fn dummy_metadata() -> EntityMetadata {
-        vec![]
+        let mut metadata = EntityMetadata::new();
+        metadata.set_sprinting(true);
+        metadata
     }
     fn dummy_chunk_data() -> ChunkData {
-        vec![]
+        ChunkData::empty(24, 0)
     }
     fn dummy_light_data() -> LightData {
-        vec![]
+        LightData::dark(24)
     }
     fn dummy_recipe_display() -> RecipeDisplay {
         vec![]
     }
-    fn dummy_slot_display() -> SlotDisplay {
-        vec![]
-    }
-    fn dummy_id_set() -> IdSet {
-        vec![]
-    }
-    fn dummy_chat_type_data() -> ChatTypeData {
-        vec![]
-    }
-    fn dummy_sound_event() -> SoundEvent {
-        vec![]
-    }
 
     #[test]
     fn test_bundle_delimiter() {
-        let _p = BundleDelimiter;
-        assert!(true);
+        let p = BundleDelimiter;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_spawn_entity() {
-        let _p = SpawnEntity {
+        let p = SpawnEntity {
             entity_id: VarInt::from(1),
             entity_uuid: Uuid::new_v4(),
             entity_type: VarInt::from(1),
@@ -934,74 +1852,74 @@ mod tests {
             velocity_y: 0,
             velocity_z: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_entity_animation() {
-        let _p = EntityAnimationPacket {
+        let p = EntityAnimationPacket {
             entity_id: VarInt::from(1),
             animation: EntityAnimation::SwingMainArm as u8,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_award_statistics() {
-        let _p = AwardStatistics {
+        let p = AwardStatistics {
             statistics: vec![Statistic {
                 category_id: VarInt::from(StatisticCategory::Custom as i32),
                 statistic_id: VarInt::from(0),
                 value: VarInt::from(1),
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_acknowledge_block_change() {
-        let _p = AcknowledgeBlockChange {
+        let p = AcknowledgeBlockChange {
             sequence_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_block_destroy_stage() {
-        let _p = SetBlockDestroyStage {
+        let p = SetBlockDestroyStage {
             entity_id: VarInt::from(1),
             location: Position { x: 0, y: 0, z: 0 },
             destroy_stage: 5,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_block_entity_data() {
-        let _p = BlockEntityData {
+        let p = BlockEntityData {
             location: Position { x: 0, y: 0, z: 0 },
             block_entity_type: VarInt::from(1),
             nbt_data: dummy_nbt(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_block_action() {
-        let _p = BlockAction {
+        let p = BlockAction {
             location: Position { x: 0, y: 0, z: 0 },
             action_id: 1,
             action_param: 0,
             block_type: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_block_update() {
-        let _p = BlockUpdate {
+        let p = BlockUpdate {
             location: Position { x: 0, y: 0, z: 0 },
             block_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_boss_bar() {
         let uuid = Uuid::new_v4();
-        let _p = BossBar::Add {
+        let p = BossBar::Add {
             uuid,
             title: dummy_text(),
             health: 0.5,
@@ -1009,44 +1927,45 @@ mod tests {
             division: VarInt::from(BossBarDivision::TenNotches as i32),
             flags: BossBarFlags::DARKEN_SKY.bits(),
         };
-        let _p2 = BossBar::Remove { uuid };
-        assert!(true);
+        let p2 = BossBar::Remove { uuid };
+        assert_round_trip(&p);
+        assert_round_trip(&p2);
     }
     #[test]
     fn test_change_difficulty() {
-        let _p = ChangeDifficulty {
+        let p = ChangeDifficulty {
             difficulty: Difficulty::Hard as u8,
             difficulty_locked: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chunk_batch_finished() {
-        let _p = ChunkBatchFinished {
+        let p = ChunkBatchFinished {
             batch_size: VarInt::from(10),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chunk_batch_start() {
-        let _p = ChunkBatchStart;
-        assert!(true);
+        let p = ChunkBatchStart;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chunk_biomes() {
-        let _p = ChunkBiomes {
+        let p = ChunkBiomes {
             chunk_biome_data: vec![],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_clear_titles() {
-        let _p = ClearTitles { reset: true };
-        assert!(true);
+        let p = ClearTitles { reset: true };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_command_suggestions_response() {
-        let _p = CommandSuggestionsResponse {
+        let p = CommandSuggestionsResponse {
             id: VarInt::from(1),
             start: VarInt::from(0),
             length: VarInt::from(5),
@@ -1055,136 +1974,136 @@ mod tests {
                 tooltip: None,
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_commands() {
-        let _p = Commands {
-            nodes: vec![],
+        let p = Commands {
+            nodes: CommandGraph::new().nodes().to_vec(),
             root_index: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_close_container() {
-        let _p = CloseContainer {
+        let p = CloseContainer {
             window_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_container_content() {
-        let _p = SetContainerContent {
+        let p = SetContainerContent {
             window_id: VarInt::from(1),
             state_id: VarInt::from(1),
             slot_data: vec![dummy_slot()],
             carried_item: dummy_slot(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_container_property() {
-        let _p = SetContainerProperty {
+        let p = SetContainerProperty {
             window_id: VarInt::from(1),
             property: 0,
             value: 100,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_container_slot() {
-        let _p = SetContainerSlot {
+        let p = SetContainerSlot {
             window_id: 1,
             state_id: VarInt::from(1),
             slot: 0,
             slot_data: dummy_slot(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_cookie_request_play() {
-        let _p = CookieRequestPlay { key: dummy_id() };
-        assert!(true);
+        let p = CookieRequestPlay { key: dummy_id() };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_cooldown() {
-        let _p = SetCooldown {
+        let p = SetCooldown {
             item_id: VarInt::from(1),
             cooldown_ticks: VarInt::from(20),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chat_suggestions() {
-        let _p = ChatSuggestions {
+        let p = ChatSuggestions {
             action: VarInt::from(ChatSuggestionAction::Add as i32),
             entries: vec!["hello".into()],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_clientbound_plugin_message_play() {
-        let _p = ClientboundPluginMessagePlay {
+        let p = ClientboundPluginMessagePlay {
             channel: dummy_id(),
             data: vec![1, 2, 3],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_damage_event() {
-        let _p = DamageEvent {
+        let p = DamageEvent {
             entity_id: VarInt::from(1),
             source_type_id: VarInt::from(0),
             source_cause_id: VarInt::from(0),
             source_direct_id: VarInt::from(0),
             source_position: Some((1.0, 2.0, 3.0)),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_debug_sample() {
-        let _p = DebugSample {
+        let p = DebugSample {
             sample: vec![100, 200],
             sample_type: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_delete_message() {
-        let _p = DeleteMessage {
+        let p = DeleteMessage {
             message_id: VarInt::from(1),
             signature: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_disconnect_play() {
-        let _p = DisconnectPlay {
+        let p = DisconnectPlay {
             reason: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_disguised_chat_message() {
-        let _p = DisguisedChatMessage {
+        let p = DisguisedChatMessage {
             message: dummy_text(),
             chat_type: IdOr::Id(VarInt::from(0)),
             sender_name: dummy_text(),
             target_name: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_entity_event() {
-        let _p = EntityEvent {
+        let p = EntityEvent {
             entity_id: 1,
             entity_status: 2,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_teleport_entity_play() {
-        let _p = TeleportEntityPlay {
+        let p = TeleportEntityPlay {
             entity_id: VarInt::from(1),
             x: 0.0,
             y: 0.0,
@@ -1196,11 +2115,11 @@ mod tests {
             pitch: 0.0,
             on_ground: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_explosion() {
-        let _p = Explosion {
+        let p = Explosion {
             x: 0.0,
             y: 64.0,
             z: 0.0,
@@ -1209,44 +2128,44 @@ mod tests {
             explosion_particle_data: vec![],
             explosion_sound: IdOr::Id(VarInt::from(0)),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_unload_chunk() {
-        let _p = UnloadChunk {
+        let p = UnloadChunk {
             chunk_x: 0,
             chunk_z: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_game_event() {
-        let _p = GameEvent {
+        let p = GameEvent {
             event: GameEventType::BeginRaining as u8,
             value: 0.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_open_horse_screen() {
-        let _p = OpenHorseScreen {
+        let p = OpenHorseScreen {
             window_id: VarInt::from(1),
             inventory_columns_count: VarInt::from(2),
             entity_id: 1,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_hurt_animation() {
-        let _p = HurtAnimation {
+        let p = HurtAnimation {
             entity_id: VarInt::from(1),
             yaw: 90.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_initialize_world_border() {
-        let _p = InitializeWorldBorder {
+        let p = InitializeWorldBorder {
             x: 0.0,
             z: 0.0,
             old_diameter: 60000.0,
@@ -1256,38 +2175,38 @@ mod tests {
             warning_blocks: VarInt::from(5),
             warning_time: VarInt::from(15),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_clientbound_keep_alive_play() {
-        let _p = ClientboundKeepAlivePlay {
+        let p = ClientboundKeepAlivePlay {
             keep_alive_id: 12345,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chunk_data_and_update_light() {
-        let _p = ChunkDataAndUpdateLight {
+        let p = ChunkDataAndUpdateLight {
             chunk_x: 0,
             chunk_z: 0,
             chunk_data: dummy_chunk_data(),
             light_data: dummy_light_data(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_world_event() {
-        let _p = WorldEvent {
+        let p = WorldEvent {
             event: 1000,
             location: Position { x: 0, y: 0, z: 0 },
             data: 0,
             disable_relative_volume: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_particle() {
-        let _p = Particle {
+        let p = Particle {
             long_distance: false,
             always_visible: true,
             x: 0.0,
@@ -1301,20 +2220,20 @@ mod tests {
             particle_id: VarInt::from(0),
             data: vec![],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_light() {
-        let _p = UpdateLight {
+        let p = UpdateLight {
             chunk_x: VarInt::from(0),
             chunk_z: VarInt::from(0),
             light_data: dummy_light_data(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_login_play() {
-        let _p = LoginPlay {
+        let p = LoginPlay {
             entity_id: 1,
             is_hardcore: false,
             dimension_names: vec![dummy_id()],
@@ -1336,11 +2255,11 @@ mod tests {
             sea_level: VarInt::from(63),
             enforces_secure_chat: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_map_data() {
-        let _p = MapData {
+        let p = MapData {
             map_id: VarInt::from(0),
             scale: 1,
             locked: false,
@@ -1359,11 +2278,11 @@ mod tests {
                 data: vec![0],
             }),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_merchant_offers() {
-        let _p = MerchantOffers {
+        let p = MerchantOffers {
             window_id: VarInt::from(1),
             trades: vec![MerchantTrade {
                 input_item1: TradeItem {
@@ -1386,22 +2305,22 @@ mod tests {
             is_regular_villager: true,
             can_restock: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_entity_position() {
-        let _p = UpdateEntityPosition {
+        let p = UpdateEntityPosition {
             entity_id: VarInt::from(1),
             delta_x: 0,
             delta_y: 10,
             delta_z: 0,
             on_ground: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_entity_position_and_rotation() {
-        let _p = UpdateEntityPositionAndRotation {
+        let p = UpdateEntityPositionAndRotation {
             entity_id: VarInt::from(1),
             delta_x: 0,
             delta_y: 10,
@@ -1410,92 +2329,92 @@ mod tests {
             pitch: 0,
             on_ground: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_move_minecart_along_track() {
-        let _p = MoveMinecartAlongTrack {
+        let p = MoveMinecartAlongTrack {
             entity_id: VarInt::from(1),
             steps: vec![],
             weight: 1.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_entity_rotation() {
-        let _p = UpdateEntityRotation {
+        let p = UpdateEntityRotation {
             entity_id: VarInt::from(1),
             yaw: 0,
             pitch: 0,
             on_ground: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_move_vehicle() {
-        let _p = MoveVehicle {
+        let p = MoveVehicle {
             x: 0.0,
             y: 0.0,
             z: 0.0,
             yaw: 0.0,
             pitch: 0.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_open_book() {
-        let _p = OpenBook {
+        let p = OpenBook {
             hand: VarInt::from(Hand::MainHand as i32),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_open_screen() {
-        let _p = OpenScreen {
+        let p = OpenScreen {
             window_id: VarInt::from(1),
             window_type: VarInt::from(0),
             window_title: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_open_sign_editor() {
-        let _p = OpenSignEditor {
+        let p = OpenSignEditor {
             location: Position { x: 0, y: 0, z: 0 },
             is_front_text: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_ping_play() {
-        let _p = PingPlay { id: 123 };
-        assert!(true);
+        let p = PingPlay { id: 123 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_ping_response_play() {
-        let _p = PingResponsePlay { payload: 123 };
-        assert!(true);
+        let p = PingResponsePlay { payload: 123 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_place_ghost_recipe() {
-        let _p = PlaceGhostRecipe {
+        let p = PlaceGhostRecipe {
             window_id: VarInt::from(1),
             recipe_display: dummy_recipe_display(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_abilities() {
-        let _p = PlayerAbilities {
+        let p = PlayerAbilities {
             flags: PlayerAbilityFlags::ALLOW_FLYING.bits(),
             flying_speed: 0.05,
             field_of_view_modifier: 0.1,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_chat_message() {
-        let _p = PlayerChatMessage {
+        let p = PlayerChatMessage {
             global_index: VarInt::from(0),
             sender: Uuid::new_v4(),
             index: VarInt::from(0),
@@ -1511,38 +2430,38 @@ mod tests {
             sender_name: dummy_text(),
             target_name: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_end_combat() {
-        let _p = EndCombat {
+        let p = EndCombat {
             duration: VarInt::from(100),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_enter_combat() {
-        let _p = EnterCombat;
-        assert!(true);
+        let p = EnterCombat;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_combat_death() {
-        let _p = CombatDeath {
+        let p = CombatDeath {
             player_id: VarInt::from(1),
             message: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_info_remove() {
-        let _p = PlayerInfoRemove {
+        let p = PlayerInfoRemove {
             uuids: vec![Uuid::new_v4()],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_info_update() {
-        let _p = PlayerInfoUpdate {
+        let p = PlayerInfoUpdate {
             actions: 0x01 | 0x04,
             players: vec![PlayerInfoEntry {
                 uuid: Uuid::new_v4(),
@@ -1557,22 +2476,22 @@ mod tests {
                 ],
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_look_at() {
-        let _p = LookAt {
+        let p = LookAt {
             feet_or_eyes: VarInt::from(LookAtAnchor::Eyes as i32),
             target_x: 0.0,
             target_y: 0.0,
             target_z: 0.0,
             entity_target: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_synchronize_player_position() {
-        let _p = SynchronizePlayerPosition {
+        let p = SynchronizePlayerPosition {
             teleport_id: VarInt::from(1),
             x: 0.0,
             y: 0.0,
@@ -1584,19 +2503,19 @@ mod tests {
             pitch: 0.0,
             flags: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_rotation() {
-        let _p = PlayerRotation {
+        let p = PlayerRotation {
             yaw: 0.0,
             pitch: 0.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_recipe_book_add() {
-        let _p = RecipeBookAdd {
+        let p = RecipeBookAdd {
             recipes: vec![RecipeBookEntry {
                 recipe_id: VarInt::from(0),
                 display: dummy_recipe_display(),
@@ -1607,18 +2526,18 @@ mod tests {
             }],
             replace: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_recipe_book_remove() {
-        let _p = RecipeBookRemove {
+        let p = RecipeBookRemove {
             recipes: vec![VarInt::from(0)],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_recipe_book_settings() {
-        let _p = RecipeBookSettings {
+        let p = RecipeBookSettings {
             crafting_open: true,
             crafting_filter: false,
             smelting_open: false,
@@ -1628,52 +2547,52 @@ mod tests {
             smoker_open: false,
             smoker_filter: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_remove_entities() {
-        let _p = RemoveEntities {
+        let p = RemoveEntities {
             entity_ids: vec![VarInt::from(1), VarInt::from(2)],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_remove_entity_effect() {
-        let _p = RemoveEntityEffect {
+        let p = RemoveEntityEffect {
             entity_id: VarInt::from(1),
             effect_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_reset_score() {
-        let _p = ResetScore {
+        let p = ResetScore {
             entity_name: "Player".into(),
             objective_name: Some("dummy".into()),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_remove_resource_pack_play() {
-        let _p = RemoveResourcePackPlay {
+        let p = RemoveResourcePackPlay {
             uuid: Some(Uuid::new_v4()),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_add_resource_pack_play() {
-        let _p = AddResourcePackPlay {
+        let p = AddResourcePackPlay {
             uuid: Uuid::new_v4(),
             url: "".into(),
             hash: "".into(),
             forced: false,
             prompt_message: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_respawn() {
-        let _p = Respawn {
+        let p = Respawn {
             dimension_type: VarInt::from(0),
             dimension_name: dummy_id(),
             hashed_seed: 0,
@@ -1686,283 +2605,325 @@ mod tests {
             sea_level: VarInt::from(63),
             data_kept: RespawnDataKeptFlags::KEEP_ATTRIBUTES.bits(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_head_rotation() {
-        let _p = SetHeadRotation {
+        let p = SetHeadRotation {
             entity_id: VarInt::from(1),
             head_yaw: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_section_blocks() {
-        let _p = UpdateSectionBlocks {
+        let p = UpdateSectionBlocks {
             chunk_section_position: 0,
             blocks: vec![ChunkSectionBlockUpdate {
                 block_state_id_and_pos: 0,
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_select_advancements_tab() {
-        let _p = SelectAdvancementsTab {
+        let p = SelectAdvancementsTab {
             identifier: Some(dummy_id()),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_server_data() {
-        let _p = ServerData {
+        let p = ServerData {
             motd: dummy_text(),
             icon: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_action_bar_text() {
-        let _p = SetActionBarText {
+        let p = SetActionBarText {
             action_bar_text: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_border_center() {
-        let _p = SetBorderCenter { x: 0.0, z: 0.0 };
-        assert!(true);
+        let p = SetBorderCenter { x: 0.0, z: 0.0 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_border_lerp_size() {
-        let _p = SetBorderLerpSize {
+        let p = SetBorderLerpSize {
             old_diameter: 60000.0,
             new_diameter: 50000.0,
             speed: VarLong::from(10000),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_border_size() {
-        let _p = SetBorderSize { diameter: 60000.0 };
-        assert!(true);
+        let p = SetBorderSize { diameter: 60000.0 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_border_warning_delay() {
-        let _p = SetBorderWarningDelay {
+        let p = SetBorderWarningDelay {
             warning_time: VarInt::from(15),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_border_warning_distance() {
-        let _p = SetBorderWarningDistance {
+        let p = SetBorderWarningDistance {
             warning_blocks: VarInt::from(5),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_camera() {
-        let _p = SetCamera {
+        let p = SetCamera {
             camera_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_center_chunk() {
-        let _p = SetCenterChunk {
+        let p = SetCenterChunk {
             chunk_x: VarInt::from(0),
             chunk_z: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_render_distance() {
-        let _p = SetRenderDistance {
+        let p = SetRenderDistance {
             view_distance: VarInt::from(10),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_cursor_item() {
-        let _p = SetCursorItem {
+        let p = SetCursorItem {
             carried_item: dummy_slot(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_default_spawn_position() {
-        let _p = SetDefaultSpawnPosition {
+        let p = SetDefaultSpawnPosition {
             location: Position { x: 0, y: 64, z: 0 },
             angle: 0.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_display_objective() {
-        let _p = DisplayObjective {
+        let p = DisplayObjective {
             position: VarInt::from(1),
             score_name: "sidebar".into(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_entity_metadata() {
-        let _p = SetEntityMetadata {
+        let p = SetEntityMetadata {
             entity_id: VarInt::from(1),
             metadata: dummy_metadata(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_link_entities() {
-        let _p = LinkEntities {
+        let p = LinkEntities {
             attached_entity_id: 1,
             holding_entity_id: 2,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_entity_velocity() {
-        let _p = SetEntityVelocity {
+        let p = SetEntityVelocity {
             entity_id: VarInt::from(1),
             velocity_x: 0,
             velocity_y: 100,
             velocity_z: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_equipment() {
-        let _p = SetEquipment {
+        let p = SetEquipment {
             entity_id: VarInt::from(1),
             equipment: vec![EquipmentEntry {
                 slot: EquipmentSlot::MainHand as u8,
                 item: dummy_slot(),
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_experience() {
-        let _p = SetExperience {
+        let p = SetExperience {
             experience_bar: 0.5,
             level: VarInt::from(10),
             total_experience: VarInt::from(100),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_health() {
-        let _p = SetHealth {
+        let p = SetHealth {
             health: 20.0,
             food: VarInt::from(20),
             food_saturation: 5.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_held_item() {
-        let _p = SetHeldItem {
+        let p = SetHeldItem {
             slot: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_objectives() {
-        let _p = UpdateObjectives {
+        let p = UpdateObjectives {
             objective_name: "dummy".into(),
             mode: ObjectiveMode::Create as u8,
             objective_value: Some(dummy_text()),
             objective_type: Some(VarInt::from(ObjectiveType::Integer as i32)),
             number_format: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_passengers() {
-        let _p = SetPassengers {
+        let p = SetPassengers {
             entity_id: VarInt::from(1),
             passengers: vec![VarInt::from(2), VarInt::from(3)],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_player_inventory_slot() {
-        let _p = SetPlayerInventorySlot {
+        let p = SetPlayerInventorySlot {
             slot: VarInt::from(9),
             slot_data: dummy_slot(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_teams() {
-        let _p = UpdateTeams::Create {
+        let p = UpdateTeams::Create {
             team_name: "blue".into(),
             display_name: dummy_text(),
             friendly_flags: FriendlyFlags::ALLOW_FRIENDLY_FIRE.bits(),
-            name_tag_visibility: "always".into(),
-            collision_rule: "always".into(),
+            name_tag_visibility: NameTagVisibility::HideForOtherTeams,
+            collision_rule: CollisionRule::PushOwnTeam,
             team_color: VarInt::from(TeamColor::Blue as i32),
             team_prefix: dummy_text(),
             team_suffix: dummy_text(),
             entities: vec!["Player".into()],
         };
-        assert!(true);
+        assert_round_trip(&p);
+    }
+    #[test]
+    fn test_update_teams_rules_per_version() {
+        let p = UpdateTeams::UpdateInfo {
+            team_name: "t".into(),
+            display_name: dummy_text(),
+            friendly_flags: 0,
+            name_tag_visibility: NameTagVisibility::HideForOtherTeams,
+            collision_rule: CollisionRule::PushOwnTeam,
+            team_color: VarInt::from(TeamColor::Blue as i32),
+            team_prefix: dummy_text(),
+            team_suffix: dummy_text(),
+        };
+        for version in [ProtocolVersion::V1_21_4, ProtocolVersion::V1_21_5] {
+            let mut head = BytesMut::from(&[1, b't', 2][..]);
+            dummy_text().encode_for(version, &mut head).unwrap();
+            head.put_u8(0);
+            let rules: &[u8] = if version == ProtocolVersion::V1_21_5 {
+                &[2, 3]
+            } else {
+                b"\x11hideForOtherTeams\x0bpushOwnTeam"
+            };
+
+            let mut buf = BytesMut::new();
+            p.encode_for(version, &mut buf).unwrap();
+            assert_eq!(&buf[..head.len()], &head[..]);
+            assert_eq!(&buf[head.len()..head.len() + rules.len()], rules);
+            assert_eq!(buf[head.len() + rules.len()], TeamColor::Blue as u8);
+
+            match UpdateTeams::decode_for(version, &mut buf).unwrap() {
+                UpdateTeams::UpdateInfo {
+                    name_tag_visibility,
+                    collision_rule,
+                    ..
+                } => {
+                    assert_eq!(name_tag_visibility, NameTagVisibility::HideForOtherTeams);
+                    assert_eq!(collision_rule, CollisionRule::PushOwnTeam);
+                }
+                _ => panic!("decoded the wrong team method"),
+            }
+            assert!(buf.is_empty());
+        }
     }
     #[test]
     fn test_update_score() {
-        let _p = UpdateScore {
+        let p = UpdateScore {
             entity_name: "Player".into(),
             objective_name: "dummy".into(),
             value: VarInt::from(10),
             display_name: None,
             number_format: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_simulation_distance() {
-        let _p = SetSimulationDistance {
+        let p = SetSimulationDistance {
             simulation_distance: VarInt::from(10),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_subtitle_text() {
-        let _p = SetSubtitleText {
+        let p = SetSubtitleText {
             subtitle_text: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_time() {
-        let _p = UpdateTime {
+        let p = UpdateTime {
             world_age: 1000,
             time_of_day: 6000,
             time_of_day_increasing: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_title_text() {
-        let _p = SetTitleText {
+        let p = SetTitleText {
             title_text: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_title_animation_times() {
-        let _p = SetTitleAnimationTimes {
+        let p = SetTitleAnimationTimes {
             fade_in: 10,
             stay: 70,
             fade_out: 20,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_entity_sound_effect() {
-        let _p = EntitySoundEffect {
+        let p = EntitySoundEffect {
             sound_event: IdOr::Id(VarInt::from(0)),
             sound_category: VarInt::from(SoundCategory::Player as i32),
             entity_id: VarInt::from(1),
@@ -1970,11 +2931,11 @@ mod tests {
             pitch: 1.0,
             seed: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_sound_effect() {
-        let _p = SoundEffect {
+        let p = SoundEffect {
             sound_event: IdOr::Id(VarInt::from(0)),
             sound_category: VarInt::from(SoundCategory::Block as i32),
             effect_position_x: 0,
@@ -1984,66 +2945,66 @@ mod tests {
             pitch: 1.0,
             seed: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_start_configuration() {
-        let _p = StartConfiguration;
-        assert!(true);
+        let p = StartConfiguration;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_stop_sound() {
-        let _p = StopSound {
+        let p = StopSound {
             flags: StopSoundFlags::HAS_SOURCE.bits(),
             source: Some(VarInt::from(SoundCategory::Master as i32)),
             sound: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_store_cookie_play() {
-        let _p = StoreCookiePlay {
+        let p = StoreCookiePlay {
             key: dummy_id(),
             payload: vec![1, 2],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_system_chat_message() {
-        let _p = SystemChatMessage {
+        let p = SystemChatMessage {
             content: dummy_text(),
             overlay: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_tab_list_header_and_footer() {
-        let _p = SetTabListHeaderAndFooter {
+        let p = SetTabListHeaderAndFooter {
             header: dummy_text(),
             footer: dummy_text(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_tag_query_response() {
-        let _p = TagQueryResponse {
+        let p = TagQueryResponse {
             transaction_id: VarInt::from(1),
             nbt: dummy_nbt(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_pickup_item() {
-        let _p = PickupItem {
+        let p = PickupItem {
             collected_entity_id: VarInt::from(1),
             collector_entity_id: VarInt::from(2),
             pickup_item_count: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_synchronize_vehicle_position() {
-        let _p = SynchronizeVehiclePosition {
+        let p = SynchronizeVehiclePosition {
             entity_id: VarInt::from(1),
             x: 0.0,
             y: 0.0,
@@ -2056,53 +3017,53 @@ mod tests {
             flags: 0,
             on_ground: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_test_instance_block_status() {
-        let _p = TestInstanceBlockStatus {
+        let p = TestInstanceBlockStatus {
             status: dummy_text(),
             size: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_ticking_state() {
-        let _p = SetTickingState {
+        let p = SetTickingState {
             tick_rate: 20.0,
             is_frozen: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_step_tick() {
-        let _p = StepTick {
+        let p = StepTick {
             tick_steps: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_transfer_play() {
-        let _p = TransferPlay {
+        let p = TransferPlay {
             host: "server.com".into(),
             port: VarInt::from(25565),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_advancements() {
-        let _p = UpdateAdvancements {
+        let p = UpdateAdvancements {
             reset_clear: false,
             advancement_mapping: HashMap::new(),
             identifiers_to_remove: vec![],
             progress_mapping: HashMap::new(),
             show_advancements: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_attributes() {
-        let _p = UpdateAttributes {
+        let p = UpdateAttributes {
             entity_id: VarInt::from(1),
             properties: vec![AttributeProperty {
                 id: VarInt::from(0),
@@ -2110,60 +3071,66 @@ mod tests {
                 modifiers: vec![],
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_entity_effect() {
-        let _p = EntityEffect {
+        let p = EntityEffect {
             entity_id: VarInt::from(1),
             effect_id: VarInt::from(1),
             amplifier: VarInt::from(0),
             duration: VarInt::from(100),
             flags: EntityEffectFlags::SHOW_PARTICLES.bits() | EntityEffectFlags::SHOW_ICON.bits(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_recipes() {
-        let _p = UpdateRecipes {
+        let p = UpdateRecipes {
             property_sets: vec![],
             stonecutter_recipes: vec![],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_tags_play() {
-        let _p = UpdateTagsPlay {
-            tags: HashMap::new(),
+        let p = UpdateTagsPlay {
+            tags: vec![RegistryTagData {
+                registry_id: "minecraft:worldgen/biome".into(),
+                tags: vec![crate::protocol::types::Tag {
+                    tag_name: "minecraft:is_ocean".into(),
+                    entries: vec![VarInt(0), VarInt(35)],
+                }],
+            }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_projectile_power() {
-        let _p = ProjectilePower {
+        let p = ProjectilePower {
             entity_id: VarInt::from(1),
             power: 1.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_custom_report_details_play() {
-        let _p = CustomReportDetailsPlay {
+        let p = CustomReportDetailsPlay {
             details: vec![CustomReportDetail {
                 title: "info".into(),
                 description: "data".into(),
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_server_links_play() {
-        let _p = ServerLinksPlay {
+        let p = ServerLinksPlay {
             links: vec![ServerLink {
                 label: ServerLinkLabel::BuiltIn(BuiltInServerLinkLabel::Website),
                 url: "url".into(),
             }],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
 }
@@ -1,62 +1,91 @@
+use crate::protocol::encoding::{Decode, DecodeResult, Encode, EncodeResult, Packet};
 use crate::protocol::types::{
-    Angle, ChatMode, Difficulty, DisplayedSkinParts, Hand, HashedSlot, Identifier,
-    JsonTextComponent, MainHand, Nbt, ParticleStatus, Position, Property, ResourcePackResult, Slot,
-    VarInt, VarLong,
+    Hand, HashedSlot, Identifier, TextComponent, Position, Slot, UntrustedSlot, VarInt, VarLong,
 };
-use std::collections::HashMap;
+use bytes::BytesMut;
 use uuid::Uuid;
 
+#[derive(Packet)]
+#[packet(id = 0x00)]
 pub struct ConfirmTeleportation {
     pub teleport_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x01)]
 pub struct QueryBlockEntityTag {
     pub transaction_id: VarInt,
     pub location: Position,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x02)]
 pub struct BundleItemSelected {
     pub slot_of_bundle: VarInt,
     pub slot_in_bundle: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x03)]
 pub struct ChangeDifficulty {
     pub new_difficulty: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x04)]
 pub struct AcknowledgeMessage {
     pub message_count: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x05)]
 pub struct ChatCommand {
     pub command: String,
 }
 
+/// Message signatures are always 256 bytes, so they are written without a length prefix.
+const MESSAGE_SIGNATURE_LEN: usize = 256;
+/// The acknowledged-messages bitset covers the last 20 messages, packed into 3 bytes.
+const ACKNOWLEDGED_LEN: usize = 3;
+
+#[derive(Packet)]
+#[packet(id = 0x06)]
 pub struct SignedChatCommand {
     pub command: String,
     pub timestamp: i64,
     pub salt: i64,
     pub argument_signatures: Vec<SignedArgument>,
     pub message_count: VarInt,
+    #[packet(fixed = ACKNOWLEDGED_LEN)]
     pub acknowledged: Vec<u8>,
+    #[packet(since = V1_21_5)]
     pub checksum: u8,
 }
 
+#[derive(Encode, Decode)]
 pub struct SignedArgument {
     pub argument_name: String,
+    #[packet(fixed = MESSAGE_SIGNATURE_LEN)]
     pub signature: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x07)]
 pub struct ChatMessage {
     pub message: String,
     pub timestamp: i64,
     pub salt: i64,
+    #[packet(optional, fixed = MESSAGE_SIGNATURE_LEN)]
     pub signature: Option<Vec<u8>>,
     pub message_count: VarInt,
+    #[packet(fixed = ACKNOWLEDGED_LEN)]
     pub acknowledged: Vec<u8>,
+    #[packet(since = V1_21_5)]
     pub checksum: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x08)]
 pub struct PlayerSession {
     pub session_id: Uuid,
     pub expires_at: i64,
@@ -64,16 +93,24 @@ pub struct PlayerSession {
     pub key_signature: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x09)]
 pub struct ChunkBatchReceived {
     pub chunks_per_tick: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0A)]
 pub struct ClientStatus {
     pub action_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0B)]
 pub struct ClientTickEnd;
 
+#[derive(Packet)]
+#[packet(id = 0x0C)]
 pub struct ClientInformation {
     pub locale: String,
     pub view_distance: i8,
@@ -86,18 +123,26 @@ pub struct ClientInformation {
     pub particle_status: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0D)]
 pub struct CommandSuggestionsRequest {
     pub transaction_id: VarInt,
     pub text: String,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x0E)]
 pub struct AcknowledgeConfiguration;
 
+#[derive(Packet)]
+#[packet(id = 0x0F)]
 pub struct ClickContainerButton {
     pub window_id: VarInt,
     pub button_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x10)]
 pub struct ClickContainer {
     pub window_id: VarInt,
     pub state_id: VarInt,
@@ -105,44 +150,68 @@ pub struct ClickContainer {
     pub button: i8,
     pub mode: VarInt,
     pub changed_slots: Vec<ChangedSlot>,
+    #[packet(since = V1_21_5)]
     pub carried_item: HashedSlot,
+    /// The full carried stack, which clients before 1.21.5 send instead of its hash.
+    #[packet(until = V1_21_5)]
+    pub carried_stack: Slot,
 }
 
+#[derive(Encode, Decode)]
 pub struct ChangedSlot {
     pub slot_number: i16,
+    #[packet(since = V1_21_5)]
     pub slot_data: HashedSlot,
+    /// The full stack, which clients before 1.21.5 send instead of its hash.
+    #[packet(until = V1_21_5)]
+    pub stack: Slot,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x11)]
 pub struct CloseContainer {
     pub window_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x12)]
 pub struct ChangeContainerSlotState {
     pub slot_id: VarInt,
     pub window_id: VarInt,
     pub state: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x13)]
 pub struct CookieResponse {
     pub key: Identifier,
     pub payload: Option<Vec<u8>>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x14)]
 pub struct ServerboundPluginMessage {
     pub channel: Identifier,
+    #[packet(rest)]
     pub data: Vec<u8>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x15)]
 pub struct DebugSampleSubscription {
     pub sample_type: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x16)]
 pub struct EditBook {
     pub slot: VarInt,
     pub entries: Vec<String>,
     pub title: Option<String>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x17)]
 pub struct QueryEntityTag {
     pub transaction_id: VarInt,
     pub entity_id: VarInt,
@@ -158,20 +227,32 @@ pub struct Interact {
     pub sneak_key_pressed: bool,
 }
 
+impl Packet for Interact {
+    const ID: i32 = 0x18;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x19)]
 pub struct JigsawGenerate {
     pub location: Position,
     pub levels: VarInt,
     pub keep_jigsaws: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1A)]
 pub struct ServerboundKeepAlive {
     pub keep_alive_id: i64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1B)]
 pub struct LockDifficulty {
     pub locked: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1C)]
 pub struct SetPlayerPosition {
     pub x: f64,
     pub feet_y: f64,
@@ -179,6 +260,8 @@ pub struct SetPlayerPosition {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1D)]
 pub struct SetPlayerPositionAndRotation {
     pub x: f64,
     pub feet_y: f64,
@@ -188,16 +271,22 @@ pub struct SetPlayerPositionAndRotation {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1E)]
 pub struct SetPlayerRotation {
     pub yaw: f32,
     pub pitch: f32,
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x1F)]
 pub struct SetPlayerMovementFlags {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x20)]
 pub struct MoveVehicle {
     pub x: f64,
     pub y: f64,
@@ -206,35 +295,49 @@ pub struct MoveVehicle {
     pub pitch: f32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x21)]
 pub struct PaddleBoat {
     pub left_paddle_turning: bool,
     pub right_paddle_turning: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x22)]
 pub struct PickItemFromBlock {
     pub location: Position,
     pub include_data: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x23)]
 pub struct PickItemFromEntity {
     pub entity_id: VarInt,
     pub include_data: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x24)]
 pub struct PingRequest {
     pub payload: i64,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x25)]
 pub struct PlaceRecipe {
     pub window_id: VarInt,
     pub recipe_id: VarInt,
     pub make_all: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x26)]
 pub struct ServerboundPlayerAbilities {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x27)]
 pub struct PlayerAction {
     pub status: VarInt,
     pub location: Position,
@@ -242,36 +345,52 @@ pub struct PlayerAction {
     pub sequence: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x28)]
 pub struct PlayerCommand {
     pub entity_id: VarInt,
     pub action_id: VarInt,
     pub jump_boost: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x29)]
 pub struct PlayerInput {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2A)]
 pub struct PlayerLoaded;
 
+#[derive(Packet)]
+#[packet(id = 0x2B)]
 pub struct Pong {
     pub id: i32,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2C)]
 pub struct ChangeRecipeBookSettings {
     pub book_id: VarInt,
     pub book_open: bool,
     pub filter_active: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2D)]
 pub struct SetSeenRecipe {
     pub recipe_id: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2E)]
 pub struct RenameItem {
     pub item_name: String,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x2F)]
 pub struct ServerboundResourcePackResponse {
     pub uuid: Uuid,
     pub result: VarInt,
@@ -282,19 +401,31 @@ pub struct SeenAdvancements {
     pub tab_id: Option<Identifier>,
 }
 
+impl Packet for SeenAdvancements {
+    const ID: i32 = 0x30;
+}
+
+#[derive(Packet)]
+#[packet(id = 0x31)]
 pub struct SelectTrade {
     pub selected_slot: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x32)]
 pub struct SetBeaconEffect {
     pub primary_effect: Option<VarInt>,
     pub secondary_effect: Option<VarInt>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x33)]
 pub struct SetHeldItem {
     pub slot: i16,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x34)]
 pub struct ProgramCommandBlock {
     pub location: Position,
     pub command: String,
@@ -302,17 +433,23 @@ pub struct ProgramCommandBlock {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x35)]
 pub struct ProgramCommandBlockMinecart {
     pub entity_id: VarInt,
     pub command: String,
     pub track_output: bool,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x36)]
 pub struct SetCreativeModeSlot {
     pub slot: i16,
-    pub clicked_item: Slot,
+    pub clicked_item: UntrustedSlot,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x37)]
 pub struct ProgramJigsawBlock {
     pub location: Position,
     pub name: Identifier,
@@ -324,6 +461,8 @@ pub struct ProgramJigsawBlock {
     pub placement_priority: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x38)]
 pub struct ProgramStructureBlock {
     pub location: Position,
     pub action: VarInt,
@@ -343,12 +482,16 @@ pub struct ProgramStructureBlock {
     pub flags: u8,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x39)]
 pub struct SetTestBlock {
     pub position: Position,
     pub mode: VarInt,
     pub message: String,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3A)]
 pub struct UpdateSign {
     pub location: Position,
     pub is_front_text: bool,
@@ -358,14 +501,20 @@ pub struct UpdateSign {
     pub line4: String,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3B)]
 pub struct SwingArm {
     pub hand: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3C)]
 pub struct TeleportToEntity {
     pub target_player: Uuid,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3D)]
 pub struct TestInstanceBlockAction {
     pub position: Position,
     pub action: VarInt,
@@ -376,9 +525,11 @@ pub struct TestInstanceBlockAction {
     pub rotation: VarInt,
     pub ignore_entities: bool,
     pub status: VarInt,
-    pub error_message: Option<JsonTextComponent>,
+    pub error_message: Option<TextComponent>,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3E)]
 pub struct UseItemOn {
     pub hand: VarInt,
     pub location: Position,
@@ -391,6 +542,8 @@ pub struct UseItemOn {
     pub sequence: VarInt,
 }
 
+#[derive(Packet)]
+#[packet(id = 0x3F)]
 pub struct UseItem {
     pub hand: VarInt,
     pub sequence: VarInt,
@@ -398,13 +551,86 @@ pub struct UseItem {
     pub pitch: f32,
 }
 
+/// Interaction types: 0 is interact, 1 is attack and 2 is interact-at, which alone carries a target.
+impl Encode for Interact {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.entity_id.encode(buf)?;
+        self.interaction_type.encode(buf)?;
+        if self.interaction_type.0 == 2 {
+            self.target_x.unwrap_or_default().encode(buf)?;
+            self.target_y.unwrap_or_default().encode(buf)?;
+            self.target_z.unwrap_or_default().encode(buf)?;
+        }
+        if self.interaction_type.0 != 1 {
+            self.hand
+                .unwrap_or(VarInt(Hand::MainHand as i32))
+                .encode(buf)?;
+        }
+        self.sneak_key_pressed.encode(buf)
+    }
+}
+
+impl Decode for Interact {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let entity_id = VarInt::decode(buf)?;
+        let interaction_type = VarInt::decode(buf)?;
+        let (target_x, target_y, target_z) = if interaction_type.0 == 2 {
+            (
+                Some(f32::decode(buf)?),
+                Some(f32::decode(buf)?),
+                Some(f32::decode(buf)?),
+            )
+        } else {
+            (None, None, None)
+        };
+        let hand = if interaction_type.0 != 1 {
+            Some(VarInt::decode(buf)?)
+        } else {
+            None
+        };
+        Ok(Interact {
+            entity_id,
+            interaction_type,
+            target_x,
+            target_y,
+            target_z,
+            hand,
+            sneak_key_pressed: bool::decode(buf)?,
+        })
+    }
+}
+
+/// The tab ID is only sent with the "opened tab" action (0).
+impl Encode for SeenAdvancements {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.action.encode(buf)?;
+        if self.action.0 == 0 {
+            self.tab_id.clone().unwrap_or_default().encode(buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for SeenAdvancements {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        let action = VarInt::decode(buf)?;
+        let tab_id = if action.0 == 0 {
+            Some(Identifier::decode(buf)?)
+        } else {
+            None
+        };
+        Ok(SeenAdvancements { action, tab_id })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::encoding::assert_round_trip;
     use crate::protocol::types::{
-        HashedSlot, Identifier, JsonTextComponent, Position, Slot, VarInt, VarLong,
+        DataComponent, HashedSlot, Identifier, Position, Slot, UntrustedSlot, VarInt, VarLong,
     }; // Add other necessary types from types.rs
-    use std::collections::HashMap;
+    use crate::protocol::version::ProtocolVersion;
     use uuid::Uuid; // If needed for any complex types
 
     // Helper function for placeholder Position
@@ -415,64 +641,63 @@ mod tests {
     fn dummy_id() -> Identifier {
         "minecraft:stone".into()
     }
-    // Helper function for placeholder JsonTextComponent
-    fn dummy_text() -> JsonTextComponent {
-        r#"{"text":""}"#.into()
-    }
     // Helper function for placeholder Slot
-    fn dummy_slot() -> Slot {
-        vec![]
+    fn dummy_slot() -> UntrustedSlot {
+        UntrustedSlot(Slot::new(1, 1).with_component(DataComponent::Other {
+            id: 25,
+            data: vec![0],
+        }))
     }
     // Helper function for placeholder HashedSlot
     fn dummy_hashed_slot() -> HashedSlot {
-        vec![]
+        Slot::new(1, 1).with_component(DataComponent::Damage(2)).hashed().unwrap()
     }
 
     #[test]
     fn test_confirm_teleportation() {
-        let _p = ConfirmTeleportation {
+        let p = ConfirmTeleportation {
             teleport_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_query_block_entity_tag() {
-        let _p = QueryBlockEntityTag {
+        let p = QueryBlockEntityTag {
             transaction_id: VarInt::from(1),
             location: dummy_pos(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_bundle_item_selected() {
-        let _p = BundleItemSelected {
+        let p = BundleItemSelected {
             slot_of_bundle: VarInt::from(0),
             slot_in_bundle: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_change_difficulty() {
-        let _p = ChangeDifficulty { new_difficulty: 1 };
-        assert!(true);
+        let p = ChangeDifficulty { new_difficulty: 1 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_acknowledge_message() {
-        let _p = AcknowledgeMessage {
+        let p = AcknowledgeMessage {
             message_count: VarInt::from(5),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chat_command() {
-        let _p = ChatCommand {
+        let p = ChatCommand {
             command: "/say hello".into(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_signed_chat_command() {
-        let _p = SignedChatCommand {
+        let p = SignedChatCommand {
             command: "/say hello".into(),
             timestamp: 0,
             salt: 0,
@@ -481,11 +706,30 @@ mod tests {
             acknowledged: vec![0u8; 3],
             checksum: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     } // Fixed BitSet size
     #[test]
+    fn test_chat_message_checksum_is_new_in_1_21_5() {
+        let p = ChatMessage {
+            message: "hello".into(),
+            timestamp: 0,
+            salt: 0,
+            signature: None,
+            message_count: VarInt::from(1),
+            acknowledged: vec![0u8; 3],
+            checksum: 7,
+        };
+        let mut old = BytesMut::new();
+        p.encode_for(ProtocolVersion::V1_21_4, &mut old).unwrap();
+        assert_eq!(old.len() + 1, p.to_bytes().unwrap().len());
+        let decoded = ChatMessage::decode_for(ProtocolVersion::V1_21_4, &mut old).unwrap();
+        assert!(old.is_empty());
+        assert_eq!(decoded.checksum, 0);
+        assert_eq!(decoded.message, "hello");
+    }
+    #[test]
     fn test_chat_message() {
-        let _p = ChatMessage {
+        let p = ChatMessage {
             message: "hello".into(),
             timestamp: 0,
             salt: 0,
@@ -494,40 +738,40 @@ mod tests {
             acknowledged: vec![0u8; 3],
             checksum: 0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     } // Fixed BitSet size
     #[test]
     fn test_player_session() {
-        let _p = PlayerSession {
+        let p = PlayerSession {
             session_id: Uuid::new_v4(),
             expires_at: 0,
             public_key: vec![],
             key_signature: vec![],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_chunk_batch_received() {
-        let _p = ChunkBatchReceived {
+        let p = ChunkBatchReceived {
             chunks_per_tick: 10.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_client_status() {
-        let _p = ClientStatus {
+        let p = ClientStatus {
             action_id: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_client_tick_end() {
-        let _p = ClientTickEnd;
-        assert!(true);
+        let p = ClientTickEnd;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_client_information() {
-        let _p = ClientInformation {
+        let p = ClientInformation {
             locale: "en_US".into(),
             view_distance: 10,
             chat_mode: VarInt::from(0),
@@ -538,32 +782,32 @@ mod tests {
             allow_server_listings: true,
             particle_status: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_command_suggestions_request() {
-        let _p = CommandSuggestionsRequest {
+        let p = CommandSuggestionsRequest {
             transaction_id: VarInt::from(1),
             text: "/gamemode ".into(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_acknowledge_configuration() {
-        let _p = AcknowledgeConfiguration;
-        assert!(true);
+        let p = AcknowledgeConfiguration;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_click_container_button() {
-        let _p = ClickContainerButton {
+        let p = ClickContainerButton {
             window_id: VarInt::from(1),
             button_id: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_click_container() {
-        let _p = ClickContainer {
+        let p = ClickContainer {
             window_id: VarInt::from(0),
             state_id: VarInt::from(1),
             slot: 36,
@@ -572,70 +816,101 @@ mod tests {
             changed_slots: vec![ChangedSlot {
                 slot_number: 36,
                 slot_data: dummy_hashed_slot(),
+                stack: Slot::EMPTY,
             }],
             carried_item: dummy_hashed_slot(),
+            carried_stack: Slot::EMPTY,
         };
-        assert!(true);
+        assert_round_trip(&p);
+    }
+    #[test]
+    fn test_click_container_sends_full_stacks_before_1_21_5() {
+        let stack = Slot::new(1, 1).with_component(DataComponent::Damage(2));
+        let p = ClickContainer {
+            window_id: VarInt::from(0),
+            state_id: VarInt::from(1),
+            slot: 36,
+            button: 0,
+            mode: VarInt::from(0),
+            changed_slots: vec![ChangedSlot {
+                slot_number: 36,
+                slot_data: None,
+                stack: stack.clone(),
+            }],
+            carried_item: None,
+            carried_stack: Slot::EMPTY,
+        };
+        let mut old = BytesMut::new();
+        p.encode_for(ProtocolVersion::V1_21_4, &mut old).unwrap();
+        // Window, state, slot, button, mode, one changed slot with its full stack, empty cursor.
+        assert_eq!(
+            old.as_ref(),
+            [0, 1, 0, 36, 0, 0, 1, 0, 36, 1, 1, 1, 0, 3, 2, 0]
+        );
+        let decoded = ClickContainer::decode_for(ProtocolVersion::V1_21_4, &mut old).unwrap();
+        assert!(old.is_empty());
+        assert_eq!(decoded.changed_slots[0].stack, stack);
+        assert_eq!(decoded.changed_slots[0].slot_data, None);
     }
     #[test]
     fn test_close_container() {
-        let _p = CloseContainer {
+        let p = CloseContainer {
             window_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_change_container_slot_state() {
-        let _p = ChangeContainerSlotState {
+        let p = ChangeContainerSlotState {
             slot_id: VarInt::from(0),
             window_id: VarInt::from(1),
             state: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_cookie_response() {
-        let _p = CookieResponse {
+        let p = CookieResponse {
             key: dummy_id(),
             payload: Some(vec![1, 2]),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_serverbound_plugin_message() {
-        let _p = ServerboundPluginMessage {
+        let p = ServerboundPluginMessage {
             channel: dummy_id(),
             data: vec![1, 2, 3],
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_debug_sample_subscription() {
-        let _p = DebugSampleSubscription {
+        let p = DebugSampleSubscription {
             sample_type: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_edit_book() {
-        let _p = EditBook {
+        let p = EditBook {
             slot: VarInt::from(0),
             entries: vec!["Page 1".into()],
             title: Some("My Book".into()),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_query_entity_tag() {
-        let _p = QueryEntityTag {
+        let p = QueryEntityTag {
             transaction_id: VarInt::from(1),
             entity_id: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_interact() {
-        let _p = Interact {
+        let p = Interact {
             entity_id: VarInt::from(1),
             interaction_type: VarInt::from(1),
             target_x: None,
@@ -644,42 +919,42 @@ mod tests {
             hand: None,
             sneak_key_pressed: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_jigsaw_generate() {
-        let _p = JigsawGenerate {
+        let p = JigsawGenerate {
             location: dummy_pos(),
             levels: VarInt::from(1),
             keep_jigsaws: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_serverbound_keep_alive() {
-        let _p = ServerboundKeepAlive {
+        let p = ServerboundKeepAlive {
             keep_alive_id: 12345,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_lock_difficulty() {
-        let _p = LockDifficulty { locked: true };
-        assert!(true);
+        let p = LockDifficulty { locked: true };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_player_position() {
-        let _p = SetPlayerPosition {
+        let p = SetPlayerPosition {
             x: 0.0,
             feet_y: 64.0,
             z: 0.0,
             flags: 0x01,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_player_position_and_rotation() {
-        let _p = SetPlayerPositionAndRotation {
+        let p = SetPlayerPositionAndRotation {
             x: 0.0,
             feet_y: 64.0,
             z: 0.0,
@@ -687,199 +962,199 @@ mod tests {
             pitch: 0.0,
             flags: 0x01,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_player_rotation() {
-        let _p = SetPlayerRotation {
+        let p = SetPlayerRotation {
             yaw: 90.0,
             pitch: 0.0,
             flags: 0x01,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_player_movement_flags() {
-        let _p = SetPlayerMovementFlags { flags: 0x01 };
-        assert!(true);
+        let p = SetPlayerMovementFlags { flags: 0x01 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_move_vehicle() {
-        let _p = MoveVehicle {
+        let p = MoveVehicle {
             x: 0.0,
             y: 64.0,
             z: 0.0,
             yaw: 0.0,
             pitch: 0.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_paddle_boat() {
-        let _p = PaddleBoat {
+        let p = PaddleBoat {
             left_paddle_turning: true,
             right_paddle_turning: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_pick_item_from_block() {
-        let _p = PickItemFromBlock {
+        let p = PickItemFromBlock {
             location: dummy_pos(),
             include_data: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_pick_item_from_entity() {
-        let _p = PickItemFromEntity {
+        let p = PickItemFromEntity {
             entity_id: VarInt::from(1),
             include_data: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_ping_request() {
-        let _p = PingRequest { payload: 123 };
-        assert!(true);
+        let p = PingRequest { payload: 123 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_place_recipe() {
-        let _p = PlaceRecipe {
+        let p = PlaceRecipe {
             window_id: VarInt::from(1),
             recipe_id: VarInt::from(0),
             make_all: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_serverbound_player_abilities() {
-        let _p = ServerboundPlayerAbilities { flags: 0x02 };
-        assert!(true);
+        let p = ServerboundPlayerAbilities { flags: 0x02 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_action() {
-        let _p = PlayerAction {
+        let p = PlayerAction {
             status: VarInt::from(0),
             location: dummy_pos(),
             face: 1,
             sequence: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_command() {
-        let _p = PlayerCommand {
+        let p = PlayerCommand {
             entity_id: VarInt::from(1),
             action_id: VarInt::from(0),
             jump_boost: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_input() {
-        let _p = PlayerInput { flags: 0x01 };
-        assert!(true);
+        let p = PlayerInput { flags: 0x01 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_player_loaded() {
-        let _p = PlayerLoaded;
-        assert!(true);
+        let p = PlayerLoaded;
+        assert_round_trip(&p);
     }
     #[test]
     fn test_pong() {
-        let _p = Pong { id: 123 };
-        assert!(true);
+        let p = Pong { id: 123 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_change_recipe_book_settings() {
-        let _p = ChangeRecipeBookSettings {
+        let p = ChangeRecipeBookSettings {
             book_id: VarInt::from(0),
             book_open: true,
             filter_active: false,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_seen_recipe() {
-        let _p = SetSeenRecipe {
+        let p = SetSeenRecipe {
             recipe_id: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_rename_item() {
-        let _p = RenameItem {
+        let p = RenameItem {
             item_name: "New Name".into(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_serverbound_resource_pack_response() {
-        let _p = ServerboundResourcePackResponse {
+        let p = ServerboundResourcePackResponse {
             uuid: Uuid::new_v4(),
             result: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_seen_advancements() {
-        let _p = SeenAdvancements {
+        let p = SeenAdvancements {
             action: VarInt::from(0),
             tab_id: Some(dummy_id()),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_select_trade() {
-        let _p = SelectTrade {
+        let p = SelectTrade {
             selected_slot: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_beacon_effect() {
-        let _p = SetBeaconEffect {
+        let p = SetBeaconEffect {
             primary_effect: Some(VarInt::from(1)),
             secondary_effect: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_held_item() {
-        let _p = SetHeldItem { slot: 0 };
-        assert!(true);
+        let p = SetHeldItem { slot: 0 };
+        assert_round_trip(&p);
     }
     #[test]
     fn test_program_command_block() {
-        let _p = ProgramCommandBlock {
+        let p = ProgramCommandBlock {
             location: dummy_pos(),
             command: "/say hi".into(),
             mode: VarInt::from(2),
             flags: 0x04,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_program_command_block_minecart() {
-        let _p = ProgramCommandBlockMinecart {
+        let p = ProgramCommandBlockMinecart {
             entity_id: VarInt::from(1),
             command: "/say hi".into(),
             track_output: true,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_creative_mode_slot() {
-        let _p = SetCreativeModeSlot {
+        let p = SetCreativeModeSlot {
             slot: 36,
             clicked_item: dummy_slot(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_program_jigsaw_block() {
-        let _p = ProgramJigsawBlock {
+        let p = ProgramJigsawBlock {
             location: dummy_pos(),
             name: dummy_id(),
             target: dummy_id(),
@@ -889,11 +1164,11 @@ mod tests {
             selection_priority: VarInt::from(0),
             placement_priority: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_program_structure_block() {
-        let _p = ProgramStructureBlock {
+        let p = ProgramStructureBlock {
             location: dummy_pos(),
             action: VarInt::from(1),
             mode: VarInt::from(0),
@@ -911,20 +1186,20 @@ mod tests {
             seed: VarLong::from(0),
             flags: 0x04,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_set_test_block() {
-        let _p = SetTestBlock {
+        let p = SetTestBlock {
             position: dummy_pos(),
             mode: VarInt::from(0),
             message: "Starting test".into(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_update_sign() {
-        let _p = UpdateSign {
+        let p = UpdateSign {
             location: dummy_pos(),
             is_front_text: true,
             line1: "Line 1".into(),
@@ -932,25 +1207,25 @@ mod tests {
             line3: "".into(),
             line4: "".into(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_swing_arm() {
-        let _p = SwingArm {
+        let p = SwingArm {
             hand: VarInt::from(0),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_teleport_to_entity() {
-        let _p = TeleportToEntity {
+        let p = TeleportToEntity {
             target_player: Uuid::new_v4(),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_test_instance_block_action() {
-        let _p = TestInstanceBlockAction {
+        let p = TestInstanceBlockAction {
             position: dummy_pos(),
             action: VarInt::from(0),
             test: None,
@@ -962,11 +1237,11 @@ mod tests {
             status: VarInt::from(0),
             error_message: None,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_use_item_on() {
-        let _p = UseItemOn {
+        let p = UseItemOn {
             hand: VarInt::from(0),
             location: dummy_pos(),
             face: VarInt::from(1),
@@ -977,16 +1252,16 @@ mod tests {
             world_border_hit: false,
             sequence: VarInt::from(1),
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
     #[test]
     fn test_use_item() {
-        let _p = UseItem {
+        let p = UseItem {
             hand: VarInt::from(0),
             sequence: VarInt::from(1),
             yaw: 0.0,
             pitch: 0.0,
         };
-        assert!(true);
+        assert_round_trip(&p);
     }
-}
\ No newline at end of file
+}
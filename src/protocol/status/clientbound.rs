@@ -1,11 +1,13 @@
-use crate::protocol::types::{JsonTextComponent, VarInt};
+use crate::protocol::encoding::Packet;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x00)]
 pub struct StatusResponse {
     pub json_response: String,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x01)]
 pub struct PongResponse {
     pub payload: i64,
 }
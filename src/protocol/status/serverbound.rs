@@ -1,9 +1,11 @@
-use crate::protocol::types::VarInt;
+use crate::protocol::encoding::Packet;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x00)]
 pub struct StatusRequest {}
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Packet)]
+#[packet(id = 0x01)]
 pub struct PingRequest {
     pub payload: i64,
 }
@@ -11,11 +13,12 @@ pub struct PingRequest {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::encoding::assert_round_trip;
 
     #[test]
     fn test_status_request_instantiation() {
-        let _packet = StatusRequest {};
-        assert!(true);
+        let packet = StatusRequest {};
+        assert_round_trip(&packet);
     }
 
     #[test]
@@ -0,0 +1,307 @@
+//! Legacy `§` formatting codes, as still used in `server.properties` MOTDs, plugin configs and
+//! the pre-1.7 server list ping.
+
+use super::{NamedColor, Style, TextColor, TextComponent};
+
+/// The section sign that starts a formatting code.
+pub const SECTION_SIGN: char = '§';
+
+const COLOR_CODES: &str = "0123456789abcdef";
+
+/// The decorations a legacy code can switch on, in the order they are written back.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct LegacyFormat {
+    color: Option<NamedColor>,
+    obfuscated: bool,
+    bold: bool,
+    strikethrough: bool,
+    underlined: bool,
+    italic: bool,
+}
+
+impl LegacyFormat {
+    fn from_style(style: &Style) -> Self {
+        LegacyFormat {
+            color: style.color.map(|color| match color {
+                TextColor::Named(color) => color,
+                TextColor::Rgb(rgb) => NamedColor::nearest(rgb),
+            }),
+            obfuscated: style.obfuscated == Some(true),
+            bold: style.bold == Some(true),
+            strikethrough: style.strikethrough == Some(true),
+            underlined: style.underlined == Some(true),
+            italic: style.italic == Some(true),
+        }
+    }
+
+    fn decorations(&self) -> [(bool, char); 5] {
+        [
+            (self.obfuscated, 'k'),
+            (self.bold, 'l'),
+            (self.strikethrough, 'm'),
+            (self.underlined, 'n'),
+            (self.italic, 'o'),
+        ]
+    }
+
+    /// Codes that switch from `self` to `next`. Decorations can only be switched off by a color
+    /// code or `§r`, which clear all of them.
+    fn transition(&self, next: &LegacyFormat) -> String {
+        let mut codes = String::new();
+        let mut push = |code: char| {
+            codes.push(SECTION_SIGN);
+            codes.push(code);
+        };
+        let drops_decoration = self
+            .decorations()
+            .iter()
+            .zip(next.decorations())
+            .any(|((was, _), (is, _))| *was && !is);
+        if drops_decoration || self.color != next.color {
+            match next.color {
+                Some(color) => push(color.code()),
+                None => push('r'),
+            }
+            for (on, code) in next.decorations() {
+                if on {
+                    push(code);
+                }
+            }
+        } else {
+            for ((was, _), (is, code)) in self.decorations().iter().zip(next.decorations()) {
+                if is && !was {
+                    push(code);
+                }
+            }
+        }
+        codes
+    }
+}
+
+/// `child`'s style with anything it leaves unset taken from `parent`.
+fn inherit(parent: &Style, child: &Style) -> Style {
+    Style {
+        color: child.color.or(parent.color),
+        font: child.font.clone().or_else(|| parent.font.clone()),
+        bold: child.bold.or(parent.bold),
+        italic: child.italic.or(parent.italic),
+        underlined: child.underlined.or(parent.underlined),
+        strikethrough: child.strikethrough.or(parent.strikethrough),
+        obfuscated: child.obfuscated.or(parent.obfuscated),
+        shadow_color: child.shadow_color.or(parent.shadow_color),
+        insertion: child.insertion.clone().or_else(|| parent.insertion.clone()),
+        click_event: child
+            .click_event
+            .clone()
+            .or_else(|| parent.click_event.clone()),
+        hover_event: child
+            .hover_event
+            .clone()
+            .or_else(|| parent.hover_event.clone()),
+    }
+}
+
+impl NamedColor {
+    /// The character following `§` for this color.
+    pub fn code(self) -> char {
+        COLOR_CODES.as_bytes()[self as usize] as char
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        let index = COLOR_CODES.find(code.to_ascii_lowercase())?;
+        Some(NamedColor::ALL[index])
+    }
+
+    /// The named color closest to `rgb`, for output that can't carry RGB colors.
+    pub fn nearest(rgb: u32) -> Self {
+        let channels = |rgb: u32| [(rgb >> 16) & 0xFF, (rgb >> 8) & 0xFF, rgb & 0xFF];
+        let distance = |color: &NamedColor| {
+            channels(color.rgb())
+                .iter()
+                .zip(channels(rgb))
+                .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        };
+        NamedColor::ALL.into_iter().min_by_key(distance).unwrap()
+    }
+}
+
+impl TextComponent {
+    /// Parses text with `§` formatting codes.
+    pub fn from_legacy(text: &str) -> Self {
+        Self::from_legacy_with(text, SECTION_SIGN)
+    }
+
+    /// Parses text with formatting codes introduced by `code_char`, such as the `&` common in
+    /// config files. Colors reset the decorations before them, as in the vanilla client, and
+    /// `§x§r§r§g§g§b§b` sets an RGB color. Unknown codes are kept as text.
+    pub fn from_legacy_with(text: &str, code_char: char) -> Self {
+        let mut segments = vec![];
+        let mut style = Style::default();
+        let mut current = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let Some(&code) = chars.peek().filter(|_| c == code_char) else {
+                current.push(c);
+                continue;
+            };
+            let mut next_style = style.clone();
+            match code.to_ascii_lowercase() {
+                'k' => next_style.obfuscated = Some(true),
+                'l' => next_style.bold = Some(true),
+                'm' => next_style.strikethrough = Some(true),
+                'n' => next_style.underlined = Some(true),
+                'o' => next_style.italic = Some(true),
+                'r' => next_style = Style::default(),
+                'x' => {
+                    let rest: String = chars.clone().skip(1).take(12).collect();
+                    let Some(rgb) = parse_hex_code(&rest, code_char) else {
+                        current.push(c);
+                        continue;
+                    };
+                    // With the `chars.next()` below, this skips the `x` and the twelve code
+                    // characters after it.
+                    chars.nth(11);
+                    next_style = Style {
+                        color: Some(TextColor::Rgb(rgb)),
+                        ..Style::default()
+                    };
+                }
+                other => match NamedColor::from_code(other) {
+                    Some(color) => {
+                        next_style = Style {
+                            color: Some(color.into()),
+                            ..Style::default()
+                        }
+                    }
+                    None => {
+                        current.push(c);
+                        continue;
+                    }
+                },
+            }
+            chars.next();
+            if !current.is_empty() {
+                segments.push(TextComponent {
+                    style: Box::new(style),
+                    ..TextComponent::text(std::mem::take(&mut current))
+                });
+            }
+            style = next_style;
+        }
+        if !current.is_empty() {
+            segments.push(TextComponent {
+                style: Box::new(style),
+                ..TextComponent::text(current)
+            });
+        }
+
+        if segments.len() == 1 {
+            segments.pop().unwrap()
+        } else {
+            TextComponent {
+                extra: segments,
+                ..TextComponent::text("")
+            }
+        }
+    }
+
+    /// Renders the component as `§`-coded text. RGB colors become the nearest named color, and
+    /// fonts and events are dropped.
+    pub fn to_legacy(&self) -> String {
+        let mut legacy = String::new();
+        self.write_legacy(&Style::default(), &mut LegacyFormat::default(), &mut legacy);
+        legacy
+    }
+
+    fn write_legacy(&self, parent: &Style, written: &mut LegacyFormat, legacy: &mut String) {
+        let style = inherit(parent, &self.style);
+        let text = self.content_text();
+        if !text.is_empty() {
+            let format = LegacyFormat::from_style(&style);
+            legacy.push_str(&written.transition(&format));
+            *written = format;
+            legacy.push_str(&text);
+        }
+        for child in &self.extra {
+            child.write_legacy(&style, written, legacy);
+        }
+    }
+}
+
+/// Reads the `§r§r§g§g§b§b` part of an RGB code.
+fn parse_hex_code(code: &str, code_char: char) -> Option<u32> {
+    let mut hex = String::with_capacity(6);
+    let mut chars = code.chars();
+    for _ in 0..6 {
+        if chars.next()? != code_char {
+            return None;
+        }
+        hex.push(chars.next().filter(char::is_ascii_hexdigit)?);
+    }
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_legacy() {
+        assert_eq!(TextComponent::from_legacy("plain"), "plain".into());
+        assert_eq!(
+            TextComponent::from_legacy("§aGreen §lbold§cRed§r plain"),
+            TextComponent::text("")
+                .append(TextComponent::text("Green ").color(NamedColor::Green))
+                .append(
+                    TextComponent::text("bold")
+                        .color(NamedColor::Green)
+                        .bold(true)
+                )
+                .append(TextComponent::text("Red").color(NamedColor::Red))
+                .append(" plain")
+        );
+    }
+
+    #[test]
+    fn test_from_legacy_with_ampersand_and_hex() {
+        assert_eq!(
+            TextComponent::from_legacy_with("&x&1&2&a&B&3&4hex &zkept &", '&'),
+            TextComponent::text("hex &zkept &").color(TextColor::Rgb(0x12AB34))
+        );
+    }
+
+    #[test]
+    fn test_to_legacy() {
+        let component = TextComponent::text("")
+            .append(TextComponent::text("Green ").color(NamedColor::Green))
+            .append(
+                TextComponent::text("bold ")
+                    .color(NamedColor::Green)
+                    .bold(true)
+                    .append(TextComponent::text("still").italic(true)),
+            )
+            .append(TextComponent::text(" orange").color(TextColor::Rgb(0xFFA500)))
+            .append(" plain");
+        assert_eq!(
+            component.to_legacy(),
+            "§aGreen §lbold §ostill§6 orange§r plain"
+        );
+    }
+
+    #[test]
+    fn test_legacy_round_trip() {
+        let text = "§6Gold §lbold§9 blue §kmagic";
+        assert_eq!(TextComponent::from_legacy(text).to_legacy(), text);
+    }
+
+    #[test]
+    fn test_nearest_color() {
+        assert_eq!(NamedColor::nearest(0xFF5050), NamedColor::Red);
+        assert_eq!(NamedColor::nearest(0xFE0000), NamedColor::DarkRed);
+        assert_eq!(NamedColor::nearest(0x010101), NamedColor::Black);
+        assert_eq!(NamedColor::from_code('F'), Some(NamedColor::White));
+        assert_eq!(NamedColor::DarkPurple.code(), '5');
+    }
+}
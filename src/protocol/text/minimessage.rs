@@ -0,0 +1,373 @@
+//! A MiniMessage-style tag markup: `<red>Hello</red> <bold><click:run_command:/help>help`.
+//!
+//! Style tags (colors, decorations, `click`, `hover`, `insert`, `font`) apply until their closing
+//! tag, or to the end of the text if never closed; closing a tag also closes any tags opened
+//! inside it. `<reset>` closes everything. `newline`/`br`, `key`, `lang`, `selector` and `score`
+//! insert a component. Arguments follow the tag name after `:` and may be quoted with `'` or `"`.
+//! Anything that isn't a known tag, such as `a < b`, is kept as text, and `\<` escapes a tag.
+
+use super::{ClickEvent, Content, HoverEvent, Style, TextColor, TextComponent};
+
+/// What an opening tag does.
+enum TagAction {
+    Style(Box<dyn FnOnce(&mut Style)>),
+    Insert(TextComponent),
+    Reset,
+}
+
+/// An open style tag and the component collecting its contents.
+struct Frame {
+    tag: String,
+    component: TextComponent,
+}
+
+/// A parsed `<...>`, with quotes removed from its arguments.
+struct Tag {
+    closing: bool,
+    name: String,
+    args: Vec<String>,
+}
+
+impl TextComponent {
+    /// Parses MiniMessage-style markup. Unknown or malformed tags are kept as text, so this never
+    /// fails.
+    pub fn from_minimessage(markup: &str) -> Self {
+        let mut stack = vec![Frame {
+            tag: String::new(),
+            component: TextComponent::text(""),
+        }];
+        let mut text = String::new();
+        let mut rest = markup;
+
+        while let Some(c) = rest.chars().next() {
+            if c == '\\' && (rest[1..].starts_with('<') || rest[1..].starts_with('\\')) {
+                text.push_str(&rest[1..2]);
+                rest = &rest[2..];
+                continue;
+            }
+            let parsed = match c {
+                '<' => parse_tag(rest),
+                _ => None,
+            };
+            let Some((tag, len)) = parsed else {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            };
+
+            let handled = if tag.closing {
+                let open = stack[1..]
+                    .iter()
+                    .rposition(|frame| closes(&tag.name, &frame.tag));
+                open.map(|index| {
+                    flush(&mut stack, &mut text);
+                    pop_to(&mut stack, index + 1);
+                })
+                .is_some()
+            } else {
+                match tag_action(&tag) {
+                    Some(action) => {
+                        flush(&mut stack, &mut text);
+                        match action {
+                            TagAction::Style(apply) => {
+                                let mut component = TextComponent::text("");
+                                apply(&mut component.style);
+                                stack.push(Frame {
+                                    tag: tag.name,
+                                    component,
+                                });
+                            }
+                            TagAction::Insert(component) => {
+                                stack.last_mut().unwrap().component.extra.push(component)
+                            }
+                            TagAction::Reset => pop_to(&mut stack, 1),
+                        }
+                        true
+                    }
+                    None => false,
+                }
+            };
+            if !handled {
+                text.push_str(&rest[..len]);
+            }
+            rest = &rest[len..];
+        }
+
+        flush(&mut stack, &mut text);
+        pop_to(&mut stack, 1);
+        let mut root = stack.pop().unwrap().component;
+        match root.extra.len() {
+            0 => TextComponent::text(""),
+            1 => root.extra.pop().unwrap(),
+            _ => root,
+        }
+    }
+}
+
+fn flush(stack: &mut [Frame], text: &mut String) {
+    if !text.is_empty() {
+        let top = &mut stack.last_mut().unwrap().component;
+        top.extra.push(TextComponent::text(std::mem::take(text)));
+    }
+}
+
+/// Closes frames until `len` are left, adding each to its parent.
+fn pop_to(stack: &mut Vec<Frame>, len: usize) {
+    while stack.len() > len {
+        let mut frame = stack.pop().unwrap();
+        if frame.component.extra.is_empty() {
+            continue;
+        }
+        // A style tag around a single piece of text becomes one styled text component.
+        if let [child] = &frame.component.extra[..]
+            && child.style.is_empty()
+            && child.extra.is_empty()
+            && let Content::Text(text) = &child.content
+        {
+            frame.component.content = Content::Text(text.clone());
+            frame.component.extra.clear();
+        }
+        stack
+            .last_mut()
+            .unwrap()
+            .component
+            .extra
+            .push(frame.component);
+    }
+}
+
+/// Whether `</closing>` closes `<open>`: the same name, or aliases of the same tag.
+fn closes(closing: &str, open: &str) -> bool {
+    closing == open || canonical_name(closing) == canonical_name(open)
+}
+
+fn canonical_name(name: &str) -> &str {
+    match name.trim_start_matches('!') {
+        "b" => "bold",
+        "i" | "em" => "italic",
+        "u" => "underlined",
+        "st" => "strikethrough",
+        "obf" => "obfuscated",
+        "c" | "colour" => "color",
+        "insertion" => "insert",
+        other => other,
+    }
+}
+
+/// Reads a tag at the start of `input`, returning it and its length in bytes.
+fn parse_tag(input: &str) -> Option<(Tag, usize)> {
+    let mut parts = vec![String::new()];
+    let mut quote = None;
+    let mut chars = input.char_indices().skip(1);
+    let end = loop {
+        let (i, c) = chars.next()?;
+        match (quote, c) {
+            (Some(q), '\\') => {
+                let (_, escaped) = chars.next()?;
+                if escaped != q && escaped != '\\' {
+                    parts.last_mut().unwrap().push('\\');
+                }
+                parts.last_mut().unwrap().push(escaped);
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => parts.last_mut().unwrap().push(c),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ':') => parts.push(String::new()),
+            (None, '>') => break i,
+            (None, '<') => return None,
+            (None, c) => parts.last_mut().unwrap().push(c),
+        }
+    };
+
+    let mut name = parts.remove(0);
+    let closing = name.starts_with('/');
+    if closing {
+        name.remove(0);
+    }
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-!#.".contains(c));
+    if !valid_name {
+        return None;
+    }
+    Some((
+        Tag {
+            closing,
+            name: name.to_ascii_lowercase(),
+            args: parts,
+        },
+        end + 1,
+    ))
+}
+
+fn decoration(name: &str, value: bool) -> Option<TagAction> {
+    let apply: fn(&mut Style, bool) = match name {
+        "bold" => |style, value| style.bold = Some(value),
+        "italic" => |style, value| style.italic = Some(value),
+        "underlined" => |style, value| style.underlined = Some(value),
+        "strikethrough" => |style, value| style.strikethrough = Some(value),
+        "obfuscated" => |style, value| style.obfuscated = Some(value),
+        _ => return None,
+    };
+    Some(TagAction::Style(Box::new(move |style| apply(style, value))))
+}
+
+fn style_action(apply: impl FnOnce(&mut Style) + 'static) -> Option<TagAction> {
+    Some(TagAction::Style(Box::new(apply)))
+}
+
+fn tag_action(tag: &Tag) -> Option<TagAction> {
+    let args = &tag.args;
+    let arg = |index: usize| args.get(index).cloned();
+    if let Ok(color) = tag.name.parse::<TextColor>() {
+        return style_action(move |style| style.color = Some(color));
+    }
+    if let Some(negated) = tag.name.strip_prefix('!') {
+        return decoration(canonical_name(negated), false);
+    }
+    match canonical_name(&tag.name) {
+        "color" => {
+            let color: TextColor = arg(0)?.parse().ok()?;
+            style_action(move |style| style.color = Some(color))
+        }
+        "click" => {
+            let value = arg(1)?;
+            let event = match arg(0)?.as_str() {
+                "open_url" => ClickEvent::OpenUrl { url: value },
+                "open_file" => ClickEvent::OpenFile { path: value },
+                "run_command" => ClickEvent::RunCommand { command: value },
+                "suggest_command" => ClickEvent::SuggestCommand { command: value },
+                "change_page" => ClickEvent::ChangePage {
+                    page: value.parse().ok()?,
+                },
+                "copy_to_clipboard" => ClickEvent::CopyToClipboard { value },
+                _ => return None,
+            };
+            style_action(move |style| style.click_event = Some(event))
+        }
+        "hover" => {
+            if arg(0)? != "show_text" {
+                return None;
+            }
+            let value = Box::new(TextComponent::from_minimessage(&arg(1)?));
+            style_action(move |style| style.hover_event = Some(HoverEvent::ShowText { value }))
+        }
+        "insert" => {
+            let insertion = arg(0)?;
+            style_action(move |style| style.insertion = Some(insertion))
+        }
+        "font" => {
+            let font = arg(0)?;
+            style_action(move |style| style.font = Some(font))
+        }
+        "reset" => Some(TagAction::Reset),
+        "newline" | "br" => Some(TagAction::Insert(TextComponent::text("\n"))),
+        "key" => Some(TagAction::Insert(TextComponent::keybind(arg(0)?))),
+        "lang" | "tr" | "translate" => {
+            let mut component = TextComponent::translatable(arg(0)?);
+            for arg in &args[1..] {
+                component = component.with_arg(TextComponent::from_minimessage(arg));
+            }
+            Some(TagAction::Insert(component))
+        }
+        "selector" | "sel" => Some(TagAction::Insert(TextComponent::selector(arg(0)?))),
+        "score" => Some(TagAction::Insert(TextComponent::score(arg(0)?, arg(1)?))),
+        name => decoration(name, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::text::NamedColor;
+
+    #[test]
+    fn test_colors_and_decorations() {
+        assert_eq!(
+            TextComponent::from_minimessage("<red>Hello</red> <b>world"),
+            TextComponent::text("")
+                .append(TextComponent::text("Hello").color(NamedColor::Red))
+                .append(" ")
+                .append(TextComponent::text("world").bold(true))
+        );
+        assert_eq!(
+            TextComponent::from_minimessage("<color:#12AB34><!italic>x"),
+            TextComponent::text("")
+                .color(TextColor::Rgb(0x12AB34))
+                .append(TextComponent::text("x").italic(false))
+        );
+    }
+
+    #[test]
+    fn test_nesting_and_closing() {
+        // Closing the outer tag closes the inner one too.
+        assert_eq!(
+            TextComponent::from_minimessage("<gold>a<bold>b</gold>c"),
+            TextComponent::text("")
+                .append(
+                    TextComponent::text("")
+                        .color(NamedColor::Gold)
+                        .append("a")
+                        .append(TextComponent::text("b").bold(true))
+                )
+                .append("c")
+        );
+        assert_eq!(
+            TextComponent::from_minimessage("<u>a<reset>b").to_legacy(),
+            "§na§rb"
+        );
+    }
+
+    #[test]
+    fn test_events_and_arguments() {
+        assert_eq!(
+            TextComponent::from_minimessage(
+                "<click:run_command:'/say hi'><hover:show_text:\"<green>Run it\">go"
+            ),
+            TextComponent::text("")
+                .click_event(ClickEvent::RunCommand {
+                    command: "/say hi".to_string()
+                })
+                .append(TextComponent::text("go").hover_event(HoverEvent::ShowText {
+                    value: Box::new(TextComponent::text("Run it").color(NamedColor::Green)),
+                }))
+        );
+    }
+
+    #[test]
+    fn test_insertion_tags() {
+        assert_eq!(
+            TextComponent::from_minimessage(
+                "Press <key:key.jump><br><lang:chat.type.text:Steve:'<i>hi'>"
+            ),
+            TextComponent::text("")
+                .append("Press ")
+                .append(TextComponent::keybind("key.jump"))
+                .append("\n")
+                .append(
+                    TextComponent::translatable("chat.type.text")
+                        .with_arg("Steve")
+                        .with_arg(TextComponent::text("hi").italic(true))
+                )
+        );
+    }
+
+    #[test]
+    fn test_unknown_tags_are_text() {
+        for text in [
+            "a < b > c",
+            "<unknown>x</unknown>",
+            "</red>",
+            "<red",
+            "\\<red>",
+        ] {
+            let expected = text.replace("\\<", "<");
+            assert_eq!(
+                TextComponent::from_minimessage(text).to_plain_text(),
+                expected
+            );
+        }
+        assert_eq!(TextComponent::from_minimessage("plain"), "plain".into());
+    }
+}
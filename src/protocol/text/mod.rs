@@ -0,0 +1,1092 @@
+//! Text components: chat messages, titles, disconnect reasons and every other piece of formatted
+//! text the server sends.
+//!
+//! [`TextComponent`] serializes to the JSON format with serde and, as its [`Encode`]/[`Decode`]
+//! implementation, to the network NBT that packets have carried since 1.20.3. Both use the field
+//! names of [`ProtocolVersion::LATEST`] (`click_event`, `hover_event`, ...); for 1.21.4 clients,
+//! [`Encode::encode_for`] and [`TextComponent::to_json_for`] write the events the way that version
+//! expects them (`clickEvent` with a `value`, `hoverEvent` with `contents`). A component with only plain text and no style or
+//! children is written in the compact form, as a bare string.
+//!
+//! Components can also be written as legacy `§` formatting codes ([`TextComponent::from_legacy`])
+//! or MiniMessage-style tags ([`TextComponent::from_minimessage`]), and rendered back to plain or
+//! legacy text for logs and pre-1.7 clients.
+
+use crate::protocol::encoding::{Decode, DecodeError, DecodeResult, Encode, EncodeResult};
+use crate::protocol::nbt::{self, Compound, Tag};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Number, Value};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+mod legacy;
+mod minimessage;
+
+pub use legacy::SECTION_SIGN;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TextComponent {
+    pub content: Content,
+    /// Boxed, as most components are unstyled and it is several times the size of the rest.
+    pub style: Box<Style>,
+    /// Children, which inherit this component's style.
+    pub extra: Vec<TextComponent>,
+}
+
+/// What a component displays, before its children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Text(String),
+    /// A translation key resolved by the client, with `%s` arguments.
+    Translatable {
+        key: String,
+        /// Shown when the client has no translation for `key`.
+        fallback: Option<String>,
+        with: Vec<TextComponent>,
+    },
+    /// A scoreboard value. `name` is a player name or `*` for the viewer.
+    Score {
+        name: String,
+        objective: String,
+    },
+    /// The names of the entities an entity selector matches.
+    Selector {
+        selector: String,
+        separator: Option<Box<TextComponent>>,
+    },
+    /// The key currently bound to a control, such as `key.jump`.
+    Keybind(String),
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::Text(String::new())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Style {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<TextColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    /// ARGB color of the text shadow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_color: Option<i32>,
+    /// Inserted into the chat box when the text is shift-clicked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    pub fn is_empty(&self) -> bool {
+        *self == Style::default()
+    }
+}
+
+/// The sixteen chat colors, in the order of their legacy `§` codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl NamedColor {
+    pub const ALL: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::DarkBlue,
+        NamedColor::DarkGreen,
+        NamedColor::DarkAqua,
+        NamedColor::DarkRed,
+        NamedColor::DarkPurple,
+        NamedColor::Gold,
+        NamedColor::Gray,
+        NamedColor::DarkGray,
+        NamedColor::Blue,
+        NamedColor::Green,
+        NamedColor::Aqua,
+        NamedColor::Red,
+        NamedColor::LightPurple,
+        NamedColor::Yellow,
+        NamedColor::White,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NamedColor::Black => "black",
+            NamedColor::DarkBlue => "dark_blue",
+            NamedColor::DarkGreen => "dark_green",
+            NamedColor::DarkAqua => "dark_aqua",
+            NamedColor::DarkRed => "dark_red",
+            NamedColor::DarkPurple => "dark_purple",
+            NamedColor::Gold => "gold",
+            NamedColor::Gray => "gray",
+            NamedColor::DarkGray => "dark_gray",
+            NamedColor::Blue => "blue",
+            NamedColor::Green => "green",
+            NamedColor::Aqua => "aqua",
+            NamedColor::Red => "red",
+            NamedColor::LightPurple => "light_purple",
+            NamedColor::Yellow => "yellow",
+            NamedColor::White => "white",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.name() == name)
+    }
+
+    /// The color as `0xRRGGBB`.
+    pub fn rgb(self) -> u32 {
+        match self {
+            NamedColor::Black => 0x000000,
+            NamedColor::DarkBlue => 0x0000AA,
+            NamedColor::DarkGreen => 0x00AA00,
+            NamedColor::DarkAqua => 0x00AAAA,
+            NamedColor::DarkRed => 0xAA0000,
+            NamedColor::DarkPurple => 0xAA00AA,
+            NamedColor::Gold => 0xFFAA00,
+            NamedColor::Gray => 0xAAAAAA,
+            NamedColor::DarkGray => 0x555555,
+            NamedColor::Blue => 0x5555FF,
+            NamedColor::Green => 0x55FF55,
+            NamedColor::Aqua => 0x55FFFF,
+            NamedColor::Red => 0xFF5555,
+            NamedColor::LightPurple => 0xFF55FF,
+            NamedColor::Yellow => 0xFFFF55,
+            NamedColor::White => 0xFFFFFF,
+        }
+    }
+}
+
+/// A named color, or any RGB color written as `#RRGGBB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextColor {
+    Named(NamedColor),
+    Rgb(u32),
+}
+
+impl From<NamedColor> for TextColor {
+    fn from(color: NamedColor) -> Self {
+        TextColor::Named(color)
+    }
+}
+
+impl fmt::Display for TextColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextColor::Named(color) => f.write_str(color.name()),
+            TextColor::Rgb(rgb) => write!(f, "#{:06X}", rgb),
+        }
+    }
+}
+
+impl FromStr for TextColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            return match u32::from_str_radix(hex, 16) {
+                Ok(rgb) if hex.len() == 6 => Ok(TextColor::Rgb(rgb)),
+                _ => Err(format!("invalid hex color {:?}", s)),
+            };
+        }
+        NamedColor::from_name(s)
+            .map(TextColor::Named)
+            .ok_or_else(|| format!("unknown color {:?}", s))
+    }
+}
+
+impl Serialize for TextColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClickEvent {
+    OpenUrl { url: String },
+    OpenFile { path: String },
+    RunCommand { command: String },
+    SuggestCommand { command: String },
+    ChangePage { page: i32 },
+    CopyToClipboard { value: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText {
+        value: Box<TextComponent>,
+    },
+    ShowItem {
+        id: String,
+        #[serde(default = "one")]
+        count: i32,
+    },
+    ShowEntity {
+        /// Entity type, such as `minecraft:pig`.
+        id: String,
+        #[serde(deserialize_with = "deserialize_uuid")]
+        uuid: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<Box<TextComponent>>,
+    },
+}
+
+fn one() -> i32 {
+    1
+}
+
+/// Accepts a UUID as a string or, as NBT writes it, as four ints.
+fn deserialize_uuid<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum UuidRepr {
+        String(Uuid),
+        Ints([i32; 4]),
+    }
+
+    Ok(match UuidRepr::deserialize(deserializer)? {
+        UuidRepr::String(uuid) => uuid,
+        UuidRepr::Ints(ints) => {
+            let high = (ints[0] as u32 as u64) << 32 | ints[1] as u32 as u64;
+            let low = (ints[2] as u32 as u64) << 32 | ints[3] as u32 as u64;
+            Uuid::from_u64_pair(high, low)
+        }
+    })
+}
+
+impl TextComponent {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::from_content(Content::Text(text.into()))
+    }
+
+    pub fn translatable(key: impl Into<String>) -> Self {
+        Self::from_content(Content::Translatable {
+            key: key.into(),
+            fallback: None,
+            with: vec![],
+        })
+    }
+
+    pub fn score(name: impl Into<String>, objective: impl Into<String>) -> Self {
+        Self::from_content(Content::Score {
+            name: name.into(),
+            objective: objective.into(),
+        })
+    }
+
+    pub fn selector(selector: impl Into<String>) -> Self {
+        Self::from_content(Content::Selector {
+            selector: selector.into(),
+            separator: None,
+        })
+    }
+
+    pub fn keybind(key: impl Into<String>) -> Self {
+        Self::from_content(Content::Keybind(key.into()))
+    }
+
+    pub fn from_content(content: Content) -> Self {
+        TextComponent {
+            content,
+            style: Box::default(),
+            extra: vec![],
+        }
+    }
+
+    /// Adds an argument to a translatable component. Other components are returned unchanged.
+    pub fn with_arg(mut self, arg: impl Into<TextComponent>) -> Self {
+        if let Content::Translatable { with, .. } = &mut self.content {
+            with.push(arg.into());
+        }
+        self
+    }
+
+    /// Sets the fallback of a translatable component.
+    pub fn fallback(mut self, text: impl Into<String>) -> Self {
+        if let Content::Translatable { fallback, .. } = &mut self.content {
+            *fallback = Some(text.into());
+        }
+        self
+    }
+
+    /// Sets the separator of a selector component.
+    pub fn separator(mut self, component: impl Into<TextComponent>) -> Self {
+        if let Content::Selector { separator, .. } = &mut self.content {
+            *separator = Some(Box::new(component.into()));
+        }
+        self
+    }
+
+    pub fn color(mut self, color: impl Into<TextColor>) -> Self {
+        self.style.color = Some(color.into());
+        self
+    }
+
+    pub fn font(mut self, font: impl Into<String>) -> Self {
+        self.style.font = Some(font.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn shadow_color(mut self, argb: i32) -> Self {
+        self.style.shadow_color = Some(argb);
+        self
+    }
+
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+
+    pub fn click_event(mut self, event: ClickEvent) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    pub fn hover_event(mut self, event: HoverEvent) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    /// Adds a child component.
+    pub fn append(mut self, child: impl Into<TextComponent>) -> Self {
+        self.extra.push(child.into());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("text component serializes to JSON")
+    }
+
+    /// The JSON `version` expects, with the events in the 1.21.4 layout for that version.
+    pub fn to_json_for(&self, version: ProtocolVersion) -> String {
+        match version >= ProtocolVersion::V1_21_5 {
+            true => self.to_json(),
+            false => self.to_json_value_for(version).to_string(),
+        }
+    }
+
+    /// [`to_json_for`](Self::to_json_for) as a JSON value, for embedding in a larger document.
+    pub fn to_json_value_for(&self, version: ProtocolVersion) -> Value {
+        if version >= ProtocolVersion::V1_21_5 {
+            return serde_json::to_value(self).expect("text component serializes to JSON");
+        }
+        let mut tag = nbt::to_tag(self).expect("text component serializes to NBT");
+        events_to_v1_21_4(&mut tag);
+        json_from_tag(tag)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// The text a client would show, without formatting. Translation keys are not resolved, so
+    /// translatable components show their fallback (or key) with the arguments filled in, and
+    /// score components, whose value only the client knows, show nothing.
+    pub fn to_plain_text(&self) -> String {
+        let mut text = self.content_text();
+        for child in &self.extra {
+            text.push_str(&child.to_plain_text());
+        }
+        text
+    }
+
+    /// The plain text of this component's own content, without its children.
+    fn content_text(&self) -> String {
+        match &self.content {
+            Content::Text(text) => text.clone(),
+            Content::Translatable {
+                key,
+                fallback,
+                with,
+            } => {
+                let args: Vec<String> = with.iter().map(TextComponent::to_plain_text).collect();
+                format_translation(fallback.as_deref().unwrap_or(key), &args)
+            }
+            Content::Score { .. } => String::new(),
+            Content::Selector { selector, .. } => selector.clone(),
+            Content::Keybind(key) => key.clone(),
+        }
+    }
+
+    fn is_plain_text(&self) -> bool {
+        matches!(self.content, Content::Text(_)) && self.style.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Fills in a Java format string the way the client does for translations: `%s` takes the next
+/// argument, `%2$s` a specific one, and `%%` is a literal `%`.
+fn format_translation(format: &str, args: &[String]) -> String {
+    let mut formatted = String::with_capacity(format.len());
+    let mut next_arg = 0;
+    let mut rest = format;
+    while let Some(percent) = rest.find('%') {
+        formatted.push_str(&rest[..percent]);
+        rest = &rest[percent + 1..];
+        if let Some(after) = rest.strip_prefix('%') {
+            formatted.push('%');
+            rest = after;
+            continue;
+        }
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        let (index, after) = match rest[digits..].strip_prefix("$s") {
+            Some(after) if digits > 0 => (
+                rest[..digits]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1)),
+                after,
+            ),
+            _ => match rest.strip_prefix('s') {
+                Some(after) => {
+                    next_arg += 1;
+                    (Some(next_arg - 1), after)
+                }
+                None => {
+                    formatted.push('%');
+                    continue;
+                }
+            },
+        };
+        formatted.push_str(index.and_then(|i| args.get(i)).map_or("", String::as_str));
+        rest = after;
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+/// The object form of a component, as it appears in JSON and NBT.
+#[derive(Default, Serialize, Deserialize)]
+struct RawComponent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    translate: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fallback: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    with: Vec<TextComponent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<RawScore>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    separator: Option<Box<TextComponent>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keybind: Option<String>,
+    #[serde(flatten)]
+    style: Style,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra: Vec<TextComponent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawScore {
+    name: String,
+    objective: String,
+}
+
+impl Serialize for TextComponent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let (true, Content::Text(text)) = (self.is_plain_text(), &self.content) {
+            return serializer.serialize_str(text);
+        }
+        let mut raw = RawComponent {
+            style: (*self.style).clone(),
+            extra: self.extra.clone(),
+            ..RawComponent::default()
+        };
+        match &self.content {
+            Content::Text(text) => raw.text = Some(text.clone()),
+            Content::Translatable {
+                key,
+                fallback,
+                with,
+            } => {
+                raw.translate = Some(key.clone());
+                raw.fallback = fallback.clone();
+                raw.with = with.clone();
+            }
+            Content::Score { name, objective } => {
+                raw.score = Some(RawScore {
+                    name: name.clone(),
+                    objective: objective.clone(),
+                })
+            }
+            Content::Selector {
+                selector,
+                separator,
+            } => {
+                raw.selector = Some(selector.clone());
+                raw.separator = separator.clone();
+            }
+            Content::Keybind(key) => raw.keybind = Some(key.clone()),
+        }
+        raw.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextComponent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Besides objects, the JSON format allows plain strings, numbers and booleans, and
+        /// arrays whose first element is the parent of the rest.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ComponentRepr {
+            String(String),
+            Number(Number),
+            Bool(bool),
+            List(Vec<TextComponent>),
+            Object(Box<RawComponent>),
+        }
+
+        match ComponentRepr::deserialize(deserializer)? {
+            ComponentRepr::String(text) => Ok(TextComponent::text(text)),
+            ComponentRepr::Number(number) => Ok(TextComponent::text(number.to_string())),
+            ComponentRepr::Bool(value) => Ok(TextComponent::text(value.to_string())),
+            ComponentRepr::List(components) => {
+                let mut components = components.into_iter();
+                let mut parent = components
+                    .next()
+                    .ok_or_else(|| de::Error::custom("empty text component list"))?;
+                parent.extra.extend(components);
+                Ok(parent)
+            }
+            ComponentRepr::Object(raw) => {
+                let raw = *raw;
+                let content = if let Some(text) = raw.text {
+                    Content::Text(text)
+                } else if let Some(key) = raw.translate {
+                    Content::Translatable {
+                        key,
+                        fallback: raw.fallback,
+                        with: raw.with,
+                    }
+                } else if let Some(score) = raw.score {
+                    Content::Score {
+                        name: score.name,
+                        objective: score.objective,
+                    }
+                } else if let Some(selector) = raw.selector {
+                    Content::Selector {
+                        selector,
+                        separator: raw.separator,
+                    }
+                } else if let Some(key) = raw.keybind {
+                    Content::Keybind(key)
+                } else {
+                    return Err(de::Error::custom("text component has no content"));
+                };
+                Ok(TextComponent {
+                    content,
+                    style: Box::new(raw.style),
+                    extra: raw.extra,
+                })
+            }
+        }
+    }
+}
+
+/// Written as a nameless network NBT tag: a string for plain text, a compound otherwise.
+impl Encode for TextComponent {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        let mut tag = nbt::to_tag(self)?;
+        if version < ProtocolVersion::V1_21_5 {
+            events_to_v1_21_4(&mut tag);
+        }
+        Ok(nbt::write_network(buf, &tag)?)
+    }
+}
+
+impl Decode for TextComponent {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let mut tag = nbt::read_network_with_limit(buf, MAX_NBT_DEPTH)?;
+        if version < ProtocolVersion::V1_21_5 {
+            events_from_v1_21_4(&mut tag);
+        }
+        serde_json::from_value(json_from_tag(tag))
+            .map_err(|e| DecodeError::InvalidNbt(format!("bad text component: {}", e)))
+    }
+}
+
+/// Rewrites the events of a component tag, and of every component inside it, into the layout
+/// used before 1.21.5: `clickEvent` carries its argument as a string `value`, and `hoverEvent`
+/// carries its text, item or entity under `contents`, where an entity's type is `type` and its
+/// UUID `id`.
+fn events_to_v1_21_4(tag: &mut Tag) {
+    let entries = match tag {
+        Tag::List(items) => return items.iter_mut().for_each(events_to_v1_21_4),
+        Tag::Compound(entries) => entries,
+        _ => return,
+    };
+    if let Some(Tag::Compound(mut event)) = entries.remove("click_event") {
+        let value = ["url", "path", "command", "page", "value"]
+            .into_iter()
+            .find_map(|key| event.remove(key));
+        let value = match value {
+            Some(Tag::Int(page)) => Tag::String(page.to_string()),
+            value => value.unwrap_or_else(|| Tag::String(String::new())),
+        };
+        event.insert("value".to_string(), value);
+        entries.insert("clickEvent".to_string(), Tag::Compound(event));
+    }
+    if let Some(Tag::Compound(mut event)) = entries.remove("hover_event") {
+        let action = event.remove("action").unwrap_or_default();
+        let contents = match action.as_str() {
+            Some("show_text") => event.remove("value").unwrap_or_default(),
+            Some("show_entity") => {
+                rename(&mut event, "id", "type");
+                rename(&mut event, "uuid", "id");
+                Tag::Compound(event)
+            }
+            _ => Tag::Compound(event),
+        };
+        let event = Compound::from([
+            ("action".to_string(), action),
+            ("contents".to_string(), contents),
+        ]);
+        entries.insert("hoverEvent".to_string(), Tag::Compound(event));
+    }
+    entries.values_mut().for_each(events_to_v1_21_4);
+}
+
+/// The inverse of [`events_to_v1_21_4`], for components sent by 1.21.4 clients.
+fn events_from_v1_21_4(tag: &mut Tag) {
+    let entries = match tag {
+        Tag::List(items) => return items.iter_mut().for_each(events_from_v1_21_4),
+        Tag::Compound(entries) => entries,
+        _ => return,
+    };
+    if let Some(Tag::Compound(mut event)) = entries.remove("clickEvent") {
+        if let Some(value) = event.remove("value") {
+            let (key, value) = match event.get("action").and_then(Tag::as_str) {
+                Some("open_url") => ("url", value),
+                Some("open_file") => ("path", value),
+                Some("run_command" | "suggest_command") => ("command", value),
+                Some("change_page") => {
+                    let page = value.as_str().and_then(|page| page.parse().ok());
+                    ("page", page.map_or(value, Tag::Int))
+                }
+                _ => ("value", value),
+            };
+            event.insert(key.to_string(), value);
+        }
+        entries.insert("click_event".to_string(), Tag::Compound(event));
+    }
+    if let Some(Tag::Compound(mut event)) = entries.remove("hoverEvent") {
+        let action = event.remove("action").unwrap_or_default();
+        let mut hover = Compound::new();
+        match (action.as_str(), event.remove("contents")) {
+            (Some("show_text"), Some(contents)) => {
+                hover.insert("value".to_string(), contents);
+            }
+            (Some("show_entity"), Some(Tag::Compound(mut contents))) => {
+                rename(&mut contents, "id", "uuid");
+                rename(&mut contents, "type", "id");
+                hover.extend(contents);
+            }
+            (Some("show_item"), Some(Tag::Compound(contents))) => hover.extend(contents),
+            (Some("show_item"), Some(id @ Tag::String(_))) => {
+                hover.insert("id".to_string(), id);
+            }
+            _ => {}
+        }
+        hover.insert("action".to_string(), action);
+        entries.insert("hover_event".to_string(), Tag::Compound(hover));
+    }
+    entries.values_mut().for_each(events_from_v1_21_4);
+}
+
+fn rename(entries: &mut Compound, from: &str, to: &str) {
+    if let Some(value) = entries.remove(from) {
+        entries.insert(to.to_string(), value);
+    }
+}
+
+/// Nesting allowed before a component is rejected. Deserializing a component recurses once per
+/// level, so this stays at serde_json's limit for JSON rather than vanilla's 512, which could
+/// overflow a connection task's stack.
+const MAX_NBT_DEPTH: usize = 128;
+
+/// The JSON form of a decoded tag, which the component's `Deserialize` understands. Text
+/// components only use bytes for booleans.
+fn json_from_tag(tag: Tag) -> Value {
+    match tag {
+        Tag::End => Value::Null,
+        Tag::Byte(value) => Value::Bool(value != 0),
+        Tag::Short(value) => value.into(),
+        Tag::Int(value) => value.into(),
+        Tag::Long(value) => value.into(),
+        Tag::Float(value) => value.into(),
+        Tag::Double(value) => value.into(),
+        Tag::ByteArray(values) => values.into_iter().map(Value::from).collect(),
+        Tag::String(value) => value.into(),
+        Tag::List(items) => items.into_iter().map(json_from_tag).collect(),
+        Tag::Compound(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(name, entry)| (name, json_from_tag(entry)))
+                .collect(),
+        ),
+        Tag::IntArray(values) => values.into_iter().map(Value::from).collect(),
+        Tag::LongArray(values) => values.into_iter().map(Value::from).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BufMut;
+
+    fn sample() -> TextComponent {
+        TextComponent::text("Hello ")
+            .color(NamedColor::Gold)
+            .bold(true)
+            .append(
+                TextComponent::translatable("chat.type.text")
+                    .with_arg("Player")
+                    .with_arg(TextComponent::keybind("key.jump").italic(true))
+                    .fallback("<%s> %s"),
+            )
+            .append(
+                TextComponent::score("*", "kills")
+                    .color(TextColor::Rgb(0x12AB34))
+                    .click_event(ClickEvent::ChangePage { page: 2 }),
+            )
+            .append(
+                TextComponent::selector("@a")
+                    .separator(TextComponent::text(", ").color(NamedColor::Gray))
+                    .hover_event(HoverEvent::ShowEntity {
+                        id: "minecraft:pig".to_string(),
+                        uuid: Uuid::from_u128(0x0123_4567_89AB_CDEF_0123_4567_89AB_CDEF),
+                        name: Some(Box::new("Pig".into())),
+                    }),
+            )
+            .append(
+                TextComponent::text("link")
+                    .underlined(true)
+                    .shadow_color(-1)
+                    .insertion("inserted")
+                    .click_event(ClickEvent::OpenUrl {
+                        url: "https://example.com".to_string(),
+                    })
+                    .hover_event(HoverEvent::ShowText {
+                        value: Box::new("Open it".into()),
+                    }),
+            )
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let component = sample();
+        assert_eq!(
+            TextComponent::from_json(&component.to_json()).unwrap(),
+            component
+        );
+    }
+
+    #[test]
+    fn test_json_format() {
+        let json: Value = serde_json::from_str(
+            &TextComponent::text("Hi")
+                .color(NamedColor::Red)
+                .click_event(ClickEvent::RunCommand {
+                    command: "/help".to_string(),
+                })
+                .append("!")
+                .to_json(),
+        )
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "text": "Hi",
+                "color": "red",
+                "click_event": { "action": "run_command", "command": "/help" },
+                "extra": ["!"]
+            })
+        );
+        assert_eq!(TextComponent::text("plain").to_json(), r#""plain""#);
+    }
+
+    #[test]
+    fn test_parses_every_json_form() {
+        assert_eq!(TextComponent::from_json(r#""a""#).unwrap(), "a".into());
+        assert_eq!(TextComponent::from_json("42").unwrap(), "42".into());
+        assert_eq!(
+            TextComponent::from_json(r##"["a", {"text": "b", "color": "#FF0000"}]"##).unwrap(),
+            TextComponent::text("a")
+                .append(TextComponent::text("b").color(TextColor::Rgb(0xFF0000)))
+        );
+        assert_eq!(
+            TextComponent::from_json(
+                r#"{"translate": "multiplayer.player.joined", "with": ["Steve"]}"#
+            )
+            .unwrap(),
+            TextComponent::translatable("multiplayer.player.joined").with_arg("Steve")
+        );
+        assert!(TextComponent::from_json(r#"{"color": "red"}"#).is_err());
+        assert!(TextComponent::from_json(r#"{"text": "a", "color": "pink"}"#).is_err());
+    }
+
+    #[test]
+    fn test_to_plain_text() {
+        assert_eq!(sample().to_plain_text(), "Hello <Player> key.jump@alink");
+        assert_eq!(
+            TextComponent::translatable("commands.give.success")
+                .fallback("Gave %2$s × %1$s, 100%% %s %s %q")
+                .with_arg("stone")
+                .with_arg("3")
+                .to_plain_text(),
+            "Gave 3 × stone, 100% stone 3 %q"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_encodes_as_nbt_string() {
+        let mut buf = BytesMut::new();
+        TextComponent::text("Hi").encode(&mut buf).unwrap();
+        assert_eq!(&buf[..], &[8, 0, 2, b'H', b'i']);
+    }
+
+    #[test]
+    fn test_styled_text_encodes_as_nbt_compound() {
+        let mut buf = BytesMut::new();
+        TextComponent::text("Hi")
+            .bold(true)
+            .encode(&mut buf)
+            .unwrap();
+        // Entries come out in key order.
+        let mut expected = vec![10];
+        expected.extend([1, 0, 4]);
+        expected.extend(b"bold");
+        expected.extend([1]);
+        expected.extend([8, 0, 4]);
+        expected.extend(b"text");
+        expected.extend([0, 2, b'H', b'i']);
+        expected.extend([0]);
+        assert_eq!(&buf[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_nbt_round_trip() {
+        let component = sample();
+        let mut buf = BytesMut::new();
+        component.encode(&mut buf).unwrap();
+        assert_eq!(TextComponent::decode(&mut buf).unwrap(), component);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_v1_21_4_event_layout() {
+        let component = TextComponent::text("Hi")
+            .click_event(ClickEvent::ChangePage { page: 2 })
+            .hover_event(HoverEvent::ShowText {
+                value: Box::new("Tip".into()),
+            });
+        let mut buf = BytesMut::new();
+        component
+            .encode_for(ProtocolVersion::V1_21_4, &mut buf)
+            .unwrap();
+        assert_eq!(
+            nbt::read_network(&mut buf.clone()).unwrap(),
+            Tag::from_snbt(
+                r#"{
+                    text: "Hi",
+                    clickEvent: {action: "change_page", value: "2"},
+                    hoverEvent: {action: "show_text", contents: "Tip"},
+                }"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            TextComponent::decode_for(ProtocolVersion::V1_21_4, &mut buf).unwrap(),
+            component
+        );
+    }
+
+    #[test]
+    fn test_v1_21_4_json_event_layout() {
+        let component = TextComponent::text("Hi")
+            .click_event(ClickEvent::RunCommand {
+                command: "/help".into(),
+            })
+            .hover_event(HoverEvent::ShowText {
+                value: Box::new("Tip".into()),
+            });
+        let json: Value =
+            serde_json::from_str(&component.to_json_for(ProtocolVersion::V1_21_4)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "text": "Hi",
+                "clickEvent": { "action": "run_command", "value": "/help" },
+                "hoverEvent": { "action": "show_text", "contents": "Tip" },
+            })
+        );
+        assert_eq!(
+            component.to_json_for(ProtocolVersion::V1_21_5),
+            component.to_json()
+        );
+    }
+
+    #[test]
+    fn test_v1_21_4_round_trip() {
+        let component = sample();
+        let mut buf = BytesMut::new();
+        component
+            .encode_for(ProtocolVersion::V1_21_4, &mut buf)
+            .unwrap();
+        let latest = component.to_bytes().unwrap();
+        assert_ne!(buf, latest);
+        assert_eq!(
+            TextComponent::decode_for(ProtocolVersion::V1_21_4, &mut buf).unwrap(),
+            component
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_nbt_strings_use_modified_utf8() {
+        let component = TextComponent::text("a\0é😀");
+        let mut buf = BytesMut::new();
+        component.encode(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            &[
+                8, 0, 11, b'a', 0xC0, 0x80, 0xC3, 0xA9, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80
+            ]
+        );
+        assert_eq!(TextComponent::decode(&mut buf).unwrap(), component);
+    }
+
+    #[test]
+    fn test_decodes_mixed_lists_and_int_array_uuids() {
+        // The list is written with "a" wrapped as {"": "a"}, as it mixes a string and a compound.
+        let tag = Tag::from_snbt(
+            r#"{
+                text: "",
+                extra: ["a", {text: "b"}],
+                hover_event: {action: show_entity, id: "minecraft:cow", uuid: [I; 1, 2, 3, -4]},
+            }"#,
+        )
+        .unwrap();
+        let mut buf = BytesMut::new();
+        tag.encode(&mut buf).unwrap();
+
+        let component = TextComponent::decode(&mut buf).unwrap();
+        assert_eq!(component.extra, vec!["a".into(), "b".into()]);
+        assert_eq!(
+            component.style.hover_event,
+            Some(HoverEvent::ShowEntity {
+                id: "minecraft:cow".to_string(),
+                uuid: Uuid::from_u64_pair(0x0000_0001_0000_0002, 0x0000_0003_FFFF_FFFC),
+                name: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_deep_nesting() {
+        let mut buf = BytesMut::new();
+        buf.put_u8(9);
+        for _ in 0..200 {
+            buf.put_u8(9);
+            buf.put_i32(1);
+        }
+        buf.put_u8(0);
+        buf.put_i32(0);
+        assert!(matches!(
+            TextComponent::decode(&mut buf),
+            Err(DecodeError::InvalidNbt(reason)) if reason.contains("nested")
+        ));
+    }
+}
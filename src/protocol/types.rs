@@ -1,13 +1,24 @@
+use crate::protocol::encoding::{
+    Decode, DecodeError, DecodeResult, Encode, EncodeResult, read_varint_sync, write_varint_sync,
+};
+use crate::protocol::version::ProtocolVersion;
+use bytes::BytesMut;
 use uuid::Uuid;
 
+pub use crate::protocol::item::DataComponent;
+pub use crate::protocol::text::TextComponent;
+
 pub type Identifier = String;
+/// A text component sent as a JSON string rather than NBT, as login `Disconnect` still is. Build
+/// one with [`TextComponent::to_json_for`].
 pub type JsonTextComponent = String;
 
-pub type Nbt = Vec<u8>;
-pub type Slot = Vec<u8>;
-pub type EntityMetadata = Vec<u8>;
-pub type ChunkData = Vec<u8>;
-pub type LightData = Vec<u8>;
+pub type Nbt = crate::protocol::nbt::Tag;
+pub use crate::protocol::chunk::{ChunkData, LightData};
+pub use crate::protocol::command::CommandNode;
+pub use crate::protocol::item::{HashedSlot, ItemStack, UntrustedSlot};
+pub use crate::protocol::metadata::EntityMetadata;
+pub type Slot = ItemStack;
 pub type RecipeDisplay = Vec<u8>;
 pub type SlotDisplay = Vec<u8>;
 pub type IdSet = Vec<u8>;
@@ -61,7 +72,7 @@ impl TryFrom<VarInt> for NextState {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct Property {
     pub name: String,
     pub value: String,
@@ -188,41 +199,41 @@ impl TryFrom<VarInt> for BuiltInServerLinkLabel {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ServerLinkLabel {
     BuiltIn(BuiltInServerLinkLabel),
-    Custom(JsonTextComponent),
+    Custom(TextComponent),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct KnownPack {
     pub namespace: String,
     pub id: String,
     pub version: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct RegistryEntry {
     pub entry_id: Identifier,
-    pub data: Option<Vec<u8>>,
+    pub data: Option<Nbt>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct Tag {
     pub tag_name: Identifier,
     pub entries: Vec<VarInt>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct RegistryTagData {
     pub registry_id: Identifier,
     pub tags: Vec<Tag>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct CustomReportDetail {
     pub title: String,
     pub description: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct ServerLink {
     pub label: ServerLinkLabel,
     pub url: String,
@@ -439,7 +450,7 @@ pub enum ObjectiveType {
 pub enum NumberFormat {
     Blank,
     Styled(Nbt),
-    Fixed(JsonTextComponent),
+    Fixed(TextComponent),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -451,20 +462,58 @@ pub enum TeamMethod {
     RemoveEntities = 4,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NameTagVisibility {
-    Always,
-    HideForOtherTeams,
-    HideForOwnTeam,
-    Never,
+    Always = 0,
+    Never = 1,
+    HideForOtherTeams = 2,
+    HideForOwnTeam = 3,
+}
+
+impl NameTagVisibility {
+    const ALL: [Self; 4] = [
+        NameTagVisibility::Always,
+        NameTagVisibility::Never,
+        NameTagVisibility::HideForOtherTeams,
+        NameTagVisibility::HideForOwnTeam,
+    ];
+
+    /// The name 1.21.4 sends in place of the ID.
+    pub fn name(self) -> &'static str {
+        match self {
+            NameTagVisibility::Always => "always",
+            NameTagVisibility::Never => "never",
+            NameTagVisibility::HideForOtherTeams => "hideForOtherTeams",
+            NameTagVisibility::HideForOwnTeam => "hideForOwnTeam",
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CollisionRule {
-    Always,
-    PushOtherTeams,
-    PushOwnTeam,
-    Never,
+    Always = 0,
+    Never = 1,
+    PushOtherTeams = 2,
+    PushOwnTeam = 3,
+}
+
+impl CollisionRule {
+    const ALL: [Self; 4] = [
+        CollisionRule::Always,
+        CollisionRule::Never,
+        CollisionRule::PushOtherTeams,
+        CollisionRule::PushOwnTeam,
+    ];
+
+    /// The name 1.21.4 sends in place of the ID.
+    pub fn name(self) -> &'static str {
+        match self {
+            CollisionRule::Always => "always",
+            CollisionRule::Never => "never",
+            CollisionRule::PushOtherTeams => "pushOtherTeams",
+            CollisionRule::PushOwnTeam => "pushOwnTeam",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -547,31 +596,26 @@ pub enum AttributeOperation {
     MultiplyPercent = 2,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct Statistic {
     pub category_id: VarInt,
     pub statistic_id: VarInt,
     pub value: VarInt,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct CommandNode {
-    pub data: Vec<u8>,
-}
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct SuggestionMatch {
     pub match_text: String,
-    pub tooltip: Option<JsonTextComponent>,
+    pub tooltip: Option<TextComponent>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct MapIcon {
     pub icon_type: VarInt,
     pub x: i8,
     pub z: i8,
     pub direction: u8,
-    pub display_name: Option<JsonTextComponent>,
+    pub display_name: Option<TextComponent>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -583,14 +627,15 @@ pub struct MapColorPatch {
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct TradeItem {
     pub item_id: VarInt,
     pub item_count: VarInt,
-    pub components: Vec<u8>,
+    /// Components the offered stack must have, not a patch: there is no removed list.
+    pub components: Vec<DataComponent>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct MerchantTrade {
     pub input_item1: TradeItem,
     pub output_item: Slot,
@@ -604,14 +649,14 @@ pub struct MerchantTrade {
     pub demand: i32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct PlayerInfoProperty {
     pub name: String,
     pub value: String,
     pub signature: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct PlayerInfoChatSession {
     pub session_id: Uuid,
     pub public_key_expiry_time: i64,
@@ -638,7 +683,7 @@ pub enum PlayerInfoActionData {
         ping: VarInt,
     },
     UpdateDisplayName {
-        display_name: Option<JsonTextComponent>,
+        display_name: Option<TextComponent>,
     },
     UpdateListPriority {
         priority: VarInt,
@@ -656,8 +701,8 @@ pub struct PlayerInfoEntry {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AdvancementDisplay {
-    pub title: JsonTextComponent,
-    pub description: JsonTextComponent,
+    pub title: TextComponent,
+    pub description: TextComponent,
     pub icon: Slot,
     pub frame_type: VarInt,
     pub flags: i32,
@@ -666,7 +711,7 @@ pub struct AdvancementDisplay {
     pub y_coord: f32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct Advancement {
     pub parent_id: Option<Identifier>,
     pub display_data: Option<AdvancementDisplay>,
@@ -674,48 +719,50 @@ pub struct Advancement {
     pub sends_telemetry_data: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct AdvancementProgressCriterion {
     pub criterion_identifier: Identifier,
     pub date_of_achieving: Option<i64>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct AdvancementProgress {
     pub criteria: Vec<AdvancementProgressCriterion>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct AttributeModifierData {
     pub id: Identifier,
     pub amount: f64,
     pub operation: u8,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct AttributeProperty {
     pub id: VarInt,
     pub value: f64,
     pub modifiers: Vec<AttributeModifierData>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct EquipmentEntry {
     pub slot: u8,
     pub item: Slot,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct TeamEntities {
     pub entities: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Block updates are sent as a VarLong packing the block state ID with the section-relative position.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct ChunkSectionBlockUpdate {
+    #[packet(varlong)]
     pub block_state_id_and_pos: i64,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct RecipeBookEntry {
     pub recipe_id: VarInt,
     pub display: RecipeDisplay,
@@ -796,10 +843,283 @@ pub enum IdOr<T> {
     Inline(T),
 }
 
-pub type HashedSlot = Slot;
+/// A Java `BitSet`, sent as its words: bit `i` is bit `i % 64` of word `i / 64`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Encode, Decode)]
+pub struct BitSet {
+    words: Vec<i64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet::default()
+    }
+
+    /// A set with bits `0..len` set.
+    pub fn filled(len: usize) -> Self {
+        let mut set = BitSet::new();
+        (0..len).for_each(|index| set.set(index, true));
+        set
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.words
+            .get(index / 64)
+            .is_some_and(|word| word >> (index % 64) & 1 != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        let word = index / 64;
+        if word >= self.words.len() {
+            if !value {
+                return;
+            }
+            self.words.resize(word + 1, 0);
+        }
+        let bit = 1i64 << (index % 64);
+        if value {
+            self.words[word] |= bit;
+        } else {
+            self.words[word] &= !bit;
+        }
+        // Like Java's, the words end at the last one with a bit set.
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
 
-#[derive(Debug, PartialEq, Clone)]
+    /// The set bits, in ascending order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.words.len() * 64).filter(|index| self.get(*index))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Argument signatures are always 256 bytes, so they carry no length prefix.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
 pub struct ArgumentSignature {
     pub argument_name: String,
+    #[packet(fixed = 256)]
     pub signature: Vec<u8>,
 }
+
+macro_rules! impl_varint_enum_codec {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+                    write_varint_sync(buf, VarInt(self.clone() as i32))
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+                    let value = read_varint_sync(buf)?;
+                    $ty::try_from(value)
+                        .map_err(|_| DecodeError::InvalidEnumValue(stringify!($ty), value.0 as i64))
+                }
+            }
+        )*
+    };
+}
+
+impl_varint_enum_codec!(
+    ChatMode,
+    MainHand,
+    ParticleStatus,
+    ResourcePackResult,
+    SoundCategory
+);
+
+/// Team rules are VarInt IDs since 1.21.5 and were their string names before that.
+macro_rules! impl_team_rule_codec {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+                    self.encode_for(ProtocolVersion::LATEST, buf)
+                }
+
+                fn encode_for(
+                    &self,
+                    version: ProtocolVersion,
+                    buf: &mut BytesMut,
+                ) -> EncodeResult<()> {
+                    if version >= ProtocolVersion::V1_21_5 {
+                        write_varint_sync(buf, VarInt(*self as i32))
+                    } else {
+                        self.name().encode(buf)
+                    }
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+                    Self::decode_for(ProtocolVersion::LATEST, buf)
+                }
+
+                fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+                    if version >= ProtocolVersion::V1_21_5 {
+                        let value = read_varint_sync(buf)?;
+                        usize::try_from(value.0)
+                            .ok()
+                            .and_then(|id| $ty::ALL.get(id).copied())
+                            .ok_or(DecodeError::InvalidEnumValue(stringify!($ty), value.0 as i64))
+                    } else {
+                        let name = String::decode(buf)?;
+                        $ty::ALL
+                            .into_iter()
+                            .find(|rule| rule.name() == name)
+                            .ok_or(DecodeError::InvalidEnumName(stringify!($ty), name))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_team_rule_codec!(NameTagVisibility, CollisionRule);
+
+impl Encode for DisplayedSkinParts {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.bits().encode(buf)
+    }
+}
+
+impl Decode for DisplayedSkinParts {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Ok(DisplayedSkinParts::from_bits_retain(u8::decode(buf)?))
+    }
+}
+
+/// Server link labels are a boolean saying whether a built-in label ID or a text component follows.
+impl Encode for ServerLinkLabel {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            ServerLinkLabel::BuiltIn(label) => {
+                true.encode(buf)?;
+                write_varint_sync(buf, VarInt(label.clone() as i32))
+            }
+            ServerLinkLabel::Custom(text) => {
+                false.encode(buf)?;
+                text.encode_for(version, buf)
+            }
+        }
+    }
+}
+
+impl Decode for ServerLinkLabel {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        if bool::decode(buf)? {
+            let value = read_varint_sync(buf)?;
+            let label = BuiltInServerLinkLabel::try_from(value).map_err(|_| {
+                DecodeError::InvalidEnumValue("BuiltInServerLinkLabel", value.0 as i64)
+            })?;
+            Ok(ServerLinkLabel::BuiltIn(label))
+        } else {
+            Ok(ServerLinkLabel::Custom(TextComponent::decode_for(
+                version, buf,
+            )?))
+        }
+    }
+}
+
+impl Encode for NumberFormat {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        match self {
+            NumberFormat::Blank => write_varint_sync(buf, VarInt(0)),
+            NumberFormat::Styled(styling) => {
+                write_varint_sync(buf, VarInt(1))?;
+                styling.encode_for(version, buf)
+            }
+            NumberFormat::Fixed(content) => {
+                write_varint_sync(buf, VarInt(2))?;
+                content.encode_for(version, buf)
+            }
+        }
+    }
+}
+
+impl Decode for NumberFormat {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        match read_varint_sync(buf)? {
+            VarInt(0) => Ok(NumberFormat::Blank),
+            VarInt(1) => Ok(NumberFormat::Styled(Nbt::decode(buf)?)),
+            VarInt(2) => Ok(NumberFormat::Fixed(TextComponent::decode_for(
+                version, buf,
+            )?)),
+            VarInt(other) => Err(DecodeError::InvalidEnumValue("NumberFormat", other as i64)),
+        }
+    }
+}
+
+/// The background texture is only present when the `HAS_BACKGROUND_TEXTURE` flag is set.
+impl Encode for AdvancementDisplay {
+    fn encode(&self, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.encode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn encode_for(&self, version: ProtocolVersion, buf: &mut BytesMut) -> EncodeResult<()> {
+        self.title.encode_for(version, buf)?;
+        self.description.encode_for(version, buf)?;
+        self.icon.encode_for(version, buf)?;
+        self.frame_type.encode(buf)?;
+        let mut flags = self.flags & !AdvancementFlags::HAS_BACKGROUND_TEXTURE.bits();
+        if self.background_texture.is_some() {
+            flags |= AdvancementFlags::HAS_BACKGROUND_TEXTURE.bits();
+        }
+        flags.encode(buf)?;
+        if let Some(texture) = &self.background_texture {
+            texture.encode(buf)?;
+        }
+        self.x_coord.encode(buf)?;
+        self.y_coord.encode(buf)
+    }
+}
+
+impl Decode for AdvancementDisplay {
+    fn decode(buf: &mut BytesMut) -> DecodeResult<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, buf)
+    }
+
+    fn decode_for(version: ProtocolVersion, buf: &mut BytesMut) -> DecodeResult<Self> {
+        let title = TextComponent::decode_for(version, buf)?;
+        let description = TextComponent::decode_for(version, buf)?;
+        let icon = Slot::decode_for(version, buf)?;
+        let frame_type = VarInt::decode(buf)?;
+        let flags = i32::decode(buf)?;
+        let background_texture = if flags & AdvancementFlags::HAS_BACKGROUND_TEXTURE.bits() != 0 {
+            Some(Identifier::decode(buf)?)
+        } else {
+            None
+        };
+        Ok(AdvancementDisplay {
+            title,
+            description,
+            icon,
+            frame_type,
+            flags,
+            background_texture,
+            x_coord: f32::decode(buf)?,
+            y_coord: f32::decode(buf)?,
+        })
+    }
+}
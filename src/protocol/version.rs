@@ -0,0 +1,175 @@
+//! Protocol versions served by this crate.
+//!
+//! The packet structs follow the layout of [`ProtocolVersion::LATEST`], and their `Packet::ID`s
+//! come from its tables. Older versions are described by the packets they lack: every other
+//! packet keeps its relative order, so a version's ID for a packet is its position in the latest
+//! table once the missing packets are taken out. Field-level differences are handled by
+//! `Packet::encode_for`/`Packet::decode_for`.
+
+use crate::protocol::packets::{ConnectionState, PacketDirection, packet_table};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    /// 1.21.4
+    V1_21_4,
+    /// 1.21.5
+    V1_21_5,
+}
+
+impl ProtocolVersion {
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_21_5;
+    pub const ALL: &'static [ProtocolVersion] =
+        &[ProtocolVersion::V1_21_4, ProtocolVersion::V1_21_5];
+
+    /// Looks up the version a client announced in its handshake.
+    pub fn from_protocol(protocol: i32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|version| version.protocol() == protocol)
+    }
+
+    pub fn protocol(self) -> i32 {
+        match self {
+            ProtocolVersion::V1_21_4 => 769,
+            ProtocolVersion::V1_21_5 => 770,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_21_4 => "1.21.4",
+            ProtocolVersion::V1_21_5 => "1.21.5",
+        }
+    }
+
+    /// Packets of the latest version that this version does not have.
+    fn missing_packets(
+        self,
+        state: ConnectionState,
+        direction: PacketDirection,
+    ) -> &'static [&'static str] {
+        match (self, state, direction) {
+            (ProtocolVersion::V1_21_4, ConnectionState::Play, PacketDirection::Clientbound) => {
+                &["TestInstanceBlockStatus"]
+            }
+            (ProtocolVersion::V1_21_4, ConnectionState::Play, PacketDirection::Serverbound) => {
+                &["SetTestBlock", "TestInstanceBlockAction"]
+            }
+            _ => &[],
+        }
+    }
+
+    /// Maps a packet's `Packet::ID` to the ID this version sends it under, or `None` if the
+    /// packet does not exist in this version.
+    pub fn packet_id(
+        self,
+        state: ConnectionState,
+        direction: PacketDirection,
+        latest_id: i32,
+    ) -> Option<i32> {
+        let missing = self.missing_packets(state, direction);
+        let table = packet_table(state, direction);
+        let (_, name) = table.iter().find(|(id, _)| *id == latest_id)?;
+        if missing.contains(name) {
+            return None;
+        }
+        let shift = table
+            .iter()
+            .filter(|(id, name)| *id < latest_id && missing.contains(name))
+            .count();
+        Some(latest_id - shift as i32)
+    }
+
+    /// Maps an ID received from a client of this version back to the packet's `Packet::ID`.
+    pub fn latest_id(
+        self,
+        state: ConnectionState,
+        direction: PacketDirection,
+        id: i32,
+    ) -> Option<i32> {
+        let missing = self.missing_packets(state, direction);
+        let index = usize::try_from(id).ok()?;
+        packet_table(state, direction)
+            .iter()
+            .filter(|(_, name)| !missing.contains(name))
+            .nth(index)
+            .map(|(latest_id, _)| *latest_id)
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (protocol {})", self.name(), self.protocol())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::encoding::Packet;
+    use crate::protocol::play::{clientbound, serverbound};
+
+    #[test]
+    fn test_from_protocol() {
+        assert_eq!(
+            ProtocolVersion::from_protocol(770),
+            Some(ProtocolVersion::V1_21_5)
+        );
+        assert_eq!(
+            ProtocolVersion::from_protocol(769),
+            Some(ProtocolVersion::V1_21_4)
+        );
+        assert_eq!(ProtocolVersion::from_protocol(767), None);
+        assert!(ProtocolVersion::V1_21_4 < ProtocolVersion::LATEST);
+    }
+
+    #[test]
+    fn test_latest_ids_are_identity() {
+        for (id, _) in packet_table(ConnectionState::Play, PacketDirection::Clientbound) {
+            let version = ProtocolVersion::LATEST;
+            let state = ConnectionState::Play;
+            let direction = PacketDirection::Clientbound;
+            assert_eq!(version.packet_id(state, direction, *id), Some(*id));
+            assert_eq!(version.latest_id(state, direction, *id), Some(*id));
+        }
+    }
+
+    #[test]
+    fn test_older_version_shifts_ids_after_missing_packets() {
+        let version = ProtocolVersion::V1_21_4;
+        let play = ConnectionState::Play;
+        let clientbound_dir = PacketDirection::Clientbound;
+        let serverbound_dir = PacketDirection::Serverbound;
+
+        assert_eq!(
+            version.packet_id(
+                play,
+                clientbound_dir,
+                clientbound::TestInstanceBlockStatus::ID
+            ),
+            None
+        );
+        assert_eq!(
+            version.packet_id(play, clientbound_dir, clientbound::SetTickingState::ID),
+            Some(clientbound::SetTickingState::ID - 1)
+        );
+        assert_eq!(
+            version.packet_id(play, clientbound_dir, clientbound::TagQueryResponse::ID),
+            Some(clientbound::TagQueryResponse::ID)
+        );
+        assert_eq!(
+            version.packet_id(play, serverbound_dir, serverbound::UseItem::ID),
+            Some(serverbound::UseItem::ID - 2)
+        );
+        assert_eq!(
+            version.latest_id(play, serverbound_dir, serverbound::UseItem::ID - 2),
+            Some(serverbound::UseItem::ID)
+        );
+        assert_eq!(
+            version.latest_id(play, serverbound_dir, serverbound::UseItem::ID),
+            None
+        );
+    }
+}
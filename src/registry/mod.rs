@@ -0,0 +1,466 @@
+//! Registries synchronised to the client during the configuration state.
+//!
+//! [`Registries::core`] is the bundled default: the entries of vanilla's `minecraft:core` pack,
+//! without their data. [`Registries::load`] adds the entries of a data directory laid out like a
+//! data pack, read from vanilla-format JSON or NBT files. The vanilla client has the core pack
+//! built in, so when it reports that pack in `ServerboundKnownPacks` core entries are sent
+//! without data; entries the server loaded itself always carry theirs. The network ID of an
+//! entry is its index in its registry. [`Tags`] are loaded from the same directory.
+
+mod tags;
+mod vanilla;
+
+pub use tags::{RegistryTags, Tags};
+pub use vanilla::{core_pack, core_registry_data};
+
+use crate::protocol::configuration::clientbound::RegistryData;
+use crate::protocol::nbt;
+use crate::protocol::types::{KnownPack, Nbt, RegistryEntry};
+use crate::protocol::version::ProtocolVersion;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// A file that doesn't parse as JSON or NBT.
+    InvalidFile { path: PathBuf, reason: String },
+    /// An entry the client doesn't know, with no data to send it instead.
+    MissingData { registry: String, entry: String },
+    /// A required tag value naming an entry or `#tag` that doesn't exist.
+    UnknownTagEntry {
+        registry: String,
+        tag: String,
+        entry: String,
+    },
+    /// Tags that include each other; the first and last are the same tag.
+    TagCycle {
+        registry: String,
+        cycle: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::Io { path, error } => {
+                write!(f, "Could not read {}: {}", path.display(), error)
+            }
+            RegistryError::InvalidFile { path, reason } => {
+                write!(f, "Invalid data file {}: {}", path.display(), reason)
+            }
+            RegistryError::MissingData { registry, entry } => {
+                write!(f, "No data to send for {} in {}", entry, registry)
+            }
+            RegistryError::UnknownTagEntry {
+                registry,
+                tag,
+                entry,
+            } => write!(f, "Tag {} of {} includes unknown {}", tag, registry, entry),
+            RegistryError::TagCycle { registry, cycle } => {
+                write!(
+                    f,
+                    "Tags of {} include each other: {}",
+                    registry,
+                    cycle.join(" -> ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RegistryError::Io { error, .. } => Some(error),
+            RegistryError::InvalidFile { .. }
+            | RegistryError::MissingData { .. }
+            | RegistryError::UnknownTagEntry { .. }
+            | RegistryError::TagCycle { .. } => None,
+        }
+    }
+}
+
+/// Where an entry comes from, which decides whether its data has to be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntrySource {
+    /// The `minecraft:core` pack, which clients of the same version have built in.
+    Core,
+    /// The server's data directory; only the server has it.
+    Server,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub id: String,
+    /// Always set for [`EntrySource::Server`] entries.
+    pub data: Option<Nbt>,
+    pub source: EntrySource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registry {
+    id: String,
+    /// The first version that synchronises this registry.
+    since: ProtocolVersion,
+    entries: Vec<Entry>,
+    network_ids: HashMap<String, usize>,
+}
+
+impl Registry {
+    fn new(id: &str, since: ProtocolVersion) -> Self {
+        Registry {
+            id: id.to_string(),
+            since,
+            entries: vec![],
+            network_ids: HashMap::new(),
+        }
+    }
+
+    /// Adds an entry at the end, or replaces the one with the same ID in place, so it keeps its
+    /// network ID.
+    fn insert(&mut self, id: String, data: Option<Nbt>, source: EntrySource) {
+        match self.network_ids.get(&id) {
+            Some(index) => self.entries[*index] = Entry { id, data, source },
+            None => {
+                self.network_ids.insert(id.clone(), self.entries.len());
+                self.entries.push(Entry { id, data, source });
+            }
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// In network ID order.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The network ID of `entry`; the `minecraft:` namespace may be left out.
+    pub fn network_id(&self, entry: &str) -> Option<i32> {
+        let index = if entry.contains(':') {
+            self.network_ids.get(entry)
+        } else {
+            self.network_ids.get(&format!("minecraft:{}", entry))
+        };
+        index.map(|index| *index as i32)
+    }
+
+    pub fn entry(&self, network_id: i32) -> Option<&Entry> {
+        self.entries.get(usize::try_from(network_id).ok()?)
+    }
+}
+
+/// Every synchronised registry, in the order they are sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registries {
+    registries: Vec<Registry>,
+}
+
+impl Default for Registries {
+    fn default() -> Self {
+        Registries::core()
+    }
+}
+
+impl Registries {
+    /// The bundled `minecraft:core` entries. They have no data, so clients must know the pack.
+    pub fn core() -> Self {
+        Registries {
+            registries: vanilla::core_registries(),
+        }
+    }
+
+    /// The core entries plus the `.json` and `.nbt` files under `dir/data/<namespace>/<registry>/`,
+    /// where `<registry>` is the registry ID's path, such as `worldgen/biome`. A file overriding
+    /// a core entry replaces it, keeping its network ID; new entries follow in ID order.
+    pub fn load(dir: &Path) -> Result<Self, RegistryError> {
+        let mut registries = Registries::core();
+        let data = dir.join("data");
+        let namespaces = namespaces(&data)?;
+        for registry in &mut registries.registries {
+            let path = registry
+                .id
+                .split_once(':')
+                .map_or(registry.id.as_str(), |(_, path)| path);
+            let mut entries = vec![];
+            for namespace in &namespaces {
+                let root = data.join(namespace).join(path);
+                for file in data_files(&root, &["json", "nbt"])? {
+                    let id = format!("{}:{}", namespace, resource_path(&root, &file));
+                    entries.push((id, read_entry(&file)?));
+                }
+            }
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (id, data) in entries {
+                registry.insert(id, Some(data), EntrySource::Server);
+            }
+        }
+        Ok(registries)
+    }
+
+    /// Every registry, including ones only later versions synchronise.
+    pub fn registries(&self) -> &[Registry] {
+        &self.registries
+    }
+
+    pub fn registry(&self, id: &str) -> Option<&Registry> {
+        self.registries.iter().find(|registry| registry.id == id)
+    }
+
+    /// The network ID of `entry` in registry `registry`.
+    pub fn network_id(&self, registry: &str, entry: &str) -> Option<i32> {
+        self.registry(registry)?.network_id(entry)
+    }
+
+    /// One `RegistryData` packet per registry `version` synchronises. Core entries are sent
+    /// without data when the client reported [`core_pack`] among `known_packs`.
+    pub fn registry_data(
+        &self,
+        version: ProtocolVersion,
+        known_packs: &[KnownPack],
+    ) -> Result<Vec<RegistryData>, RegistryError> {
+        let knows_core = known_packs.contains(&core_pack(version));
+        self.registries
+            .iter()
+            .filter(|registry| registry.since <= version)
+            .map(|registry| {
+                let entries = registry
+                    .entries
+                    .iter()
+                    .map(|entry| {
+                        let data = match (entry.source, &entry.data) {
+                            (EntrySource::Core, _) if knows_core => None,
+                            (_, Some(data)) => Some(data.clone()),
+                            (_, None) => {
+                                return Err(RegistryError::MissingData {
+                                    registry: registry.id.clone(),
+                                    entry: entry.id.clone(),
+                                });
+                            }
+                        };
+                        Ok(RegistryEntry {
+                            entry_id: entry.id.clone(),
+                            data,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(RegistryData {
+                    registry_id: registry.id.clone(),
+                    entries,
+                })
+            })
+            .collect()
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> RegistryError + '_ {
+    move |error| RegistryError::Io {
+        path: path.to_path_buf(),
+        error,
+    }
+}
+
+/// The namespace directories under a data pack's `data` directory, sorted.
+fn namespaces(data: &Path) -> Result<Vec<String>, RegistryError> {
+    let mut namespaces = vec![];
+    for entry in std::fs::read_dir(data).map_err(io_error(data))? {
+        let entry = entry.map_err(io_error(data))?;
+        if entry.path().is_dir() {
+            namespaces.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    namespaces.sort();
+    Ok(namespaces)
+}
+
+/// The files under `root` with one of `extensions`, recursively and sorted. A missing `root`
+/// has none.
+fn data_files(root: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, RegistryError> {
+    let mut files = vec![];
+    if root.is_dir() {
+        collect_files(root, extensions, &mut files)?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn collect_files(
+    dir: &Path,
+    extensions: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> Result<(), RegistryError> {
+    for entry in std::fs::read_dir(dir).map_err(io_error(dir))? {
+        let path = entry.map_err(io_error(dir))?.path();
+        if path.is_dir() {
+            collect_files(&path, extensions, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extensions.iter().any(|wanted| extension == *wanted))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The path of `file` below `root` without its extension, `/`-separated as in resource IDs.
+fn resource_path(root: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file).with_extension("");
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn read_entry(path: &Path) -> Result<Nbt, RegistryError> {
+    let bytes = std::fs::read(path).map_err(io_error(path))?;
+    let invalid = |reason: String| RegistryError::InvalidFile {
+        path: path.to_path_buf(),
+        reason,
+    };
+    if path.extension().is_some_and(|extension| extension == "nbt") {
+        nbt::read_file(&bytes)
+            .map(|(_, tag)| tag)
+            .map_err(|e| invalid(e.to_string()))
+    } else {
+        serde_json::from_slice(&bytes).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::nbt::FileCompression;
+
+    /// A data directory under the system temp directory, removed when dropped.
+    pub(super) struct TempDataDir(pub(super) PathBuf);
+
+    impl TempDataDir {
+        pub(super) fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("mcprototool-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(dir.join("data")).unwrap();
+            TempDataDir(dir)
+        }
+
+        pub(super) fn write(&self, path: &str, contents: &[u8]) {
+            let path = self.0.join("data").join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for TempDataDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_core_network_ids() {
+        let registries = Registries::core();
+        let dimension_types = registries.registry("minecraft:dimension_type").unwrap();
+        assert_eq!(dimension_types.network_id("overworld"), Some(0));
+        assert_eq!(dimension_types.network_id("minecraft:the_nether"), Some(3));
+        assert_eq!(
+            dimension_types.entry(2).map(|entry| entry.id.as_str()),
+            Some("minecraft:the_end")
+        );
+        assert_eq!(
+            registries.network_id("minecraft:worldgen/biome", "plains"),
+            Some(40)
+        );
+        assert_eq!(
+            registries.network_id("minecraft:worldgen/biome", "nowhere"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unknown_core_pack_needs_data() {
+        let registries = Registries::core();
+        let version = ProtocolVersion::LATEST;
+        assert!(matches!(
+            registries.registry_data(version, &[]),
+            Err(RegistryError::MissingData { .. })
+        ));
+        let mut other_version = core_pack(version);
+        other_version.version = "1.0".to_string();
+        assert!(registries.registry_data(version, &[other_version]).is_err());
+        assert!(
+            registries
+                .registry_data(version, &[core_pack(version)])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_load_data_directory() {
+        let dir = TempDataDir::new("registries");
+        dir.write(
+            "minecraft/dimension_type/overworld.json",
+            br#"{"height": 256, "has_skylight": true}"#,
+        );
+        dir.write(
+            "example/worldgen/biome/deep/void.json",
+            br#"{"temperature": 0.5}"#,
+        );
+        let mut compound = nbt::Compound::new();
+        compound.insert("asset_id".to_string(), "example:spotted".into());
+        let tag = Nbt::Compound(compound);
+        dir.write(
+            "example/wolf_variant/spotted.nbt",
+            &nbt::write_file("", &tag, FileCompression::Gzip).unwrap(),
+        );
+        dir.write("example/wolf_variant/readme.txt", b"ignored");
+
+        let registries = Registries::load(&dir.0).unwrap();
+        let biomes = registries.registry("minecraft:worldgen/biome").unwrap();
+        let core_biomes = Registries::core()
+            .registry("minecraft:worldgen/biome")
+            .unwrap()
+            .entries()
+            .len();
+        assert_eq!(
+            biomes.network_id("example:deep/void"),
+            Some(core_biomes as i32)
+        );
+        let wolf_variants = registries.registry("minecraft:wolf_variant").unwrap();
+        assert_eq!(wolf_variants.entries().last().unwrap().data, Some(tag));
+
+        let version = ProtocolVersion::LATEST;
+        let sent = registries
+            .registry_data(version, &[core_pack(version)])
+            .unwrap();
+        let dimension_types = &sent[0];
+        assert_eq!(dimension_types.registry_id, "minecraft:dimension_type");
+        let overworld = &dimension_types.entries[0];
+        assert_eq!(overworld.entry_id, "minecraft:overworld");
+        let data = overworld.data.as_ref().unwrap();
+        assert_eq!(data.get("height").and_then(Nbt::as_i64), Some(256));
+        assert_eq!(data.get("has_skylight"), Some(&Nbt::Byte(1)));
+        assert_eq!(dimension_types.entries[1].data, None);
+    }
+
+    #[test]
+    fn test_load_reports_bad_files() {
+        let dir = TempDataDir::new("bad-registries");
+        dir.write("minecraft/chat_type/broken.json", b"{");
+        assert!(matches!(
+            Registries::load(&dir.0),
+            Err(RegistryError::InvalidFile { path, .. }) if path.ends_with("broken.json")
+        ));
+        assert!(matches!(
+            Registries::load(&dir.0.join("missing")),
+            Err(RegistryError::Io { .. })
+        ));
+    }
+}
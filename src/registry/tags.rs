@@ -0,0 +1,426 @@
+//! Tags: named sets of registry entries, sent in Update Tags.
+//!
+//! Tags are read from `data/<namespace>/tags/<registry path>/<tag path>.json` in the vanilla
+//! format, `{"values": ["minecraft:plains", "#minecraft:is_forest", ...]}`. A value starting with
+//! `#` includes another tag; values can also be `{"id": ..., "required": false}` to be skipped
+//! when missing. Tags are resolved once at load time into the network IDs of their registry.
+//!
+//! Tags only cover the data-driven registries in [`Registries`], the only ones with network IDs
+//! here. Tags of the built-in registries (`block`, `item`, `fluid`, `entity_type`, `game_event`,
+//! ...) can't be resolved and are never sent, so clients keep their vanilla tags for those;
+//! [`Tags::skipped`] lists the registries whose tag files were left out.
+
+use super::{Registries, Registry, RegistryError, data_files, io_error, namespaces, resource_path};
+use crate::protocol::configuration::clientbound::UpdateTagsConfiguration;
+use crate::protocol::play::clientbound::UpdateTagsPlay;
+use crate::protocol::types::{RegistryTagData, Tag, VarInt};
+use crate::protocol::version::ProtocolVersion;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// A tag file. `replace` is ignored: there is only the one data directory to merge.
+#[derive(Deserialize)]
+struct TagFile {
+    values: Vec<TagValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagValue {
+    Id(String),
+    Entry {
+        id: String,
+        #[serde(default = "required_by_default")]
+        required: bool,
+    },
+}
+
+fn required_by_default() -> bool {
+    true
+}
+
+impl TagValue {
+    fn id(&self) -> &str {
+        match self {
+            TagValue::Id(id) | TagValue::Entry { id, .. } => id,
+        }
+    }
+
+    fn required(&self) -> bool {
+        match self {
+            TagValue::Id(_) => true,
+            TagValue::Entry { required, .. } => *required,
+        }
+    }
+}
+
+/// `id` with the default `minecraft:` namespace filled in, and without a leading `#`.
+fn qualified(id: &str) -> String {
+    let id = id.strip_prefix('#').unwrap_or(id);
+    if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    }
+}
+
+/// The resolved tags of one registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryTags {
+    registry: String,
+    since: ProtocolVersion,
+    /// Network IDs per tag, in the order the tag files list them, without duplicates.
+    tags: BTreeMap<String, Vec<i32>>,
+}
+
+impl RegistryTags {
+    pub fn registry(&self) -> &str {
+        &self.registry
+    }
+
+    /// The network IDs tagged `tag`; the `#` and the `minecraft:` namespace may be left out.
+    pub fn get(&self, tag: &str) -> Option<&[i32]> {
+        self.tags.get(&qualified(tag)).map(Vec::as_slice)
+    }
+
+    /// Whether the entry with network ID `id` is tagged `tag`.
+    pub fn contains(&self, tag: &str, id: i32) -> bool {
+        self.get(tag).is_some_and(|ids| ids.contains(&id))
+    }
+
+    /// Tag names, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tags.keys().map(String::as_str)
+    }
+}
+
+/// The registry a tag file belongs to, from its path under `tags/`: the first directory, or the
+/// first two under `worldgen/`.
+fn tag_registry(path: &str) -> String {
+    let mut directories = path.split('/');
+    let registry = match directories.next() {
+        Some("worldgen") => format!("worldgen/{}", directories.next().unwrap_or_default()),
+        first => first.unwrap_or_default().to_string(),
+    };
+    format!("minecraft:{}", registry)
+}
+
+/// Tags for each registry that has any.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tags {
+    registries: Vec<RegistryTags>,
+    skipped: Vec<String>,
+}
+
+impl Tags {
+    /// Loads the tags under `dir/data/*/tags/` for every registry in `registries`. Tags of
+    /// registries the server doesn't synchronise are left alone, as their entries have no
+    /// network IDs here; [`Tags::skipped`] names those registries.
+    pub fn load(dir: &Path, registries: &Registries) -> Result<Self, RegistryError> {
+        let data = dir.join("data");
+        let namespaces = namespaces(&data)?;
+        let mut tags = Tags::default();
+        for registry in registries.registries() {
+            let path = registry
+                .id()
+                .split_once(':')
+                .map_or(registry.id(), |(_, path)| path);
+            let mut files = HashMap::new();
+            for namespace in &namespaces {
+                let root = data.join(namespace).join("tags").join(path);
+                for file in data_files(&root, &["json"])? {
+                    let contents = std::fs::read(&file).map_err(io_error(&file))?;
+                    let tag: TagFile = serde_json::from_slice(&contents).map_err(|e| {
+                        RegistryError::InvalidFile {
+                            path: file.clone(),
+                            reason: e.to_string(),
+                        }
+                    })?;
+                    files.insert(
+                        format!("{}:{}", namespace, resource_path(&root, &file)),
+                        tag,
+                    );
+                }
+            }
+            if files.is_empty() {
+                continue;
+            }
+
+            let mut resolver = Resolver {
+                registry,
+                files: &files,
+                resolved: BTreeMap::new(),
+                visiting: vec![],
+            };
+            for name in files.keys() {
+                resolver.resolve(name)?;
+            }
+            tags.registries.push(RegistryTags {
+                registry: registry.id().to_string(),
+                since: registry.since,
+                tags: resolver.resolved,
+            });
+        }
+
+        for namespace in &namespaces {
+            let root = data.join(namespace).join("tags");
+            for file in data_files(&root, &["json"])? {
+                let registry = tag_registry(&resource_path(&root, &file));
+                if registries.registry(&registry).is_none() && !tags.skipped.contains(&registry) {
+                    tags.skipped.push(registry);
+                }
+            }
+        }
+        tags.skipped.sort();
+        Ok(tags)
+    }
+
+    /// The registries with tag files that [`Tags::load`] left out, sorted, such as
+    /// `minecraft:block`.
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    pub fn registry(&self, registry: &str) -> Option<&RegistryTags> {
+        self.registries
+            .iter()
+            .find(|tags| tags.registry == registry)
+    }
+
+    /// Whether the entry of `registry` with network ID `id` is tagged `tag`.
+    pub fn contains(&self, registry: &str, tag: &str, id: i32) -> bool {
+        self.registry(registry)
+            .is_some_and(|tags| tags.contains(tag, id))
+    }
+
+    /// The tags of every registry `version` synchronises, as Update Tags carries them.
+    pub fn tag_data(&self, version: ProtocolVersion) -> Vec<RegistryTagData> {
+        self.registries
+            .iter()
+            .filter(|registry| registry.since <= version)
+            .map(|registry| RegistryTagData {
+                registry_id: registry.registry.clone(),
+                tags: registry
+                    .tags
+                    .iter()
+                    .map(|(name, ids)| Tag {
+                        tag_name: name.clone(),
+                        entries: ids.iter().map(|id| VarInt(*id)).collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    pub fn update_tags_configuration(&self, version: ProtocolVersion) -> UpdateTagsConfiguration {
+        UpdateTagsConfiguration {
+            tags: self.tag_data(version),
+        }
+    }
+
+    pub fn update_tags_play(&self, version: ProtocolVersion) -> UpdateTagsPlay {
+        UpdateTagsPlay {
+            tags: self.tag_data(version),
+        }
+    }
+}
+
+/// Resolves the tags of one registry depth first, keeping the path of tags being resolved to
+/// catch one that includes itself.
+struct Resolver<'a> {
+    registry: &'a Registry,
+    files: &'a HashMap<String, TagFile>,
+    resolved: BTreeMap<String, Vec<i32>>,
+    visiting: Vec<String>,
+}
+
+impl Resolver<'_> {
+    /// The IDs of tag `name`, or `None` if there is no such tag.
+    fn resolve(&mut self, name: &str) -> Result<Option<Vec<i32>>, RegistryError> {
+        if let Some(ids) = self.resolved.get(name) {
+            return Ok(Some(ids.clone()));
+        }
+        if let Some(start) = self.visiting.iter().position(|tag| tag == name) {
+            let mut cycle = self.visiting[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(RegistryError::TagCycle {
+                registry: self.registry.id().to_string(),
+                cycle,
+            });
+        }
+        let Some(file) = self.files.get(name) else {
+            return Ok(None);
+        };
+
+        self.visiting.push(name.to_string());
+        let mut ids = vec![];
+        for value in &file.values {
+            let included = if value.id().starts_with('#') {
+                self.resolve(&qualified(value.id()))?
+            } else {
+                self.registry
+                    .network_id(&qualified(value.id()))
+                    .map(|id| vec![id])
+            };
+            match included {
+                Some(included) => {
+                    for id in included {
+                        if !ids.contains(&id) {
+                            ids.push(id);
+                        }
+                    }
+                }
+                None if value.required() => {
+                    return Err(RegistryError::UnknownTagEntry {
+                        registry: self.registry.id().to_string(),
+                        tag: name.to_string(),
+                        entry: value.id().to_string(),
+                    });
+                }
+                None => {}
+            }
+        }
+        self.visiting.pop();
+        self.resolved.insert(name.to_string(), ids.clone());
+        Ok(Some(ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::tests::TempDataDir;
+
+    const BIOMES: &str = "minecraft:worldgen/biome";
+
+    fn biome(name: &str) -> i32 {
+        Registries::core().network_id(BIOMES, name).unwrap()
+    }
+
+    #[test]
+    fn test_nested_tags() {
+        let dir = TempDataDir::new("tags");
+        dir.write(
+            "minecraft/tags/worldgen/biome/is_deep_ocean.json",
+            br#"{"values": ["deep_ocean", "minecraft:deep_cold_ocean"]}"#,
+        );
+        dir.write(
+            "minecraft/tags/worldgen/biome/is_ocean.json",
+            br##"{"replace": false, "values": ["#minecraft:is_deep_ocean", "ocean", "deep_ocean",
+                {"id": "minecraft:missing_ocean", "required": false}]}"##,
+        );
+        dir.write(
+            "example/tags/worldgen/biome/wet/all.json",
+            br##"{"values": ["#is_ocean", "river"]}"##,
+        );
+        dir.write(
+            "minecraft/tags/block/mineable/pickaxe.json",
+            br#"{"values": ["stone"]}"#,
+        );
+        dir.write(
+            "minecraft/tags/worldgen/structure/village.json",
+            br#"{"values": ["village_plains"]}"#,
+        );
+
+        let registries = Registries::core();
+        let tags = Tags::load(&dir.0, &registries).unwrap();
+        let biomes = tags.registry(BIOMES).unwrap();
+        assert_eq!(
+            biomes.get("#minecraft:is_ocean").unwrap(),
+            [
+                biome("deep_ocean"),
+                biome("deep_cold_ocean"),
+                biome("ocean")
+            ]
+        );
+        assert!(biomes.contains("example:wet/all", biome("river")));
+        assert!(biomes.contains("is_ocean", biome("ocean")));
+        assert!(!biomes.contains("is_deep_ocean", biome("ocean")));
+        assert!(tags.contains(BIOMES, "example:wet/all", biome("deep_ocean")));
+        assert!(!tags.contains("minecraft:block", "mineable/pickaxe", 1));
+        assert_eq!(
+            tags.skipped(),
+            ["minecraft:block", "minecraft:worldgen/structure"]
+        );
+
+        let packet = tags.update_tags_configuration(ProtocolVersion::LATEST);
+        assert_eq!(packet.tags.len(), 1);
+        assert_eq!(packet.tags[0].registry_id, BIOMES);
+        let names: Vec<&str> = packet.tags[0]
+            .tags
+            .iter()
+            .map(|tag| tag.tag_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "example:wet/all",
+                "minecraft:is_deep_ocean",
+                "minecraft:is_ocean"
+            ]
+        );
+        assert_eq!(
+            tags.update_tags_play(ProtocolVersion::LATEST).tags,
+            packet.tags
+        );
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let dir = TempDataDir::new("tag-cycle");
+        dir.write(
+            "minecraft/tags/worldgen/biome/a.json",
+            br##"{"values": ["plains", "#b"]}"##,
+        );
+        dir.write(
+            "minecraft/tags/worldgen/biome/b.json",
+            br##"{"values": ["#c"]}"##,
+        );
+        dir.write(
+            "minecraft/tags/worldgen/biome/c.json",
+            br##"{"values": ["#a"]}"##,
+        );
+        let Err(RegistryError::TagCycle { registry, cycle }) =
+            Tags::load(&dir.0, &Registries::core())
+        else {
+            panic!("expected a tag cycle");
+        };
+        assert_eq!(registry, BIOMES);
+        assert_eq!(cycle.len(), 4);
+        assert_eq!(cycle.first(), cycle.last());
+    }
+
+    #[test]
+    fn test_unknown_entries_are_rejected() {
+        let dir = TempDataDir::new("tag-unknown");
+        dir.write(
+            "minecraft/tags/worldgen/biome/a.json",
+            br##"{"values": ["#missing"]}"##,
+        );
+        assert!(matches!(
+            Tags::load(&dir.0, &Registries::core()),
+            Err(RegistryError::UnknownTagEntry { entry, .. }) if entry == "#missing"
+        ));
+        dir.write(
+            "minecraft/tags/worldgen/biome/a.json",
+            br#"{"values": ["nowhere"]}"#,
+        );
+        assert!(matches!(
+            Tags::load(&dir.0, &Registries::core()),
+            Err(RegistryError::UnknownTagEntry { tag, .. }) if tag == "minecraft:a"
+        ));
+    }
+
+    #[test]
+    fn test_tags_follow_registry_versions() {
+        let dir = TempDataDir::new("tag-versions");
+        dir.write(
+            "minecraft/tags/pig_variant/spotted.json",
+            br#"{"values": ["temperate"]}"#,
+        );
+        let tags = Tags::load(&dir.0, &Registries::core()).unwrap();
+        assert_eq!(tags.tag_data(ProtocolVersion::V1_21_5).len(), 1);
+        assert!(tags.tag_data(ProtocolVersion::V1_21_4).is_empty());
+    }
+}
@@ -0,0 +1,529 @@
+//! The entries of vanilla's `minecraft:core` pack, bundled so a server needs no data files.
+//!
+//! Only entry IDs are bundled: the data stays with the client, which has the pack built in. The
+//! network ID of an entry is its index in the list.
+
+use super::{EntrySource, Registries, Registry};
+use crate::protocol::configuration::clientbound::RegistryData;
+use crate::protocol::types::KnownPack;
+use crate::protocol::version::ProtocolVersion;
+
+/// A synchronised registry and the entries the `minecraft:core` pack defines for it.
+struct CoreRegistry {
+    id: &'static str,
+    /// The first version that synchronises this registry.
+    since: ProtocolVersion,
+    entries: &'static [&'static str],
+}
+
+const CORE_REGISTRIES: &[CoreRegistry] = &[
+    CoreRegistry {
+        id: "minecraft:dimension_type",
+        since: ProtocolVersion::V1_21_4,
+        entries: &["overworld", "overworld_caves", "the_end", "the_nether"],
+    },
+    CoreRegistry {
+        id: "minecraft:worldgen/biome",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "badlands",
+            "bamboo_jungle",
+            "basalt_deltas",
+            "beach",
+            "birch_forest",
+            "cherry_grove",
+            "cold_ocean",
+            "crimson_forest",
+            "dark_forest",
+            "deep_cold_ocean",
+            "deep_dark",
+            "deep_frozen_ocean",
+            "deep_lukewarm_ocean",
+            "deep_ocean",
+            "desert",
+            "dripstone_caves",
+            "end_barrens",
+            "end_highlands",
+            "end_midlands",
+            "eroded_badlands",
+            "flower_forest",
+            "forest",
+            "frozen_ocean",
+            "frozen_peaks",
+            "frozen_river",
+            "grove",
+            "ice_spikes",
+            "jagged_peaks",
+            "jungle",
+            "lukewarm_ocean",
+            "lush_caves",
+            "mangrove_swamp",
+            "meadow",
+            "mushroom_fields",
+            "nether_wastes",
+            "ocean",
+            "old_growth_birch_forest",
+            "old_growth_pine_taiga",
+            "old_growth_spruce_taiga",
+            "pale_garden",
+            "plains",
+            "river",
+            "savanna",
+            "savanna_plateau",
+            "small_end_islands",
+            "snowy_beach",
+            "snowy_plains",
+            "snowy_slopes",
+            "snowy_taiga",
+            "soul_sand_valley",
+            "sparse_jungle",
+            "stony_peaks",
+            "stony_shore",
+            "sunflower_plains",
+            "swamp",
+            "taiga",
+            "the_end",
+            "the_void",
+            "warm_ocean",
+            "warped_forest",
+            "windswept_forest",
+            "windswept_gravelly_hills",
+            "windswept_hills",
+            "windswept_savanna",
+            "wooded_badlands",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:chat_type",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "chat",
+            "emote_command",
+            "msg_command_incoming",
+            "msg_command_outgoing",
+            "say_command",
+            "team_msg_command_incoming",
+            "team_msg_command_outgoing",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:trim_pattern",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "bolt",
+            "coast",
+            "dune",
+            "eye",
+            "flow",
+            "host",
+            "raiser",
+            "rib",
+            "sentry",
+            "shaper",
+            "silence",
+            "snout",
+            "spire",
+            "tide",
+            "vex",
+            "ward",
+            "wayfinder",
+            "wild",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:trim_material",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "amethyst",
+            "copper",
+            "diamond",
+            "emerald",
+            "gold",
+            "iron",
+            "lapis",
+            "netherite",
+            "quartz",
+            "redstone",
+            "resin",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:wolf_variant",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "ashen", "black", "chestnut", "pale", "rusty", "snowy", "spotted", "striped", "woods",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:wolf_sound_variant",
+        since: ProtocolVersion::V1_21_5,
+        entries: &["angry", "big", "classic", "cute", "grumpy", "puglin", "sad"],
+    },
+    CoreRegistry {
+        id: "minecraft:pig_variant",
+        since: ProtocolVersion::V1_21_5,
+        entries: &["cold", "temperate", "warm"],
+    },
+    CoreRegistry {
+        id: "minecraft:frog_variant",
+        since: ProtocolVersion::V1_21_5,
+        entries: &["cold", "temperate", "warm"],
+    },
+    CoreRegistry {
+        id: "minecraft:cat_variant",
+        since: ProtocolVersion::V1_21_5,
+        entries: &[
+            "all_black",
+            "black",
+            "british_shorthair",
+            "calico",
+            "jellie",
+            "persian",
+            "ragdoll",
+            "red",
+            "siamese",
+            "tabby",
+            "white",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:cow_variant",
+        since: ProtocolVersion::V1_21_5,
+        entries: &["cold", "temperate", "warm"],
+    },
+    CoreRegistry {
+        id: "minecraft:chicken_variant",
+        since: ProtocolVersion::V1_21_5,
+        entries: &["cold", "temperate", "warm"],
+    },
+    CoreRegistry {
+        id: "minecraft:painting_variant",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "alban",
+            "aztec",
+            "aztec2",
+            "backyard",
+            "baroque",
+            "bomb",
+            "bouquet",
+            "burning_skull",
+            "bust",
+            "cavebird",
+            "changing",
+            "cotan",
+            "courbet",
+            "creebet",
+            "donkey_kong",
+            "earth",
+            "endboss",
+            "fern",
+            "fighters",
+            "finding",
+            "fire",
+            "graham",
+            "humble",
+            "kebab",
+            "lowmist",
+            "match",
+            "meditative",
+            "orb",
+            "owlemons",
+            "passage",
+            "pigscene",
+            "plant",
+            "pointer",
+            "pond",
+            "pool",
+            "prairie_ride",
+            "sea",
+            "skeleton",
+            "skull_and_roses",
+            "stage",
+            "sunflowers",
+            "sunset",
+            "tides",
+            "unpacked",
+            "void",
+            "wanderer",
+            "wasteland",
+            "water",
+            "wind",
+            "wither",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:damage_type",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "arrow",
+            "bad_respawn_point",
+            "cactus",
+            "campfire",
+            "cramming",
+            "dragon_breath",
+            "drown",
+            "dry_out",
+            "ender_pearl",
+            "explosion",
+            "fall",
+            "falling_anvil",
+            "falling_block",
+            "falling_stalactite",
+            "fireball",
+            "fireworks",
+            "fly_into_wall",
+            "freeze",
+            "generic",
+            "generic_kill",
+            "hot_floor",
+            "in_fire",
+            "in_wall",
+            "indirect_magic",
+            "lava",
+            "lightning_bolt",
+            "mace_smash",
+            "magic",
+            "mob_attack",
+            "mob_attack_no_aggro",
+            "mob_projectile",
+            "on_fire",
+            "out_of_world",
+            "outside_border",
+            "player_attack",
+            "player_explosion",
+            "sonic_boom",
+            "spit",
+            "stalagmite",
+            "starve",
+            "sting",
+            "sweet_berry_bush",
+            "thorns",
+            "thrown",
+            "trident",
+            "unattributed_fireball",
+            "wind_charge",
+            "wither",
+            "wither_skull",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:banner_pattern",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "base",
+            "border",
+            "bricks",
+            "circle",
+            "creeper",
+            "cross",
+            "curly_border",
+            "diagonal_left",
+            "diagonal_right",
+            "diagonal_up_left",
+            "diagonal_up_right",
+            "flow",
+            "flower",
+            "globe",
+            "gradient",
+            "gradient_up",
+            "guster",
+            "half_horizontal",
+            "half_horizontal_bottom",
+            "half_vertical",
+            "half_vertical_right",
+            "mojang",
+            "piglin",
+            "rhombus",
+            "skull",
+            "small_stripes",
+            "square_bottom_left",
+            "square_bottom_right",
+            "square_top_left",
+            "square_top_right",
+            "straight_cross",
+            "stripe_bottom",
+            "stripe_center",
+            "stripe_downleft",
+            "stripe_downright",
+            "stripe_left",
+            "stripe_middle",
+            "stripe_right",
+            "stripe_top",
+            "triangle_bottom",
+            "triangle_top",
+            "triangles_bottom",
+            "triangles_top",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:enchantment",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "aqua_affinity",
+            "bane_of_arthropods",
+            "binding_curse",
+            "blast_protection",
+            "breach",
+            "channeling",
+            "density",
+            "depth_strider",
+            "efficiency",
+            "feather_falling",
+            "fire_aspect",
+            "fire_protection",
+            "flame",
+            "fortune",
+            "frost_walker",
+            "impaling",
+            "infinity",
+            "knockback",
+            "looting",
+            "loyalty",
+            "luck_of_the_sea",
+            "lure",
+            "mending",
+            "multishot",
+            "piercing",
+            "power",
+            "projectile_protection",
+            "protection",
+            "punch",
+            "quick_charge",
+            "respiration",
+            "riptide",
+            "sharpness",
+            "silk_touch",
+            "smite",
+            "soul_speed",
+            "sweeping_edge",
+            "swift_sneak",
+            "thorns",
+            "unbreaking",
+            "vanishing_curse",
+            "wind_burst",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:jukebox_song",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "11",
+            "13",
+            "5",
+            "blocks",
+            "cat",
+            "chirp",
+            "creator",
+            "creator_music_box",
+            "far",
+            "mall",
+            "mellohi",
+            "otherside",
+            "pigstep",
+            "precipice",
+            "relic",
+            "stal",
+            "strad",
+            "wait",
+            "ward",
+        ],
+    },
+    CoreRegistry {
+        id: "minecraft:instrument",
+        since: ProtocolVersion::V1_21_4,
+        entries: &[
+            "admire_goat_horn",
+            "call_goat_horn",
+            "dream_goat_horn",
+            "feel_goat_horn",
+            "ponder_goat_horn",
+            "seek_goat_horn",
+            "sing_goat_horn",
+            "yearn_goat_horn",
+        ],
+    },
+];
+
+/// The `minecraft:core` pack of `version`, offered to the client in `ClientboundKnownPacks`.
+pub fn core_pack(version: ProtocolVersion) -> KnownPack {
+    KnownPack {
+        namespace: "minecraft".to_string(),
+        id: "core".to_string(),
+        version: version.name().to_string(),
+    }
+}
+
+/// One `RegistryData` packet per registry `version` synchronises, listing the entries without
+/// data. Only valid for clients that reported [`core_pack`] as known.
+pub fn core_registry_data(version: ProtocolVersion) -> Vec<RegistryData> {
+    Registries::core()
+        .registry_data(version, &[core_pack(version)])
+        .expect("core entries are sent without data")
+}
+
+/// Every synchronised registry, holding the core entries.
+pub(super) fn core_registries() -> Vec<Registry> {
+    CORE_REGISTRIES
+        .iter()
+        .map(|core| {
+            let mut registry = Registry::new(core.id, core.since);
+            for entry in core.entries {
+                registry.insert(format!("minecraft:{}", entry), None, EntrySource::Core);
+            }
+            registry
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_core_registry_entries_are_unique_and_sorted() {
+        for registry in CORE_REGISTRIES {
+            let unique: HashSet<_> = registry.entries.iter().collect();
+            assert_eq!(unique.len(), registry.entries.len(), "{}", registry.id);
+            assert!(registry.entries.is_sorted(), "{}", registry.id);
+        }
+    }
+
+    #[test]
+    fn test_core_registry_data_per_version() {
+        let latest = core_registry_data(ProtocolVersion::V1_21_5);
+        let older = core_registry_data(ProtocolVersion::V1_21_4);
+        assert_eq!(latest.len(), CORE_REGISTRIES.len());
+        assert!(older.len() < latest.len());
+        assert!(
+            !older
+                .iter()
+                .any(|registry| registry.registry_id == "minecraft:pig_variant")
+        );
+
+        let biomes = latest
+            .iter()
+            .find(|registry| registry.registry_id == "minecraft:worldgen/biome")
+            .unwrap();
+        assert!(
+            biomes
+                .entries
+                .iter()
+                .any(|entry| entry.entry_id == "minecraft:plains" && entry.data.is_none())
+        );
+    }
+
+    #[test]
+    fn test_core_pack_matches_version() {
+        let pack = core_pack(ProtocolVersion::V1_21_4);
+        assert_eq!(
+            (
+                pack.namespace.as_str(),
+                pack.id.as_str(),
+                pack.version.as_str()
+            ),
+            ("minecraft", "core", "1.21.4")
+        );
+    }
+}
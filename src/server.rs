@@ -1,55 +1,306 @@
+use crate::auth::{self, AuthError, GameProfile, MojangSessionService, ServerKey, SessionService};
+use crate::command::{CommandDispatcher, CommandSource, OP_PERMISSION_LEVEL};
+use crate::config::ServerConfig;
+use crate::keep_alive::{KeepAlive, KeepAliveAction};
 use crate::protocol::{
-    encoding::{
-        write_packet_frame, read_packet_frame, DecodeError,
-        write_string, write_varint, write_i64,
-        read_string_sync, read_uuid_sync, read_varint_sync, 
-        read_bool_sync,
+    codec::MinecraftCodec,
+    compression::Compression,
+    configuration::clientbound::{ClientboundKeepAliveConfiguration, ClientboundKnownPacks, DisconnectConfiguration, FeatureFlags, FinishConfiguration},
+    encoding::{DecodeError, DecodeResult},
+    encryption::{EncryptedReader, EncryptedWriter},
+    legacy::{self, LegacyStatus, LEGACY_PING_ID},
+    login::clientbound::{DisconnectLogin, EncryptionRequest, LoginSuccess, SetCompression},
+    packets::{
+        ClientboundConfigurationPacket, ClientboundLoginPacket, ClientboundPlayPacket, ClientboundStatusPacket, ConnectionState,
+        ServerboundConfigurationPacket, ServerboundHandshakingPacket, ServerboundLoginPacket, ServerboundPlayPacket,
+        ServerboundStatusPacket, StatePacket,
     },
-    handshaking::serverbound::Handshake,
-    login::{
-        clientbound::{LoginSuccess, LoginProperty},
-        serverbound::LoginStart,
+    play::clientbound::{
+        ClientboundKeepAlivePlay, DisconnectPlay, GameEvent, LoginPlay, PlayerInfoUpdate, SetCenterChunk, SynchronizePlayerPosition,
+        SystemChatMessage,
     },
-    status::{
-        clientbound::{StatusResponse, PongResponse},
-        serverbound::{StatusRequest, PingRequest},
-    },
-    types::{NextState, VarInt, JsonTextComponent},
+    play::serverbound::{ChatCommand, SignedChatCommand},
+    status::clientbound::{StatusResponse, PongResponse},
+    text::TextComponent,
+    types::{PlayerInfoActionData, PlayerInfoEntry, PlayerInfoProperty, VarInt},
+    version::ProtocolVersion,
 };
+use crate::registry::{self, Registries, Tags};
+use crate::status::{self, PlayerList, ServerStatus, StatusPlayers, StatusVersion};
+use bytes::BytesMut;
+use futures_util::{SinkExt, StreamExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::{FramedRead, FramedWrite};
 use std::error::Error;
-use bytes::{Buf, BytesMut};
-use uuid::Uuid;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
-const STATUS_JSON: &str = r#"{
-    "version": {
-        "name": "mcprototool-rs 1.20.6",
-        "protocol": 767
-    },
-    "players": {
-        "max": 20,
-        "online": 0,
-        "sample": []
-    },
-    "description": {
-        "text": "Minimal Rust Server"
-    },
-    "enforcesSecureChat": false,
-    "previewsChat": false
-}"#;
+type ConnectionReader = Box<dyn AsyncRead + Unpin + Send>;
+type ConnectionWriter = Box<dyn AsyncWrite + Unpin + Send>;
 
+/// `GameEvent` that tells the client to show the world once the chunks around it have arrived.
+const START_WAITING_FOR_CHUNKS: u8 = 13;
+/// `PlayerInfoUpdate` action bits.
+const ADD_PLAYER: u8 = 0x01;
+const UPDATE_LISTED: u8 = 0x08;
+const UPDATE_LATENCY: u8 = 0x10;
+/// Shown to clients that stop answering keep-alives, as in vanilla.
+const TIMED_OUT: &str = "Timed out";
 
-pub async fn run_server(address: &str) -> Result<(), Box<dyn Error>> {
-    let listener = TcpListener::bind(address).await?;
-    println!("Minimal Minecraft server listening on {}", address);
+pub struct ServerOptions {
+    pub config: ServerConfig,
+    /// Switches clients to compressed frames during login when set.
+    pub compression: Option<Compression>,
+    /// Runs the server in online mode, verifying every player with this service, when set.
+    pub session_service: Option<Arc<dyn SessionService>>,
+    /// The commands players can run; each is sent the ones its permission level allows.
+    pub commands: CommandDispatcher,
+}
+
+impl ServerOptions {
+    /// Runs `config` as written: compression from its threshold and level, in online mode
+    /// Mojang's session server, and the built-in commands.
+    pub fn from_config(config: ServerConfig) -> Self {
+        let compression = usize::try_from(config.network_compression_threshold)
+            .ok()
+            .map(|threshold| Compression { threshold, level: config.network_compression_level });
+        let session_service: Option<Arc<dyn SessionService>> = if config.online_mode {
+            Some(Arc::new(MojangSessionService::new()))
+        } else {
+            None
+        };
+        ServerOptions { config, compression, session_service, commands: CommandDispatcher::with_builtins() }
+    }
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self::from_config(ServerConfig::default())
+    }
+}
+
+struct ServerState {
+    options: ServerOptions,
+    /// Only generated in online mode.
+    key: Option<ServerKey>,
+    next_entity_id: AtomicI32,
+    players: PlayerList,
+    /// `config.server_icon` as a data URI, read once at startup.
+    favicon: Option<String>,
+    /// The bundled registries, plus `config.data_directory` if set.
+    registries: Registries,
+    /// From `config.data_directory`; there are no bundled tags, and only data-driven registries
+    /// have any.
+    tags: Tags,
+}
+
+/// One client connection. Packets can only be sent and received for the state the connection
+/// is in, and go through whatever compression and encryption login switched on.
+struct Connection {
+    // Boxed so the halves can be wrapped in encryption part way through login.
+    reader: FramedRead<ConnectionReader, MinecraftCodec>,
+    writer: FramedWrite<ConnectionWriter, MinecraftCodec>,
+    state: ConnectionState,
+    version: ProtocolVersion,
+    /// Only runs in the configuration and play states.
+    keep_alive: KeepAlive,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Connection {
+            reader: FramedRead::new(Box::new(reader), MinecraftCodec::new()),
+            writer: FramedWrite::new(Box::new(writer), MinecraftCodec::new()),
+            state: ConnectionState::Handshaking,
+            version: ProtocolVersion::LATEST,
+            keep_alive: KeepAlive::new(Instant::now()),
+        }
+    }
+
+    fn check_state<P: StatePacket>(&self) -> Result<(), Box<dyn Error>> {
+        if P::STATE != self.state {
+            return Err(format!("Cannot use {} {} packets in the {} state", P::DIRECTION, P::STATE, self.state).into());
+        }
+        Ok(())
+    }
+
+    async fn send<P: StatePacket>(&mut self, packet: P) -> Result<(), Box<dyn Error>> {
+        self.check_state::<P>()?;
+        self.writer.feed(packet.encode_for(self.version)?).await?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    async fn read_frame(&mut self) -> DecodeResult<(VarInt, BytesMut)> {
+        match self.reader.next().await {
+            Some(frame) => frame,
+            None => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+
+    /// Reads the next frame. In configuration and play, keep-alives are sent while waiting, and
+    /// a client that leaves one unanswered for too long is disconnected.
+    async fn next_frame(&mut self) -> Result<(VarInt, BytesMut), Box<dyn Error>> {
+        if !matches!(self.state, ConnectionState::Configuration | ConnectionState::Play) {
+            return Ok(self.read_frame().await?);
+        }
+        loop {
+            let deadline = self.keep_alive.deadline();
+            tokio::select! {
+                frame = self.read_frame() => return Ok(frame?),
+                _ = tokio::time::sleep_until(deadline.into()) => {}
+            }
+            match self.keep_alive.poll(Instant::now()) {
+                KeepAliveAction::Send(keep_alive_id) => {
+                    match self.state {
+                        ConnectionState::Configuration => self.send(ClientboundConfigurationPacket::from(ClientboundKeepAliveConfiguration { keep_alive_id })).await?,
+                        _ => self.send(ClientboundPlayPacket::from(ClientboundKeepAlivePlay { keep_alive_id })).await?,
+                    }
+                    self.flush().await?;
+                }
+                KeepAliveAction::TimedOut => {
+                    self.disconnect(TIMED_OUT).await?;
+                    return Err("Client did not answer keep-alive".into());
+                }
+                KeepAliveAction::Wait => {}
+            }
+        }
+    }
+
+    async fn receive<P: StatePacket>(&mut self) -> Result<P, Box<dyn Error>> {
+        self.check_state::<P>()?;
+        let (id, mut data) = self.next_frame().await?;
+        Ok(P::decode_for(self.version, id, &mut data)?)
+    }
+
+    /// Checks a keep-alive reply, disconnecting the client if it isn't for the last one sent.
+    /// Returns the player's latency in milliseconds.
+    async fn keep_alive_received(&mut self, keep_alive_id: i64) -> Result<i32, Box<dyn Error>> {
+        match self.keep_alive.receive(keep_alive_id, Instant::now()) {
+            Ok(latency) => Ok(latency.as_millis().try_into().unwrap_or(i32::MAX)),
+            Err(e) => {
+                self.disconnect(TIMED_OUT).await?;
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Wraps both halves in AES/CFB8; everything after this point is encrypted in both directions.
+    async fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.flush().await?;
+        let reader = std::mem::replace(&mut self.reader, FramedRead::new(Box::new(tokio::io::empty()), MinecraftCodec::new()));
+        let parts = reader.into_parts();
+        // Bytes read ahead of the last frame were already encrypted by the client.
+        let read_ahead = std::io::Cursor::new(parts.read_buf).chain(parts.io);
+        let decrypted: ConnectionReader = Box::new(EncryptedReader::new(read_ahead, shared_secret)?);
+        self.reader = FramedRead::new(decrypted, parts.codec);
+
+        let writer = std::mem::replace(&mut self.writer, FramedWrite::new(Box::new(tokio::io::sink()), MinecraftCodec::new()));
+        let parts = writer.into_parts();
+        let encrypted: ConnectionWriter = Box::new(EncryptedWriter::new(parts.io, shared_secret)?);
+        self.writer = FramedWrite::new(encrypted, parts.codec);
+        Ok(())
+    }
+
+    /// Switches both directions to compressed framing; only called right after `SetCompression`.
+    fn enable_compression(&mut self, compression: Compression) {
+        self.reader.decoder_mut().set_compression(Some(compression));
+        self.writer.encoder_mut().set_compression(Some(compression));
+    }
+
+    /// Sends the disconnect packet of the current state. Status connections are simply closed.
+    async fn disconnect(&mut self, message: &str) -> Result<(), Box<dyn Error>> {
+        let reason = TextComponent::text(message);
+        match self.state {
+            ConnectionState::Login => self.send(ClientboundLoginPacket::from(DisconnectLogin { reason: reason.to_json_for(self.version) })).await?,
+            ConnectionState::Configuration => self.send(ClientboundConfigurationPacket::from(DisconnectConfiguration { reason })).await?,
+            ConnectionState::Play => self.send(ClientboundPlayPacket::from(DisconnectPlay { reason })).await?,
+            ConnectionState::Handshaking | ConnectionState::Status => return Ok(()),
+        }
+        self.flush().await
+    }
+}
+
+fn version_name(version: ProtocolVersion) -> String {
+    format!("mcprototool-rs {}", version.name())
+}
+
+fn server_status(state: &ServerState, version: ProtocolVersion) -> ServerStatus {
+    let config = &state.options.config;
+    ServerStatus {
+        version: StatusVersion { name: version_name(version), protocol: version.protocol() },
+        players: StatusPlayers { max: config.max_players, online: state.players.online(), sample: state.players.sample() },
+        description: config.motd_component(),
+        favicon: state.favicon.clone(),
+        enforces_secure_chat: config.enforce_secure_chat,
+    }
+}
+
+/// The fields of `server_status`, for clients that only speak the legacy ping.
+fn legacy_status(state: &ServerState) -> LegacyStatus {
+    LegacyStatus {
+        protocol: ProtocolVersion::LATEST.protocol(),
+        version_name: version_name(ProtocolVersion::LATEST),
+        motd: state.options.config.motd_component().to_legacy(),
+        online_players: state.players.online(),
+        max_players: state.options.config.max_players,
+    }
+}
+
+
+pub async fn run_server(options: ServerOptions) -> Result<(), Box<dyn Error>> {
+    let address = options.config.bind_address();
+    let listener = TcpListener::bind(&address).await?;
+    println!("Minimal Minecraft server listening on {} ({})", address, options.config.motd_component().to_plain_text());
+    serve(listener, options).await
+}
+
+/// Accepts connections from an already bound listener.
+pub async fn serve(listener: TcpListener, options: ServerOptions) -> Result<(), Box<dyn Error>> {
+    let key = match options.session_service {
+        Some(_) => {
+            println!("Online mode: generating server key pair...");
+            Some(ServerKey::generate()?)
+        }
+        None => None,
+    };
+    let favicon = match status::load_favicon(Path::new(&options.config.server_icon)) {
+        Ok(favicon) => Some(favicon),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("Ignoring server icon {}: {}", options.config.server_icon, e);
+            None
+        }
+    };
+    let (registries, tags) = match options.config.data_directory.as_str() {
+        "" => (Registries::core(), Tags::default()),
+        data_directory => {
+            let registries = Registries::load(Path::new(data_directory))?;
+            let tags = Tags::load(Path::new(data_directory), &registries)?;
+            if !tags.skipped().is_empty() {
+                eprintln!("Ignoring tags of registries without network IDs: {}", tags.skipped().join(", "));
+            }
+            (registries, tags)
+        }
+    };
+    let state = Arc::new(ServerState { options, key, next_entity_id: AtomicI32::new(1), players: PlayerList::new(), favicon, registries, tags });
 
     loop {
         let (socket, addr) = listener.accept().await?;
         println!("Accepted connection from: {}", addr);
 
+        let state = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(socket).await {
+            if let Err(e) = handle_connection(socket, &state).await {
                 eprintln!("Error handling connection from {}: {}", addr, e);
             } else {
                 println!("Connection closed gracefully: {}", addr);
@@ -58,150 +309,704 @@ pub async fn run_server(address: &str) -> Result<(), Box<dyn Error>> {
     }
 }
 
-async fn handle_connection(stream: TcpStream) -> Result<(), Box<dyn Error>> {
-    let (reader, writer) = stream.into_split();
-    let mut buf_reader = BufReader::new(reader);
-    let mut buf_writer = BufWriter::new(writer);
+async fn handle_connection(stream: TcpStream, state: &ServerState) -> Result<(), Box<dyn Error>> {
+    let peer_ip = stream.peer_addr()?.ip();
 
-    let (handshake_id, mut handshake_data) = read_packet_frame(&mut buf_reader).await?;
-    if handshake_id.0 != 0x00 {
-        return Err(format!("Expected Handshake packet (ID 0x00), got {:#04x}", handshake_id.0).into());
+    // Pre-1.7 clients start with 0xFE rather than a frame length.
+    let mut first_byte = [0u8; 1];
+    if stream.peek(&mut first_byte).await? == 1 && first_byte[0] == LEGACY_PING_ID {
+        return handle_legacy_ping(stream, state).await;
     }
-    let handshake = deserialize_handshake(&mut handshake_data).await?;
+
+    let mut connection = Connection::new(stream);
+
+    let ServerboundHandshakingPacket::Handshake(handshake) = connection.receive().await?;
     println!("Received Handshake: Proto={}, Addr={}, Port={}, Next={:?}",
         handshake.protocol_version.0, handshake.server_address, handshake.server_port, handshake.next_state);
 
+    // Unsupported clients still get a status response advertising our latest version, so their
+    // server list shows the version mismatch instead of an error.
+    let negotiated = ProtocolVersion::from_protocol(handshake.protocol_version.0);
+    connection.version = negotiated.unwrap_or(ProtocolVersion::LATEST);
+
     match handshake.next_state.0 {
         1 => {
-            println!("Handling Status request...");
+            connection.state = ConnectionState::Status;
+            handle_status(&mut connection, state).await?;
+        }
+        2 => {
+            connection.state = ConnectionState::Login;
+            let Some(version) = negotiated else {
+                let message = format!("Unsupported protocol version {}, this server runs {}",
+                    handshake.protocol_version.0, ProtocolVersion::LATEST.name());
+                connection.disconnect(&message).await?;
+                return Err(format!("Client uses unsupported protocol version {}", handshake.protocol_version.0).into());
+            };
+            println!("Negotiated protocol version {}", version);
+
+            let profile = handle_login(&mut connection, state, peer_ip).await?;
+            connection.state = ConnectionState::Configuration;
+            connection.keep_alive = KeepAlive::new(Instant::now());
+            handle_configuration(&mut connection, state).await?;
+            connection.state = ConnectionState::Play;
+            handle_play(&mut connection, state, &profile).await?;
+        }
+        _ => {
+            return Err(format!("Client requested unsupported state: {:?}", handshake.next_state).into());
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_status(connection: &mut Connection, state: &ServerState) -> Result<(), Box<dyn Error>> {
+    println!("Handling Status request...");
 
-            let (status_req_id, _status_req_data) = read_packet_frame(&mut buf_reader).await?;
-            if status_req_id.0 != 0x00 {
-                 return Err(format!("Expected Status Request packet (ID 0x00), got {:#04x}", status_req_id.0).into());
+    loop {
+        let packet = connection.receive().await?;
+        match packet {
+            ServerboundStatusPacket::StatusRequest(_) => {
+                connection.send(ClientboundStatusPacket::from(StatusResponse {
+                    json_response: server_status(state, connection.version).to_json_for(connection.version),
+                })).await?;
+                connection.flush().await?;
+                println!("Sent Status Response.");
             }
+            ServerboundStatusPacket::PingRequest(ping_request) => {
+                println!("Received Ping Request: Payload={}", ping_request.payload);
 
-            let status_response_packet = StatusResponse {
-                json_response: STATUS_JSON.to_string(),
-            };
-            let status_response_data = serialize_status_response(&status_response_packet).await?;
-            write_packet_frame(&mut buf_writer, VarInt::from(0x00), &status_response_data).await?;
-            buf_writer.flush().await?;
-            println!("Sent Status Response.");
+                connection.send(ClientboundStatusPacket::from(PongResponse {
+                    payload: ping_request.payload,
+                })).await?;
+                connection.flush().await?;
+                println!("Sent Pong Response.");
+                break;
+            }
+        }
+    }
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    println!("Waited briefly after Pong.");
+    Ok(())
+}
+
+async fn handle_legacy_ping(mut stream: TcpStream, state: &ServerState) -> Result<(), Box<dyn Error>> {
+    stream.read_u8().await?;
+    let ping = legacy::read_legacy_ping(&mut stream).await?;
+    println!("Received legacy server list ping: {:?}", ping);
+
+    stream.write_all(&legacy::legacy_status_response(&ping, &legacy_status(state))).await?;
+    stream.shutdown().await?;
+    println!("Sent legacy status response.");
+    Ok(())
+}
+
+/// Runs the login state up to `LoginAcknowledged` and returns the player's profile.
+async fn handle_login(connection: &mut Connection, state: &ServerState, peer_ip: IpAddr) -> Result<GameProfile, Box<dyn Error>> {
+    println!("Handling Login request...");
+
+    let packet = connection.receive().await?;
+    let login_start = match packet {
+        ServerboundLoginPacket::LoginStart(login_start) => login_start,
+        other => {
+            return Err(format!("Expected Login Start packet, got {} ({:#04x})", other.name(), other.id().0).into());
+        }
+    };
+    println!("Received Login Start: Name={}, UUID={}", login_start.name, login_start.player_uuid);
+
+    let mut profile = GameProfile {
+        id: login_start.player_uuid,
+        name: login_start.name.clone(),
+        properties: vec![],
+    };
 
-            let (ping_req_id, mut ping_req_data) = read_packet_frame(&mut buf_reader).await?;
-             if ping_req_id.0 != 0x01 {
-                 return Err(format!("Expected Ping Request packet (ID 0x01), got {:#04x}", ping_req_id.0).into());
+    if let (Some(session_service), Some(key)) = (&state.options.session_service, &state.key) {
+        let verify_token = auth::generate_verify_token();
+        connection.send(ClientboundLoginPacket::from(EncryptionRequest {
+            server_id: String::new(),
+            public_key: key.public_key_der().to_vec(),
+            verify_token: verify_token.to_vec(),
+            should_authenticate: true,
+        })).await?;
+        connection.flush().await?;
+
+        let packet = connection.receive().await?;
+        let encryption_response = match packet {
+            ServerboundLoginPacket::EncryptionResponse(encryption_response) => encryption_response,
+            other => {
+                return Err(format!("Expected Encryption Response packet, got {} ({:#04x})", other.name(), other.id().0).into());
             }
+        };
+        let shared_secret = key.decrypt(&encryption_response.shared_secret)?;
+        if key.decrypt(&encryption_response.verify_token)? != verify_token {
+            return Err(AuthError::VerifyTokenMismatch.into());
+        }
 
-            let ping_request = deserialize_ping_request(&mut ping_req_data).await?;
-            println!("Received Ping Request: Payload={}", ping_request.payload);
+        connection.enable_encryption(&shared_secret).await?;
+        println!("Enabled encryption for {}", login_start.name);
 
-            let pong_response_packet = PongResponse {
-                payload: ping_request.payload,
-            };
+        let server_hash = auth::server_hash("", &shared_secret, key.public_key_der());
+        // Like vanilla, only ask the session server to match the address when proxies are refused.
+        let ip = state.options.config.prevent_proxy_connections.then_some(peer_ip);
+        match session_service.has_joined(&login_start.name, &server_hash, ip).await? {
+            Some(verified) => {
+                println!("Authenticated {} as {}", verified.name, verified.id);
+                profile = verified;
+            }
+            None => {
+                connection.disconnect("Failed to verify username!").await?;
+                return Err(format!("Session service did not verify {}", login_start.name).into());
+            }
+        }
+    }
 
-            let pong_response_data = serialize_pong_response(&pong_response_packet).await?;
-            write_packet_frame(&mut buf_writer, VarInt::from(0x01), &pong_response_data).await?;
-            buf_writer.flush().await?;
-            println!("Sent Pong Response.");
+    // Set Compression is the last uncompressed packet; everything after it uses the new framing.
+    if let Some(compression) = state.options.compression {
+        connection.send(ClientboundLoginPacket::from(SetCompression {
+            threshold: VarInt(compression.threshold as i32),
+        })).await?;
+        connection.enable_compression(compression);
+        println!("Enabled compression with threshold {}", compression.threshold);
+    }
 
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            println!("Waited briefly after Pong.");
+    connection.send(ClientboundLoginPacket::from(LoginSuccess {
+        uuid: profile.id,
+        username: profile.name.clone(),
+        properties: profile.properties.clone(),
+        strict_error_handling: false,
+    })).await?;
+    connection.flush().await?;
+    println!("Sent Login Success for {}", profile.name);
 
+    let packet = connection.receive().await?;
+    match packet {
+        ServerboundLoginPacket::LoginAcknowledged(_) => println!("Received Login Acknowledged from {}", profile.name),
+        other => {
+            return Err(format!("Expected Login Acknowledged packet, got {} ({:#04x})", other.name(), other.id().0).into());
         }
-        2 => {
-            println!("Handling Login request...");
+    }
+
+    Ok(profile)
+}
 
-            let (login_start_id, mut login_start_data) = read_packet_frame(&mut buf_reader).await?;
-            if login_start_id.0 != 0x00 {
-                return Err(format!("Expected Login Start packet (ID 0x00), got {:#04x}", login_start_id.0).into());
+/// Runs the configuration state: agrees on the vanilla data pack, sends the registries and tags
+/// and waits for the client to acknowledge the switch to play.
+async fn handle_configuration(connection: &mut Connection, state: &ServerState) -> Result<(), Box<dyn Error>> {
+    let core_pack = registry::core_pack(connection.version);
+
+    connection.send(ClientboundConfigurationPacket::from(FeatureFlags {
+        feature_flags: vec!["minecraft:vanilla".to_string()],
+    })).await?;
+    connection.send(ClientboundConfigurationPacket::from(ClientboundKnownPacks {
+        known_packs: vec![core_pack.clone()],
+    })).await?;
+    connection.flush().await?;
+
+    // The client sends its settings and brand while we wait for its known packs.
+    let known_packs = loop {
+        let packet = connection.receive().await?;
+        match packet {
+            ServerboundConfigurationPacket::ServerboundKnownPacks(known_packs) => break known_packs.known_packs,
+            ServerboundConfigurationPacket::ClientInformationConfiguration(information) => {
+                println!("Received Client Information: Locale={}, ViewDistance={}", information.locale, information.view_distance);
+            }
+            ServerboundConfigurationPacket::ServerboundKeepAliveConfiguration(keep_alive) => {
+                connection.keep_alive_received(keep_alive.keep_alive_id).await?;
             }
-            let login_start = deserialize_login_start(&mut login_start_data).await?;
-            println!("Received Login Start: Name={}, UUID={}", login_start.name, login_start.player_uuid);
+            other => println!("Ignoring {} packet during configuration", other.name()),
+        }
+    };
 
-            let login_success_packet = LoginSuccess {
-                uuid: login_start.player_uuid,
-                username: login_start.name.clone(),
-                properties: vec![],
-                strict_error_handling: false,
-            };
-            let login_success_data = serialize_login_success(&login_success_packet).await?;
-            write_packet_frame(&mut buf_writer, VarInt::from(0x02), &login_success_data).await?;
-            buf_writer.flush().await?;
-            println!("Sent Login Success for {}", login_start.name);
+    // Bundled core entries are only sent as names, so the client has to bring their data itself.
+    let registry_data = match state.registries.registry_data(connection.version, &known_packs) {
+        Ok(registry_data) => registry_data,
+        Err(e) => {
+            connection.disconnect(&format!("This server requires the vanilla {} data pack", connection.version.name())).await?;
+            return Err(e.into());
         }
-        _ => {
-            return Err(format!("Client requested unsupported state: {:?}", handshake.next_state).into());
+    };
+    for registry_data in registry_data {
+        connection.send(ClientboundConfigurationPacket::from(registry_data)).await?;
+    }
+    connection.send(ClientboundConfigurationPacket::from(state.tags.update_tags_configuration(connection.version))).await?;
+    connection.send(ClientboundConfigurationPacket::from(FinishConfiguration)).await?;
+    connection.flush().await?;
+    println!("Sent registries, finishing configuration");
+
+    loop {
+        let packet = connection.receive().await?;
+        match packet {
+            ServerboundConfigurationPacket::AcknowledgeFinishConfiguration(_) => break,
+            ServerboundConfigurationPacket::ServerboundKeepAliveConfiguration(keep_alive) => {
+                connection.keep_alive_received(keep_alive.keep_alive_id).await?;
+            }
+            other => println!("Ignoring {} packet during configuration", other.name()),
         }
     }
 
     Ok(())
 }
 
-async fn deserialize_handshake(buf: &mut BytesMut) -> Result<Handshake, DecodeError> {
-    let protocol_version = read_varint_sync(buf)?;
-    let server_address = read_string_sync(buf)?;
-    if buf.remaining() < 2 { return Err(DecodeError::NotEnoughBytes); }
-    let server_port = buf.get_u16();
-    let next_state = read_varint_sync(buf)?;
-    Ok(Handshake { protocol_version, server_address, server_port, next_state })
-}
+/// Spawns the player and reads play packets until the client disconnects.
+async fn handle_play(connection: &mut Connection, state: &ServerState, profile: &GameProfile) -> Result<(), Box<dyn Error>> {
+    let config = &state.options.config;
+    let entity_id = state.next_entity_id.fetch_add(1, Ordering::Relaxed);
+    // Listed in the status response until this function returns, however it returns.
+    let _player_list_entry = state.players.join(entity_id, profile.clone());
 
-async fn deserialize_login_start(buf: &mut BytesMut) -> Result<LoginStart, DecodeError> {
-    let name = read_string_sync(buf)?;
-    let player_uuid = read_uuid_sync(buf)?;
-    Ok(LoginStart { name, player_uuid })
+    connection.send(ClientboundPlayPacket::from(LoginPlay {
+        entity_id,
+        is_hardcore: false,
+        dimension_names: vec!["minecraft:overworld".to_string()],
+        max_players: VarInt(config.max_players),
+        view_distance: VarInt(config.view_distance),
+        simulation_distance: VarInt(config.simulation_distance),
+        reduced_debug_info: false,
+        enable_respawn_screen: true,
+        do_limited_crafting: false,
+        // Index of minecraft:overworld in the dimension_type registry.
+        dimension_type: VarInt(0),
+        dimension_name: "minecraft:overworld".to_string(),
+        hashed_seed: 0,
+        game_mode: 1,
+        previous_game_mode: -1,
+        is_debug: false,
+        is_flat: true,
+        death_location: None,
+        portal_cooldown: VarInt(0),
+        sea_level: VarInt(63),
+        enforces_secure_chat: config.enforce_secure_chat,
+    })).await?;
+    connection.send(ClientboundPlayPacket::from(GameEvent { event: START_WAITING_FOR_CHUNKS, value: 0.0 })).await?;
+    connection.send(ClientboundPlayPacket::from(SetCenterChunk { chunk_x: VarInt(0), chunk_z: VarInt(0) })).await?;
+    connection.send(ClientboundPlayPacket::from(SynchronizePlayerPosition {
+        teleport_id: VarInt(1),
+        x: 0.5,
+        y: 64.0,
+        z: 0.5,
+        velocity_x: 0.0,
+        velocity_y: 0.0,
+        velocity_z: 0.0,
+        yaw: 0.0,
+        pitch: 0.0,
+        flags: 0,
+    })).await?;
+    let source = CommandSource {
+        name: profile.name.clone(),
+        permission_level: if config.is_op(&profile.name) { OP_PERMISSION_LEVEL } else { 0 },
+    };
+    let commands = &state.options.commands;
+    connection.send(ClientboundPlayPacket::from(commands.commands(&source))).await?;
+    // Lists the player in their own tab list, so latency updates have an entry to go to.
+    connection.send(ClientboundPlayPacket::from(PlayerInfoUpdate {
+        actions: ADD_PLAYER | UPDATE_LISTED | UPDATE_LATENCY,
+        players: vec![PlayerInfoEntry {
+            uuid: profile.id,
+            actions: vec![
+                PlayerInfoActionData::AddPlayer {
+                    name: profile.name.clone(),
+                    properties: profile.properties.iter().map(|property| PlayerInfoProperty {
+                        name: property.name.clone(),
+                        value: property.value.clone(),
+                        signature: property.signature.clone(),
+                    }).collect(),
+                },
+                PlayerInfoActionData::UpdateListed { listed: true },
+                PlayerInfoActionData::UpdateLatency { ping: VarInt(0) },
+            ],
+        }],
+    })).await?;
+    connection.flush().await?;
+    println!("{} joined the game with entity ID {}", profile.name, entity_id);
+
+    loop {
+        let (packet_id, mut packet_data) = match connection.next_frame().await {
+            Ok(frame) => frame,
+            Err(e) if matches!(e.downcast_ref(), Some(DecodeError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof) => {
+                println!("{} left the game", profile.name);
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        // A packet we cannot decode yet is skipped rather than dropping the player.
+        match ServerboundPlayPacket::decode_for(connection.version, packet_id, &mut packet_data) {
+            Ok(ServerboundPlayPacket::ConfirmTeleportation(confirm)) => {
+                println!("{} confirmed teleport {}", profile.name, confirm.teleport_id.0);
+            }
+            Ok(ServerboundPlayPacket::PlayerLoaded(_)) => println!("{} finished loading the world", profile.name),
+            Ok(ServerboundPlayPacket::ServerboundKeepAlive(keep_alive)) => {
+                let ping = connection.keep_alive_received(keep_alive.keep_alive_id).await?;
+                connection.send(ClientboundPlayPacket::from(PlayerInfoUpdate {
+                    actions: UPDATE_LATENCY,
+                    players: vec![PlayerInfoEntry { uuid: profile.id, actions: vec![PlayerInfoActionData::UpdateLatency { ping: VarInt(ping) }] }],
+                })).await?;
+                connection.flush().await?;
+            }
+            // Argument signatures are only checked by other players' clients, and nothing is
+            // broadcast yet, so signed commands run the same as unsigned ones.
+            Ok(ServerboundPlayPacket::ChatCommand(ChatCommand { command }))
+            | Ok(ServerboundPlayPacket::SignedChatCommand(SignedChatCommand { command, .. })) => {
+                println!("{} issued server command: /{}", profile.name, command);
+                let feedback = match commands.execute(&source, &command) {
+                    Ok(feedback) => feedback,
+                    Err(e) => Some(e.to_text(&command)),
+                };
+                if let Some(content) = feedback {
+                    connection.send(ClientboundPlayPacket::from(SystemChatMessage { content, overlay: false })).await?;
+                    connection.flush().await?;
+                }
+            }
+            Ok(ServerboundPlayPacket::CommandSuggestionsRequest(request)) => {
+                connection.send(ClientboundPlayPacket::from(commands.suggestions(&source, &request))).await?;
+                connection.flush().await?;
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Skipping play packet {:#04x} from {}: {}", packet_id.0, profile.name, e),
+        }
+    }
 }
 
-async fn serialize_login_success(packet: &LoginSuccess) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut buf = Vec::new();
-    let mut writer = BufWriter::new(&mut buf);
-    writer.write_all(&packet.uuid.into_bytes()).await?;
-    write_string(&mut writer, &packet.username).await?;
-    write_varint(&mut writer, VarInt(packet.properties.len() as i32)).await?;
-    for prop in &packet.properties {
-        write_string(&mut writer, &prop.name).await?;
-        write_string(&mut writer, &prop.value).await?;
-        let is_signed = prop.signature.is_some();
-        writer.write_u8(if is_signed { 1 } else { 0 }).await?;
-        if let Some(sig) = &prop.signature {
-            write_string(&mut writer, sig).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::MockSessionService;
+    use crate::protocol::compression::{read_frame, write_frame};
+    use crate::protocol::configuration::serverbound::{AcknowledgeFinishConfiguration, ServerboundKnownPacks};
+    use crate::protocol::handshaking::serverbound::Handshake;
+    use crate::protocol::login::serverbound::{EncryptionResponse, LoginAcknowledged, LoginStart};
+    use crate::protocol::play::serverbound::{ChatCommand, CommandSuggestionsRequest, ConfirmTeleportation, ServerboundKeepAlive};
+    use crate::protocol::status::serverbound::StatusRequest;
+    use crate::protocol::types::KnownPack;
+    use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Encrypt, RsaPublicKey};
+    use uuid::Uuid;
+
+    /// The client end of a connection. It frames packets with the async helpers rather than the
+    /// codec, so the two are checked against each other.
+    struct TestClient {
+        reader: ConnectionReader,
+        writer: ConnectionWriter,
+        compression: Option<Compression>,
+    }
+
+    impl TestClient {
+        async fn connect(address: std::net::SocketAddr) -> Self {
+            let stream = TcpStream::connect(address).await.unwrap();
+            let (reader, writer) = stream.into_split();
+            let mut client = TestClient { reader: Box::new(reader), writer: Box::new(writer), compression: None };
+            client.send(ServerboundHandshakingPacket::from(Handshake {
+                protocol_version: VarInt(ProtocolVersion::LATEST.protocol()),
+                server_address: "localhost".to_string(),
+                server_port: address.port(),
+                next_state: VarInt(2),
+            })).await;
+            client.send(ServerboundLoginPacket::from(LoginStart {
+                name: "Player".to_string(),
+                player_uuid: Uuid::nil(),
+            })).await;
+            client
+        }
+
+        async fn send<P: StatePacket>(&mut self, packet: P) {
+            let (id, data) = packet.encode_for(ProtocolVersion::LATEST).unwrap();
+            write_frame(&mut self.writer, self.compression.as_ref(), id, &data).await.unwrap();
+            self.writer.flush().await.unwrap();
+        }
+
+        async fn receive<P: StatePacket>(&mut self) -> P {
+            let (id, mut data) = read_frame(&mut self.reader, self.compression.as_ref()).await.unwrap();
+            P::decode_for(ProtocolVersion::LATEST, id, &mut data).unwrap()
+        }
+
+        fn enable_encryption(&mut self, shared_secret: &[u8]) {
+            let reader = std::mem::replace(&mut self.reader, Box::new(tokio::io::empty()));
+            self.reader = Box::new(EncryptedReader::new(reader, shared_secret).unwrap());
+            let writer = std::mem::replace(&mut self.writer, Box::new(tokio::io::sink()));
+            self.writer = Box::new(EncryptedWriter::new(writer, shared_secret).unwrap());
+        }
+
+        /// Reads `SetCompression` and `LoginSuccess`, then acknowledges the login.
+        async fn finish_login(&mut self) -> LoginSuccess {
+            let ClientboundLoginPacket::SetCompression(set_compression) = self.receive().await else {
+                panic!("expected Set Compression");
+            };
+            self.compression = Some(Compression {
+                threshold: set_compression.threshold.0 as usize,
+                ..Compression::default()
+            });
+            let ClientboundLoginPacket::LoginSuccess(login_success) = self.receive().await else {
+                panic!("expected Login Success");
+            };
+            self.send(ServerboundLoginPacket::from(LoginAcknowledged)).await;
+            login_success
+        }
+
+        /// Reads the opening configuration packets and answers with `known_packs`.
+        async fn send_known_packs(&mut self, known_packs: Vec<KnownPack>) {
+            let ClientboundConfigurationPacket::FeatureFlags(_) = self.receive().await else {
+                panic!("expected Feature Flags");
+            };
+            let ClientboundConfigurationPacket::ClientboundKnownPacks(offered) = self.receive().await else {
+                panic!("expected Known Packs");
+            };
+            assert_eq!(offered.known_packs, vec![registry::core_pack(ProtocolVersion::LATEST)]);
+            self.send(ServerboundConfigurationPacket::from(ServerboundKnownPacks { known_packs })).await;
         }
     }
 
-    writer.flush().await?;
-    Ok(buf)
-}
+    async fn query_status(address: std::net::SocketAddr, protocol: i32) -> ServerStatus {
+        let stream = TcpStream::connect(address).await.unwrap();
+        let (reader, writer) = stream.into_split();
+        let mut client = TestClient { reader: Box::new(reader), writer: Box::new(writer), compression: None };
+        client.send(ServerboundHandshakingPacket::from(Handshake {
+            protocol_version: VarInt(protocol),
+            server_address: "localhost".to_string(),
+            server_port: address.port(),
+            next_state: VarInt(1),
+        })).await;
+        client.send(ServerboundStatusPacket::from(StatusRequest {})).await;
+        let ClientboundStatusPacket::StatusResponse(response) = client.receive().await else {
+            panic!("expected Status Response");
+        };
+        serde_json::from_str(&response.json_response).unwrap()
+    }
 
-async fn serialize_status_response(packet: &StatusResponse) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut buf = Vec::new();
-    let mut writer = BufWriter::new(&mut buf);
-    write_string(&mut writer, &packet.json_response).await?;
-    writer.flush().await?;
-    Ok(buf)
-}
+    async fn start_server(options: ServerOptions) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { serve(listener, options).await.unwrap() });
+        address
+    }
 
-async fn deserialize_ping_request(buf: &mut BytesMut) -> Result<PingRequest, DecodeError> {
-    let payload = read_i64_sync(buf)?;
-    Ok(PingRequest { payload })
-}
+    async fn start_online_server(session: Arc<MockSessionService>) -> std::net::SocketAddr {
+        start_server(ServerOptions {
+            session_service: Some(session),
+            ..ServerOptions::default()
+        })
+        .await
+    }
 
-async fn serialize_pong_response(packet: &PongResponse) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut buf = Vec::new();
-    let mut writer = BufWriter::new(&mut buf);
-    write_i64(&mut writer, packet.payload).await?;
-    writer.flush().await?;
-    Ok(buf)
-}
+    /// Runs the client side of an online-mode login up to the encryption response. When `join` is
+    /// set the client registers with the session service first, as the vanilla client does.
+    async fn login_until_encrypted(address: std::net::SocketAddr, session: &MockSessionService, join: Option<&GameProfile>) -> TestClient {
+        let mut client = TestClient::connect(address).await;
 
-fn read_i64_sync(buf: &mut BytesMut) -> Result<i64, DecodeError> {
-    if buf.remaining() < 8 { return Err(DecodeError::NotEnoughBytes); }
-    Ok(buf.get_i64())
-}
+        let ClientboundLoginPacket::EncryptionRequest(request) = client.receive().await else {
+            panic!("expected Encryption Request");
+        };
+        assert!(request.should_authenticate);
+        let public_key = RsaPublicKey::from_public_key_der(&request.public_key).unwrap();
+        let shared_secret = [0x42u8; 16];
+
+        if let Some(profile) = join {
+            let server_hash = auth::server_hash(&request.server_id, &shared_secret, &request.public_key);
+            session.join(profile.clone(), &server_hash);
+        }
+
+        let mut rng = rand::thread_rng();
+        client.send(ServerboundLoginPacket::from(EncryptionResponse {
+            shared_secret: public_key.encrypt(&mut rng, Pkcs1v15Encrypt, &shared_secret).unwrap(),
+            verify_token: public_key.encrypt(&mut rng, Pkcs1v15Encrypt, &request.verify_token).unwrap(),
+        })).await;
+
+        client.enable_encryption(&shared_secret);
+        client
+    }
+
+    #[test]
+    fn test_options_from_config() {
+        let config = ServerConfig { network_compression_threshold: 64, network_compression_level: 1, ..ServerConfig::default() };
+        assert_eq!(ServerOptions::from_config(config).compression, Some(Compression { threshold: 64, level: 1 }));
+        let config = ServerConfig { network_compression_threshold: -1, ..ServerConfig::default() };
+        assert_eq!(ServerOptions::from_config(config).compression, None);
+    }
+
+    #[tokio::test]
+    async fn test_online_login_flow() {
+        let session = Arc::new(MockSessionService::new());
+        let address = start_online_server(session.clone()).await;
+        let profile = GameProfile {
+            id: Uuid::new_v4(),
+            name: "Player".to_string(),
+            properties: vec![],
+        };
+
+        let mut client = login_until_encrypted(address, &session, Some(&profile)).await;
+        let login_success = client.finish_login().await;
+        assert_eq!(login_success.uuid, profile.id);
+        assert_eq!(login_success.username, profile.name);
+
+        client.send_known_packs(vec![registry::core_pack(ProtocolVersion::LATEST)]).await;
+        let ClientboundConfigurationPacket::RegistryData(_) = client.receive().await else {
+            panic!("expected Registry Data over the encrypted, compressed stream");
+        };
+        assert_eq!(session.queried_ips(), vec![None]);
+    }
+
+    #[tokio::test]
+    async fn test_online_login_can_prevent_proxy_connections() {
+        let session = Arc::new(MockSessionService::new());
+        let config = ServerConfig { prevent_proxy_connections: true, ..ServerConfig::default() };
+        let address = start_server(ServerOptions {
+            session_service: Some(session.clone()),
+            ..ServerOptions::from_config(config)
+        })
+        .await;
+        let profile = GameProfile {
+            id: Uuid::new_v4(),
+            name: "Player".to_string(),
+            properties: vec![],
+        };
+
+        let mut client = login_until_encrypted(address, &session, Some(&profile)).await;
+        client.finish_login().await;
+        assert_eq!(session.queried_ips(), vec![Some(address.ip())]);
+    }
 
-async fn write_bool<W: AsyncWriteExt + Unpin>(writer: &mut W, value: bool) -> Result<(), std::io::Error> {
-    writer.write_u8(if value { 1 } else { 0 }).await
+    #[tokio::test]
+    async fn test_online_login_rejects_unverified_player() {
+        let session = Arc::new(MockSessionService::new());
+        let address = start_online_server(session.clone()).await;
+
+        let mut client = login_until_encrypted(address, &session, None).await;
+
+        let ClientboundLoginPacket::DisconnectLogin(disconnect) = client.receive().await else {
+            panic!("expected Disconnect");
+        };
+        assert!(disconnect.reason.contains("Failed to verify username!"));
+    }
+
+    #[tokio::test]
+    async fn test_offline_join_reaches_play() {
+        let address = start_server(ServerOptions::default()).await;
+        let mut client = TestClient::connect(address).await;
+        client.finish_login().await;
+        client.send_known_packs(vec![registry::core_pack(ProtocolVersion::LATEST)]).await;
+
+        let mut registries = vec![];
+        loop {
+            match client.receive().await {
+                ClientboundConfigurationPacket::RegistryData(registry_data) => registries.push(registry_data),
+                ClientboundConfigurationPacket::UpdateTagsConfiguration(_) => {}
+                ClientboundConfigurationPacket::FinishConfiguration(_) => break,
+                other => panic!("unexpected {} during configuration", other.name()),
+            }
+        }
+        assert_eq!(registries, registry::core_registry_data(ProtocolVersion::LATEST));
+        client.send(ServerboundConfigurationPacket::from(AcknowledgeFinishConfiguration)).await;
+
+        let ClientboundPlayPacket::LoginPlay(login_play) = client.receive().await else {
+            panic!("expected Login (play)");
+        };
+        assert_eq!(login_play.dimension_name, "minecraft:overworld");
+        let ClientboundPlayPacket::GameEvent(game_event) = client.receive().await else {
+            panic!("expected Game Event");
+        };
+        assert_eq!(game_event.event, START_WAITING_FOR_CHUNKS);
+        let ClientboundPlayPacket::SetCenterChunk(_) = client.receive().await else {
+            panic!("expected Set Center Chunk");
+        };
+        let ClientboundPlayPacket::SynchronizePlayerPosition(position) = client.receive().await else {
+            panic!("expected Synchronize Player Position");
+        };
+        let ClientboundPlayPacket::Commands(commands) = client.receive().await else {
+            panic!("expected Commands");
+        };
+        assert!(commands.nodes.iter().any(|node| node.name() == "help"));
+        let ClientboundPlayPacket::PlayerInfoUpdate(player_info) = client.receive().await else {
+            panic!("expected Player Info Update");
+        };
+        assert_eq!(player_info.actions, ADD_PLAYER | UPDATE_LISTED | UPDATE_LATENCY);
+        client.send(ServerboundPlayPacket::from(ConfirmTeleportation { teleport_id: position.teleport_id })).await;
+
+        client.send(ServerboundPlayPacket::from(ChatCommand { command: "help".to_string() })).await;
+        let ClientboundPlayPacket::SystemChatMessage(message) = client.receive().await else {
+            panic!("expected System Chat Message");
+        };
+        assert_eq!(message.content.to_plain_text(), "/help");
+        client.send(ServerboundPlayPacket::from(ChatCommand { command: "nope".to_string() })).await;
+        let ClientboundPlayPacket::SystemChatMessage(message) = client.receive().await else {
+            panic!("expected System Chat Message");
+        };
+        assert!(message.content.to_plain_text().starts_with("Unknown or incomplete command"));
+        client.send(ServerboundPlayPacket::from(CommandSuggestionsRequest { transaction_id: VarInt(3), text: "/he".to_string() })).await;
+        let ClientboundPlayPacket::CommandSuggestionsResponse(response) = client.receive().await else {
+            panic!("expected Command Suggestions Response");
+        };
+        assert_eq!((response.id, response.start, response.length), (VarInt(3), VarInt(1), VarInt(2)));
+        assert_eq!(response.matches[0].match_text, "help");
+
+        // No keep-alive has been sent yet, so any reply is unexpected.
+        client.send(ServerboundPlayPacket::from(ServerboundKeepAlive { keep_alive_id: 42 })).await;
+        let ClientboundPlayPacket::DisconnectPlay(disconnect) = client.receive().await else {
+            panic!("expected Disconnect (play)");
+        };
+        assert_eq!(disconnect.reason.to_plain_text(), TIMED_OUT);
+    }
+
+    #[tokio::test]
+    async fn test_answers_legacy_ping() {
+        let address = start_server(ServerOptions::default()).await;
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(&[LEGACY_PING_ID, 0x01]).await.unwrap();
+
+        let mut response = vec![];
+        stream.read_to_end(&mut response).await.unwrap();
+        let config = ServerConfig::default();
+        let expected = LegacyStatus {
+            protocol: ProtocolVersion::LATEST.protocol(),
+            version_name: version_name(ProtocolVersion::LATEST),
+            motd: config.motd_component().to_legacy(),
+            online_players: 0,
+            max_players: config.max_players,
+        };
+        assert_eq!(response, legacy::legacy_status_response(&legacy::LegacyPing::V1_4, &expected));
+    }
+
+    #[tokio::test]
+    async fn test_configuration_requires_core_pack() {
+        let address = start_server(ServerOptions::default()).await;
+        let mut client = TestClient::connect(address).await;
+        client.finish_login().await;
+        client.send_known_packs(vec![]).await;
+
+        let ClientboundConfigurationPacket::DisconnectConfiguration(disconnect) = client.receive().await else {
+            panic!("expected Disconnect (configuration)");
+        };
+        assert_eq!(disconnect.reason, TextComponent::text("This server requires the vanilla 1.21.5 data pack"));
+    }
+
+    #[tokio::test]
+    async fn test_status_lists_players_in_game() {
+        let address = start_server(ServerOptions::default()).await;
+        let status = query_status(address, ProtocolVersion::LATEST.protocol()).await;
+        assert_eq!(status.version.protocol, ProtocolVersion::LATEST.protocol());
+        assert_eq!(status.description, ServerConfig::default().motd_component());
+        assert_eq!(status.players.online, 0);
+        let older = query_status(address, ProtocolVersion::V1_21_4.protocol()).await;
+        assert_eq!((older.version.protocol, older.version.name), (769, version_name(ProtocolVersion::V1_21_4)));
+
+        let mut client = TestClient::connect(address).await;
+        let login_success = client.finish_login().await;
+        client.send_known_packs(vec![registry::core_pack(ProtocolVersion::LATEST)]).await;
+        while !matches!(client.receive().await, ClientboundConfigurationPacket::FinishConfiguration(_)) {}
+        client.send(ServerboundConfigurationPacket::from(AcknowledgeFinishConfiguration)).await;
+        let ClientboundPlayPacket::LoginPlay(_) = client.receive().await else {
+            panic!("expected Login (play)");
+        };
+
+        let status = query_status(address, ProtocolVersion::LATEST.protocol()).await;
+        assert_eq!(status.players.online, 1);
+        assert_eq!(status.players.sample.len(), 1);
+        assert_eq!(status.players.sample[0].id, login_success.uuid);
+        assert_eq!(status.players.sample[0].name, "Player");
+
+        drop(client);
+        for _ in 0..50 {
+            if query_status(address, ProtocolVersion::LATEST.protocol()).await.players.online == 0 {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("player still listed after disconnecting");
+    }
 }
-async fn write_uuid<W: AsyncWriteExt + Unpin>(writer: &mut W, value: Uuid) -> Result<(), std::io::Error> {
-    writer.write_all(&value.into_bytes()).await
-}
\ No newline at end of file
@@ -0,0 +1,287 @@
+//! The server list entry sent in `StatusResponse`.
+//!
+//! [`ServerStatus`] mirrors the JSON the client expects, and is filled in per request from the
+//! config, the negotiated protocol version and the [`PlayerList`] of players currently in game.
+
+use crate::auth::GameProfile;
+use crate::protocol::text::TextComponent;
+use crate::protocol::version::ProtocolVersion;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Most players listed in the hover sample, as in vanilla.
+pub const SAMPLE_SIZE: usize = 12;
+/// Side length, in pixels, the client requires of a favicon.
+pub const FAVICON_SIZE: u32 = 64;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub version: StatusVersion,
+    pub players: StatusPlayers,
+    pub description: TextComponent,
+    /// A `data:image/png;base64,` URI, see [`load_favicon`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    #[serde(default)]
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusVersion {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusPlayers {
+    pub max: i32,
+    pub online: i32,
+    #[serde(default)]
+    pub sample: Vec<PlayerSample>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: Uuid,
+}
+
+impl ServerStatus {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("status serializes to JSON")
+    }
+
+    /// The JSON for a client of `version`, whose description uses that version's text layout.
+    pub fn to_json_for(&self, version: ProtocolVersion) -> String {
+        let mut json = serde_json::to_value(self).expect("status serializes to JSON");
+        json["description"] = self.description.to_json_value_for(version);
+        json.to_string()
+    }
+}
+
+/// Reads a PNG from disk and returns it as the data URI `ServerStatus::favicon` expects. The image
+/// must be 64x64, or the client ignores it.
+pub fn load_favicon(path: &Path) -> std::io::Result<String> {
+    let png = std::fs::read(path)?;
+    favicon_from_png(&png)
+}
+
+pub fn favicon_from_png(png: &[u8]) -> std::io::Result<String> {
+    let (width, height) = png_dimensions(png)
+        .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "favicon is not a PNG image"))?;
+    if (width, height) != (FAVICON_SIZE, FAVICON_SIZE) {
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            format!(
+                "favicon must be {0}x{0} pixels, got {1}x{2}",
+                FAVICON_SIZE, width, height
+            ),
+        ));
+    }
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+/// Width and height from the IHDR chunk, which always comes right after the signature.
+fn png_dimensions(png: &[u8]) -> Option<(u32, u32)> {
+    if png.get(..8)? != PNG_SIGNATURE || png.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(png.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(png.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+/// Players currently in game, keyed by entity ID.
+#[derive(Debug, Default)]
+pub struct PlayerList {
+    players: Mutex<BTreeMap<i32, GameProfile>>,
+}
+
+impl PlayerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a player until the returned guard is dropped.
+    pub fn join(&self, entity_id: i32, profile: GameProfile) -> PlayerListEntry<'_> {
+        self.players.lock().unwrap().insert(entity_id, profile);
+        PlayerListEntry {
+            list: self,
+            entity_id,
+        }
+    }
+
+    pub fn online(&self) -> i32 {
+        self.players.lock().unwrap().len() as i32
+    }
+
+    /// Up to [`SAMPLE_SIZE`] players, picked at random when more are online.
+    pub fn sample(&self) -> Vec<PlayerSample> {
+        let players = self.players.lock().unwrap();
+        players
+            .values()
+            .choose_multiple(&mut rand::thread_rng(), SAMPLE_SIZE)
+            .into_iter()
+            .map(|profile| PlayerSample {
+                name: profile.name.clone(),
+                id: profile.id,
+            })
+            .collect()
+    }
+}
+
+/// Removes its player from the [`PlayerList`] when dropped.
+pub struct PlayerListEntry<'a> {
+    list: &'a PlayerList,
+    entity_id: i32,
+}
+
+impl Drop for PlayerListEntry<'_> {
+    fn drop(&mut self) {
+        self.list.players.lock().unwrap().remove(&self.entity_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::text::HoverEvent;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(13u32.to_be_bytes());
+        png.extend(b"IHDR");
+        png.extend(width.to_be_bytes());
+        png.extend(height.to_be_bytes());
+        png.extend([8, 6, 0, 0, 0]);
+        png
+    }
+
+    fn profile(name: &str) -> GameProfile {
+        GameProfile {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            properties: vec![],
+        }
+    }
+
+    #[test]
+    fn test_status_json_shape() {
+        let status = ServerStatus {
+            version: StatusVersion {
+                name: "1.21.5".to_string(),
+                protocol: 770,
+            },
+            players: StatusPlayers {
+                max: 20,
+                online: 1,
+                sample: vec![PlayerSample {
+                    name: "Player".to_string(),
+                    id: Uuid::nil(),
+                }],
+            },
+            description: TextComponent::text("Hello").bold(true),
+            favicon: None,
+            enforces_secure_chat: true,
+        };
+        let json: serde_json::Value = serde_json::from_str(&status.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": { "name": "1.21.5", "protocol": 770 },
+                "players": {
+                    "max": 20,
+                    "online": 1,
+                    "sample": [{ "name": "Player", "id": "00000000-0000-0000-0000-000000000000" }]
+                },
+                "description": { "text": "Hello", "bold": true },
+                "enforcesSecureChat": true
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<ServerStatus>(json).unwrap(),
+            status
+        );
+    }
+
+    #[test]
+    fn test_status_description_per_version() {
+        let description = TextComponent::text("Hello").hover_event(HoverEvent::ShowText {
+            value: Box::new("Tip".into()),
+        });
+        let status = ServerStatus {
+            version: StatusVersion {
+                name: "1.21.4".to_string(),
+                protocol: 769,
+            },
+            players: StatusPlayers {
+                max: 20,
+                online: 0,
+                sample: vec![],
+            },
+            description: description.clone(),
+            favicon: None,
+            enforces_secure_chat: false,
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&status.to_json_for(ProtocolVersion::V1_21_4)).unwrap();
+        assert_eq!(
+            json["description"],
+            serde_json::json!({
+                "text": "Hello",
+                "hoverEvent": { "action": "show_text", "contents": "Tip" },
+            })
+        );
+        assert_eq!(json["version"]["protocol"], 769);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&status.to_json_for(ProtocolVersion::V1_21_5)).unwrap();
+        assert_eq!(json["description"]["hover_event"]["value"], "Tip");
+    }
+
+    #[test]
+    fn test_favicon_from_png() {
+        let favicon = favicon_from_png(&png(64, 64)).unwrap();
+        let encoded = favicon.strip_prefix("data:image/png;base64,").unwrap();
+        assert_eq!(STANDARD.decode(encoded).unwrap(), png(64, 64));
+
+        let err = favicon_from_png(&png(128, 64)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(favicon_from_png(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_player_list_tracks_entries() {
+        let list = PlayerList::new();
+        let first = list.join(1, profile("First"));
+        {
+            let _second = list.join(2, profile("Second"));
+            assert_eq!(list.online(), 2);
+        }
+        assert_eq!(list.online(), 1);
+        assert_eq!(list.sample()[0].name, "First");
+        drop(first);
+        assert_eq!(list.online(), 0);
+        assert!(list.sample().is_empty());
+    }
+
+    #[test]
+    fn test_sample_is_capped() {
+        let list = PlayerList::new();
+        let _entries: Vec<_> = (0..20)
+            .map(|i| list.join(i, profile(&format!("Player{}", i))))
+            .collect();
+        assert_eq!(list.online(), 20);
+        assert_eq!(list.sample().len(), SAMPLE_SIZE);
+    }
+}